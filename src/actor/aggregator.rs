@@ -0,0 +1,162 @@
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::envelope::Envelope;
+
+/// Rolling statistics for one aggregation window.
+#[derive(Copy, Clone, Default, Debug, PartialEq)]
+pub(crate) struct StatsSummary {
+    pub(crate) fizz: u64,
+    pub(crate) buzz: u64,
+    pub(crate) fizzbuzz: u64,
+    pub(crate) values: u64,
+    /// Values given at least one label by the --rule settings.
+    pub(crate) labeled: u64,
+    pub(crate) messages_per_sec: f64,
+    /// Smallest and largest plain value seen, classified messages do not carry one.
+    pub(crate) min_value: Option<u64>,
+    pub(crate) max_value: Option<u64>,
+}
+
+impl StatsSummary {
+    pub(crate) fn record(&mut self, msg: &FizzBuzzMessage) {
+        match msg {
+            FizzBuzzMessage::Fizz => self.fizz += 1,
+            FizzBuzzMessage::Buzz => self.buzz += 1,
+            FizzBuzzMessage::FizzBuzz => self.fizzbuzz += 1,
+            FizzBuzzMessage::Value(v) => self.record_value(*v),
+            FizzBuzzMessage::Classified(c) if c.labels().is_empty() => self.record_value(c.value()),
+            FizzBuzzMessage::Classified(_) => self.labeled += 1,
+        }
+    }
+
+    fn record_value(&mut self, v: u64) {
+        self.values += 1;
+        self.min_value = Some(self.min_value.map_or(v, |m| m.min(v)));
+        self.max_value = Some(self.max_value.map_or(v, |m| m.max(v)));
+    }
+
+    pub(crate) fn total(&self) -> u64 {
+        self.fizz + self.buzz + self.fizzbuzz + self.values + self.labeled
+    }
+
+    /// Closes the window, computing the rate over its actual elapsed time.
+    pub(crate) fn close(mut self, elapsed: Duration) -> Self {
+        let secs = elapsed.as_secs_f64();
+        self.messages_per_sec = if secs > 0.0 { self.total() as f64 / secs } else { 0.0 };
+        self
+    }
+}
+
+/// Windowed aggregation stage, forwarding every message unchanged while emitting a
+/// `StatsSummary` on its own channel each time the heartbeat ticks, every `--aggregate-ms`.
+pub async fn run(actor: SteadyActorShadow
+                 , tick_rx: SteadyRx<u64>
+                 , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                 , forward_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , summary_tx: SteadyTx<StatsSummary>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&tick_rx, &rx], [&forward_tx, &summary_tx]), tick_rx, rx, forward_tx, summary_tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , tick_rx: SteadyRx<u64>
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , forward_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , summary_tx: SteadyTx<StatsSummary>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let clock = args.clock.clone();
+
+    let mut tick_rx = tick_rx.lock().await;
+    let mut rx = rx.lock().await;
+    let mut forward_tx = forward_tx.lock().await;
+    let mut summary_tx = summary_tx.lock().await;
+
+    let mut summary = StatsSummary::default();
+    let mut window_start = clock.now();
+
+    while actor.is_running(|| i!(rx.is_closed_and_empty())
+                              && i!(forward_tx.mark_closed())
+                              && i!(summary_tx.mark_closed())) {
+        // wake for new messages or for the tick closing the window, whichever comes first
+        if tick_rx.is_closed_and_empty() {
+            // a one-shot heartbeat closed the ticks, only the final window closes, at shutdown
+            await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut forward_tx, 1));
+        } else {
+            await_for_any!(wait_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut forward_tx, 1))
+                         , actor.wait_avail(&mut tick_rx, 1));
+        }
+
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut forward_tx));
+        for _ in 0..items {
+            if let Some(msg) = actor.try_take(&mut rx) {
+                summary.record(&msg.payload);
+                let _ = actor.try_send(&mut forward_tx, msg);
+            }
+        }
+
+        // close the window on the tick, and one final partial window at shutdown;
+        // ticks which piled up while the stage was busy close a single window
+        let mut ticked = false;
+        while actor.try_take(&mut tick_rx).is_some() {
+            ticked = true;
+        }
+        let closing = rx.is_closed_and_empty();
+        if ticked || (closing && summary.total() > 0) {
+            let closed = summary.close(clock.since(window_start));
+            actor.send_async(&mut summary_tx, closed, SendSaturation::AwaitForRoom).await;
+            summary = StatsSummary::default();
+            window_start = clock.now();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod aggregator_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::clock::Clock;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_aggregator() -> Result<(), Box<dyn Error>> {
+        // the virtual clock stands still, so the window closes with a zero rate
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { clock, ..MainArg::default() });
+        let (tick_tx, tick_rx) = graph.channel_builder().build();
+        let (tx, rx) = graph.channel_builder().build();
+        let (forward_tx, forward_rx) = graph.channel_builder().build();
+        let (summary_tx, summary_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, tick_rx.clone(), rx.clone(), forward_tx.clone(), summary_tx.clone())
+                   , SoloAct);
+
+        let sent = sealed(Instant::now(), (1..=15).map(FizzBuzzMessage::new));
+        tx.testing_send_all(sent.clone(), true);
+        tick_tx.testing_send_all(vec![0], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        // pass-through is untouched by aggregation
+        assert_steady_rx_eq_take!(&forward_rx, sent);
+        // the tick closed one window holding everything, leaving nothing for shutdown
+        let summary = StatsSummary { fizz: 4, buzz: 2, fizzbuzz: 1, values: 8, labeled: 0, messages_per_sec: 0.0
+                                   , min_value: Some(1), max_value: Some(14) };
+        assert_steady_rx_eq_take!(&summary_rx, vec!(summary));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_summary() {
+        let mut summary = StatsSummary::default();
+        (1..=15).map(FizzBuzzMessage::new).for_each(|m| summary.record(&m));
+        let summary = summary.close(Duration::from_millis(500));
+        assert_eq!((summary.fizz, summary.buzz, summary.fizzbuzz, summary.values), (4, 2, 1, 8));
+        assert_eq!((summary.min_value, summary.max_value), (Some(1), Some(14)));
+        assert_eq!(summary.messages_per_sec, 30.0);
+    }
+}
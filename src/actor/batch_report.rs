@@ -0,0 +1,132 @@
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::clock;
+
+/// Batches between two summaries while the graph runs.
+const REPORT_EVERY: u64 = 60;
+
+/// What one heartbeat-triggered worker batch emitted and how long it took,
+/// sent by the worker so nothing downstream has to parse its log lines.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct BatchReport {
+    pub(crate) items: u64,
+    pub(crate) duration: Duration,
+    pub(crate) fizz: u64,
+    pub(crate) buzz: u64,
+    pub(crate) fizzbuzz: u64,
+    pub(crate) values: u64,
+    /// Results carrying at least one `--rule` label.
+    pub(crate) labeled: u64,
+}
+
+impl BatchReport {
+    /// Counts one emitted message under its kind, a result no rule matched counts as a value.
+    pub(crate) fn record(&mut self, msg: &FizzBuzzMessage) {
+        match msg {
+            FizzBuzzMessage::Fizz => self.fizz += 1,
+            FizzBuzzMessage::Buzz => self.buzz += 1,
+            FizzBuzzMessage::FizzBuzz => self.fizzbuzz += 1,
+            FizzBuzzMessage::Value(_) => self.values += 1,
+            FizzBuzzMessage::Classified(c) if c.labels().is_empty() => self.values += 1,
+            FizzBuzzMessage::Classified(_) => self.labeled += 1,
+        }
+    }
+}
+
+/// Running totals over the batch reports seen so far.
+#[derive(Default)]
+pub(crate) struct ReportTotals {
+    batches: u64,
+    totals: BatchReport,
+    max: Duration,
+}
+
+impl ReportTotals {
+    pub(crate) fn record(&mut self, report: &BatchReport) {
+        self.batches += 1;
+        self.totals.items += report.items;
+        self.totals.duration += report.duration;
+        self.totals.fizz += report.fizz;
+        self.totals.buzz += report.buzz;
+        self.totals.fizzbuzz += report.fizzbuzz;
+        self.totals.values += report.values;
+        self.totals.labeled += report.labeled;
+        self.max = self.max.max(report.duration);
+    }
+
+    pub(crate) fn batches(&self) -> u64 {
+        self.batches
+    }
+
+    pub(crate) fn report(&self) -> String {
+        let t = &self.totals;
+        let mean = clock::mean(t.duration, self.batches);
+        // only shown under --rule, the classic run has nothing labeled
+        let labeled = if t.labeled > 0 { format!(" labeled {}", t.labeled) } else { String::new() };
+        format!("Batches {} items {} fizz {} buzz {} fizzbuzz {} values {}{} time mean {:?} max {:?}"
+                , self.batches, t.items, t.fizz, t.buzz, t.fizzbuzz, t.values, labeled, mean, self.max)
+    }
+}
+
+/// Terminal consumer of the worker's per-batch reports, logging their totals every
+/// `REPORT_EVERY` batches and once more at shutdown.
+pub async fn run(actor: SteadyActorShadow, report_rx: SteadyRx<BatchReport>) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&report_rx], []);
+    if actor.use_internal_behavior {
+        internal_behavior(actor, report_rx).await
+    } else { //edge actor so we simulate it when testing from main
+        actor.simulated_behavior(vec!(&report_rx)).await
+    }
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A, rx: SteadyRx<BatchReport>) -> Result<(),Box<dyn Error>> {
+    let mut rx = rx.lock().await;
+    let mut totals = ReportTotals::default();
+    while actor.is_running(|| i!(rx.is_closed_and_empty())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1));
+
+        while let Some(report) = actor.try_take(&mut rx) {
+            totals.record(&report);
+            if totals.batches() % REPORT_EVERY == 0 {
+                info!("{}", totals.report());
+            }
+        }
+    }
+    if totals.batches() % REPORT_EVERY != 0 {
+        info!("{}", totals.report());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod batch_report_tests {
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut report = BatchReport::default();
+        (1..=15).map(FizzBuzzMessage::new).for_each(|msg| report.record(&msg));
+        assert_eq!((report.fizz, report.buzz, report.fizzbuzz, report.values, report.labeled), (4, 2, 1, 8, 0));
+    }
+
+    #[test]
+    fn test_batch_report() -> Result<(), Box<dyn std::error::Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (report_tx, report_rx) = graph.channel_builder().build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, report_rx.clone()), SoloAct);
+
+        let report = |items, ms, fizz| BatchReport { items, duration: Duration::from_millis(ms), fizz, values: items - fizz, ..BatchReport::default() };
+        report_tx.testing_send_all(vec![report(10, 2, 3), report(6, 4, 2)], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_in_logs!(["Batches 2 items 16 fizz 5 buzz 0 fizzbuzz 0 values 11 time mean 3ms max 4ms"]);
+        Ok(())
+    }
+}
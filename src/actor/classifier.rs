@@ -0,0 +1,188 @@
+use steady_state::*;
+use crate::actor::batch_report::BatchReport;
+use crate::actor::dead_letter::DeadLetter;
+use crate::actor::heartbeat::Pulse;
+use crate::actor::history::BatchStats;
+use crate::actor::parser::Number;
+use crate::actor::worker::{classify, reload, BatchTally, FizzBuzzMessage, Transform};
+use crate::envelope::Envelope;
+use crate::rules::{ActiveRules, RuleSet, Rules};
+
+/// Second stage of `--topology staged`: classifies the parsed numbers one heartbeat batch at
+/// a time, taking the place of the worker towards the logger, the history and the heartbeat.
+/// Every number was validated by the parser, so only a value the transform fails on is
+/// rejected here, sent on `poisoned_tx` to the dead letters.
+pub async fn run(actor: SteadyActorShadow
+                 , heartbeat_rx: SteadyRx<Pulse>
+                 , parsed_rx: SteadyRx<Envelope<Number>>
+                 , rules_rx: SteadyRx<RuleSet>
+                 , classified_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , poisoned_tx: SteadyTx<DeadLetter>
+                 , stats_tx: SteadyTx<BatchStats>
+                 , report_tx: SteadyTx<BatchReport>
+                 , backlog_tx: SteadyTx<usize>
+                 , rules: Option<Rules>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors like the worker
+    let actor = actor.into_spotlight([&heartbeat_rx, &parsed_rx, &rules_rx], [&classified_tx, &poisoned_tx, &stats_tx, &report_tx, &backlog_tx]);
+    internal_behavior(actor, heartbeat_rx, parsed_rx, rules_rx, classified_tx, poisoned_tx, stats_tx, report_tx, backlog_tx, ActiveRules(rules)).await
+}
+
+async fn internal_behavior<A: SteadyActor, T: Transform<u64, FizzBuzzMessage>>(mut actor: A
+                                           , heartbeat_rx: SteadyRx<Pulse>
+                                           , parsed_rx: SteadyRx<Envelope<Number>>
+                                           , rules_rx: SteadyRx<RuleSet>
+                                           , classified_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , poisoned_tx: SteadyTx<DeadLetter>
+                                           , stats_tx: SteadyTx<BatchStats>
+                                           , report_tx: SteadyTx<BatchReport>
+                                           , backlog_tx: SteadyTx<usize>
+                                           , mut transform: T) -> Result<(),Box<dyn Error>> {
+    let clock = actor.args::<crate::MainArg>().expect("unable to downcast").clock.clone();
+
+    let mut heartbeat_rx = heartbeat_rx.lock().await;
+    let mut parsed_rx = parsed_rx.lock().await;
+    let mut rules_rx = rules_rx.lock().await;
+    let mut classified_tx = classified_tx.lock().await;
+    let mut poisoned_tx = poisoned_tx.lock().await;
+    let mut stats_tx = stats_tx.lock().await;
+    let mut report_tx = report_tx.lock().await;
+    let mut backlog_tx = backlog_tx.lock().await;
+
+    while actor.is_running(|| i!(heartbeat_rx.is_closed_and_empty())
+                              && i!(parsed_rx.is_closed_and_empty())
+                              && i!(rules_rx.is_closed_and_empty())
+                              && i!(classified_tx.mark_closed())
+                              && i!(poisoned_tx.mark_closed())
+                              && i!(stats_tx.mark_closed())
+                              && i!(report_tx.mark_closed())
+                              && i!(backlog_tx.mark_closed())) {
+        if heartbeat_rx.is_closed_and_empty() {
+            // no further batch until the shutdown drain, as in the worker
+            actor.wait_shutdown().await;
+        }
+        let clean = await_for_all!(actor.wait_avail(&mut heartbeat_rx, 1)
+                                 , actor.wait_avail(&mut parsed_rx, 1)
+                                 , actor.wait_vacant(&mut classified_tx, 1));
+
+        let pulse = actor.try_take(&mut heartbeat_rx);
+        let (beat, drain) = match pulse {
+            Some(Pulse::Beat(heartbeat)) => (Some(heartbeat.beat), false),
+            Some(Pulse::Flush) | Some(Pulse::LastBeat) => (None, true),
+            None => (None, false),
+        };
+        if pulse.is_some() || !clean {
+            let mut latest = None;
+            while let Some(rules) = actor.try_take(&mut rules_rx) {
+                latest = Some(rules);
+            }
+            reload(&mut transform, latest, beat);
+            let started = clock.now();
+            // a drain takes everything parsed and awaits room for what does not fit
+            let room = if drain { usize::MAX } else { actor.vacant_units(&mut classified_tx) };
+            let items = actor.avail_units(&mut parsed_rx).min(room);
+            let mut tally = BatchTally::default();
+            for _ in 0..items {
+                if let Some(item) = actor.try_take(&mut parsed_rx) {
+                    // validated by the parser already, only a failing transform is caught here
+                    match classify(item.map(|number| number.value()), false, u64::MAX, &mut transform, &mut tally) {
+                        Ok(msg) => { actor.send_async(&mut classified_tx, msg, SendSaturation::AwaitForRoom).await; }
+                        Err(letter) => { actor.send_async(&mut poisoned_tx, letter, SendSaturation::AwaitForRoom).await; }
+                    }
+                }
+            }
+            // observability only, dropped rather than stalling the next batch
+            let _ = actor.try_send(&mut stats_tx, tally.stats(beat));
+            let _ = actor.try_send(&mut report_tx, tally.report(clock.since(started)));
+            // the heartbeat paces on what waits here, the parser ahead of it runs free
+            let backlog = actor.avail_units(&mut parsed_rx);
+            let _ = actor.try_send(&mut backlog_tx, backlog);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod classifier_tests {
+    use std::time::{Instant, SystemTime};
+    use steady_state::*;
+    use crate::actor::heartbeat::Heartbeat;
+    use crate::actor::dead_letter::Reason;
+    use crate::actor::worker::{FizzBuzz, Poisoned};
+    use crate::arg::MainArg;
+    use crate::clock::Clock;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_classifier() -> Result<(), Box<dyn Error>> {
+        // a clock standing still makes the batch duration zero
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (parsed_tx, parsed_rx) = graph.channel_builder().build();
+        let (rules_tx, rules_rx) = graph.channel_builder().build::<RuleSet>();
+        let (classified_tx, classified_rx) = graph.channel_builder().build();
+        let (poisoned_tx, _poisoned_rx) = graph.channel_builder().build::<DeadLetter>();
+        let (stats_tx, _stats_rx) = graph.channel_builder().build::<BatchStats>();
+        let (report_tx, report_rx) = graph.channel_builder().build::<BatchReport>();
+        let (backlog_tx, _backlog_rx) = graph.channel_builder().build::<usize>();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, heartbeat_rx.clone(), parsed_rx.clone(), rules_rx.clone(), classified_tx.clone()
+                                                    , poisoned_tx.clone(), stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), FizzBuzz)
+                   , SoloAct);
+
+        let captured = Instant::now();
+        let parsed = sealed(captured, [3, 5, 15, 7].map(|value| Number::parse(value, u64::MAX).expect("below max")));
+        parsed_tx.testing_send_all(parsed, true);
+        rules_tx.testing_send_all(vec![], true);
+        heartbeat_tx.testing_send_all(vec![Pulse::Beat(Heartbeat::on_time(0, SystemTime::now()))], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // the intermediate type is gone again, each message in the envelope of its number
+        assert_steady_rx_eq_take!(&classified_rx, sealed(captured, [FizzBuzzMessage::Fizz, FizzBuzzMessage::Buzz
+                                                                   , FizzBuzzMessage::FizzBuzz, FizzBuzzMessage::Value(7)]));
+        assert_steady_rx_eq_take!(&report_rx, [BatchReport { items: 4, fizz: 1, buzz: 1, fizzbuzz: 1, values: 1, ..BatchReport::default() }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_classifier_poisoned() -> Result<(), Box<dyn Error>> {
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (parsed_tx, parsed_rx) = graph.channel_builder().build();
+        let (rules_tx, rules_rx) = graph.channel_builder().build::<RuleSet>();
+        let (classified_tx, classified_rx) = graph.channel_builder().build();
+        let (poisoned_tx, poisoned_rx) = graph.channel_builder().build::<DeadLetter>();
+        let (stats_tx, stats_rx) = graph.channel_builder().build::<BatchStats>();
+        let (report_tx, _report_rx) = graph.channel_builder().build::<BatchReport>();
+        let (backlog_tx, _backlog_rx) = graph.channel_builder().build::<usize>();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, heartbeat_rx.clone(), parsed_rx.clone(), rules_rx.clone(), classified_tx.clone()
+                                                    , poisoned_tx.clone(), stats_tx.clone(), report_tx.clone(), backlog_tx.clone()
+                                                    , Poisoned { poison: 5, inner: FizzBuzz })
+                   , SoloAct);
+
+        let captured = Instant::now();
+        let parsed = sealed(captured, [3, 5, 7].map(|value| Number::parse(value, u64::MAX).expect("below max")));
+        parsed_tx.testing_send_all(parsed, true);
+        rules_tx.testing_send_all(vec![], true);
+        heartbeat_tx.testing_send_all(vec![Pulse::Beat(Heartbeat::on_time(0, SystemTime::now()))], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // the classifier keeps running around the value its transform failed on
+        assert_steady_rx_eq_take!(&classified_rx, sealed(captured, [FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(7)]));
+        assert_steady_rx_eq_take!(&poisoned_rx, [DeadLetter { value: 5, reason: Reason::Poisoned }]);
+        let mut emitted = BatchTally::default();
+        emitted.emitted(&FizzBuzzMessage::Fizz);
+        emitted.emitted(&FizzBuzzMessage::Value(7));
+        assert_steady_rx_eq_take!(&stats_rx, [BatchStats { items: 3, rejected: 1, ..emitted.stats(Some(0)) }]);
+        Ok(())
+    }
+}
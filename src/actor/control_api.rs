@@ -0,0 +1,346 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use steady_state::*;
+use crate::actor::generator::GeneratorCommand;
+use crate::actor::heartbeat::{DriftGauge, HeartbeatCommand, HeartbeatEvent};
+use crate::actor::history::{HistoryQuery, HistoryReply};
+use crate::actor::watchdog::WatchdogAlert;
+use crate::envelope::{Envelope, UNSEQUENCED};
+use crate::error::{AppError, Context};
+use crate::rules::RuleSet;
+use crate::request::RequestClient;
+use crate::toggles::{Toggle, Toggles};
+
+/// How often the listener is polled for new connections.
+/// Polling keeps this actor responsive to shutdown without a dedicated blocking thread.
+const POLL_RATE: Duration = Duration::from_millis(50);
+
+/// Longest we hold an HTTP client while waiting for the history actor to answer.
+const HISTORY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Requests understood by the control endpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ControlRequest {
+    Pause,
+    /// Resume, the heartbeat emitting the beat missed while paused when `catch_up` is set.
+    Resume { catch_up: bool },
+    SetRate(u64),
+    /// Have the worker drain everything waiting without waiting for the next beat.
+    Flush,
+    /// Move the generator counter to a new position.
+    SetValue(u64),
+    /// Have the worker classify a value ahead of the generator traffic.
+    Priority(u64),
+    /// Replace the worker's rules from its next batch on.
+    SetRules(RuleSet),
+    Shutdown,
+    History(Duration),
+    SetToggle(Toggle, bool),
+    Info,
+    /// Drain and stop one pipeline of the parallel topology.
+    StopPipeline(usize),
+}
+
+/// Parses a window such as `90s`, `5m` or `1h`; a bare number is taken as seconds.
+pub(crate) fn parse_window(text: &str) -> Option<Duration> {
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(split) => text.split_at(split),
+        None => (text, "s"),
+    };
+    let number: u64 = number.parse().ok()?;
+    let seconds = match unit {
+        "s" => number,
+        "m" => number.checked_mul(60)?,
+        "h" => number.checked_mul(3600)?,
+        _ => return None,
+    };
+    Some(Duration::from_secs(seconds))
+}
+
+/// Parses the HTTP request line, e.g. `POST /set-rate?ms=250 HTTP/1.1`.
+/// Any method is accepted so the endpoint can be driven with a plain `curl <url>`.
+pub(crate) fn parse_request(request_line: &str) -> Option<ControlRequest> {
+    let target = request_line.split_whitespace().nth(1)?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    match path {
+        "/pause" => Some(ControlRequest::Pause),
+        "/resume" => match query.split('&').find_map(|pair| pair.strip_prefix("catch_up=")) {
+            None | Some("false") => Some(ControlRequest::Resume { catch_up: false }),
+            Some("true") => Some(ControlRequest::Resume { catch_up: true }),
+            Some(_) => None,
+        },
+        "/shutdown" => Some(ControlRequest::Shutdown),
+        "/flush" => Some(ControlRequest::Flush),
+        "/set-rate" => query.split('&')
+                            .find_map(|pair| pair.strip_prefix("ms="))
+                            .and_then(|ms| ms.parse().ok())
+                            .filter(|ms| *ms > 0)
+                            .map(ControlRequest::SetRate),
+        "/set-value" => query.split('&')
+                             .find_map(|pair| pair.strip_prefix("value="))
+                             .and_then(|value| value.parse().ok())
+                             .map(ControlRequest::SetValue),
+        "/priority" => query.split('&')
+                            .find_map(|pair| pair.strip_prefix("value="))
+                            .and_then(|value| value.parse().ok())
+                            .map(ControlRequest::Priority),
+        // every rule=divisor=label in order, none returns to the classic kinds
+        "/rules" => {
+            let rules = query.split('&').filter_map(|pair| pair.strip_prefix("rule=")).map(str::to_string).collect::<Vec<_>>();
+            RuleSet::from_settings(&rules).ok().map(ControlRequest::SetRules)
+        }
+        "/history" => query.split('&')
+                           .find_map(|pair| pair.strip_prefix("window="))
+                           .map_or(Some(Duration::from_secs(300)), parse_window)
+                           .map(ControlRequest::History),
+        "/toggle" => {
+            let param = |key: &str| query.split('&').find_map(|pair| pair.strip_prefix(key));
+            let toggle = Toggle::parse(param("name=")?)?;
+            let enabled = match param("state=")? {
+                "on" => true,
+                "off" => false,
+                _ => return None,
+            };
+            Some(ControlRequest::SetToggle(toggle, enabled))
+        }
+        "/info" => Some(ControlRequest::Info),
+        "/stop-pipeline" => query.split('&')
+                                 .find_map(|pair| pair.strip_prefix("index="))
+                                 .and_then(|index| index.parse().ok())
+                                 .map(ControlRequest::StopPipeline),
+        _ => None,
+    }
+}
+
+/// Control plane actor exposing a tiny HTTP server for runtime commands.
+/// Commands are forwarded over control channels so the heartbeat and generator
+/// remain the only owners of their own behavior. New rules go out on `rules_tx`, which
+/// every member of a worker pool shares.
+pub async fn run(actor: SteadyActorShadow
+                 , heartbeat_control_tx: SteadyTx<HeartbeatCommand>
+                 , generator_control_tx: SteadyTx<GeneratorCommand>
+                 , priority_tx: SteadyTx<Envelope<u64>>
+                 , rules_tx: SteadyTx<RuleSet>
+                 , history_query_tx: SteadyTx<HistoryQuery>
+                 , history_reply_rx: SteadyRx<HistoryReply>
+                 , heartbeat_events_rx: SteadyRx<HeartbeatEvent>
+                 , watchdog_rx: SteadyRx<WatchdogAlert>
+                 , toggles: Toggles
+                 , drift: DriftGauge) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&history_reply_rx, &heartbeat_events_rx, &watchdog_rx], [&heartbeat_control_tx, &generator_control_tx, &priority_tx, &rules_tx, &history_query_tx]);
+    if actor.use_internal_behavior {
+        internal_behavior(actor, heartbeat_control_tx, generator_control_tx, priority_tx, rules_tx, history_query_tx, history_reply_rx, heartbeat_events_rx, watchdog_rx, toggles, drift).await
+    } else {
+        actor.simulated_behavior(vec!(&heartbeat_control_tx, &generator_control_tx, &priority_tx, &rules_tx, &history_query_tx)).await
+    }
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , heartbeat_control_tx: SteadyTx<HeartbeatCommand>
+                                           , generator_control_tx: SteadyTx<GeneratorCommand>
+                                           , priority_tx: SteadyTx<Envelope<u64>>
+                                           , rules_tx: SteadyTx<RuleSet>
+                                           , history_query_tx: SteadyTx<HistoryQuery>
+                                           , history_reply_rx: SteadyRx<HistoryReply>
+                                           , heartbeat_events_rx: SteadyRx<HeartbeatEvent>
+                                           , watchdog_rx: SteadyRx<WatchdogAlert>
+                                           , toggles: Toggles
+                                           , drift: DriftGauge) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    // Without an address the actor stays idle, keeping the graph shape identical either way.
+    let listener = match &args.control_addr {
+        Some(addr) => {
+            let listener = TcpListener::bind(addr).map_err(AppError::Network)
+                .context(format!("binding control endpoint {}", addr))?;
+            listener.set_nonblocking(true).map_err(AppError::Network)?;
+            info!("Control endpoint listening on http://{}", addr);
+            Some(listener)
+        }
+        None => None,
+    };
+
+    let mut heartbeat_control_tx = heartbeat_control_tx.lock().await;
+    let mut generator_control_tx = generator_control_tx.lock().await;
+    let mut priority_tx = priority_tx.lock().await;
+    let mut rules_tx = rules_tx.lock().await;
+    let mut history_query_tx = history_query_tx.lock().await;
+    let mut history_reply_rx = history_reply_rx.lock().await;
+    let mut heartbeat_events_rx = heartbeat_events_rx.lock().await;
+    let mut watchdog_rx = watchdog_rx.lock().await;
+    let mut history_requests = RequestClient::new();
+    // times the heartbeat reached its beat limit with --on-beats-complete emit-event
+    let mut beats_completed = 0u64;
+    // heartbeat silences the watchdog reported, and the latest of them
+    let mut watchdog_alerts = 0u64;
+    let mut last_alert = None;
+
+    while actor.is_running(|| i!(history_reply_rx.is_closed_and_empty())
+                              && i!(heartbeat_events_rx.is_closed_and_empty())
+                              && i!(watchdog_rx.is_closed_and_empty())
+                              && i!(heartbeat_control_tx.mark_closed())
+                              && i!(generator_control_tx.mark_closed())
+                              && i!(priority_tx.mark_closed())
+                              && i!(rules_tx.mark_closed())
+                              && i!(history_query_tx.mark_closed())) {
+        await_for_all!(actor.wait_periodic(POLL_RATE));
+
+        while let Some(event) = actor.try_take(&mut heartbeat_events_rx) {
+            info!("Heartbeat event {:?}", event);
+            match event {
+                HeartbeatEvent::BeatsComplete(_) => beats_completed += 1,
+            }
+        }
+        while let Some(alert) = actor.try_take(&mut watchdog_rx) {
+            watchdog_alerts += 1;
+            last_alert = Some(alert);
+        }
+        let Some(listener) = &listener else { continue };
+        while let Ok((stream, _)) = listener.accept() {
+            let request = read_request_line(&stream).and_then(|line| parse_request(&line));
+            let (status, body) = match request {
+                Some(ControlRequest::History(window)) => {
+                    let reply = history_requests.call(&mut actor, &mut history_query_tx, &mut history_reply_rx
+                                                      , HISTORY_TIMEOUT, |id| HistoryQuery { id, window }).await;
+                    match reply {
+                        Ok(reply) => respond(stream, "200 OK", "application/json"
+                                             , &serde_json::to_string(&reply.batches)?),
+                        Err(e) => {
+                            warn!("History query failed: {:?}", e);
+                            respond(stream, "503 Service Unavailable", "text/plain", "history unavailable")
+                        }
+                    }
+                    continue;
+                }
+                Some(ControlRequest::Info) => {
+                    let calibration = args.calibration.map(|c| c.to_json());
+                    let info = serde_json::json!({ "toggles": toggles.snapshot(), "calibration": calibration
+                                                 , "beats_completed": beats_completed, "heartbeat_drift": drift.to_json()
+                                                 , "watchdog": { "alerts": watchdog_alerts
+                                                               , "last_silent_ms": last_alert.map(|alert| alert.silent_for.as_millis() as u64) } });
+                    respond(stream, "200 OK", "application/json", &info.to_string());
+                    continue;
+                }
+                Some(ControlRequest::SetToggle(toggle, enabled)) => {
+                    toggles.set(toggle, enabled);
+                    ("200 OK", "ok")
+                }
+                Some(ControlRequest::Pause) => {
+                    let sent = actor.try_send(&mut heartbeat_control_tx, HeartbeatCommand::Pause).is_sent()
+                             & actor.try_send(&mut generator_control_tx, GeneratorCommand::Pause).is_sent();
+                    accepted(sent)
+                }
+                Some(ControlRequest::Resume { catch_up }) => {
+                    let sent = actor.try_send(&mut heartbeat_control_tx, HeartbeatCommand::Resume { catch_up }).is_sent()
+                             & actor.try_send(&mut generator_control_tx, GeneratorCommand::Resume).is_sent();
+                    accepted(sent)
+                }
+                Some(ControlRequest::SetRate(ms)) => {
+                    accepted(actor.try_send(&mut heartbeat_control_tx, HeartbeatCommand::SetRate(ms)).is_sent())
+                }
+                Some(ControlRequest::Flush) => {
+                    accepted(actor.try_send(&mut heartbeat_control_tx, HeartbeatCommand::Flush).is_sent())
+                }
+                Some(ControlRequest::SetValue(_)) if args.input.is_some() => {
+                    ("409 Conflict", "--input sets the values, the counter cannot move")
+                }
+                Some(ControlRequest::SetValue(value)) => {
+                    accepted(actor.try_send(&mut generator_control_tx, GeneratorCommand::SetValue(value)).is_sent())
+                }
+                Some(ControlRequest::Priority(value)) => {
+                    // injected beside the generator stream, so it carries no sequence number of its own
+                    let envelope = Envelope::new(UNSEQUENCED, args.clock.now(), value);
+                    accepted(actor.try_send(&mut priority_tx, envelope).is_sent())
+                }
+                Some(ControlRequest::SetRules(_)) if args.route_by_kind => {
+                    ("409 Conflict", "--route-by-kind splits the classic kinds, the rules cannot change")
+                }
+                Some(ControlRequest::SetRules(rules)) => {
+                    accepted(actor.try_send(&mut rules_tx, rules).is_sent())
+                }
+                Some(ControlRequest::Shutdown) => {
+                    actor.request_shutdown().await;
+                    ("200 OK", "shutting down")
+                }
+                Some(ControlRequest::StopPipeline(_)) => ("404 Not Found", "not available in this topology"),
+                None => ("404 Not Found", "unknown command"),
+            };
+            info!("Control request {:?} -> {}", request, status);
+            respond(stream, status, "text/plain", body);
+        }
+    }
+    Ok(())
+}
+
+fn accepted(sent: bool) -> (&'static str, &'static str) {
+    if sent {
+        ("200 OK", "ok")
+    } else {
+        ("503 Service Unavailable", "control channel full")
+    }
+}
+
+pub(crate) fn read_request_line(stream: &TcpStream) -> Option<String> {
+    stream.set_nonblocking(false).ok()?;
+    stream.set_read_timeout(Some(Duration::from_secs(1))).ok()?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line).ok()?;
+    Some(line)
+}
+
+pub(crate) fn respond(mut stream: TcpStream, status: &str, content_type: &str, body: &str) {
+    let response = format!("HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}"
+                           , status, content_type, body.len(), body);
+    if let Err(e) = stream.write_all(response.as_bytes()) {
+        warn!("Control response failed: {}", e);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod control_api_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_request() {
+        assert_eq!(parse_request("POST /pause HTTP/1.1\r\n"), Some(ControlRequest::Pause));
+        assert_eq!(parse_request("GET /resume HTTP/1.1"), Some(ControlRequest::Resume { catch_up: false }));
+        assert_eq!(parse_request("GET /resume?catch_up=true HTTP/1.1"), Some(ControlRequest::Resume { catch_up: true }));
+        assert_eq!(parse_request("GET /resume?catch_up=maybe HTTP/1.1"), None);
+        assert_eq!(parse_request("GET /set-rate?ms=250 HTTP/1.1"), Some(ControlRequest::SetRate(250)));
+        assert_eq!(parse_request("GET /set-rate?ms=0 HTTP/1.1"), None);
+        assert_eq!(parse_request("GET /set-rate?ms=fast HTTP/1.1"), None);
+        assert_eq!(parse_request("POST /shutdown HTTP/1.1"), Some(ControlRequest::Shutdown));
+        assert_eq!(parse_request("POST /flush HTTP/1.1"), Some(ControlRequest::Flush));
+        assert_eq!(parse_request("POST /set-value?value=1000 HTTP/1.1"), Some(ControlRequest::SetValue(1000)));
+        assert_eq!(parse_request("POST /set-value HTTP/1.1"), None);
+        assert_eq!(parse_request("POST /priority?value=42 HTTP/1.1"), Some(ControlRequest::Priority(42)));
+        assert_eq!(parse_request("POST /priority?value=soon HTTP/1.1"), None);
+        assert_eq!(parse_request("POST /rules?rule=3=Fizz&rule=7=Bazz HTTP/1.1")
+                   , Some(ControlRequest::SetRules(RuleSet::from_settings(&["3=Fizz".to_string(), "7=Bazz".to_string()]).expect("valid rules"))));
+        assert_eq!(parse_request("POST /rules HTTP/1.1"), Some(ControlRequest::SetRules(RuleSet(None))));
+        assert_eq!(parse_request("POST /rules?rule=0=Never HTTP/1.1"), None);
+        assert_eq!(parse_request("GET /unknown HTTP/1.1"), None);
+        assert_eq!(parse_request(""), None);
+        assert_eq!(parse_request("GET /history?window=5m HTTP/1.1"), Some(ControlRequest::History(Duration::from_secs(300))));
+        assert_eq!(parse_request("GET /history HTTP/1.1"), Some(ControlRequest::History(Duration::from_secs(300))));
+        assert_eq!(parse_request("GET /history?window=5d HTTP/1.1"), None);
+        assert_eq!(parse_request("POST /toggle?name=validation&state=off HTTP/1.1"), Some(ControlRequest::SetToggle(Toggle::Validation, false)));
+        assert_eq!(parse_request("POST /toggle?state=on&name=audit HTTP/1.1"), Some(ControlRequest::SetToggle(Toggle::Audit, true)));
+        assert_eq!(parse_request("POST /toggle?name=enrichment&state=on HTTP/1.1"), None);
+        assert_eq!(parse_request("POST /toggle?name=audit HTTP/1.1"), None);
+        assert_eq!(parse_request("GET /info HTTP/1.1"), Some(ControlRequest::Info));
+        assert_eq!(parse_request("POST /stop-pipeline?index=2 HTTP/1.1"), Some(ControlRequest::StopPipeline(2)));
+        assert_eq!(parse_request("POST /stop-pipeline HTTP/1.1"), None);
+    }
+
+    #[test]
+    fn test_parse_window() {
+        assert_eq!(parse_window("90s"), Some(Duration::from_secs(90)));
+        assert_eq!(parse_window("5m"), Some(Duration::from_secs(300)));
+        assert_eq!(parse_window("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_window("42"), Some(Duration::from_secs(42)));
+        assert_eq!(parse_window("m"), None);
+        // too long to count in seconds, refused like any other bad window
+        assert_eq!(parse_window("18446744073709551615h"), None);
+        assert_eq!(parse_window("18446744073709551615m"), None);
+    }
+}
@@ -0,0 +1,199 @@
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+use steady_state::*;
+use crate::error::{AppError, Context};
+use crate::sink::Sink;
+
+/// Message describing a value the pipeline refused to process.
+/// Keeping the reason next to the value means the dead-letter file can be
+/// read on its own without cross-referencing logs from the run.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct DeadLetter {
+    pub(crate) value: u64,
+    pub(crate) reason: Reason,
+}
+
+/// Why a value was refused, spelled out in the dead letter file as `Display` writes it.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) enum Reason {
+    /// Above `--max-value`, with validation on.
+    #[default]
+    AboveMaxValue,
+    /// The transform failed on it.
+    Poisoned,
+}
+
+impl fmt::Display for Reason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Reason::AboveMaxValue => "above max value",
+            Reason::Poisoned => "transform failed",
+        })
+    }
+}
+
+/// Dead letter file, one `value,reason` line per letter.
+/// Opened in append mode so restarts of the actor never truncate
+/// the letters written before the failure.
+pub(crate) struct DeadLetterFile {
+    file: File,
+}
+
+impl DeadLetterFile {
+    pub(crate) fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(DeadLetterFile { file: OpenOptions::new().create(true).append(true).open(path)? })
+    }
+}
+
+impl Sink<DeadLetter> for DeadLetterFile {
+    fn write(&mut self, letter: &DeadLetter) -> io::Result<()> {
+        writeln!(self.file, "{},{}", letter.value, letter.reason)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Fault-isolation actor which receives unprocessable messages from the worker.
+/// Routing failures to a dedicated channel keeps the main pipeline flowing while
+/// preserving every rejected value for later inspection.
+/// `poisoned_rx` carries the values the classifier of `--topology staged` could not
+/// transform, the parser ahead of it holding the dead letter channel.
+pub async fn run(actor: SteadyActorShadow, dead_letter_rx: SteadyRx<DeadLetter>, poisoned_rx: Option<SteadyRx<DeadLetter>>) -> Result<(),Box<dyn Error>> {
+    let actor = match &poisoned_rx {
+        Some(poisoned_rx) => actor.into_spotlight([&dead_letter_rx, poisoned_rx], []),
+        None => actor.into_spotlight([&dead_letter_rx], []),
+    };
+    if actor.use_internal_behavior {
+        let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+        let sink = DeadLetterFile::open(&args.dead_letter_path).map_err(AppError::SinkIo)
+            .context(format!("opening dead letter file {}", args.dead_letter_path))?;
+        internal_behavior(actor, dead_letter_rx, poisoned_rx, sink).await
+    } else { //edge actor so we simulate it when testing from main
+        match &poisoned_rx {
+            Some(poisoned_rx) => actor.simulated_behavior(vec!(&dead_letter_rx, poisoned_rx)).await,
+            None => actor.simulated_behavior(vec!(&dead_letter_rx)).await,
+        }
+    }
+}
+
+/// Persists each dead letter to the sink, warning as it goes so rejections are visible live.
+async fn internal_behavior<A: SteadyActor, S: Sink<DeadLetter>>(mut actor: A
+                                           , rx: SteadyRx<DeadLetter>
+                                           , poisoned_rx: Option<SteadyRx<DeadLetter>>
+                                           , mut sink: S) -> Result<(),Box<dyn Error>> {
+    let mut rx = rx.lock().await;
+    let mut poisoned_rx = match &poisoned_rx {
+        Some(poisoned_rx) => Some(poisoned_rx.lock().await),
+        None => None,
+    };
+    while actor.is_running(|| i!(rx.is_closed_and_empty())
+                              && i!(poisoned_rx.as_mut().is_none_or(|rx| rx.is_closed_and_empty()))) {
+        // a closed channel is ready at once, so only the ones still open are awaited
+        match poisoned_rx.as_mut() {
+            Some(poisoned_rx) if rx.is_closed_and_empty() => { await_for_all!(actor.wait_avail(poisoned_rx, 1)); }
+            Some(poisoned_rx) if !poisoned_rx.is_closed_and_empty() => { await_for_any!(actor.wait_avail(&mut rx, 1), actor.wait_avail(poisoned_rx, 1)); }
+            _ => { await_for_all!(actor.wait_avail(&mut rx, 1)); }
+        }
+
+        while let Some(letter) = actor.try_take(&mut rx) {
+            warn!("Dead letter {} ({})", letter.value, letter.reason);
+            sink.write(&letter)?;
+        }
+        if let Some(poisoned_rx) = poisoned_rx.as_mut() {
+            while let Some(letter) = actor.try_take(poisoned_rx) {
+                warn!("Dead letter {} ({})", letter.value, letter.reason);
+                sink.write(&letter)?;
+            }
+        }
+        // flush once per drained batch rather than once per message
+        sink.flush()?;
+    }
+    Ok(())
+}
+
+/// Testing against the file system verifies the side effect this actor exists for.
+#[cfg(test)]
+pub(crate) mod dead_letter_tests {
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use super::*;
+
+    #[test]
+    fn test_dead_letter() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("standard_dead_letter_test.log");
+        let _ = std::fs::remove_file(&path);
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().build();
+
+        let sink_path = path.clone();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| {
+                let sink = DeadLetterFile::open(&sink_path).expect("open dead letter file");
+                internal_behavior(context, dead_letter_rx.clone(), None, sink)
+            }, SoloAct);
+
+        graph.start();
+        dead_letter_tx.testing_send_all(vec![DeadLetter { value: 42, reason: Reason::AboveMaxValue }], true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        assert_eq!(std::fs::read_to_string(&path)?, "42,above max value\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_dead_letter_order() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("standard_dead_letter_order_test.log");
+        let _ = std::fs::remove_file(&path);
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().with_capacity(1024).build();
+
+        let sink_path = path.clone();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| {
+                let sink = DeadLetterFile::open(&sink_path).expect("open dead letter file");
+                internal_behavior(context, dead_letter_rx.clone(), None, sink)
+            }, SoloAct);
+
+        graph.start();
+        dead_letter_tx.testing_send_all((0..1000).map(|value| DeadLetter { value, reason: Reason::AboveMaxValue }).collect(), true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // the dead letter file is strictly ordered
+        let values: Vec<u64> = std::fs::read_to_string(&path)?.lines()
+            .filter_map(|line| line.split(',').next()?.parse().ok())
+            .collect();
+        assert_eq!(values.len(), 1000);
+        crate::sink::verify_order(&values, |v| *v)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_dead_letter_fake_sink() -> Result<(), Box<dyn Error>> {
+        use crate::sink::FakeSink;
+
+        let sink = FakeSink::default();
+        let mut graph = GraphBuilder::for_testing().build(());
+        let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().build();
+
+        let actor_sink = sink.clone();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, dead_letter_rx.clone(), None, actor_sink.clone()), SoloAct);
+
+        let letters = vec![DeadLetter { value: 7, reason: Reason::AboveMaxValue }, DeadLetter { value: 9, reason: Reason::AboveMaxValue }];
+        graph.start();
+        dead_letter_tx.testing_send_all(letters.clone(), true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        assert_eq!(sink.take_records(), letters);
+        Ok(())
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{HashSet, VecDeque};
+use steady_state::*;
+use crate::envelope::Envelope;
+
+/// Sliding window of the most recently seen values, memory is bounded by `capacity`.
+/// Once full the oldest value is forgotten, so a repeat is only caught while it is
+/// still inside the window.
+pub(crate) struct SlidingWindow {
+    capacity: usize,
+    order: VecDeque<u64>,
+    seen: HashSet<u64>,
+}
+
+impl SlidingWindow {
+    pub(crate) fn new(capacity: usize) -> Self {
+        SlidingWindow { capacity, order: VecDeque::with_capacity(capacity), seen: HashSet::with_capacity(capacity) }
+    }
+
+    /// Returns true the first time a value is seen within the window.
+    pub(crate) fn insert(&mut self, value: u64) -> bool {
+        if self.capacity == 0 {
+            return true;
+        }
+        if !self.seen.insert(value) {
+            return false;
+        }
+        self.order.push_back(value);
+        if self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.seen.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+/// Stateful filter placed between generator and worker.
+/// Only values not already seen in the window are forwarded downstream,
+/// so the sequence numbers of dropped repeats show up as gaps at the logger.
+pub async fn run(actor: SteadyActorShadow
+                 , rx: SteadyRx<Envelope<u64>>
+                 , tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&rx], [&tx]), rx, tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , rx: SteadyRx<Envelope<u64>>
+                                           , tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let mut window = SlidingWindow::new(args.dedup_window.unwrap_or(0));
+    let mut dropped = 0u64;
+
+    let mut rx = rx.lock().await;
+    let mut tx = tx.lock().await;
+
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut tx, 1));
+
+        // every taken value produces at most one send so vacancy bounds the batch
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut tx));
+        for _ in 0..items {
+            if let Some(envelope) = actor.try_take(&mut rx) {
+                if window.insert(envelope.payload) {
+                    let _ = actor.try_send(&mut tx, envelope);
+                } else {
+                    dropped += 1;
+                }
+            }
+        }
+    }
+    info!("Dedup dropped {} repeated values", dropped);
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod dedup_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_dedup() -> Result<(), Box<dyn Error>> {
+        let args = MainArg { dedup_window: Some(2), ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (in_tx, in_rx) = graph.channel_builder().build();
+        let (out_tx, out_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, in_rx.clone(), out_tx.clone()), SoloAct);
+
+        // 1 repeats inside the window and is dropped, later it has aged out and passes again
+        let sent = sealed(Instant::now(), vec![1, 2, 1, 3, 4, 1]);
+        in_tx.testing_send_all(sent.clone(), true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // the repeat keeps its place in the sequence, its number is simply missing downstream
+        assert_steady_rx_eq_take!(&out_rx, [sent[0], sent[1], sent[3], sent[4], sent[5]]);
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use steady_state::*;
+use crate::actor::logger::{self, LoggerState};
+use crate::actor::worker::FizzBuzzMessage;
+use crate::crypto::{self, CryptoError, KeyRing};
+use crate::envelope::Envelope;
+use crate::error::{AppError, Context};
+use crate::sink::Sink;
+
+/// Append-only file with size based rotation.
+/// When the next write would exceed the limit the active file becomes `path.1`,
+/// older files shift up by one and anything beyond `keep` is deleted.
+pub(crate) struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    written: u64,
+    file: File,
+}
+
+impl RotatingFile {
+    pub(crate) fn open(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        // continue counting from the existing size so a restart does not overrun the limit
+        let written = file.metadata()?.len();
+        Ok(RotatingFile { path, max_bytes, keep, written, file })
+    }
+
+    pub(crate) fn write_line(&mut self, line: &str) -> io::Result<()> {
+        let len = line.len() as u64 + 1;
+        if self.written > 0 && self.written + len > self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.written += len;
+        Ok(())
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.keep == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.keep));
+            for index in (1..self.keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// Rotating file sink, optionally sealing every line with the payload keyring.
+pub(crate) struct FileSink {
+    file: RotatingFile,
+    keys: Option<KeyRing>,
+}
+
+impl FileSink {
+    pub(crate) fn from_args(args: &crate::MainArg) -> Result<Self, Box<dyn Error>> {
+        let path = args.out_file.clone().expect("file logger requires --out-file");
+        Self::open(&path, args)
+    }
+
+    /// The same lines in `path`, rotated and sealed as the --out-file ones, for `--log-sink file=PATH`.
+    pub(crate) fn open(path: &str, args: &crate::MainArg) -> Result<Self, Box<dyn Error>> {
+        let file = RotatingFile::open(path, args.rotate_bytes, args.rotate_keep).map_err(AppError::SinkIo)
+            .context(format!("opening output file {}", path))?;
+        // Optional payload encryption so the file stays confidential at rest, independent of transport.
+        let keys = match &args.payload_keys {
+            Some(key_file) => Some(KeyRing::parse(&fs::read_to_string(key_file)?)?),
+            None => None,
+        };
+        Ok(FileSink { file, keys })
+    }
+}
+
+impl Sink<FizzBuzzMessage> for FileSink {
+    fn write(&mut self, msg: &FizzBuzzMessage) -> io::Result<()> {
+        let line = format!("{:?}", msg);
+        match &self.keys {
+            Some(keys) => self.file.write_line(&crypto::to_hex(&keys.seal(line.as_bytes()))),
+            None => self.file.write_line(&line),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Lines of an `--out-file` file sealed with the payload keys, in the order they were written.
+/// Any key still in the ring opens its lines, so files rotated out before a key rotation
+/// stay readable as long as their key stays in the key file.
+pub(crate) fn open_archive(path: impl AsRef<Path>, keys: &KeyRing) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut lines = Vec::new();
+    for (index, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let opened = crypto::from_hex(line).ok_or(CryptoError::Malformed)
+            .and_then(|sealed| keys.open(&sealed))
+            .map_err(|e| format!("line {}: {}", index + 1, e))?;
+        lines.push(String::from_utf8(opened)?);
+    }
+    Ok(lines)
+}
+
+/// Prints the lines of the sealed archive at `path`, opened with the `--payload-keys` ring.
+pub(crate) fn print_archive(args: &crate::MainArg, path: &str) -> Result<(), Box<dyn Error>> {
+    let key_file = args.payload_keys.as_ref().ok_or("opening an archive needs the --payload-keys it was sealed with")?;
+    let keys = KeyRing::parse(&fs::read_to_string(key_file)?)?;
+    for line in open_archive(path, &keys)? {
+        println!("{}", line);
+    }
+    Ok(())
+}
+
+/// File sink variant of the logger intended for long soak runs.
+/// Output lands on disk with bounded growth instead of scrolling past on the console.
+/// The consuming loop is the logger's own, only the sink differs.
+pub async fn run(actor: SteadyActorShadow, fizz_buzz_rx: SteadyRx<Envelope<FizzBuzzMessage>>, state: SteadyState<LoggerState>) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&fizz_buzz_rx], []);
+    if actor.use_internal_behavior {
+        let sink = FileSink::from_args(actor.args::<crate::MainArg>().expect("unable to downcast"))?;
+        logger::internal_behavior(actor, fizz_buzz_rx, state, sink).await
+    } else {
+        actor.simulated_behavior(vec!(&fizz_buzz_rx)).await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod file_logger_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_file_logger_rotation() -> Result<(), Box<dyn Error>> {
+        let dir = std::env::temp_dir().join("standard_file_logger_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("out.log");
+        // each "Fizz" line is 5 bytes so only two lines fit per file
+        let args = MainArg { out_file: Some(path.to_string_lossy().to_string())
+                           , rotate_bytes: 10
+                           , rotate_keep: 2
+                           , ..MainArg::default() };
+
+        let sink_args = args.clone();
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| {
+                let sink = FileSink::from_args(&sink_args).expect("open sink");
+                logger::internal_behavior(context, fizz_buzz_rx.clone(), new_state(), sink)
+            }, SoloAct);
+
+        graph.start();
+        fizz_buzz_tx.testing_send_all(sealed(Instant::now(), vec![FizzBuzzMessage::Fizz; 7]), true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // 7 lines: two rotated files of two lines each are kept, the oldest was dropped
+        assert_eq!(fs::read_to_string(&path)?, "Fizz\n");
+        assert_eq!(fs::read_to_string(dir.join("out.log.1"))?, "Fizz\nFizz\n");
+        assert_eq!(fs::read_to_string(dir.join("out.log.2"))?, "Fizz\nFizz\n");
+        assert!(!dir.join("out.log.3").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_archive_after_key_rotation() -> Result<(), Box<dyn Error>> {
+        let dir = std::env::temp_dir().join(format!("standard_open_archive_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let (path, key_file) = (dir.join("out.log"), dir.join("keys.txt"));
+        // a sealed "Fizz" line is 73 bytes, so the third line rotates the first two out
+        let args = MainArg { out_file: Some(path.to_string_lossy().to_string())
+                           , payload_keys: Some(key_file.to_string_lossy().to_string())
+                           , rotate_bytes: 150
+                           , rotate_keep: 2
+                           , ..MainArg::default() };
+
+        fs::write(&key_file, format!("1={}\n", crypto::to_hex(&[7u8; 32])))?;
+        let mut sink = FileSink::from_args(&args)?;
+        sink.write(&FizzBuzzMessage::Fizz)?;
+        sink.write(&FizzBuzzMessage::Buzz)?;
+        sink.flush()?;
+        drop(sink);
+
+        // the keys are rotated, the file logger restarts sealing with key 2
+        fs::write(&key_file, format!("1={}\n2={}\n", crypto::to_hex(&[7u8; 32]), crypto::to_hex(&[9u8; 32])))?;
+        let mut sink = FileSink::from_args(&args)?;
+        sink.write(&FizzBuzzMessage::FizzBuzz)?;
+        sink.flush()?;
+
+        let keys = KeyRing::parse(&fs::read_to_string(&key_file)?)?;
+        let archived = dir.join("out.log.1");
+        assert_eq!(open_archive(&archived, &keys)?, vec!["Fizz", "Buzz"]);
+        assert_eq!(open_archive(&path, &keys)?, vec!["FizzBuzz"]);
+        // without the retired key the old archive stays sealed
+        let newest_only = KeyRing::parse(&format!("2={}\n", crypto::to_hex(&[9u8; 32])))?;
+        assert_eq!(open_archive(&archived, &newest_only).map_err(|e| e.to_string()), Err("line 1: no key with id 1".to_string()));
+        // plain text is not an archive
+        fs::write(&archived, "Fizz\n")?;
+        assert_eq!(open_archive(&archived, &keys).map_err(|e| e.to_string()), Err("line 1: malformed envelope".to_string()));
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_logger_order() -> Result<(), Box<dyn Error>> {
+        let dir = std::env::temp_dir().join("standard_file_logger_order_test");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("out.log");
+        let args = MainArg { out_file: Some(path.to_string_lossy().to_string()), ..MainArg::default() };
+
+        let sink_args = args.clone();
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().with_capacity(1024).build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| {
+                let sink = FileSink::from_args(&sink_args).expect("open sink");
+                logger::internal_behavior(context, fizz_buzz_rx.clone(), new_state(), sink)
+            }, SoloAct);
+
+        // plain values only, so every line carries its sequence number
+        let values: Vec<u64> = (0..1000).filter(|v| v % 3 != 0 && v % 5 != 0).collect();
+        graph.start();
+        fizz_buzz_tx.testing_send_all(sealed(Instant::now(), values.iter().map(|v| FizzBuzzMessage::Value(*v))), true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // the file is strictly ordered: nothing lost and nothing out of place
+        let logged: Vec<u64> = fs::read_to_string(&path)?.lines()
+            .filter_map(|line| line.strip_prefix("Value(")?.strip_suffix(')')?.parse().ok())
+            .collect();
+        assert_eq!(logged.len(), values.len());
+        crate::sink::verify_order(&logged, |v| *v)?;
+        Ok(())
+    }
+}
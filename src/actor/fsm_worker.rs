@@ -0,0 +1,354 @@
+use std::collections::VecDeque;
+use steady_state::*;
+use crate::actor::batch_report::BatchReport;
+use crate::actor::dead_letter::DeadLetter;
+use crate::actor::heartbeat::Pulse;
+use crate::actor::history::BatchStats;
+use crate::actor::worker::{classify, reload, BatchTally, FizzBuzzMessage, Transform};
+use crate::envelope::Envelope;
+use crate::rules::{ActiveRules, RuleSet, Rules};
+use crate::toggles::{Toggle, Toggles};
+
+/// Where the worker stands between two flushes.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) enum Phase {
+    /// Nothing held, waiting for the first value.
+    #[default]
+    Idle,
+    /// Holding values until the beat, --fsm-flush of them or the end of the run.
+    Collecting,
+    /// Classifying and sending what is held, resumed as it was after a restart.
+    Flushing,
+}
+
+/// What moves the machine from one phase to the next.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Event {
+    /// Values were taken.
+    Arrived,
+    /// A heartbeat pulse came.
+    Beat,
+    /// --fsm-flush values are held.
+    Full,
+    /// Every input is closed and empty, what is held goes out now.
+    Drain,
+    /// Everything held was sent.
+    Flushed,
+}
+
+impl Phase {
+    /// The transition table, any event not listed leaves the phase as it is.
+    pub(crate) fn on(self, event: Event) -> Phase {
+        match (self, event) {
+            (Phase::Idle, Event::Arrived) => Phase::Collecting,
+            (Phase::Collecting, Event::Beat | Event::Full | Event::Drain) => Phase::Flushing,
+            (Phase::Flushing, Event::Flushed) => Phase::Idle,
+            (phase, _) => phase,
+        }
+    }
+}
+
+/// The machine kept in a `SteadyState`: its phase and the values it holds, so a restart
+/// picks up in the phase it failed in, still holding every value not yet sent.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct MachineState {
+    pub(crate) phase: Phase,
+    pub(crate) pending: VecDeque<Envelope<u64>>,
+    /// Heartbeat which ended the collecting, None for a full buffer or the drain.
+    pub(crate) beat: Option<u64>,
+    pub(crate) flushes: u64,
+}
+
+impl MachineState {
+    /// Applies one event, logging the transition when the phase changes.
+    fn step(&mut self, event: Event) {
+        let next = self.phase.on(event);
+        if next != self.phase {
+            info!("Worker machine {:?} -> {:?} on {:?} holding {} values", self.phase, next, event, self.pending.len());
+            self.phase = next;
+        }
+    }
+}
+
+/// Waits for a value on `rx`, or forever once it is closed and empty: a closed channel would
+/// end every wait at once, so only the open inputs can wake the machine.
+async fn wait_open<A: SteadyActor, T>(actor: &A, rx: &mut Rx<T>) -> bool {
+    if rx.is_closed_and_empty() {
+        std::future::pending::<bool>().await
+    } else {
+        actor.wait_avail(rx, 1).await
+    }
+}
+
+/// Worker modeled as an explicit state machine, selected with --fsm-flush: Idle until values
+/// arrive, Collecting them until the beat or --fsm-flush of them, then Flushing everything held
+/// to the logger before going Idle again. Stands where the worker does towards every channel.
+pub async fn run(actor: SteadyActorShadow
+                 , heartbeat_rx: SteadyRx<Pulse>
+                 , generator_rx: SteadyRx<Envelope<u64>>
+                 , priority_rx: SteadyRx<Envelope<u64>>
+                 , rules_rx: SteadyRx<RuleSet>
+                 , logger_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , dead_letter_tx: SteadyTx<DeadLetter>
+                 , stats_tx: SteadyTx<BatchStats>
+                 , report_tx: SteadyTx<BatchReport>
+                 , backlog_tx: SteadyTx<usize>
+                 , state: SteadyState<MachineState>
+                 , toggles: Toggles
+                 , rules: Option<Rules>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors like the worker
+    let actor = actor.into_spotlight([&heartbeat_rx, &generator_rx, &priority_rx, &rules_rx], [&logger_tx, &dead_letter_tx, &stats_tx, &report_tx, &backlog_tx]);
+    internal_behavior(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_tx, dead_letter_tx, stats_tx, report_tx, backlog_tx, state, toggles, ActiveRules(rules)).await
+}
+
+async fn internal_behavior<A: SteadyActor, T: Transform<u64, FizzBuzzMessage>>(mut actor: A
+                                           , heartbeat_rx: SteadyRx<Pulse>
+                                           , generator_rx: SteadyRx<Envelope<u64>>
+                                           , priority_rx: SteadyRx<Envelope<u64>>
+                                           , rules_rx: SteadyRx<RuleSet>
+                                           , logger_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , dead_letter_tx: SteadyTx<DeadLetter>
+                                           , stats_tx: SteadyTx<BatchStats>
+                                           , report_tx: SteadyTx<BatchReport>
+                                           , backlog_tx: SteadyTx<usize>
+                                           , state: SteadyState<MachineState>
+                                           , toggles: Toggles
+                                           , mut transform: T) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let flush_at = args.fsm_flush.unwrap_or(crate::actor::worker::WORKER_SLICE as u64) as usize;
+    let max_value = args.max_value.unwrap_or(u64::MAX);
+    let clock = args.clock.clone();
+
+    let mut heartbeat_rx = heartbeat_rx.lock().await;
+    let mut generator_rx = generator_rx.lock().await;
+    let mut priority_rx = priority_rx.lock().await;
+    let mut rules_rx = rules_rx.lock().await;
+    let mut logger_tx = logger_tx.lock().await;
+    let mut dead_letter_tx = dead_letter_tx.lock().await;
+    let mut stats_tx = stats_tx.lock().await;
+    let mut report_tx = report_tx.lock().await;
+    let mut backlog_tx = backlog_tx.lock().await;
+    let mut state = state.lock(MachineState::default).await;
+    if state.phase != Phase::Idle {
+        info!("Worker machine resuming {:?} holding {} values", state.phase, state.pending.len());
+    }
+
+    // nothing held may be left behind, the machine only stops once it is Idle and empty
+    while actor.is_running(|| i!(heartbeat_rx.is_closed_and_empty())
+                              && i!(generator_rx.is_closed_and_empty())
+                              && i!(priority_rx.is_closed_and_empty())
+                              && i!(rules_rx.is_closed_and_empty())
+                              && i!(state.pending.is_empty())
+                              && i!(logger_tx.mark_closed())
+                              && i!(dead_letter_tx.mark_closed())
+                              && i!(stats_tx.mark_closed())
+                              && i!(report_tx.mark_closed())
+                              && i!(backlog_tx.mark_closed())) {
+        match state.phase {
+            Phase::Idle | Phase::Collecting => {
+                if heartbeat_rx.is_closed_and_empty() {
+                    // as for the worker, a one-shot heartbeat means no flush until the shutdown drain
+                    actor.wait_shutdown().await;
+                }
+                if heartbeat_rx.is_closed_and_empty() && generator_rx.is_closed_and_empty() && priority_rx.is_closed_and_empty() {
+                    // nothing more can arrive
+                    if !state.pending.is_empty() {
+                        state.beat = None;
+                        state.step(Event::Drain);
+                    }
+                    continue;
+                }
+                // at least one input is open, and its wait ends on a shutdown too
+                await_for_any!(wait_open(&actor, &mut heartbeat_rx)
+                             , wait_open(&actor, &mut generator_rx)
+                             , wait_open(&actor, &mut priority_rx));
+
+                // priority values first, never holding more than one flush
+                for rx in [&mut priority_rx, &mut generator_rx] {
+                    let room = flush_at.saturating_sub(state.pending.len());
+                    for _ in 0..actor.avail_units(rx).min(room) {
+                        if let Some(item) = actor.try_take(rx) {
+                            state.pending.push_back(item);
+                        }
+                    }
+                }
+                if !state.pending.is_empty() {
+                    state.step(Event::Arrived);
+                }
+                // a full buffer leaves the pulse queued, it flushes whatever arrives next
+                if state.pending.len() >= flush_at {
+                    state.beat = None;
+                    state.step(Event::Full);
+                } else if let Some(pulse) = actor.try_take(&mut heartbeat_rx) {
+                    state.beat = match pulse {
+                        Pulse::Beat(heartbeat) => Some(heartbeat.beat),
+                        Pulse::Flush | Pulse::LastBeat => None,
+                    };
+                    state.step(Event::Beat);
+                }
+            }
+            Phase::Flushing => {
+                let mut latest = None;
+                while let Some(rules) = actor.try_take(&mut rules_rx) {
+                    latest = Some(rules);
+                }
+                reload(&mut transform, latest, state.beat);
+                let started = clock.now();
+                let validate = toggles.is_enabled(Toggle::Validation);
+                let mut tally = BatchTally::default();
+                // send, then let go: a restart mid-flush resends only what was not yet sent
+                while let Some(item) = state.pending.front().copied() {
+                    let sent = match classify(item, validate, max_value, &mut transform, &mut tally) {
+                        Ok(msg) => actor.send_async(&mut logger_tx, msg, SendSaturation::AwaitForRoom).await.is_sent(),
+                        Err(letter) => actor.send_async(&mut dead_letter_tx, letter, SendSaturation::AwaitForRoom).await.is_sent(),
+                    };
+                    if !sent {
+                        // cut short by a shutdown, the machine stays Flushing and tries again
+                        break;
+                    }
+                    state.pending.pop_front();
+                }
+                if state.pending.is_empty() {
+                    state.flushes += 1;
+                    let _ = actor.try_send(&mut stats_tx, tally.stats(state.beat));
+                    let _ = actor.try_send(&mut report_tx, tally.report(clock.since(started)));
+                    let backlog = actor.avail_units(&mut generator_rx);
+                    let _ = actor.try_send(&mut backlog_tx, backlog);
+                    state.step(Event::Flushed);
+                }
+            }
+        }
+    }
+    info!("Worker machine flushed {} times", state.flushes);
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod fsm_worker_tests {
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::time::{Instant, SystemTime};
+    use steady_state::*;
+    use crate::actor::heartbeat::Heartbeat;
+    use crate::actor::worker::FizzBuzz;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_transitions() {
+        assert_eq!(Phase::Idle.on(Event::Arrived), Phase::Collecting);
+        // a beat with nothing held has nothing to flush
+        assert_eq!(Phase::Idle.on(Event::Beat), Phase::Idle);
+        for event in [Event::Beat, Event::Full, Event::Drain] {
+            assert_eq!(Phase::Collecting.on(event), Phase::Flushing);
+        }
+        assert_eq!(Phase::Collecting.on(Event::Arrived), Phase::Collecting);
+        // values arriving mid-flush wait for the next round
+        assert_eq!(Phase::Flushing.on(Event::Arrived), Phase::Flushing);
+        assert_eq!(Phase::Flushing.on(Event::Flushed), Phase::Idle);
+    }
+
+    /// The test side of the channels around one machine, the outputs other than the logger ignored.
+    struct Harness {
+        generate_tx: SteadyTx<Envelope<u64>>,
+        priority_tx: SteadyTx<Envelope<u64>>,
+        rules_tx: SteadyTx<RuleSet>,
+        heartbeat_tx: SteadyTx<Pulse>,
+        logger_rx: SteadyRx<Envelope<FizzBuzzMessage>>,
+    }
+
+    impl Harness {
+        fn spawn<T: Transform<u64, FizzBuzzMessage> + Clone + Sync + 'static>(graph: &mut Graph, transform: T) -> Self {
+            let (generate_tx, generate_rx) = graph.channel_builder().build();
+            let (priority_tx, priority_rx) = graph.channel_builder().build();
+            let (rules_tx, rules_rx) = graph.channel_builder().build::<RuleSet>();
+            let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+            let (logger_tx, logger_rx) = graph.channel_builder().build::<Envelope<FizzBuzzMessage>>();
+            let (dead_letter_tx, _dead_letter_rx) = graph.channel_builder().build::<DeadLetter>();
+            let (stats_tx, _stats_rx) = graph.channel_builder().build::<BatchStats>();
+            let (report_tx, _report_rx) = graph.channel_builder().build::<BatchReport>();
+            let (backlog_tx, _backlog_rx) = graph.channel_builder().build::<usize>();
+            let state = new_state();
+
+            graph.actor_builder().with_name("UnitTest")
+                .build(move |context| internal_behavior(context, heartbeat_rx.clone(), generate_rx.clone(), priority_rx.clone(), rules_rx.clone()
+                                                        , logger_tx.clone(), dead_letter_tx.clone(), stats_tx.clone(), report_tx.clone()
+                                                        , backlog_tx.clone(), state.clone(), Toggles::default(), transform.clone())
+                       , SoloAct);
+            Harness { generate_tx, priority_tx, rules_tx, heartbeat_tx, logger_rx }
+        }
+
+        /// Sends `values` from the generator, `rules` and one beat, closing every input.
+        fn send(&self, captured: Instant, values: impl IntoIterator<Item = u64>, rules: Vec<RuleSet>) {
+            self.generate_tx.testing_send_all(sealed(captured, values), true);
+            self.priority_tx.testing_send_all(vec![], true);
+            self.rules_tx.testing_send_all(rules, true);
+            self.heartbeat_tx.testing_send_all(vec![Pulse::Beat(Heartbeat::on_time(0, SystemTime::now()))], true);
+        }
+    }
+
+    #[test]
+    fn test_fsm_worker() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg { fsm_flush: Some(3), ..MainArg::default() });
+        let machine = Harness::spawn(&mut graph, FizzBuzz);
+
+        let captured = Instant::now();
+        machine.send(captured, 1..=7, vec![]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // two full flushes of three, the last value going out on the beat or the drain, all in order
+        assert_steady_rx_eq_take!(&machine.logger_rx, sealed(captured, (1..=7).map(FizzBuzzMessage::new)));
+        assert_in_logs!(["Worker machine Idle -> Collecting on Arrived holding 3 values"
+                       , "Worker machine Collecting -> Flushing on Full holding 3 values"
+                       , "Worker machine Flushing -> Idle on Flushed holding 0 values"
+                       , "Worker machine flushed 3 times"]);
+        Ok(())
+    }
+
+    /// Classic rule which fails the first time it is handed rules, outside the guard `classify`
+    /// puts around each value, so the machine fails as its flush begins.
+    #[derive(Clone)]
+    struct FailOnReload(Arc<AtomicBool>);
+
+    impl Transform<u64, FizzBuzzMessage> for FailOnReload {
+        fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+            FizzBuzzMessage::new(value)
+        }
+
+        fn reload(&mut self, _rules: &RuleSet) -> Result<(), String> {
+            if self.0.swap(false, Ordering::SeqCst) {
+                panic!("deliberate worker failure reloading");
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_fsm_worker_restart() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg { fsm_flush: Some(3), ..MainArg::default() });
+        let machine = Harness::spawn(&mut graph, FailOnReload(Arc::new(AtomicBool::new(true))));
+
+        let captured = Instant::now();
+        machine.send(captured, 1..=4, vec![RuleSet::from_settings(&[]).expect("no rules is valid")]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // the restarted machine is still Flushing the three it held, and sends each value once
+        assert_steady_rx_eq_take!(&machine.logger_rx, sealed(captured, (1..=4).map(FizzBuzzMessage::new)));
+        assert_in_logs!(["Worker machine Collecting -> Flushing on Full holding 3 values"
+                       , "Worker machine resuming Flushing holding 3 values"
+                       , "Worker machine Flushing -> Idle on Flushed holding 0 values"
+                       , "Worker machine flushed 2 times"]);
+        Ok(())
+    }
+}
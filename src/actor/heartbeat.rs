@@ -1,20 +1,258 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Instant, SystemTime};
 use steady_state::*;
+use crate::actor::generator::next_random;
+use crate::arg::{CatchupPolicy, OnBeatsComplete};
+use crate::clock::Clock;
 
 /// Persistent counter-state that survives actor restarts.
 /// Heartbeat actors maintain timing consistency across failures.
 pub(crate) struct HeartbeatState {
-    pub(crate) count: u64
+    pub(crate) count: u64,
+    /// Paused by command or by reaching the beat limit, still paused after a restart.
+    pub(crate) paused: bool,
+    /// Wall-clock reading when the pause began, so a resume after a restart still knows
+    /// whether a beat fell due while paused.
+    pub(crate) paused_at: Option<Duration>,
+    /// Jitter generator position, seeded from --seed and the process id, so a restart carries
+    /// on with the same offsets instead of repeating them.
+    pub(crate) rng: u64,
+    /// Wall-clock time the last beat went out, None until the first. Found set when the
+    /// actor starts, it tells a restart how long the heartbeat was down.
+    pub(crate) last_beat: Option<Duration>,
+}
+
+/// One beat: its number, when it was due and when it actually went out, both on the
+/// wall clock, so consumers can measure how closely the heartbeat keeps to its schedule.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Heartbeat {
+    pub(crate) beat: u64,
+    pub(crate) scheduled: SystemTime,
+    pub(crate) actual: SystemTime,
+}
+
+impl Heartbeat {
+    /// A beat sent exactly when it was due, for sources without a schedule of their own.
+    pub(crate) fn on_time(beat: u64, at: SystemTime) -> Self {
+        Heartbeat { beat, scheduled: at, actual: at }
+    }
+
+    /// How late the beat went out, zero when it was early.
+    pub(crate) fn skew(&self) -> Duration {
+        self.actual.duration_since(self.scheduled).unwrap_or_default()
+    }
+}
+
+/// Interval accuracy of one beat: the time since the previous beat it was scheduled for
+/// and the time that actually passed between the two.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct TimingReport {
+    pub(crate) expected: Duration,
+    pub(crate) actual: Duration,
+}
+
+impl TimingReport {
+    /// Report for `beat` following `previous`.
+    pub(crate) fn between(previous: &Heartbeat, beat: &Heartbeat) -> Self {
+        TimingReport { expected: beat.scheduled.duration_since(previous.scheduled).unwrap_or_default()
+                     , actual: beat.actual.duration_since(previous.actual).unwrap_or_default() }
+    }
+
+    /// How far the actual interval was off the expected one, either way.
+    pub(crate) fn error(&self) -> Duration {
+        self.actual.abs_diff(self.expected)
+    }
+}
+
+/// What the heartbeat tells the worker: a protocol rather than a bare counter, so the worker
+/// can tell a regular batch from a request to drain and from the last beat before shutdown.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Pulse {
+    /// A scheduled beat, one batch of work.
+    Beat(Heartbeat),
+    /// Drain everything waiting now, without waiting for the next beat.
+    Flush,
+    /// No beat follows, drain everything before the shutdown the heartbeat requests next.
+    LastBeat,
+}
+
+/// Runtime commands accepted on the heartbeat control channel.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum HeartbeatCommand {
+    Pause,
+    /// Beats start again an interval after the resume, or with `catch_up` at once when
+    /// one fell due while paused, so downstream processes the batch it missed without delay.
+    Resume { catch_up: bool },
+    SetRate(u64), // new period in milliseconds
+    /// Pass a `Pulse::Flush` on to the worker.
+    Flush,
+}
+
+/// Events the heartbeat publishes for other actors to react to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum HeartbeatEvent {
+    /// The beat limit was reached, carrying the number of beats sent so far.
+    BeatsComplete(u64),
+}
+
+/// Heartbeat scheduling accuracy, shared with the control endpoint which reports it at `/info`
+/// and with the watchdog.
+/// Beats are due at absolute deadlines, the previous deadline plus the interval, so the
+/// lateness of the last beat stays small however long the run. `accumulated` adds up the
+/// lateness of every beat: the drift scheduling each beat from the one before would have built up.
+/// `interval_us` is the interval the heartbeat is on now, which the watchdog alerts against.
+#[derive(Clone, Default)]
+pub(crate) struct DriftGauge {
+    last_us: Arc<AtomicU64>,
+    max_us: Arc<AtomicU64>,
+    accumulated_us: Arc<AtomicU64>,
+    interval_us: Arc<AtomicU64>,
+}
+
+impl DriftGauge {
+    /// Records how long after its deadline a beat went out.
+    pub(crate) fn record(&self, late: Duration) {
+        let late = late.as_micros() as u64;
+        self.last_us.store(late, Ordering::Relaxed);
+        self.max_us.fetch_max(late, Ordering::Relaxed);
+        self.accumulated_us.fetch_add(late, Ordering::Relaxed);
+    }
+
+    /// Records the interval to the next beat, after any `SetRate`, backlog pacing or jitter.
+    pub(crate) fn set_interval(&self, interval: Duration) {
+        self.interval_us.store(interval.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// The interval the heartbeat is on, None until it has started.
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        match self.interval_us.load(Ordering::Relaxed) {
+            0 => None,
+            us => Some(Duration::from_micros(us)),
+        }
+    }
+
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "last_us": self.last_us.load(Ordering::Relaxed)
+                          , "max_us": self.max_us.load(Ordering::Relaxed)
+                          , "accumulated_us": self.accumulated_us.load(Ordering::Relaxed)
+                          , "interval_us": self.interval_us.load(Ordering::Relaxed) })
+    }
+}
+
+/// An extra timer driven by the heartbeat on a channel of its own, numbering its ticks
+/// from zero. One heartbeat serves every rate the graph needs, a fast tick for batching
+/// and a slow one closing windows, instead of a timer actor per rate.
+/// Ticks follow pause and resume but not `SetRate` or `--beats`, and one which finds its
+/// channel full is skipped rather than holding up the others.
+#[derive(Clone)]
+pub(crate) struct Tick {
+    pub(crate) rate: Duration,
+    pub(crate) tx: SteadyTx<u64>,
+}
+
+/// How many times faster the heartbeat beats while the generator backlog is high.
+const BACKLOG_SPEEDUP: u32 = 4;
+
+/// Whether the heartbeat should beat fast given the latest generator backlog: it speeds up
+/// above the threshold and relaxes only once the backlog is under half of it, so a backlog
+/// hovering at the threshold does not flip the rate on every report.
+pub(crate) fn draining(draining: bool, backlog: usize, threshold: usize) -> bool {
+    match draining {
+        false => backlog > threshold,
+        true => backlog >= threshold / 2,
+    }
+}
+
+/// The period between beats, `rate` shortened while draining a backlog.
+fn paced(rate: Duration, draining: bool) -> Duration {
+    if draining { rate / BACKLOG_SPEEDUP } else { rate }
+}
+
+/// Index of the `period` long wall-clock slot containing `wall`.
+/// Every instance aligned on the same period numbers its beats by slot, so the same
+/// beat number means the same span of wall time in every pipeline.
+pub(crate) fn slot(wall: Duration, period: Duration) -> u64 {
+    (wall.as_nanos() / period.as_nanos().max(1)) as u64
+}
+
+/// Wall-clock time at which `slot` starts.
+fn slot_start(slot: u64, period: Duration) -> Duration {
+    Duration::from_nanos((slot as u128 * period.as_nanos()).min(u64::MAX as u128) as u64)
+}
+
+/// Time from `wall` to the start of the next slot.
+pub(crate) fn until_next_slot(wall: Duration, period: Duration) -> Duration {
+    let period_nanos = period.as_nanos().max(1);
+    Duration::from_nanos((period_nanos - wall.as_nanos() % period_nanos) as u64)
+}
+
+/// The latest deadline not after now on the grid `interval` apart through `due`, for
+/// phase-locked beats skipping the deadlines they missed without drifting off the grid.
+fn latest_deadline(clock: &Clock, due: Instant, interval: Duration) -> Instant {
+    let behind = clock.since(due).as_nanos();
+    due + Duration::from_nanos((behind - behind % interval.as_nanos().max(1)) as u64)
+}
+
+/// `rate` moved by a random offset within ±`jitter`, never below zero.
+pub(crate) fn jittered(rate: Duration, jitter: Duration, rng: &mut u64) -> Duration {
+    if jitter.is_zero() {
+        return rate;
+    }
+    let jitter_nanos = jitter.as_nanos().min(u64::MAX as u128 / 2) as u64;
+    let offset = Duration::from_nanos(next_random(rng) % (2 * jitter_nanos + 1));
+    (rate + offset).saturating_sub(Duration::from_nanos(jitter_nanos))
+}
+
+/// Jitter seed of the process `pid`: a fleet started with one --seed still jitters apart,
+/// each instance running under its own pid.
+pub(crate) fn jitter_seed(seed: u64, pid: u32) -> u64 {
+    seed ^ u64::from(pid).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+}
+
+/// Time until the next beat is due: `interval` after the deadline of the last beat, or when
+/// aligned, the start of the next wall-clock slot after the one the last beat fired in.
+fn until_due(clock: &Clock, align: bool, interval: Duration, last_due: Instant, last_slot: u64) -> Duration {
+    if !align {
+        interval.saturating_sub(clock.since(last_due))
+    } else if slot(clock.wall(), interval) > last_slot {
+        Duration::ZERO
+    } else {
+        until_next_slot(clock.wall(), interval)
+    }
 }
 
 /// Entry point demonstrating simulation conditional for full graph testing
 pub async fn run(actor: SteadyActorShadow
-                 , heartbeat_tx: SteadyTx<u64>
-                 , state: SteadyState<HeartbeatState>) -> Result<(),Box<dyn Error>> {
-    let actor = actor.into_spotlight([], [&heartbeat_tx]);
-    if actor.use_internal_behavior {
-        internal_behavior(actor, heartbeat_tx, state).await
-    } else {
-        actor.simulated_behavior(vec!(&heartbeat_tx)).await
+                 , control_rx: SteadyRx<HeartbeatCommand>
+                 , backlog_rx: SteadyRx<usize>
+                 , heartbeat_tx: SteadyTx<Pulse>
+                 , subscribers: Vec<SteadyTx<Heartbeat>>
+                 , timing_tx: SteadyTx<TimingReport>
+                 , events_tx: SteadyTx<HeartbeatEvent>
+                 , state: SteadyState<HeartbeatState>
+                 , drift: DriftGauge
+                 , ticks: Vec<Tick>) -> Result<(),Box<dyn Error>> {
+    // The spotlight takes its channels as fixed arrays: the metrics and watchdog copies are
+    // always there, the windower's only with --window-count or --window-beats.
+    match subscribers.as_slice() {
+        [metrics_tx, watchdog_tx] => {
+            let actor = actor.into_spotlight([&control_rx, &backlog_rx], [&heartbeat_tx, metrics_tx, watchdog_tx, &timing_tx, &events_tx]);
+            if actor.use_internal_behavior {
+                internal_behavior(actor, control_rx, backlog_rx, heartbeat_tx, subscribers, timing_tx, events_tx, state, drift, ticks).await
+            } else {
+                actor.simulated_behavior(vec!(&heartbeat_tx, metrics_tx, watchdog_tx, &timing_tx, &events_tx)).await
+            }
+        }
+        [metrics_tx, watchdog_tx, window_tx] => {
+            let actor = actor.into_spotlight([&control_rx, &backlog_rx], [&heartbeat_tx, metrics_tx, watchdog_tx, window_tx, &timing_tx, &events_tx]);
+            if actor.use_internal_behavior {
+                internal_behavior(actor, control_rx, backlog_rx, heartbeat_tx, subscribers, timing_tx, events_tx, state, drift, ticks).await
+            } else {
+                actor.simulated_behavior(vec!(&heartbeat_tx, metrics_tx, watchdog_tx, window_tx, &timing_tx, &events_tx)).await
+            }
+        }
+        _ => Err(format!("the heartbeat feeds two or three subscribers, not {}", subscribers.len()).into()),
     }
 }
 
@@ -22,40 +260,312 @@ pub async fn run(actor: SteadyActorShadow
 /// This pattern enables time-based coordination across multiple actors
 /// while maintaining precise timing control and graceful termination.
 async fn internal_behavior<A: SteadyActor>(mut actor: A
-                                               , heartbeat_tx: SteadyTx<u64>
-                                               , state: SteadyState<HeartbeatState> ) -> Result<(),Box<dyn Error>> {
+                                               , control_rx: SteadyRx<HeartbeatCommand>
+                                               , backlog_rx: SteadyRx<usize>
+                                               , heartbeat_tx: SteadyTx<Pulse>
+                                               , subscribers: Vec<SteadyTx<Heartbeat>>
+                                               , timing_tx: SteadyTx<TimingReport>
+                                               , events_tx: SteadyTx<HeartbeatEvent>
+                                               , state: SteadyState<HeartbeatState>
+                                               , drift: DriftGauge
+                                               , ticks: Vec<Tick>) -> Result<(),Box<dyn Error>> {
     // Runtime argument access allows dynamic behavior configuration.
     // This enables the same actor code to work across different deployment scenarios
     // without recompilation or environment-specific builds.
     let args = actor.args::<crate::MainArg>().expect("unable to downcast");
-    let rate = Duration::from_millis(args.rate_ms);
+    let mut rate = Duration::from_millis(args.rate_ms);
     let beats = args.beats;
+    let clock = args.clock.clone();
+    let align = args.align_beats;
+    // beats phase-locked to a wall-clock second or minute, numbered by count as usual
+    let phase = args.align.filter(|_| !align);
+    let on_complete = args.on_beats_complete;
+    let trigger_after = args.trigger_after_ms.map(Duration::from_millis);
+    // aligned beats must land on the slot boundaries, jitter would only move them off
+    let jitter = match align || phase.is_some() {
+        true => Duration::ZERO,
+        false => Duration::from_millis(args.jitter_ms),
+    };
+    // nor may a backlog change their period
+    let backlog_threshold = if align { 0 } else { args.backlog_threshold };
 
     // lock our state and init if it has not been initialized yet
     // upon panic and restart this same state with no data loss will be restored
-    let mut state = state.lock(|| HeartbeatState{ count: 0}).await;
+    let mut state = state.lock(|| HeartbeatState{ count: 0, paused: false, paused_at: None, rng: jitter_seed(args.seed, std::process::id()), last_beat: None }).await;
     let mut heartbeat_tx = heartbeat_tx.lock().await;
+    let mut subscriber_txs = Vec::with_capacity(subscribers.len());
+    for tx in &subscribers {
+        subscriber_txs.push(tx.lock().await);
+    }
+    // per subscriber: beats skipped because its channel was full
+    let mut skipped = vec![0u64; subscribers.len()];
+    let mut timing_tx = timing_tx.lock().await;
+    // the beat before, measuring the interval of the next one against it
+    let mut previous: Option<Heartbeat> = None;
+    let mut events_tx = events_tx.lock().await;
+    let mut control_rx = control_rx.lock().await;
+    let mut backlog_rx = backlog_rx.lock().await;
+    let mut tick_txs = Vec::with_capacity(ticks.len());
+    for tick in &ticks {
+        tick_txs.push(tick.tx.lock().await);
+    }
+    // per tick: deadline of its last tick and the number of the next one
+    let mut tick_due = vec![clock.now(); ticks.len()];
+    let mut tick_count = vec![0u64; ticks.len()];
+    // Deadline of the last beat: the next is due an interval after it, not after the moment
+    // the last one actually went out, so time lost in the loop does not add up over the run.
+    let mut last_due = clock.now();
+    // aligned beats fire once per wall-clock slot, starting with the next one
+    let mut last_slot = slot(clock.wall(), rate);
+    // beating fast to drain the generator backlog the worker reports
+    let mut fast = false;
+    // the paced rate with this interval's jitter applied, drawn anew after every beat
+    let mut interval = jittered(rate, jitter, &mut state.rng);
+    if let Some(boundary) = phase {
+        // the first beat is due on the next boundary, the grid of later ones runs through it
+        let first_due = clock.now() + until_next_slot(clock.wall(), boundary.period());
+        last_due = first_due.checked_sub(interval).unwrap_or(last_due);
+    }
+    // beats missed while the actor was down, sent back to back with --catchup-policy burst
+    let mut burst = 0u64;
+    if let Some(last_beat) = state.last_beat.filter(|_| !align && !state.paused) {
+        let down = clock.wall().saturating_sub(last_beat);
+        let missed = (down.as_nanos() / rate.as_nanos().max(1)) as u64;
+        match args.catchup_policy {
+            CatchupPolicy::Skip => info!("Heartbeat restarted after {:?}, skipping {} missed beats", down, missed),
+            CatchupPolicy::Burst => {
+                info!("Heartbeat restarted after {:?}, sending {} missed beats", down, missed);
+                burst = missed;
+                // the schedule carries on from the last beat sent before the restart
+                last_due = clock.now().checked_sub(down).unwrap_or(last_due);
+            }
+        }
+    }
+    if let Some(delay) = trigger_after {
+        // a one-shot trigger's only beat is due after the delay instead of the interval
+        last_due = (clock.now() + delay).checked_sub(interval).unwrap_or(last_due);
+    }
 
     // Shutdown coordination with proper channel cleanup signaling.
-    while actor.is_running(|| heartbeat_tx.mark_closed() //true accept any shutdown
+    while actor.is_running(|| i!(heartbeat_tx.mark_closed()) && i!(timing_tx.mark_closed()) && i!(events_tx.mark_closed()) //true accept any shutdown
+                              && i!(subscriber_txs.iter_mut().fold(true, |closed, tx| tx.mark_closed() && closed))
+                              && i!(tick_txs.iter_mut().fold(true, |closed, tx| tx.mark_closed() && closed))
     ) {
-        // Synchronized waiting demonstrates multi-condition coordination.
-        // await_for_all! it ensures both timing requirements and channel capacity
-        // are satisfied before proceeding, preventing timing drift and overflow.
-        await_for_all!(actor.wait_periodic(rate),
-                       actor.wait_vacant(&mut heartbeat_tx, 1));
-
-        // since we used actor.wait_vacant() above we know this try will never fail
-        assert!(actor.try_send(&mut heartbeat_tx, state.count).is_sent(),"unable to send");//#!#//
+        // every change of the interval comes round here before it is awaited
+        drift.set_interval(interval);
+        // Multi-condition coordination: wake for the next beat (time elapsed and room to send)
+        // or as soon as a control command arrives, so a new rate applies to the current interval
+        // instead of only after the old period has run out.
+        if state.paused {
+            // Quiesced: nothing to do until the next command arrives (or shutdown). A closed
+            // control channel is ready at once and brings no further command, so then only
+            // shutdown is awaited.
+            if control_rx.is_closed_and_empty() {
+                actor.wait_shutdown().await;
+            } else {
+                await_for_any!(actor.wait_avail(&mut control_rx, 1), actor.wait_shutdown());
+            }
+        } else {
+            let remaining = until_due(&clock, align, interval, last_due, last_slot);
+            // the earliest tick, which does not wait for room on the heartbeat channel
+            let next_tick = ticks.iter().zip(&tick_due).map(|(tick, due)| tick.rate.saturating_sub(clock.since(*due))).min();
+            await_for_any!(wait_for_all!(actor.wait(remaining), actor.wait_vacant(&mut heartbeat_tx, 1)),
+                           actor.wait(next_tick.unwrap_or(remaining)),
+                           actor.wait_avail(&mut control_rx, 1),
+                           actor.wait_avail(&mut backlog_rx, 1));
+        }
+
+        while let Some(command) = actor.try_take(&mut control_rx) {
+            info!("Heartbeat command {:?}", command);
+            match command {
+                HeartbeatCommand::Pause => {
+                    state.paused = true;
+                    state.paused_at.get_or_insert(clock.wall());
+                }
+                HeartbeatCommand::Resume { catch_up } => {
+                    match state.paused_at.take() {
+                        // paused through a deadline: the missed beat is due now, even when a
+                        // restart while paused lost the deadline of the last beat
+                        Some(at) if catch_up && clock.wall().saturating_sub(at) >= interval => {
+                            last_due = clock.now().checked_sub(interval).map_or(last_due, |due| due.min(last_due));
+                            last_slot = last_slot.min(slot(clock.wall(), rate).saturating_sub(1));
+                        }
+                        // the schedule starts over from the resume, or from the next deadline on the grid
+                        Some(_) => {
+                            last_due = match phase {
+                                Some(_) => latest_deadline(&clock, last_due, interval),
+                                None => clock.now(),
+                            };
+                            last_slot = slot(clock.wall(), rate);
+                        }
+                        None => {}
+                    }
+                    state.paused = false;
+                }
+                HeartbeatCommand::SetRate(ms) => {
+                    rate = Duration::from_millis(ms);
+                    interval = jittered(paced(rate, fast), jitter, &mut state.rng);
+                    // slots of the new period are numbered differently
+                    last_slot = slot(clock.wall(), rate);
+                }
+                HeartbeatCommand::Flush => {
+                    if !actor.try_send(&mut heartbeat_tx, Pulse::Flush).is_sent() {
+                        // a full channel holds beats the worker has yet to act on anyway
+                        warn!("Heartbeat flush dropped, heartbeat channel full");
+                    }
+                }
+            }
+        }
+        // Closed loop: only the latest report matters, the interval shortens at once when it crosses.
+        let mut backlog = None;
+        while let Some(depth) = actor.try_take(&mut backlog_rx) {
+            backlog = Some(depth);
+        }
+        if let Some(backlog) = backlog && backlog_threshold > 0 && draining(fast, backlog, backlog_threshold) != fast {
+            fast = !fast;
+            info!("Heartbeat {} at a generator backlog of {}", if fast { "speeding up" } else { "relaxing" }, backlog);
+            interval = jittered(paced(rate, fast), jitter, &mut state.rng);
+        }
+        if !state.paused {
+            for (index, tick) in ticks.iter().enumerate() {
+                let due = tick_due[index] + tick.rate;
+                let late = clock.since(due);
+                if clock.now() < due {
+                    continue;
+                }
+                if !actor.try_send(&mut tick_txs[index], tick_count[index]).is_sent() {
+                    warn!("Heartbeat tick {} skipped, its {:?} channel is full", tick_count[index], tick.rate);
+                }
+                tick_count[index] += 1;
+                // absolute deadlines like the beats, re-anchored once a whole period behind
+                tick_due[index] = if late >= tick.rate { clock.now() } else { due };
+            }
+        }
+        // Woken by a command before the beat is due, or with nowhere to send it yet.
+        if state.paused || !until_due(&clock, align, interval, last_due, last_slot).is_zero() || actor.vacant_units(&mut heartbeat_tx) == 0 {
+            continue;
+        }
+        let wall = clock.wall();
+        last_slot = slot(wall, rate);
+        let late = if align {
+            wall.saturating_sub(slot_start(last_slot, rate))
+        } else {
+            let due = last_due + interval;
+            let late = clock.since(due);
+            drift.record(late);
+            // more than a whole interval behind, after a pause or a long wait for room:
+            // start again from now, or the last deadline passed when phase-locked,
+            // rather than bursting through the missed deadlines
+            last_due = match phase {
+                // a missed beat of the burst, the next deadline is the one after it
+                _ if burst > 0 => {
+                    burst -= 1;
+                    due
+                }
+                Some(_) => latest_deadline(&clock, due, interval),
+                None if late >= interval => clock.now(),
+                None => due,
+            };
+            late
+        };
+        interval = jittered(paced(rate, fast), jitter, &mut state.rng);
+        // aligned beats carry their slot so batch ids match across pipelines and instances
+        let actual = clock.system_time();
+        let beat = Heartbeat { beat: if align { last_slot } else { state.count }, scheduled: actual - late, actual };
+
+        // since we checked vacant_units() above we know this try will never fail
+        assert!(actor.try_send(&mut heartbeat_tx, Pulse::Beat(beat)).is_sent(),"unable to send");//#!#//
         //OR:
-        //actor.try_send(&mut heartbeat_tx, state.count).expect("unable to send");
+        //actor.try_send(&mut heartbeat_tx, Pulse::Beat(beat)).expect("unable to send");
+        // Broadcast: every subscriber gets a copy, one with a full channel misses it rather than
+        // holding up the beat for the worker and the others.
+        for (index, tx) in subscriber_txs.iter_mut().enumerate() {
+            if !actor.try_send(tx, beat).is_sent() {
+                if skipped[index] == 0 {
+                    warn!("Heartbeat subscriber {} is falling behind, skipping beats while its channel is full", index);
+                }
+                skipped[index] += 1;
+            }
+        }
+
+        // interval accuracy for telemetry, dropped rather than holding up the beat like the copies
+        if let Some(previous) = previous {
+            let _ = actor.try_send(&mut timing_tx, TimingReport::between(&previous, &beat));
+        }
+        previous = Some(beat);
 
         state.count += 1;
-        // Self-terminating behavior allows actors to control the application lifecycle.
+        state.last_beat = Some(wall);
+        if trigger_after.is_some() {
+            // One-shot trigger: done after its beat. Every channel is closed so consumers
+            // see the end of input, and the actor exits without waiting for the shutdown.
+            info!("Heartbeat trigger fired, closing");
+            let _ = heartbeat_tx.mark_closed();
+            let _ = timing_tx.mark_closed();
+            let _ = events_tx.mark_closed();
+            for tx in subscriber_txs.iter_mut() {
+                let _ = tx.mark_closed();
+            }
+            for tx in tick_txs.iter_mut() {
+                let _ = tx.mark_closed();
+            }
+            break;
+        }
         if beats == state.count {
-            actor.request_shutdown().await;
+            match on_complete {
+                // Self-terminating behavior allows actors to control the application lifecycle.
+                OnBeatsComplete::Shutdown => {
+                    // the worker drains what is left while the shutdown is underway
+                    actor.send_async(&mut heartbeat_tx, Pulse::LastBeat, SendSaturation::AwaitForRoom).await;
+                    actor.request_shutdown().await;
+                }
+                // stays paused until a Resume command, counting on past the limit from there
+                OnBeatsComplete::Pause => {
+                    info!("Heartbeat paused after {} beats", state.count);
+                    state.paused = true;
+                    state.paused_at = Some(clock.wall());
+                }
+                // cyclical runs: the next beat starts the count over
+                OnBeatsComplete::RestartCount => state.count = 0,
+                OnBeatsComplete::EmitEvent => {
+                    if !actor.try_send(&mut events_tx, HeartbeatEvent::BeatsComplete(state.count)).is_sent() {
+                        warn!("Heartbeat event dropped, events channel full");
+                    }
+                }
+            }
+        }
+    }
+    for (index, skipped) in skipped.iter().enumerate().filter(|(_, skipped)| **skipped > 0) {
+        info!("Heartbeat subscriber {} skipped {} beats", index, skipped);
+    }
+    Ok(())
+}
+
+/// Terminal consumer of one `--tick-ms` rate, counting its ticks and reporting the count
+/// at shutdown so a skipped tick shows as a gap between the count and the tick numbers.
+pub async fn run_tick_logger(actor: SteadyActorShadow, rate: Duration, tick_rx: SteadyRx<u64>) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&tick_rx], []);
+    if actor.use_internal_behavior {
+        tick_logger_behavior(actor, rate, tick_rx).await
+    } else { //edge actor so we simulate it when testing from main
+        actor.simulated_behavior(vec!(&tick_rx)).await
+    }
+}
+
+async fn tick_logger_behavior<A: SteadyActor>(mut actor: A, rate: Duration, rx: SteadyRx<u64>) -> Result<(),Box<dyn Error>> {
+    let mut rx = rx.lock().await;
+    let (mut count, mut last) = (0u64, None);
+    while actor.is_running(|| i!(rx.is_closed_and_empty())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1));
+
+        while let Some(tick) = actor.try_take(&mut rx) {
+            trace!("Tick {} every {:?}", tick, rate);
+            count += 1;
+            last = Some(tick);
         }
     }
+    let last = last.map_or("-".to_string(), |tick| tick.to_string());
+    info!("Tick every {:?} received {} ticks, last {}", rate, count, last);
     Ok(())
 }
 
@@ -64,14 +574,63 @@ async fn internal_behavior<A: SteadyActor>(mut actor: A
 /// under various load and configuration conditions.
 #[cfg(test)]
 pub(crate) mod heartbeat_tests {
+    use std::time::UNIX_EPOCH;
     use steady_state::*;
     use crate::arg::MainArg;
+    use crate::sink::{FakeSink, Sink};
     use super::*;
 
+    /// Drains up to `limit` beats into a fake sink, keeping their numbers, then leaves
+    /// the channel to fill; the scheduled and actual times differ from run to run.
+    fn collect_beats(graph: &mut Graph, rx: SteadyRx<Pulse>, limit: usize) -> FakeSink<u64> {
+        let sink = FakeSink::default();
+        let collector_sink = sink.clone();
+        graph.actor_builder().with_name("Collector")
+            .build(move |context| collect(context, rx.clone(), collector_sink.clone(), limit), SoloAct);
+        sink
+    }
+
+    async fn collect<A: SteadyActor>(mut actor: A, rx: SteadyRx<Pulse>, mut sink: FakeSink<u64>, limit: usize) -> Result<(), Box<dyn Error>> {
+        let mut rx = rx.lock().await;
+        let mut taken = 0;
+        while actor.is_running(|| true) {
+            await_for_any!(actor.wait_avail(&mut rx, 1), actor.wait_shutdown());
+            while taken < limit {
+                let Some(pulse) = actor.try_take(&mut rx) else { break };
+                // only beats count, the drain signals are checked in tests of their own
+                if let Pulse::Beat(beat) = pulse {
+                    sink.write(&beat.beat)?;
+                    taken += 1;
+                }
+            }
+            if taken == limit {
+                actor.wait_shutdown().await;
+            }
+        }
+        Ok(())
+    }
+
+    /// Keeps every pulse as it is, for runs on a virtual clock where the times are exact.
+    async fn collect_pulses<A: SteadyActor>(mut actor: A, rx: SteadyRx<Pulse>, mut sink: FakeSink<Pulse>) -> Result<(), Box<dyn Error>> {
+        let mut rx = rx.lock().await;
+        while actor.is_running(|| true) {
+            await_for_any!(actor.wait_avail(&mut rx, 1), actor.wait_shutdown());
+            while let Some(pulse) = actor.try_take(&mut rx) {
+                sink.write(&pulse)?;
+            }
+        }
+        Ok(())
+    }
+
     #[test]
     fn test_heartbeat() -> Result<(), Box<dyn Error>> {
         let mut graph = GraphBuilder::for_testing().build(MainArg::default());
         let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let beats = collect_beats(&mut graph, heartbeat_rx, usize::MAX);
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
 
         // Requires state so we create one here.
         let state = new_state();
@@ -79,7 +638,7 @@ pub(crate) mod heartbeat_tests {
             .with_name("UnitTest")
             .build(move |context|
                 //As always, use the internal behavior for testing
-                internal_behavior(context, heartbeat_tx.clone(), state.clone()), SoloAct
+                internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct
             );
 
         graph.start();
@@ -88,7 +647,507 @@ pub(crate) mod heartbeat_tests {
         std::thread::sleep(Duration::from_millis(1000 * 3));
         graph.request_shutdown(); //required for tests to not block
         graph.block_until_stopped(Duration::from_secs(1))?;
-        assert_steady_rx_eq_take!(&heartbeat_rx, vec!(0,1));
+        assert_eq!(beats.take_records(), vec![0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_draining() {
+        // speeds up above the threshold, relaxes below half of it
+        assert!(!draining(false, 100, 100));
+        assert!(draining(false, 101, 100));
+        assert!(draining(true, 60, 100));
+        assert!(draining(true, 50, 100));
+        assert!(!draining(true, 49, 100));
+        assert_eq!(paced(Duration::from_millis(1000), true), Duration::from_millis(250));
+    }
+
+    #[test]
+    fn test_heartbeat_backlog() -> Result<(), Box<dyn Error>> {
+        // the beat itself is too slow to fire during the test
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 1000, backlog_threshold: 10, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let beats = collect_beats(&mut graph, heartbeat_rx, usize::MAX);
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        // a backlog over the threshold brings the beats in to a quarter of the rate
+        backlog_tx.testing_send_all(vec![50], false);
+        graph.start();
+        std::thread::sleep(Duration::from_millis(600));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_eq!(beats.take_records(), vec![0, 1]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_drift_gauge() {
+        let drift = DriftGauge::default();
+        let shared = drift.clone();
+        for late in [3, 40, 7] {
+            shared.record(Duration::from_micros(late));
+        }
+        assert_eq!(drift.interval(), None);
+        shared.set_interval(Duration::from_millis(100));
+        assert_eq!(drift.interval(), Some(Duration::from_millis(100)));
+        assert_eq!(drift.to_json(), serde_json::json!({ "last_us": 7, "max_us": 40, "accumulated_us": 50, "interval_us": 100_000 }));
+    }
+
+    #[test]
+    fn test_heartbeat_absolute_deadlines() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 20, beats: 10, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let beats = collect_beats(&mut graph, heartbeat_rx, usize::MAX);
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+        let drift = DriftGauge::default();
+
+        let state = new_state();
+        let gauge = drift.clone();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), gauge.clone(), Vec::new()), SoloAct);
+
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_eq!(beats.take_records(), (0..10).collect::<Vec<u64>>());
+        // every beat was measured against its deadline and none fell a whole interval behind
+        let report = drift.to_json();
+        assert!(report["max_us"].as_u64().expect("max") < 20_000, "{}", report);
+        assert!(report["accumulated_us"].as_u64().expect("accumulated") >= report["max_us"].as_u64().expect("max"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_ticks() -> Result<(), Box<dyn Error>> {
+        // the beat itself is too slow to fire during the test
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 60_000, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let beats = collect_beats(&mut graph, heartbeat_rx, usize::MAX);
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+        let (fast_tx, fast_rx) = graph.channel_builder().build();
+        let (slow_tx, slow_rx) = graph.channel_builder().build();
+        let ticks = vec![Tick { rate: Duration::from_millis(150), tx: fast_tx }, Tick { rate: Duration::from_millis(400), tx: slow_tx }];
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), ticks.clone()), SoloAct);
+
+        graph.start();
+        std::thread::sleep(Duration::from_millis(500));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // each tick counts on its own channel at its own rate
+        assert_steady_rx_eq_take!(&fast_rx, vec!(0, 1, 2));
+        assert_steady_rx_eq_take!(&slow_rx, vec!(0));
+        assert_eq!(beats.take_records(), Vec::<u64>::new());
         Ok(())
     }
+
+    #[test]
+    fn test_tick_logger() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (tick_tx, tick_rx) = graph.channel_builder().build();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| tick_logger_behavior(context, Duration::from_millis(50), tick_rx.clone()), SoloAct);
+
+        // tick 2 was skipped on a full channel
+        tick_tx.testing_send_all(vec![0, 1, 3], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_in_logs!(["Tick every 50ms received 3 ticks, last 3"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_broadcast() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 100, clock, ..MainArg::default() });
+        let (heartbeat_tx, _heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+        let (wide_tx, wide_rx) = graph.channel_builder().build();
+        let (narrow_tx, narrow_rx) = graph.channel_builder().with_capacity(1).build();
+        let subscribers = vec![wide_tx, narrow_tx];
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), subscribers.clone(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        graph.start();
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(200));
+            source.advance(Duration::from_millis(100));
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // every subscriber sees the beats, the one left full misses those without room
+        let beat = |n: u64| Heartbeat::on_time(n, UNIX_EPOCH + Duration::from_millis(100 * (n + 1)));
+        assert_steady_rx_eq_take!(&wide_rx, vec!(beat(0), beat(1), beat(2)));
+        assert_steady_rx_eq_take!(&narrow_rx, vec!(beat(0)));
+        assert_in_logs!(["Heartbeat subscriber 1 skipped 2 beats"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_slot_alignment() {
+        let period = Duration::from_millis(100);
+        // two instances out of phase by 45ms both wake exactly on the same boundary
+        for wall in [Duration::from_millis(10_040), Duration::from_millis(10_085)] {
+            let woken = wall + until_next_slot(wall, period);
+            assert_eq!(woken, Duration::from_millis(10_100));
+            assert_eq!(slot(woken, period), 101);
+        }
+        // on a boundary the next slot is a full period away
+        assert_eq!(until_next_slot(Duration::from_secs(10), period), period);
+        assert_eq!(slot(Duration::from_millis(10_099), period), 100);
+    }
+
+    #[test]
+    fn test_jittered() {
+        let (rate, jitter) = (Duration::from_millis(100), Duration::from_millis(10));
+        let draw = |seed: u64| {
+            let mut rng = seed;
+            (0..200).map(|_| jittered(rate, jitter, &mut rng)).collect::<Vec<_>>()
+        };
+        let intervals = draw(7);
+        assert!(intervals.iter().all(|i| (Duration::from_millis(90)..=Duration::from_millis(110)).contains(i)));
+        // spread to both sides of the rate, and the same seed gives the same intervals
+        assert!(intervals.iter().any(|i| *i < rate) && intervals.iter().any(|i| *i > rate));
+        assert_eq!(intervals, draw(7));
+        assert_ne!(intervals, draw(8));
+        // instances of one fleet share the seed but not the pid
+        assert_ne!(draw(jitter_seed(7, 100)), draw(jitter_seed(7, 101)));
+        assert_eq!(draw(jitter_seed(7, 100)), draw(jitter_seed(7, 100)));
+        // a jitter beyond the rate clamps at zero rather than underflowing
+        let mut rng = 0;
+        assert!((0..50).all(|_| jittered(Duration::from_millis(5), Duration::from_millis(50), &mut rng) <= Duration::from_millis(55)));
+        assert_eq!(jittered(rate, Duration::ZERO, &mut rng), rate);
+    }
+
+    #[test]
+    fn test_heartbeat_aligned() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock_at(Duration::from_millis(10_040));
+        let args = MainArg { rate_ms: 100, align_beats: true, clock, ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        graph.start();
+        // no beat until virtual time crosses a boundary, then one beat per slot entered
+        std::thread::sleep(Duration::from_millis(200));
+        source.advance(Duration::from_millis(100));
+        std::thread::sleep(Duration::from_millis(200));
+        source.advance(Duration::from_millis(100));
+        std::thread::sleep(Duration::from_millis(200));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the virtual clock stood on the boundaries, so both beats went out exactly on schedule
+        let on_boundary = |beat: u64| Heartbeat::on_time(beat, UNIX_EPOCH + Duration::from_millis(beat * 100));
+        assert_steady_rx_eq_take!(&heartbeat_rx, vec!(Pulse::Beat(on_boundary(101)), Pulse::Beat(on_boundary(102))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_align_second() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock_at(Duration::from_millis(10_400));
+        let args = MainArg { rate_ms: 250, align: Some(crate::arg::Align::Second), clock, ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        graph.start();
+        // nothing before the top of the second, then beats every 250ms from it: a late beat
+        // leaves the next on its deadline, a missed one is skipped without leaving the grid
+        // each step outlasts the longest real-time wait of the heartbeat, a whole interval
+        for advance in [600, 300, 200, 600, 150] {
+            std::thread::sleep(Duration::from_millis(300));
+            source.advance(Duration::from_millis(advance));
+        }
+        std::thread::sleep(Duration::from_millis(300));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+        let beats = [Heartbeat::on_time(0, at(11_000))
+                   , Heartbeat { beat: 1, scheduled: at(11_250), actual: at(11_300) }
+                   , Heartbeat::on_time(2, at(11_500))
+                   , Heartbeat { beat: 3, scheduled: at(11_750), actual: at(12_100) }
+                   , Heartbeat::on_time(4, at(12_250))];
+        assert_steady_rx_eq_take!(&heartbeat_rx, beats.map(Pulse::Beat));
+        // each interval against the one scheduled, from the second beat on
+        let report = |expected: u64, actual: u64| TimingReport { expected: Duration::from_millis(expected), actual: Duration::from_millis(actual) };
+        assert_steady_rx_eq_take!(&timing_rx, vec!(report(250, 300), report(250, 200), report(250, 600), report(500, 150)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_pulses() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 100, beats: 1, clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        control_tx.testing_send_all(vec![HeartbeatCommand::Flush], false);
+        graph.start();
+        std::thread::sleep(Duration::from_millis(100));
+        source.advance(Duration::from_millis(100));
+        // no request_shutdown, the heartbeat requests it after its last beat
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // a flush goes out at once, the beat limit ends with the last beat signal
+        let beat = Heartbeat::on_time(0, UNIX_EPOCH + Duration::from_millis(100));
+        assert_steady_rx_eq_take!(&heartbeat_rx, vec!(Pulse::Flush, Pulse::Beat(beat), Pulse::LastBeat));
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_trigger() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { trigger_after_ms: Some(250), clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        graph.start();
+        // a single beat once the delay has passed, none at the rate after it
+        for _ in 0..3 {
+            std::thread::sleep(Duration::from_millis(200));
+            source.advance(Duration::from_millis(250));
+        }
+        std::thread::sleep(Duration::from_millis(200));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        let beat = Heartbeat::on_time(0, UNIX_EPOCH + Duration::from_millis(250));
+        assert_steady_rx_eq_take!(&heartbeat_rx, vec!(Pulse::Beat(beat)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_catchup_burst() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock_at(Duration::from_millis(10_000));
+        let args = MainArg { rate_ms: 100, catchup_policy: CatchupPolicy::Burst, clock, ..MainArg::default() };
+        // the state outlives the first run like it outlives a panic, the second run is the restart
+        let state = new_state();
+        let run = |advance: u64| -> Result<Vec<Pulse>, Box<dyn Error>> {
+            let mut graph = GraphBuilder::for_testing().build(args.clone());
+            let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+            let (events_tx, _events_rx) = graph.channel_builder().build();
+            let (timing_tx, _timing_rx) = graph.channel_builder().build();
+            let (_control_tx, control_rx) = graph.channel_builder().build();
+            let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+            let pulses = FakeSink::default();
+            let collected = pulses.clone();
+            graph.actor_builder().with_name("Collector")
+                .build(move |context| collect_pulses(context, heartbeat_rx.clone(), collected.clone()), SoloAct);
+            let state = state.clone();
+            graph.actor_builder()
+                .with_name("UnitTest")
+                .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+            graph.start();
+            std::thread::sleep(Duration::from_millis(150));
+            source.advance(Duration::from_millis(advance));
+            std::thread::sleep(Duration::from_millis(150));
+            graph.request_shutdown();
+            graph.block_until_stopped(Duration::from_secs(1))?;
+            Ok(pulses.take_records())
+        };
+        let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+        assert_eq!(run(100)?, vec![Pulse::Beat(Heartbeat::on_time(0, at(10_100)))]);
+        // down from 10.1s to 10.45s: the three deadlines passed meanwhile go out at once
+        source.advance(Duration::from_millis(250));
+        assert_eq!(run(0)?, vec![Pulse::Beat(Heartbeat { beat: 1, scheduled: at(10_200), actual: at(10_450) })
+                                , Pulse::Beat(Heartbeat { beat: 2, scheduled: at(10_300), actual: at(10_450) })
+                                , Pulse::Beat(Heartbeat { beat: 3, scheduled: at(10_400), actual: at(10_450) })]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_set_rate() -> Result<(), Box<dyn Error>> {
+        // at the default 1s rate three beats would not fit in the time allowed below
+        let mut graph = GraphBuilder::for_testing().build(MainArg { beats: 3, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let beats = collect_beats(&mut graph, heartbeat_rx, usize::MAX);
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        control_tx.testing_send_all(vec![HeartbeatCommand::SetRate(50)], false);
+        graph.start();
+        // no request_shutdown, the heartbeat requests it after its last beat
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_eq!(beats.take_records(), vec![0, 1, 2]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_resume_catch_up() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock_at(Duration::from_millis(10_000));
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 100, clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        control_tx.testing_send_all(vec![HeartbeatCommand::Pause], false);
+        graph.start();
+        let at = |ms: u64| UNIX_EPOCH + Duration::from_millis(ms);
+        // paused through the 10.1s deadline, the catch-up beat goes out on resume marked late
+        std::thread::sleep(Duration::from_millis(100));
+        source.advance(Duration::from_millis(250));
+        control_tx.testing_send_all(vec![HeartbeatCommand::Resume { catch_up: true }], false);
+        std::thread::sleep(Duration::from_millis(200));
+        assert_steady_rx_eq_take!(&heartbeat_rx, vec!(Pulse::Beat(Heartbeat { beat: 0, scheduled: at(10_100), actual: at(10_250) })));
+
+        // without catch-up the next beat is a whole interval after the resume
+        control_tx.testing_send_all(vec![HeartbeatCommand::Pause], false);
+        std::thread::sleep(Duration::from_millis(100));
+        source.advance(Duration::from_millis(250));
+        control_tx.testing_send_all(vec![HeartbeatCommand::Resume { catch_up: false }], false);
+        std::thread::sleep(Duration::from_millis(200));
+        source.advance(Duration::from_millis(100));
+        std::thread::sleep(Duration::from_millis(200));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_steady_rx_eq_take!(&heartbeat_rx, vec!(Pulse::Beat(Heartbeat::on_time(1, at(10_600)))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_heartbeat_paused_control_closed() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 100, clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (events_tx, _events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        // paused for good, no command can follow on the closed channel
+        control_tx.testing_send_all(vec![HeartbeatCommand::Pause], true);
+        graph.start();
+        source.advance(Duration::from_millis(1_000));
+        std::thread::sleep(Duration::from_millis(100));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_steady_rx_eq_take!(&heartbeat_rx, Vec::<Pulse>::new());
+        Ok(())
+    }
+
+    /// Runs `beats: 2` at 20ms with `on_complete` into a heartbeat channel too small for
+    /// more than four beats, then checks the beats and events sent.
+    fn assert_beats_complete(on_complete: OnBeatsComplete, expected_beats: Vec<u64>, expected_events: Vec<HeartbeatEvent>) -> Result<(), Box<dyn Error>> {
+        let args = MainArg { beats: 2, rate_ms: 20, on_beats_complete: on_complete, ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().with_capacity(4).build();
+        // the first four, after which the channel fills up and beating stalls
+        let beats = collect_beats(&mut graph, heartbeat_rx, 4);
+        let (events_tx, events_rx) = graph.channel_builder().build();
+        let (timing_tx, _timing_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+        let (_backlog_tx, backlog_rx) = graph.channel_builder().build();
+
+        let state = new_state();
+        graph.actor_builder()
+            .with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), Vec::new(), timing_tx.clone(), events_tx.clone(), state.clone(), DriftGauge::default(), Vec::new()), SoloAct);
+
+        graph.start();
+        std::thread::sleep(Duration::from_millis(300));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_eq!(beats.take_records(), expected_beats);
+        assert_steady_rx_eq_take!(&events_rx, expected_events);
+        Ok(())
+    }
+
+    #[test]
+    fn test_beats_complete_pause() -> Result<(), Box<dyn Error>> {
+        // paused at the limit, nothing after the second beat
+        assert_beats_complete(OnBeatsComplete::Pause, vec![0, 1], vec![])
+    }
+
+    #[test]
+    fn test_beats_complete_restart_count() -> Result<(), Box<dyn Error>> {
+        // the count starts over each time the limit is reached
+        assert_beats_complete(OnBeatsComplete::RestartCount, vec![0, 1, 0, 1], vec![])
+    }
+
+    #[test]
+    fn test_beats_complete_emit_event() -> Result<(), Box<dyn Error>> {
+        // beating carries on past the limit, which is announced once
+        assert_beats_complete(OnBeatsComplete::EmitEvent, vec![0, 1, 2, 3], vec![HeartbeatEvent::BeatsComplete(2)])
+    }
 }
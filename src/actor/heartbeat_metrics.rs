@@ -0,0 +1,133 @@
+use steady_state::*;
+use crate::actor::heartbeat::{Heartbeat, TimingReport};
+use crate::clock;
+
+/// Beats between two skew reports while the graph runs.
+const REPORT_EVERY: u64 = 60;
+
+/// Scheduling skew over the beats seen so far: how long after its scheduled time
+/// each beat actually went out.
+#[derive(Default)]
+pub(crate) struct SkewStats {
+    beats: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl SkewStats {
+    pub(crate) fn record(&mut self, beat: &Heartbeat) {
+        let skew = beat.skew();
+        self.beats += 1;
+        self.total += skew;
+        self.max = self.max.max(skew);
+    }
+
+    pub(crate) fn beats(&self) -> u64 {
+        self.beats
+    }
+
+    pub(crate) fn report(&self) -> String {
+        let mean = clock::mean(self.total, self.beats);
+        format!("Heartbeat skew over {} beats: mean {:?} max {:?}", self.beats, mean, self.max)
+    }
+}
+
+/// Interval accuracy over the timing reports seen so far: how far the time between two beats
+/// was off the time scheduled between them.
+#[derive(Default)]
+pub(crate) struct IntervalStats {
+    intervals: u64,
+    total_error: Duration,
+    max_error: Duration,
+}
+
+impl IntervalStats {
+    pub(crate) fn record(&mut self, report: &TimingReport) {
+        let error = report.error();
+        self.intervals += 1;
+        self.total_error += error;
+        self.max_error = self.max_error.max(error);
+    }
+
+    pub(crate) fn report(&self) -> String {
+        let mean = clock::mean(self.total_error, self.intervals);
+        format!("Heartbeat intervals over {} beats: mean error {:?} max error {:?}", self.intervals, mean, self.max_error)
+    }
+}
+
+/// Terminal consumer of a copy of the heartbeat and of its timing reports, logging how closely
+/// beats keep to their schedule every `REPORT_EVERY` beats and once more at shutdown.
+pub async fn run(actor: SteadyActorShadow, rx: SteadyRx<Heartbeat>, timing_rx: SteadyRx<TimingReport>) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&rx, &timing_rx], []);
+    if actor.use_internal_behavior {
+        internal_behavior(actor, rx, timing_rx).await
+    } else { //edge actor so we simulate it when testing from main
+        actor.simulated_behavior(vec!(&rx, &timing_rx)).await
+    }
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A, rx: SteadyRx<Heartbeat>, timing_rx: SteadyRx<TimingReport>) -> Result<(),Box<dyn Error>> {
+    let mut rx = rx.lock().await;
+    let mut timing_rx = timing_rx.lock().await;
+    let mut stats = SkewStats::default();
+    let mut intervals = IntervalStats::default();
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(timing_rx.is_closed_and_empty())) {
+        if rx.is_closed_and_empty() && timing_rx.is_closed_and_empty() {
+            // a one-shot heartbeat closed its channels, nothing more will come
+            actor.wait_shutdown().await;
+            continue;
+        }
+        await_for_any!(actor.wait_avail(&mut rx, 1), actor.wait_avail(&mut timing_rx, 1));
+
+        while let Some(report) = actor.try_take(&mut timing_rx) {
+            intervals.record(&report);
+        }
+        while let Some(beat) = actor.try_take(&mut rx) {
+            stats.record(&beat);
+            if stats.beats() % REPORT_EVERY == 0 {
+                info!("{}", stats.report());
+                info!("{}", intervals.report());
+            }
+        }
+    }
+    if stats.beats() % REPORT_EVERY != 0 {
+        info!("{}", stats.report());
+        info!("{}", intervals.report());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod heartbeat_metrics_tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use super::*;
+
+    #[test]
+    fn test_heartbeat_metrics() -> Result<(), Box<dyn std::error::Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (tx, rx) = graph.channel_builder().build();
+        let (timing_tx, timing_rx) = graph.channel_builder().build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, rx.clone(), timing_rx.clone()), SoloAct);
+
+        let scheduled = UNIX_EPOCH + Duration::from_secs(1_000);
+        let beat = |beat, late_ms| Heartbeat { beat, scheduled, actual: scheduled + Duration::from_millis(late_ms) };
+        // a beat which went out early counts as on time
+        let early = Heartbeat { beat: 2, scheduled, actual: scheduled - Duration::from_millis(5) };
+        assert_eq!(early.skew(), Duration::ZERO);
+        tx.testing_send_all(vec![beat(0, 2), beat(1, 6), early, Heartbeat::on_time(3, SystemTime::now())], true);
+        // intervals a little long, a little short and right on time
+        let report = |expected: u64, actual: u64| TimingReport { expected: Duration::from_millis(expected), actual: Duration::from_millis(actual) };
+        timing_tx.testing_send_all(vec![report(100, 104), report(100, 98), report(100, 100)], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_in_logs!(["Heartbeat skew over 4 beats: mean 2ms max 6ms", "Heartbeat intervals over 3 beats: mean error 2ms max error 4ms"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+use serde::Serialize;
+use steady_state::*;
+use crate::request::Correlated;
+
+/// Aggregated statistics for one heartbeat-triggered worker batch.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Serialize)]
+pub(crate) struct BatchStats {
+    pub(crate) beat: Option<u64>,
+    pub(crate) items: u64,
+    pub(crate) rejected: u64,
+    pub(crate) checksum: u64,
+}
+
+/// Ask for every batch recorded within the trailing window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct HistoryQuery {
+    pub(crate) id: u64,
+    pub(crate) window: Duration,
+}
+
+/// Answer to a [`HistoryQuery`], oldest batch first.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct HistoryReply {
+    /// Id of the query being answered.
+    pub(crate) id: u64,
+    pub(crate) batches: Vec<BatchStats>,
+}
+
+impl Correlated for HistoryReply {
+    fn correlation_id(&self) -> u64 {
+        self.id
+    }
+}
+
+/// Bounded in-memory history, entries older than the retention are evicted on insert.
+/// This answers short-horizon questions without an external metrics store.
+pub(crate) struct History {
+    retention: Duration,
+    entries: VecDeque<(Instant, BatchStats)>,
+}
+
+impl History {
+    pub(crate) fn new(retention: Duration) -> Self {
+        History { retention, entries: VecDeque::new() }
+    }
+
+    pub(crate) fn record(&mut self, now: Instant, stats: BatchStats) {
+        self.entries.push_back((now, stats));
+        while let Some((at, _)) = self.entries.front() {
+            if now.duration_since(*at) > self.retention {
+                self.entries.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub(crate) fn window(&self, now: Instant, window: Duration) -> Vec<BatchStats> {
+        self.entries.iter()
+            .filter(|(at, _)| now.duration_since(*at) <= window)
+            .map(|(_, stats)| *stats)
+            .collect()
+    }
+}
+
+/// Retains recent batch statistics and answers queries from the control API.
+pub async fn run(actor: SteadyActorShadow
+                 , stats_rx: SteadyRx<BatchStats>
+                 , query_rx: SteadyRx<HistoryQuery>
+                 , reply_tx: SteadyTx<HistoryReply>) -> Result<(),Box<dyn Error>> {
+    // not on the edge of the graph, tested through its neighbors in simulation
+    internal_behavior(actor.into_spotlight([&stats_rx, &query_rx], [&reply_tx]), stats_rx, query_rx, reply_tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , stats_rx: SteadyRx<BatchStats>
+                                           , query_rx: SteadyRx<HistoryQuery>
+                                           , reply_tx: SteadyTx<HistoryReply>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let mut history = History::new(Duration::from_secs(args.history_minutes * 60));
+    let clock = args.clock.clone();
+
+    let mut stats_rx = stats_rx.lock().await;
+    let mut query_rx = query_rx.lock().await;
+    let mut reply_tx = reply_tx.lock().await;
+
+    while actor.is_running(|| i!(stats_rx.is_closed_and_empty())
+                              && i!(query_rx.is_closed_and_empty())
+                              && i!(reply_tx.mark_closed())) {
+        // either new stats to record or a question to answer
+        await_for_any!(actor.wait_avail(&mut stats_rx, 1)
+                     , actor.wait_avail(&mut query_rx, 1));
+
+        while let Some(stats) = actor.try_take(&mut stats_rx) {
+            history.record(clock.now(), stats);
+        }
+        while let Some(query) = actor.try_take(&mut query_rx) {
+            let reply = HistoryReply { id: query.id, batches: history.window(clock.now(), query.window) };
+            actor.send_async(&mut reply_tx, reply, SendSaturation::AwaitForRoom).await;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod history_tests {
+    use super::*;
+
+    #[test]
+    fn test_history_window_and_retention() {
+        let start = Instant::now();
+        let mut history = History::new(Duration::from_secs(60));
+        let stats = |beat| BatchStats { beat: Some(beat), ..BatchStats::default() };
+        history.record(start, stats(0));
+        history.record(start + Duration::from_secs(30), stats(1));
+        history.record(start + Duration::from_secs(61), stats(2)); // evicts beat 0
+
+        let now = start + Duration::from_secs(61);
+        assert_eq!(history.window(now, Duration::from_secs(3600)), vec![stats(1), stats(2)]);
+        assert_eq!(history.window(now, Duration::from_secs(10)), vec![stats(2)]);
+    }
+}
@@ -0,0 +1,121 @@
+use std::io;
+use steady_state::*;
+use crate::actor::generator::InputError;
+use crate::sink::Sink;
+
+/// Longest the actor sleeps on one quiet generator before checking the others.
+const POLL_RATE: Duration = Duration::from_millis(20);
+
+/// Console output of input errors, one warning per line which yielded no value.
+pub(crate) struct InputErrorLog;
+
+impl Sink<InputError> for InputErrorLog {
+    fn write(&mut self, error: &InputError) -> io::Result<()> {
+        warn!("Input error {}", error);
+        Ok(())
+    }
+}
+
+/// Terminal consumer for the lines the generators could not turn into values,
+/// one channel per generator. Bad input is reported here, on its own channel, so
+/// the value stream carries only values and a malformed row never stops the run.
+/// The spotlight takes its channels as fixed arrays, so they are passed by shape; past four
+/// generators only the first four channels are watched, every one is still drained.
+pub async fn run(actor: SteadyActorShadow, errors_rx: Vec<SteadyRx<InputError>>) -> Result<(),Box<dyn Error>> {
+    match errors_rx.as_slice() {
+        [a] => {
+            let actor = actor.into_spotlight([a], []);
+            if actor.use_internal_behavior {
+                internal_behavior(actor, errors_rx, InputErrorLog).await
+            } else { //edge actor so we simulate it when testing from main
+                actor.simulated_behavior(vec!(a)).await
+            }
+        }
+        [a, b] => {
+            let actor = actor.into_spotlight([a, b], []);
+            if actor.use_internal_behavior {
+                internal_behavior(actor, errors_rx, InputErrorLog).await
+            } else {
+                actor.simulated_behavior(vec!(a, b)).await
+            }
+        }
+        [a, b, c] => {
+            let actor = actor.into_spotlight([a, b, c], []);
+            if actor.use_internal_behavior {
+                internal_behavior(actor, errors_rx, InputErrorLog).await
+            } else {
+                actor.simulated_behavior(vec!(a, b, c)).await
+            }
+        }
+        [a, b, c, d, ..] => {
+            let actor = actor.into_spotlight([a, b, c, d], []);
+            if actor.use_internal_behavior {
+                internal_behavior(actor, errors_rx, InputErrorLog).await
+            } else {
+                actor.simulated_behavior(vec!(a, b, c, d)).await
+            }
+        }
+        [] => Err("input errors need at least one generator channel".into()),
+    }
+}
+
+pub(crate) async fn internal_behavior<A: SteadyActor, S: Sink<InputError>>(mut actor: A
+                                           , errors_rx: Vec<SteadyRx<InputError>>
+                                           , mut sink: S) -> Result<(),Box<dyn Error>> {
+    let mut rxs = Vec::with_capacity(errors_rx.len());
+    for rx in &errors_rx {
+        rxs.push(rx.lock().await);
+    }
+    let mut next = 0;
+    let mut reported = 0u64;
+
+    while actor.is_running(|| rxs.iter_mut().all(|rx| rx.is_closed_and_empty())) {
+        await_for_any!(actor.wait_avail(&mut rxs[next], 1), actor.wait(POLL_RATE));
+
+        for rx in rxs.iter_mut() {
+            while let Some(error) = actor.try_take(rx) {
+                sink.write(&error)?;
+                reported += 1;
+            }
+        }
+        sink.flush()?;
+        next = (next + 1) % rxs.len();
+    }
+    if reported > 0 {
+        warn!("{} input lines yielded no value", reported);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod input_errors_tests {
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::sink::FakeSink;
+    use super::*;
+
+    #[test]
+    fn test_input_errors() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (error_txs, error_rxs): (Vec<_>, Vec<_>) = (0..2).map(|_| graph.channel_builder().build()).unzip();
+        let sink = FakeSink::default();
+
+        let actor_sink = sink.clone();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, error_rxs.clone(), actor_sink.clone()), SoloAct);
+
+        let error = |line| InputError { source: "in.csv".to_string(), line, reason: "column 1 is empty".to_string() };
+        error_txs[0].testing_send_all(vec![error(2), error(5)], true);
+        error_txs[1].testing_send_all(vec![error(3)], true);
+        graph.start();
+        // every channel is closed, so the shutdown completes once they are drained
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        let mut lines: Vec<u64> = sink.take_records().iter().map(|e| e.line).collect();
+        lines.sort();
+        assert_eq!(lines, vec![2, 3, 5]);
+        assert_eq!(error(2).to_string(), "in.csv:2: column 1 is empty");
+        Ok(())
+    }
+}
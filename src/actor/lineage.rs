@@ -0,0 +1,169 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use serde_json::{json, Value};
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::arg::{GenMode, MainArg};
+use crate::envelope::Envelope;
+use crate::error::{AppError, Context};
+use crate::sink::Sink;
+
+/// Path every message of a run takes from its source to its destination.
+/// Messages carry no provenance beyond their source sequence number, so the path is fixed when
+/// the graph is built and each lineage record pairs it with that sequence number.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Lineage {
+    pub(crate) source: Value,
+    /// Actor names in pipeline order, from the source to the last stage before the destination.
+    pub(crate) stages: Vec<&'static str>,
+    pub(crate) destination: String,
+}
+
+impl Lineage {
+    /// Source description for the configured input: replay file, input file, stdin or generator settings.
+    pub(crate) fn source(args: &MainArg) -> Value {
+        if let Some(path) = &args.replay {
+            json!({ "kind": "replay", "path": path })
+        } else if let Some(path) = &args.input {
+            match args.gen_mode {
+                GenMode::Csv => json!({ "kind": "csv", "path": path, "column": args.csv_column }),
+                _ => json!({ "kind": "file", "path": path }),
+            }
+        } else {
+            match args.gen_mode {
+                GenMode::Counter => json!({ "kind": "counter", "start": args.start, "step": args.step }),
+                GenMode::Random => json!({ "kind": "random", "seed": args.seed }),
+                GenMode::Primes => json!({ "kind": "primes", "start": args.start }),
+                GenMode::Stdin => json!({ "kind": "stdin" }),
+                // rejected at startup without --input
+                GenMode::Csv => json!({ "kind": "csv" }),
+                GenMode::Burst => json!({ "kind": "burst", "start": args.start, "step": args.step
+                                        , "burst_size": args.burst_size, "burst_idle_ms": args.burst_idle_ms }),
+            }
+        }
+    }
+}
+
+/// Appends one JSON line per message: sequence, message, source, stages with versions
+/// and destination, so a single `jq` or `grep` answers where any output came from.
+pub(crate) struct LineageSink<W: Write + Send> {
+    out: W,
+    source: Value,
+    stages: Value,
+    destination: String,
+}
+
+impl<W: Write + Send> LineageSink<W> {
+    pub(crate) fn new(out: W, lineage: Lineage) -> Self {
+        // every stage is built from this crate, so each carries the crate version
+        let stages = lineage.stages.iter()
+            .map(|stage| json!({ "stage": stage, "version": env!("CARGO_PKG_VERSION") }))
+            .collect();
+        LineageSink { out, source: lineage.source, stages, destination: lineage.destination }
+    }
+}
+
+impl LineageSink<BufWriter<File>> {
+    pub(crate) fn append(path: &str, lineage: Lineage) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(LineageSink::new(BufWriter::new(file), lineage))
+    }
+}
+
+impl<W: Write + Send> Sink<Envelope<FizzBuzzMessage>> for LineageSink<W> {
+    fn write(&mut self, msg: &Envelope<FizzBuzzMessage>) -> io::Result<()> {
+        let record = json!({
+            "seq": msg.seq,
+            "message": format!("{:?}", msg.payload),
+            "source": self.source,
+            "stages": self.stages,
+            "destination": self.destination,
+        });
+        writeln!(self.out, "{}", record)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Pass-through stage in front of the terminal sink, recording each message's lineage
+/// to `--lineage` as it is handed on to the destination.
+pub async fn run(actor: SteadyActorShadow
+                 , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                 , forward_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , lineage: Lineage) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&rx], [&forward_tx]), rx, forward_tx, lineage).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , forward_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , lineage: Lineage) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let path = args.lineage.clone().expect("lineage stage requires --lineage");
+    let mut sink = LineageSink::append(&path, lineage).map_err(AppError::SinkIo)
+        .context(format!("opening lineage file {}", path))?;
+
+    let mut rx = rx.lock().await;
+    let mut forward_tx = forward_tx.lock().await;
+
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(forward_tx.mark_closed())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut forward_tx, 1));
+
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut forward_tx));
+        for _ in 0..items {
+            if let Some(msg) = actor.try_take(&mut rx) {
+                sink.write(&msg).map_err(AppError::SinkIo)?;
+                let _ = actor.try_send(&mut forward_tx, msg);
+            }
+        }
+        sink.flush().map_err(AppError::SinkIo)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod lineage_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_lineage() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("standard_lineage_test.jsonl");
+        let _ = std::fs::remove_file(&path);
+        let args = MainArg { lineage: Some(path.to_string_lossy().to_string())
+                           , gen_mode: GenMode::Random
+                           , seed: 7
+                           , ..MainArg::default() };
+        let lineage = Lineage { source: Lineage::source(&args), stages: vec!["GENERATOR", "WORKER"], destination: "LOGGER".to_string() };
+
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (tx, rx) = graph.channel_builder().build();
+        let (forward_tx, forward_rx) = graph.channel_builder().build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, rx.clone(), forward_tx.clone(), lineage.clone()), SoloAct);
+
+        let sent = sealed(Instant::now(), (1..=5).map(FizzBuzzMessage::new));
+        tx.testing_send_all(sent.clone(), true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        // messages pass through untouched, one lineage line each
+        assert_steady_rx_eq_take!(&forward_rx, sent);
+        let records: Vec<Value> = std::fs::read_to_string(&path)?.lines()
+            .map(serde_json::from_str).collect::<Result<_, _>>()?;
+        assert_eq!(records.len(), 5);
+        assert_eq!(records[2]["seq"], 2);
+        assert_eq!(records[2]["message"], "Fizz");
+        assert_eq!(records[2]["source"], json!({ "kind": "random", "seed": 7 }));
+        assert_eq!(records[2]["stages"][1]["stage"], "WORKER");
+        assert_eq!(records[2]["stages"][1]["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(records[2]["destination"], "LOGGER");
+        Ok(())
+    }
+}
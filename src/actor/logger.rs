@@ -1,23 +1,205 @@
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::sync::Arc;
+use std::sync::atomic::AtomicU64;
+use serde::Serialize;
 use steady_state::*;
+use crate::actor::file_logger::FileSink;
 use crate::actor::worker::FizzBuzzMessage;
+use crate::arg::LogFormat;
+use crate::clock;
+use crate::envelope::{Delivery, Envelope};
+use crate::error::{AppError, Context};
+use crate::sink::{ConsoleSink, CsvLog, FanOut, JsonSink, Sampled, Sink, SinkSpec};
+use crate::syslog::SyslogSink;
+
+/// What the logger keeps across a restart after a panic: whether the CSV header is out,
+/// so a restarted logger goes on with the rows instead of starting the table again,
+/// the errors of each `--log-sink` so far and the summary of the whole run.
+#[derive(Default)]
+pub(crate) struct LoggerState {
+    pub(crate) header_written: bool,
+    pub(crate) sink_errors: Vec<Arc<AtomicU64>>,
+    pub(crate) summary: RunSummary,
+}
+
+/// What a logger saw over the whole run, logged as a block on a clean shutdown and written as
+/// JSON to `--summary-out`. Times are when the logger took the messages, in milliseconds
+/// since the Unix epoch as in the JSON lines.
+#[derive(Serialize, Default)]
+pub(crate) struct RunSummary {
+    messages: u64,
+    /// Messages per kind, `Fizz` or the labels of --rule run together, `Value` for the rest.
+    kinds: BTreeMap<String, u64>,
+    first_ts: Option<u64>,
+    last_ts: Option<u64>,
+    /// Messages per second between the first and the last, None before time has passed.
+    rate: Option<f64>,
+}
+
+impl RunSummary {
+    pub(crate) fn record(&mut self, msg: &FizzBuzzMessage, ts: u64) {
+        self.messages += 1;
+        *self.kinds.entry(msg.kind()).or_default() += 1;
+        self.first_ts.get_or_insert(ts);
+        self.last_ts = Some(ts);
+        self.rate = match (self.first_ts, self.last_ts) {
+            // n messages are n - 1 intervals apart
+            (Some(first), Some(last)) if last > first => Some((self.messages - 1) as f64 * 1000.0 / (last - first) as f64),
+            _ => None,
+        };
+    }
+
+    /// The summary as the lines of a block, one count or time per line.
+    pub(crate) fn block(&self) -> String {
+        let mut lines = vec!["Run summary".to_string(), format!("  messages  {}", self.messages)];
+        lines.extend(self.kinds.iter().map(|(kind, count)| format!("  {:<9} {}", kind, count)));
+        let time = |ts: Option<u64>| ts.map_or("-".to_string(), clock::timestamp);
+        lines.push(format!("  first     {}", time(self.first_ts)));
+        lines.push(format!("  last      {}", time(self.last_ts)));
+        lines.push(format!("  rate      {}", self.rate.map_or("-".to_string(), |rate| format!("{:.1}/s", rate))));
+        lines.join("\n")
+    }
+
+    pub(crate) fn write_json(&self, path: &str) -> Result<(), AppError> {
+        let file = File::create(path).map_err(AppError::SinkIo).context(format!("creating summary file {}", path))?;
+        let mut out = BufWriter::new(file);
+        serde_json::to_writer_pretty(&mut out, self).map_err(io::Error::from)
+            .and_then(|_| out.flush()).map_err(AppError::SinkIo).context(format!("writing summary file {}", path))
+    }
+}
 
 /// Simple consumer actor demonstrating reactive message processing.
 /// Logger actors typically have no outgoing channels and focus on
 /// efficient message consumption and external system integration.
-pub async fn run(actor: SteadyActorShadow, fizz_buzz_rx: SteadyRx<FizzBuzzMessage>) -> Result<(),Box<dyn Error>> {
+/// Under `--log-format json` each message is a JSON line on stdout instead of a log line,
+/// under `--log-format csv` a row on stdout or in `--csv-file`, and with `--syslog` an RFC 5424
+/// line to the syslog endpoint. With `--log-sink` it goes to each of the sinks given.
+/// Whichever it is, `--log-filter` and `--log-sample` decide which messages get there.
+pub async fn run(actor: SteadyActorShadow
+                 , fizz_buzz_rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                 , state: SteadyState<LoggerState>) -> Result<(),Box<dyn Error>> {
     let actor = actor.into_spotlight([&fizz_buzz_rx], []);
     if actor.use_internal_behavior {
-        internal_behavior(actor, fizz_buzz_rx).await
+        let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+        if args.syslog.is_some() {
+            let sink = SyslogSink::from_args(args)?;
+            return sampled_behavior(actor, fizz_buzz_rx, state, sink).await;
+        }
+        if !args.log_sinks.is_empty() {
+            let sink = fan_out(args, &mut state.lock(LoggerState::default).await.sink_errors)?;
+            return sampled_behavior(actor, fizz_buzz_rx, state, sink).await;
+        }
+        let (format, clock, csv_file) = (args.log_format, args.clock.clone(), args.csv_file.clone());
+        match (format, csv_file) {
+            (LogFormat::Text, _) => sampled_behavior(actor, fizz_buzz_rx, state, ConsoleSink::default()).await,
+            (LogFormat::Json, _) => sampled_behavior(actor, fizz_buzz_rx, state, JsonSink::new(io::stdout(), clock)).await,
+            (LogFormat::Csv, Some(path)) => {
+                let fresh = !state.lock(LoggerState::default).await.header_written;
+                let file = open_csv(&path, fresh).map_err(AppError::SinkIo).context(format!("opening csv file {}", path))?;
+                csv_behavior(actor, fizz_buzz_rx, state, BufWriter::new(file)).await
+            }
+            (LogFormat::Csv, None) => csv_behavior(actor, fizz_buzz_rx, state, io::stdout()).await,
+        }
     } else { //as with other edge actors, we use simulated behavior to enable testing from main
         actor.simulated_behavior(vec!(&fizz_buzz_rx)).await
     }
 }
 
+/// Kind-specific consumer used with `--route-by-kind`, receiving only its subset.
+/// Lines carry `label` so they stand apart from the merged view's `Msg` lines.
+/// Its delivery summary counts the messages routed to the other kinds as missing.
+/// JSON and syslog lines carry their kind already, so `label` is only on text lines.
+pub async fn run_for_kind(actor: SteadyActorShadow, fizz_buzz_rx: SteadyRx<Envelope<FizzBuzzMessage>>, state: SteadyState<LoggerState>
+                          , label: String) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&fizz_buzz_rx], []);
+    if actor.use_internal_behavior {
+        let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+        if args.syslog.is_some() {
+            let sink = SyslogSink::from_args(args)?;
+            return sampled_behavior(actor, fizz_buzz_rx, state, sink).await;
+        }
+        let (format, clock) = (args.log_format, args.clock.clone());
+        match format {
+            // csv is one table from the single logger, rejected at startup with kinds or shards
+            LogFormat::Text | LogFormat::Csv => sampled_behavior(actor, fizz_buzz_rx, state, ConsoleSink::labeled(label)).await,
+            LogFormat::Json => sampled_behavior(actor, fizz_buzz_rx, state, JsonSink::new(io::stdout(), clock)).await,
+        }
+    } else {
+        actor.simulated_behavior(vec!(&fizz_buzz_rx)).await
+    }
+}
+
+/// The sinks of `--log-sink` in the order given. JSON files are appended to, as the file logger's are.
+/// Each counts its errors into `errors`, started on the first start and handed on after a restart.
+fn fan_out(args: &crate::MainArg, errors: &mut Vec<Arc<AtomicU64>>) -> Result<FanOut<FizzBuzzMessage>, Box<dyn Error>> {
+    let mut fan_out = FanOut::default();
+    for (index, spec) in SinkSpec::parse_all(&args.log_sinks).map_err(AppError::Config)?.into_iter().enumerate() {
+        let name = spec.to_string();
+        if errors.len() <= index {
+            errors.push(Arc::default());
+        }
+        let errors = errors[index].clone();
+        fan_out = match spec {
+            SinkSpec::Console => fan_out.with(name, ConsoleSink::default(), errors),
+            SinkSpec::File(path) => fan_out.with(name, FileSink::open(&path, args)?, errors),
+            SinkSpec::Json(path) => {
+                let file = OpenOptions::new().create(true).append(true).open(&path).map_err(AppError::SinkIo)
+                    .context(format!("opening json file {}", path))?;
+                fan_out.with(name, JsonSink::new(BufWriter::new(file), args.clock.clone()), errors)
+            }
+        };
+    }
+    Ok(fan_out)
+}
+
+/// A fresh table on the first start of a run, a restart appends to the one it left.
+fn open_csv(path: &str, fresh: bool) -> io::Result<File> {
+    OpenOptions::new().create(true).write(true).truncate(fresh).append(!fresh).open(path)
+}
+
+/// The logger loop writing CSV rows to `out`, the header only if no earlier start wrote it.
+async fn csv_behavior<A: SteadyActor, W: Write + Send>(actor: A
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , state: SteadyState<LoggerState>
+                                           , out: W) -> Result<(),Box<dyn Error>> {
+    let clock = actor.args::<crate::MainArg>().expect("unable to downcast").clock.clone();
+    let mut sink = CsvLog::new(out, clock);
+    let mut started = state.lock(LoggerState::default).await;
+    if !started.header_written {
+        sink.header()?;
+        started.header_written = true;
+    }
+    drop(started);
+    sampled_behavior(actor, rx, state, sink).await
+}
+
+/// The logger loop with `--log-filter` and `--log-sample` in front of `sink`. Only the output
+/// is thinned out, the delivery summary still counts every message that arrived.
+async fn sampled_behavior<A: SteadyActor, S: Sink<FizzBuzzMessage>>(actor: A
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , state: SteadyState<LoggerState>
+                                           , sink: S) -> Result<(),Box<dyn Error>> {
+    let sink = Sampled::from_args(sink, actor.args::<crate::MainArg>().expect("unable to downcast")).map_err(AppError::Config)?;
+    internal_behavior(actor, rx, state, sink).await
+}
+
 /// Event-driven processing pattern for immediate message handling.
 /// This approach ensures minimal latency between message arrival and processing,
 /// making it ideal for logging, monitoring, and real-time notification systems.
-async fn internal_behavior<A: SteadyActor>(mut actor: A
-                                           , rx: SteadyRx<FizzBuzzMessage>) -> Result<(),Box<dyn Error>> {
+/// The sink decides where messages go, so other terminal actors reuse this loop.
+/// Most sinks only take the payload, the envelope feeds the delivery summary
+/// logged at shutdown: end-to-end latency and sequence numbers never seen.
+/// The run summary is kept in `state`, so after a restart it still covers the whole run.
+pub(crate) async fn internal_behavior<A: SteadyActor, S: Sink<FizzBuzzMessage>>(mut actor: A
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , state: SteadyState<LoggerState>
+                                           , mut sink: S) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let (clock, summary_out) = (args.clock.clone(), args.summary_out.clone());
+    let mut delivery = Delivery::default();
+    let mut state = state.lock(LoggerState::default).await;
     let mut rx = rx.lock().await;
     // Termination condition waits for channel closure and message drainage.
     // This ensures all messages are processed before the actor terminates,
@@ -32,13 +214,26 @@ async fn internal_behavior<A: SteadyActor>(mut actor: A
         // so we may be here longer than we want. NOTE: is_running() checks
         // for shutdown and relays collected telemetry.
         while let Some(msg) = actor.try_take(&mut rx) { //#!#//
-            // Message processing with structured logging integration.
-            // The framework automatically handles log formatting, threading,
-            // and output routing based on configuration. 
-            info!("Msg {:?}", msg );
+            delivery.record(&msg, &clock);
+            state.summary.record(&msg.payload, clock.wall().as_millis() as u64);
+            sink.write_sealed(&msg)?;
         }
+        sink.flush()?;
 
     }
+    if delivery.delivered() > 0 {
+        info!("{}", delivery.report());
+    }
+    if let Some(report) = sink.report() {
+        info!("{}", report);
+    }
+    // only a clean shutdown saw the whole run, anything else would pass for a short one
+    if rx.is_closed_and_empty() {
+        info!("{}", state.summary.block());
+        if let Some(path) = summary_out {
+            state.summary.write_json(&path)?;
+        }
+    }
     Ok(())
 }
 
@@ -52,24 +247,220 @@ fn test_logger() -> Result<(), Box<dyn std::error::Error>> {
     //in this case, there is no outgoing channel, so we must test against the logs
     let _guard = start_log_capture();  //#!#//
 
-    let mut graph = GraphBuilder::for_testing().build(());
+    let mut graph = GraphBuilder::for_testing().build(crate::MainArg::default());
     let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
 
     graph.actor_builder().with_name("UnitTest")
         .build(move |context| {
-            internal_behavior(context, fizz_buzz_rx.clone())
+            internal_behavior(context, fizz_buzz_rx.clone(), new_state(), ConsoleSink::default())
         }, SoloAct);
 
     graph.start();
     // Testing infrastructure provides message injection capabilities
     // for precise control over actor input during verification.
-    fizz_buzz_tx.testing_send_all(vec![FizzBuzzMessage::Fizz],true);
+    // under --keep-values the input is printed with its result
+    fizz_buzz_tx.testing_send_all(vec![Envelope::new(0, std::time::Instant::now(), FizzBuzzMessage::Fizz)
+                                       , Envelope::new(1, std::time::Instant::now(), FizzBuzzMessage::Classified(FizzBuzzMessage::classified(15)))],true);
 
     graph.request_shutdown(); //essential to finish running test
     graph.block_until_stopped(Duration::from_secs(10000))?;
     // Log assertion macros enable verification of logging behavior
     // across multi-threaded execution environments.
-    assert_in_logs!(["Msg Fizz"]); //#!#//
+    assert_in_logs!(["Msg Fizz", "Msg 15 -> FizzBuzz", "Delivered 2 messages, 0 missing"]); //#!#//
+
+    Ok(())
+}
+
+/// Fake sinks capture typed records, so assertions need no log scraping.
+#[test]
+fn test_logger_fake_sink() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::envelope::envelope_tests::sealed;
+    use crate::sink::FakeSink;
+
+    let sink = FakeSink::default();
+    let mut graph = GraphBuilder::for_testing().build(crate::MainArg::default());
+    let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
+
+    let actor_sink = sink.clone();
+    graph.actor_builder().with_name("UnitTest")
+        .build(move |context| internal_behavior(context, fizz_buzz_rx.clone(), new_state(), actor_sink.clone()), SoloAct);
+
+    graph.start();
+    fizz_buzz_tx.testing_send_all(sealed(std::time::Instant::now(), vec![FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(7)]), true);
+    graph.request_shutdown();
+    graph.block_until_stopped(Duration::from_secs(1))?;
+
+    assert_eq!(sink.take_records(), vec![FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(7)]);
+    Ok(())
+}
+
+/// A restarted logger goes on with the rows, the header stays at the top of the table.
+#[test]
+fn test_logger_csv() -> Result<(), Box<dyn std::error::Error>> {
+    use std::sync::{Arc, Mutex};
+    use crate::clock::Clock;
+    use crate::envelope::UNSEQUENCED;
+
+    /// Output shared with the test, as stdout would be shared by both starts of the logger.
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+            self.0.lock().expect("shared output lock").write(bytes)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let out = Shared::default();
+    let state = new_state();
+    let captured = std::time::Instant::now();
+    // two runs of the actor on the same state stand in for a start and a restart
+    for messages in [vec![Envelope::new(0, captured, FizzBuzzMessage::Fizz), Envelope::new(1, captured, FizzBuzzMessage::Value(4))]
+                     , vec![Envelope::new(UNSEQUENCED, captured, FizzBuzzMessage::Classified(FizzBuzzMessage::classified(15)))]] {
+        let (clock, _source) = Clock::virtual_clock_at(Duration::from_millis(1_700_000_000_000));
+        let mut graph = GraphBuilder::for_testing().build(crate::MainArg { clock, ..crate::MainArg::default() });
+        let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
+        let (out, state) = (out.clone(), state.clone());
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| csv_behavior(context, fizz_buzz_rx.clone(), state.clone(), out.clone()), SoloAct);
+
+        graph.start();
+        fizz_buzz_tx.testing_send_all(messages, true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+    }
+
+    let table = String::from_utf8(out.0.lock().expect("shared output lock").clone())?;
+    assert_eq!(table, "sequence,value,classification,timestamp\n\
+                       0,,Fizz,1700000000000\n\
+                       1,4,Value,1700000000000\n\
+                       ,15,FizzBuzz,1700000000000\n");
+    Ok(())
+}
+
+/// The first start truncates what an earlier run left in --csv-file, a restart appends to it.
+#[test]
+fn test_logger_csv_file() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::clock::Clock;
+
+    let path = std::env::temp_dir().join(format!("standard_logger_csv_test_{}.csv", std::process::id()));
+    std::fs::write(&path, "left by an earlier run\n")?;
+    let path_name = path.to_string_lossy().to_string();
+    let state = new_state();
+    let captured = std::time::Instant::now();
+    for messages in [vec![Envelope::new(0, captured, FizzBuzzMessage::Value(7))]
+                     , vec![Envelope::new(1, captured, FizzBuzzMessage::Classified(FizzBuzzMessage::classified(9)))]] {
+        let (clock, _source) = Clock::virtual_clock_at(Duration::from_millis(1_700_000_000_000));
+        let mut graph = GraphBuilder::for_testing().build(crate::MainArg { clock, ..crate::MainArg::default() });
+        let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
+        let (path_name, state) = (path_name.clone(), state.clone());
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| {
+                let (path_name, state) = (path_name.clone(), state.clone());
+                async move {
+                    // as in run, the table is fresh until the header is written
+                    let fresh = !state.lock(LoggerState::default).await.header_written;
+                    let file = open_csv(&path_name, fresh)?;
+                    csv_behavior(context, fizz_buzz_rx.clone(), state, BufWriter::new(file)).await
+                }
+            }, SoloAct);
+
+        graph.start();
+        fizz_buzz_tx.testing_send_all(messages, true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+    }
+
+    let table = std::fs::read_to_string(&path)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(table, "sequence,value,classification,timestamp\n\
+                       0,7,Value,1700000000000\n\
+                       1,9,Fizz,1700000000000\n");
+    Ok(())
+}
+
+/// Every sink of `--log-sink` gets every message, each in its own format.
+#[test]
+fn test_logger_fan_out() -> Result<(), Box<dyn std::error::Error>> {
+    use crate::clock::Clock;
+    use crate::envelope::envelope_tests::sealed;
+
+    let dir = std::env::temp_dir().join("standard_logger_fan_out_test");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let (text, json) = (dir.join("out.log"), dir.join("out.jsonl"));
+    let (clock, _source) = Clock::virtual_clock_at(Duration::from_millis(1_700_000_000_000));
+    let args = crate::MainArg { log_sinks: vec!["console".to_string()
+                                                , format!("file={}", text.display())
+                                                , format!("json={}", json.display())]
+                              , clock
+                              , ..crate::MainArg::default() };
+
+    let sink_args = args.clone();
+    let mut graph = GraphBuilder::for_testing().build(args);
+    let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
+    graph.actor_builder().with_name("UnitTest")
+        .build(move |context| internal_behavior(context, fizz_buzz_rx.clone(), new_state(), fan_out(&sink_args, &mut Vec::new()).expect("sinks open")), SoloAct);
+
+    graph.start();
+    fizz_buzz_tx.testing_send_all(sealed(std::time::Instant::now(), vec![FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(7)]), true);
+    graph.request_shutdown();
+    graph.block_until_stopped(Duration::from_secs(1))?;
+
+    assert_eq!(std::fs::read_to_string(&text)?, "Fizz\nValue(7)\n");
+    assert_eq!(std::fs::read_to_string(&json)?, "{\"kind\":\"Fizz\",\"ts\":1700000000000}\n\
+                                                 {\"kind\":\"Value\",\"value\":7,\"ts\":1700000000000}\n");
+    Ok(())
+}
+
+/// Counts, the first and last time and the rate in between, as a block and as JSON.
+#[test]
+fn test_run_summary() {
+    let mut summary = RunSummary::default();
+    assert_eq!(summary.block(), "Run summary\n  messages  0\n  first     -\n  last      -\n  rate      -");
+
+    summary.record(&FizzBuzzMessage::Fizz, 1_700_000_000_000);
+    summary.record(&FizzBuzzMessage::Value(7), 1_700_000_000_500);
+    summary.record(&FizzBuzzMessage::Fizz, 1_700_000_002_000);
+    assert_eq!(summary.block(), "Run summary\n  messages  3\n  Fizz      2\n  Value     1\n\
+                                 \x20 first     2023-11-14T22:13:20.000Z\n  last      2023-11-14T22:13:22.000Z\n  rate      1.0/s");
+    assert_eq!(serde_json::to_string(&summary).expect("serializable")
+               , "{\"messages\":3,\"kinds\":{\"Fizz\":2,\"Value\":1},\"first_ts\":1700000000000,\"last_ts\":1700000002000,\"rate\":1.0}");
+}
+
+/// A clean shutdown logs the block and leaves the JSON in `--summary-out`, counting what the
+/// logger took before a restart too.
+#[test]
+fn test_logger_summary_out() -> Result<(), Box<dyn std::error::Error>> {
+    use steady_logger::*;
+    use crate::envelope::envelope_tests::sealed;
+
+    let _guard = start_log_capture();
+    let path = std::env::temp_dir().join(format!("standard_logger_summary_test_{}.json", std::process::id()));
+    let state = new_state();
+    // two runs of the actor on the same state stand in for a start and a restart
+    for messages in [vec![FizzBuzzMessage::Buzz, FizzBuzzMessage::FizzBuzz], vec![FizzBuzzMessage::Buzz]] {
+        let args = crate::MainArg { summary_out: Some(path.to_string_lossy().to_string()), ..crate::MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (fizz_buzz_tx, fizz_buzz_rx) = graph.channel_builder().build();
+        let state = state.clone();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, fizz_buzz_rx.clone(), state.clone(), ConsoleSink::default()), SoloAct);
+
+        graph.start();
+        fizz_buzz_tx.testing_send_all(sealed(std::time::Instant::now(), messages), true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+    }
 
+    assert_in_logs!(["Run summary"]);
+    let written: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+    std::fs::remove_file(&path)?;
+    assert_eq!(written["messages"], 3);
+    assert_eq!(written["kinds"], serde_json::json!({"Buzz": 2, "FizzBuzz": 1}));
     Ok(())
 }
@@ -0,0 +1,105 @@
+use steady_state::*;
+use crate::actor::generator::GeneratorCommand;
+use crate::envelope::Envelope;
+
+/// Longest the merger sleeps on one idle partition before checking the others.
+const POLL_RATE: Duration = Duration::from_millis(5);
+
+/// Fan-in for `--generators N`: takes from every partition generator in rotation and
+/// forwards into the single generator channel, so everything downstream is unchanged.
+/// Every partition numbers its own values, so they are renumbered here in merged order
+/// while keeping their capture time.
+/// Control commands are fanned out the other way, pausing or resuming every generator.
+pub async fn run(actor: SteadyActorShadow
+                 , control_rx: SteadyRx<GeneratorCommand>
+                 , partition_controls: Vec<SteadyTx<GeneratorCommand>>
+                 , partitions: Vec<SteadyRx<Envelope<u64>>>
+                 , generated_tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&control_rx], [&generated_tx]), control_rx, partition_controls, partitions, generated_tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , control_rx: SteadyRx<GeneratorCommand>
+                                           , partition_controls: Vec<SteadyTx<GeneratorCommand>>
+                                           , partitions: Vec<SteadyRx<Envelope<u64>>>
+                                           , generated_tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    let mut control_rx = control_rx.lock().await;
+    let mut generated_tx = generated_tx.lock().await;
+    let mut controls = Vec::with_capacity(partition_controls.len());
+    for control in &partition_controls {
+        controls.push(control.lock().await);
+    }
+    let mut rxs = Vec::with_capacity(partitions.len());
+    for rx in &partitions {
+        rxs.push(rx.lock().await);
+    }
+    let mut next = 0;
+    let mut seq = 0;
+
+    // every channel is marked closed, not just up to the first which reports false
+    while actor.is_running(|| i!(rxs.iter_mut().all(|rx| rx.is_closed_and_empty()))
+                              && i!(controls.iter_mut().fold(true, |closed, tx| tx.mark_closed() && closed))
+                              && i!(generated_tx.mark_closed())) {
+        await_for_any!(actor.wait_avail(&mut rxs[next], 1)
+                     , actor.wait_avail(&mut control_rx, 1)
+                     , actor.wait(POLL_RATE));
+
+        while let Some(command) = actor.try_take(&mut control_rx) {
+            for control in controls.iter_mut() {
+                let _ = actor.try_send(control, command);
+            }
+        }
+        // one pass over every partition, each drained as far as there is room downstream
+        for _ in 0..rxs.len() {
+            let items = actor.avail_units(&mut rxs[next]).min(actor.vacant_units(&mut generated_tx));
+            for _ in 0..items {
+                if let Some(envelope) = actor.try_take(&mut rxs[next]) {
+                    let _ = actor.try_send(&mut generated_tx, Envelope { seq, ..envelope });
+                    seq += 1;
+                }
+            }
+            next = (next + 1) % rxs.len();
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod merger_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_merger() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (control_tx, control_rx) = graph.channel_builder().build();
+        let (part_control_txs, part_control_rxs): (Vec<_>, Vec<_>) = (0..2).map(|_| graph.channel_builder().build()).unzip();
+        let (part_txs, part_rxs): (Vec<_>, Vec<_>) = (0..2).map(|_| graph.channel_builder().build()).unzip();
+        let (generated_tx, generated_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), part_control_txs.clone()
+                                                    , part_rxs.clone(), generated_tx.clone()), SoloAct);
+
+        let captured = Instant::now();
+        part_txs[0].testing_send_all(sealed(captured, vec![0, 2, 4]), true);
+        part_txs[1].testing_send_all(sealed(captured, vec![1, 3]), true);
+        control_tx.testing_send_all(vec![GeneratorCommand::Pause], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        // one rotation drains each partition in turn, every partition keeping its own order,
+        // and the merged stream is numbered again from zero
+        assert_steady_rx_eq_take!(&generated_rx, sealed(captured, vec![0, 2, 4, 1, 3]));
+        // the command reached both generators
+        for rx in &part_control_rxs {
+            assert_steady_rx_eq_take!(rx, vec![GeneratorCommand::Pause]);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use steady_state::*;
+use crate::actor::dead_letter::{DeadLetter, Reason};
+use crate::envelope::Envelope;
+use crate::toggles::{Toggle, Toggles};
+
+/// A value the parser accepted, the only input the classifier of the staged topology takes.
+/// Holding one means the value passed validation, so the classifier never checks it again.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct Number(u64);
+
+impl Number {
+    /// Accepts `value` up to `max_value`, anything above becomes a dead letter as in the worker.
+    pub(crate) fn parse(value: u64, max_value: u64) -> Result<Self, DeadLetter> {
+        if value <= max_value {
+            Ok(Number(value))
+        } else {
+            Err(DeadLetter { value, reason: Reason::AboveMaxValue })
+        }
+    }
+
+    pub(crate) fn value(self) -> u64 {
+        self.0
+    }
+}
+
+/// First stage of `--topology staged`: validates the source and priority values into typed
+/// `Number`s for the classifier, rejected values going to the dead letters.
+/// Runs free of the heartbeat, the classifier after it keeps the batch pacing.
+pub async fn run(actor: SteadyActorShadow
+                 , generator_rx: SteadyRx<Envelope<u64>>
+                 , priority_rx: SteadyRx<Envelope<u64>>
+                 , parsed_tx: SteadyTx<Envelope<Number>>
+                 , dead_letter_tx: SteadyTx<DeadLetter>
+                 , toggles: Toggles) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    let actor = actor.into_spotlight([&generator_rx, &priority_rx], [&parsed_tx, &dead_letter_tx]);
+    internal_behavior(actor, generator_rx, priority_rx, parsed_tx, dead_letter_tx, toggles).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , generator_rx: SteadyRx<Envelope<u64>>
+                                           , priority_rx: SteadyRx<Envelope<u64>>
+                                           , parsed_tx: SteadyTx<Envelope<Number>>
+                                           , dead_letter_tx: SteadyTx<DeadLetter>
+                                           , toggles: Toggles) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let max_value = args.max_value.unwrap_or(u64::MAX);
+
+    let mut generator_rx = generator_rx.lock().await;
+    let mut priority_rx = priority_rx.lock().await;
+    let mut parsed_tx = parsed_tx.lock().await;
+    let mut dead_letter_tx = dead_letter_tx.lock().await;
+    let mut rejected = 0u64;
+
+    while actor.is_running(|| i!(generator_rx.is_closed_and_empty())
+                              && i!(priority_rx.is_closed_and_empty())
+                              && i!(parsed_tx.mark_closed())
+                              && i!(dead_letter_tx.mark_closed())) {
+        await_for_all!(wait_for_any!(actor.wait_avail(&mut generator_rx, 1), actor.wait_avail(&mut priority_rx, 1))
+                     , actor.wait_vacant(&mut parsed_tx, 1));
+
+        // read once per round, the same toggle the worker reads once per batch
+        let validate = toggles.is_enabled(Toggle::Validation);
+        // priority values first, every taken value produces at most one parsed one
+        let mut room = actor.vacant_units(&mut parsed_tx);
+        for rx in [&mut priority_rx, &mut generator_rx] {
+            let items = actor.avail_units(rx).min(room);
+            for _ in 0..items {
+                if let Some(item) = actor.try_take(rx) {
+                    match Number::parse(item.payload, if validate { max_value } else { u64::MAX }) {
+                        Ok(number) => {
+                            let _ = actor.try_send(&mut parsed_tx, item.map(|_| number));
+                            room -= 1;
+                        }
+                        Err(letter) => {
+                            rejected += 1;
+                            actor.send_async(&mut dead_letter_tx, letter, SendSaturation::AwaitForRoom).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    info!("Parser rejected {} values", rejected);
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod parser_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_number() {
+        assert_eq!(Number::parse(7, 10).map(Number::value), Ok(7));
+        assert_eq!(Number::parse(11, 10), Err(DeadLetter { value: 11, reason: Reason::AboveMaxValue }));
+    }
+
+    #[test]
+    fn test_parser() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg { max_value: Some(4), ..MainArg::default() });
+        let (generate_tx, generate_rx) = graph.channel_builder().build();
+        let (priority_tx, priority_rx) = graph.channel_builder().build();
+        let (parsed_tx, parsed_rx) = graph.channel_builder().build();
+        let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, generate_rx.clone(), priority_rx.clone(), parsed_tx.clone()
+                                                    , dead_letter_tx.clone(), Toggles::default())
+                   , SoloAct);
+
+        let captured = Instant::now();
+        generate_tx.testing_send_all(sealed(captured, vec![2, 5, 3]), true);
+        priority_tx.testing_send_all(vec![], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // each number keeps the envelope of its value, the rejected one leaves a gap in the sequence
+        let parsed = sealed(captured, [Number(2), Number(5), Number(3)]);
+        assert_steady_rx_eq_take!(&parsed_rx, [parsed[0], parsed[2]]);
+        assert_steady_rx_eq_take!(&dead_letter_rx, [DeadLetter { value: 5, reason: Reason::AboveMaxValue }]);
+        Ok(())
+    }
+}
@@ -0,0 +1,140 @@
+use steady_state::*;
+use crate::envelope::Envelope;
+
+/// Accounting period of the limiter, each tick releases a slice of the per-second budget.
+const TICK: Duration = Duration::from_millis(100);
+
+/// Batch accounting for a messages-per-second limit.
+/// Budget is granted per tick in thousandths of a message so rates which do not
+/// divide evenly into ticks are still honored exactly over time.
+pub(crate) struct Budget {
+    per_sec: u64,
+    milli_messages: u64,
+}
+
+impl Budget {
+    pub(crate) fn new(per_sec: u64) -> Self {
+        Budget { per_sec, milli_messages: 0 }
+    }
+
+    /// Grants budget for the elapsed tick, never accumulating more than one second's worth
+    /// so an idle period cannot be followed by an unbounded burst.
+    pub(crate) fn tick(&mut self, elapsed: Duration) {
+        let granted = self.per_sec.saturating_mul(elapsed.as_millis() as u64);
+        self.milli_messages = self.milli_messages.saturating_add(granted).min(self.per_sec.saturating_mul(1000));
+    }
+
+    pub(crate) fn available(&self) -> usize {
+        (self.milli_messages / 1000) as usize
+    }
+
+    pub(crate) fn spend(&mut self, messages: usize) {
+        self.milli_messages -= (messages as u64 * 1000).min(self.milli_messages);
+    }
+}
+
+/// Throttles the generator stream to `--max-rate` messages per second,
+/// independent of the heartbeat, so demos run at a pace people can follow.
+pub async fn run(actor: SteadyActorShadow
+                 , rx: SteadyRx<Envelope<u64>>
+                 , tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&rx], [&tx]), rx, tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , rx: SteadyRx<Envelope<u64>>
+                                           , tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let mut budget = Budget::new(args.max_rate.expect("rate limiter requires --max-rate"));
+
+    let mut rx = rx.lock().await;
+    let mut tx = tx.lock().await;
+
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+        let clean = await_for_all!(actor.wait_periodic(TICK)
+                                 , actor.wait_avail(&mut rx, 1)
+                                 , actor.wait_vacant(&mut tx, 1));
+        budget.tick(TICK);
+
+        // on shutdown we drain without limit so the pipeline can close promptly
+        let allowed = if clean { budget.available() } else { usize::MAX };
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut tx)).min(allowed);
+        for _ in 0..items {
+            if let Some(envelope) = actor.try_take(&mut rx) {
+                let _ = actor.try_send(&mut tx, envelope);
+            }
+        }
+        budget.spend(items);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod rate_limiter_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use crate::sink::{FakeSink, Sink};
+    use super::*;
+
+    /// Records everything the limiter lets through, as it arrives.
+    async fn collect<A: SteadyActor>(mut actor: A, rx: SteadyRx<Envelope<u64>>, mut sink: FakeSink<Envelope<u64>>) -> Result<(), Box<dyn Error>> {
+        let mut rx = rx.lock().await;
+        while actor.is_running(|| rx.is_closed_and_empty()) {
+            await_for_all!(actor.wait_avail(&mut rx, 1));
+            while let Some(envelope) = actor.try_take(&mut rx) {
+                sink.write(&envelope)?;
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_budget() {
+        let mut budget = Budget::new(15);
+        budget.tick(TICK);
+        assert_eq!(budget.available(), 1); // 1.5 messages granted
+        budget.spend(1);
+        budget.tick(TICK);
+        assert_eq!(budget.available(), 2); // carried half plus 1.5
+        for _ in 0..100 {
+            budget.tick(TICK);
+        }
+        assert_eq!(budget.available(), 15); // capped at one second of budget
+    }
+
+    #[test]
+    fn test_rate_limiter() -> Result<(), Box<dyn Error>> {
+        let args = MainArg { max_rate: Some(10), ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (in_tx, in_rx) = graph.channel_builder().build();
+        let (out_tx, out_rx) = graph.channel_builder().build();
+        let released = FakeSink::default();
+        let collector_sink = released.clone();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, in_rx.clone(), out_tx.clone()), SoloAct);
+        graph.actor_builder().with_name("Collector")
+            .build(move |context| collect(context, out_rx.clone(), collector_sink.clone()), SoloAct);
+
+        let captured = Instant::now();
+        in_tx.testing_send_all(sealed(captured, 0..20u64), true);
+        graph.start();
+        // 10/s for half a second releases about five messages, one per tick
+        std::thread::sleep(Duration::from_millis(550));
+        let mut out = released.take_records();
+        let paced = out.len();
+        assert!((3..=7).contains(&paced), "{} messages released within half a second at 10/s", paced);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the rest were held back by the limit until the shutdown drained them
+        let delayed = released.take_records();
+        assert_eq!(delayed.len(), 20 - paced);
+        // order is preserved and nothing is lost
+        out.extend(delayed);
+        assert_eq!(out, sealed(captured, 0..20u64));
+        Ok(())
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::time::Instant;
+use steady_state::*;
+use crate::clock::Clock;
+use crate::envelope::Envelope;
+use crate::error::{AppError, Context};
+
+/// Identifies a recording file and its layout version.
+const MAGIC: &[u8; 8] = b"SSREC01\n";
+
+/// Binary log of channel traffic: the magic header followed by fixed 16 byte
+/// records of (microseconds since recording start, value), both little endian.
+/// Fixed size records keep the format trivial to read from any language.
+pub(crate) struct RecordWriter {
+    out: BufWriter<File>,
+    clock: Clock,
+    started: Instant,
+}
+
+impl RecordWriter {
+    /// Offsets written by [`RecordWriter::write`] are measured on `clock` from this call.
+    pub(crate) fn create(path: impl AsRef<Path>, clock: Clock) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        out.write_all(MAGIC)?;
+        let started = clock.now();
+        Ok(RecordWriter { out, clock, started })
+    }
+
+    pub(crate) fn write(&mut self, value: u64) -> io::Result<()> {
+        self.write_at(self.clock.since(self.started).as_micros() as u64, value)
+    }
+
+    pub(crate) fn write_at(&mut self, micros: u64, value: u64) -> io::Result<()> {
+        self.out.write_all(&micros.to_le_bytes())?;
+        self.out.write_all(&value.to_le_bytes())
+    }
+
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Streaming reader for files produced by [`RecordWriter`].
+pub(crate) struct RecordReader {
+    input: BufReader<File>,
+}
+
+impl RecordReader {
+    pub(crate) fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut input = BufReader::new(File::open(path)?);
+        let mut magic = [0u8; 8];
+        input.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a recording file"));
+        }
+        Ok(RecordReader { input })
+    }
+
+    /// Next (offset, value) record, None at a clean end of file.
+    pub(crate) fn next_record(&mut self) -> io::Result<Option<(Duration, u64)>> {
+        let mut record = [0u8; 16];
+        match self.input.read_exact(&mut record) {
+            Ok(()) => {
+                let (micros, value) = record.split_at(8);
+                Ok(Some((Duration::from_micros(u64::from_le_bytes(micros.try_into().expect("8 bytes")))
+                        , u64::from_le_bytes(value.try_into().expect("8 bytes")))))
+            }
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Tap on the generator channel which records every message with its arrival time
+/// while forwarding it unchanged, so production traffic can be captured for regression tests.
+/// Only the payload is recorded, a replay stamps its messages afresh.
+pub async fn run(actor: SteadyActorShadow
+                 , rx: SteadyRx<Envelope<u64>>
+                 , tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&rx], [&tx]), rx, tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , rx: SteadyRx<Envelope<u64>>
+                                           , tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let path = args.record.as_ref().expect("recorder requires --record");
+    let mut writer = RecordWriter::create(path, args.clock.clone()).map_err(AppError::SinkIo)
+        .context(format!("creating recording {}", path))?;
+
+    let mut rx = rx.lock().await;
+    let mut tx = tx.lock().await;
+
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut tx, 1));
+
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut tx));
+        for _ in 0..items {
+            if let Some(envelope) = actor.try_take(&mut rx) {
+                writer.write(envelope.payload)?;
+                let _ = actor.try_send(&mut tx, envelope);
+            }
+        }
+        writer.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod recorder_tests {
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_recorder() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("standard_recorder_test.bin");
+        // the virtual clock never moves unless told to, so every offset is exactly zero
+        let (clock, _source) = Clock::virtual_clock();
+        let args = MainArg { record: Some(path.to_string_lossy().to_string()), clock: clock.clone(), ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (in_tx, in_rx) = graph.channel_builder().build();
+        let (out_tx, out_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, in_rx.clone(), out_tx.clone()), SoloAct);
+
+        in_tx.testing_send_all(sealed(clock.now(), vec![3, 1, 4]), true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        assert_steady_rx_eq_take!(&out_rx, sealed(clock.now(), vec![3, 1, 4]));
+        let mut reader = RecordReader::open(&path)?;
+        let mut records = Vec::new();
+        while let Some(record) = reader.next_record()? {
+            records.push(record);
+        }
+        assert_eq!(records, vec![(Duration::ZERO, 3), (Duration::ZERO, 1), (Duration::ZERO, 4)]);
+        Ok(())
+    }
+}
@@ -0,0 +1,118 @@
+use steady_state::*;
+use crate::actor::generator::GeneratorCommand;
+use crate::actor::recorder::RecordReader;
+use crate::envelope::Envelope;
+use crate::error::{AppError, Context};
+
+/// Source actor which feeds a recording back into the graph in place of the generator.
+/// Message spacing follows `--replay-pacing`, and reaching the end of the recording
+/// closes the channel and requests shutdown, just like a finite batch job.
+/// It takes over the generator's control channel so pause and resume keep working.
+/// Replayed values are stamped as they are sent, numbered from zero.
+pub async fn run(actor: SteadyActorShadow
+                 , control_rx: SteadyRx<GeneratorCommand>
+                 , replay_tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&control_rx], [&replay_tx]);
+    if actor.use_internal_behavior {
+        internal_behavior(actor, control_rx, replay_tx).await
+    } else {
+        actor.simulated_behavior(vec!(&replay_tx)).await
+    }
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , control_rx: SteadyRx<GeneratorCommand>
+                                           , replay_tx: SteadyTx<Envelope<u64>>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let pacing = args.replay_pacing;
+    let clock = args.clock.clone();
+    let path = args.replay.as_ref().expect("replayer requires --replay");
+    let mut reader = RecordReader::open(path).map_err(AppError::SinkIo)
+        .context(format!("opening recording {}", path))?;
+
+    let mut control_rx = control_rx.lock().await;
+    let mut replay_tx = replay_tx.lock().await;
+    let mut previous = Duration::ZERO;
+    let mut done = false;
+    let mut paused = false;
+    let mut seq = 0;
+
+    while actor.is_running(|| replay_tx.mark_closed()) {
+        while let Some(command) = actor.try_take(&mut control_rx) {
+            info!("Replayer command {:?}", command);
+            match command {
+                GeneratorCommand::Pause => paused = true,
+                GeneratorCommand::Resume => paused = false,
+                // a recording has no counter to move
+                GeneratorCommand::SetValue(_) => warn!("Replayer ignores {:?}", command),
+            }
+        }
+        if paused && !done {
+            await_for_all!(actor.wait_avail(&mut control_rx, 1));
+            continue;
+        }
+        if done {
+            // everything was sent, wait here until the shutdown completes
+            await_for_all!(actor.wait_shutdown());
+            continue;
+        }
+        match reader.next_record()? {
+            Some((offset, value)) => {
+                let delay = pacing.delay(offset.saturating_sub(previous));
+                previous = offset;
+                if !delay.is_zero() {
+                    actor.wait(delay).await;
+                }
+                let envelope = Envelope::new(seq, clock.now(), value);
+                if actor.send_async(&mut replay_tx, envelope, SendSaturation::AwaitForRoom).await.is_sent() {
+                    seq += 1;
+                }
+            }
+            None => {
+                info!("Replay complete");
+                done = true;
+                actor.request_shutdown().await;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod replayer_tests {
+    use steady_state::*;
+    use crate::actor::recorder::RecordWriter;
+    use crate::arg::MainArg;
+    use crate::clock::Clock;
+    use crate::envelope::envelope_tests::sealed;
+    use crate::pacing::ReplayPacing;
+    use super::*;
+
+    #[test]
+    fn test_replayer() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("standard_replayer_test.bin");
+        let mut writer = RecordWriter::create(&path, Clock::default())?;
+        writer.write_at(0, 10)?;
+        writer.write_at(1_000, 20)?;
+        writer.write_at(2_000, 30)?;
+        writer.flush()?;
+
+        let (clock, _source) = Clock::virtual_clock();
+        let args = MainArg { replay: Some(path.to_string_lossy().to_string())
+                           , replay_pacing: ReplayPacing::Max
+                           , clock: clock.clone()
+                           , ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let (replay_tx, replay_rx) = graph.channel_builder().build();
+        let (_control_tx, control_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, control_rx.clone(), replay_tx.clone()), SoloAct);
+
+        graph.start();
+        // the replayer requests shutdown by itself at the end of the recording
+        graph.block_until_stopped(Duration::from_secs(2))?;
+        assert_steady_rx_eq_take!(&replay_rx, sealed(clock.now(), [10, 20, 30]));
+        Ok(())
+    }
+}
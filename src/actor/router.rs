@@ -0,0 +1,146 @@
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::envelope::Envelope;
+
+/// Content-based fan-out actor, the mirror image of the worker's fan-in.
+/// Each message kind leaves on its own typed channel so every category can have
+/// a dedicated consumer with its own capacity, telemetry and failure domain.
+/// Every message is also copied to the merged channel, so the main sink keeps the full view.
+pub async fn run(actor: SteadyActorShadow
+                 , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                 , merged_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , fizz_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , buzz_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , fizzbuzz_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , value_tx: SteadyTx<Envelope<FizzBuzzMessage>>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&rx], [&merged_tx, &fizz_tx, &buzz_tx, &fizzbuzz_tx, &value_tx])
+                      , rx, merged_tx, fizz_tx, buzz_tx, fizzbuzz_tx, value_tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , merged_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , fizz_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , buzz_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , fizzbuzz_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , value_tx: SteadyTx<Envelope<FizzBuzzMessage>>) -> Result<(),Box<dyn Error>> {
+    let mut rx = rx.lock().await;
+    let mut merged_tx = merged_tx.lock().await;
+    let mut fizz_tx = fizz_tx.lock().await;
+    let mut buzz_tx = buzz_tx.lock().await;
+    let mut fizzbuzz_tx = fizzbuzz_tx.lock().await;
+    let mut value_tx = value_tx.lock().await;
+
+    while actor.is_running(|| i!(rx.is_closed_and_empty())
+                              && i!(merged_tx.mark_closed())
+                              && i!(fizz_tx.mark_closed())
+                              && i!(buzz_tx.mark_closed())
+                              && i!(fizzbuzz_tx.mark_closed())
+                              && i!(value_tx.mark_closed())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1));
+
+        while let Some(msg) = actor.try_take(&mut rx) {
+            // The routing decision is made per message, only the selected channel
+            // applies backpressure so a slow category never blocks on a fast one's room.
+            let tx = match msg.payload {
+                FizzBuzzMessage::Fizz => &mut fizz_tx,
+                FizzBuzzMessage::Buzz => &mut buzz_tx,
+                FizzBuzzMessage::FizzBuzz => &mut fizzbuzz_tx,
+                FizzBuzzMessage::Value(_) => &mut value_tx,
+                // --rule conflicts with --route-by-kind, a labeled value never gets here
+                FizzBuzzMessage::Classified(_) => &mut value_tx,
+            };
+            actor.send_async(tx, msg, SendSaturation::AwaitForRoom).await;
+            actor.send_async(&mut merged_tx, msg, SendSaturation::AwaitForRoom).await;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod router_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    #[test]
+    fn test_router() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (tx, rx) = graph.channel_builder().build();
+        let (merged_tx, merged_rx) = graph.channel_builder().build();
+        let (fizz_tx, fizz_rx) = graph.channel_builder().build();
+        let (buzz_tx, buzz_rx) = graph.channel_builder().build();
+        let (fizzbuzz_tx, fizzbuzz_rx) = graph.channel_builder().build();
+        let (value_tx, value_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, rx.clone(), merged_tx.clone(), fizz_tx.clone(), buzz_tx.clone()
+                                                    , fizzbuzz_tx.clone(), value_tx.clone())
+                   , SoloAct);
+
+        let sent = sealed(Instant::now(), (0..7).map(FizzBuzzMessage::new));
+        tx.testing_send_all(sent.clone(), true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+
+        // every kind keeps the sequence numbers of its messages
+        assert_steady_rx_eq_take!(&fizzbuzz_rx, [sent[0]]);
+        assert_steady_rx_eq_take!(&fizz_rx, [sent[3], sent[6]]);
+        assert_steady_rx_eq_take!(&buzz_rx, [sent[5]]);
+        assert_steady_rx_eq_take!(&value_rx, [sent[1], sent[2], sent[4]]);
+        // the merged view keeps every message in its original order
+        assert_steady_rx_eq_take!(&merged_rx, sent);
+        Ok(())
+    }
+
+    #[test]
+    fn test_router_per_kind_order() -> Result<(), Box<dyn Error>> {
+        use crate::actor::logger;
+        use crate::sink::{verify_order_per_shard, FakeSink};
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let channel_builder = graph.channel_builder().with_capacity(1024);
+        let (tx, rx) = channel_builder.build();
+        let (merged_tx, merged_rx) = channel_builder.build();
+        let (fizz_tx, fizz_rx) = channel_builder.build();
+        let (buzz_tx, buzz_rx) = channel_builder.build();
+        let (fizzbuzz_tx, fizzbuzz_rx) = channel_builder.build();
+        let (value_tx, value_rx) = channel_builder.build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, rx.clone(), merged_tx.clone(), fizz_tx.clone(), buzz_tx.clone()
+                                                    , fizzbuzz_tx.clone(), value_tx.clone())
+                   , SoloAct);
+        // one logger per shard, as --route-by-kind builds them, each writing to its own sink
+        let sinks: Vec<(&'static str, FakeSink<FizzBuzzMessage>)> = vec![("merged", FakeSink::default()), ("fizz", FakeSink::default())
+                                                                , ("buzz", FakeSink::default()), ("fizzbuzz", FakeSink::default())
+                                                                , ("value", FakeSink::default())];
+        for ((name, sink), shard_rx) in sinks.iter().zip([merged_rx, fizz_rx, buzz_rx, fizzbuzz_rx, value_rx]) {
+            let sink = sink.clone();
+            graph.actor_builder().with_name(*name)
+                .build(move |context| logger::internal_behavior(context, shard_rx.clone(), new_state(), sink.clone()), SoloAct);
+        }
+
+        tx.testing_send_all(sealed(Instant::now(), (0..1000).map(FizzBuzzMessage::new)), true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        let mut records = Vec::new();
+        for (name, sink) in &sinks {
+            records.extend(sink.take_records().into_iter().map(|msg| (*name, msg)));
+        }
+        assert_eq!(records.len(), 2000);
+        // only plain values carry their sequence number, so those are the records checked;
+        // the merged and value shards are not ordered against each other, only within themselves
+        let values: Vec<(&str, u64)> = records.iter()
+            .filter_map(|(name, msg)| match msg { FizzBuzzMessage::Value(v) => Some((*name, *v)), _ => None })
+            .collect();
+        verify_order_per_shard(&values, |(name, _)| *name, |(_, v)| *v)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::Instant;
+use steady_state::*;
+use crate::envelope::Envelope;
+use crate::error::{AppError, Context};
+
+/// Bytes of one spilled value: sequence number, payload and capture time, all little endian.
+const RECORD: usize = 24;
+
+/// Disk-backed queue of the values the worker could not deliver, fixed size records appended
+/// at the end and read from the front. Truncated when created, a spill never outlives its run,
+/// and again whenever it runs empty, so the file only grows while the logger stays behind.
+pub(crate) struct SpillFile {
+    file: File,
+    /// Capture times are kept as signed nanoseconds from here, an Instant has no bytes of its own.
+    base: Instant,
+    read_at: u64,
+    write_at: u64,
+}
+
+impl SpillFile {
+    pub(crate) fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).read(true).write(true).truncate(true).open(path)?;
+        Ok(SpillFile { file, base: Instant::now(), read_at: 0, write_at: 0 })
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        ((self.write_at - self.read_at) / RECORD as u64) as usize
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.read_at == self.write_at
+    }
+
+    pub(crate) fn push(&mut self, item: &Envelope<u64>) -> io::Result<()> {
+        // values captured before the file was created come before its base
+        let nanos = match item.captured.checked_duration_since(self.base) {
+            Some(after) => after.as_nanos() as i64,
+            None => -(self.base.duration_since(item.captured).as_nanos() as i64),
+        };
+        let mut record = [0u8; RECORD];
+        record[..8].copy_from_slice(&item.seq.to_le_bytes());
+        record[8..16].copy_from_slice(&item.payload.to_le_bytes());
+        record[16..].copy_from_slice(&nanos.to_le_bytes());
+        self.file.seek(SeekFrom::Start(self.write_at))?;
+        self.file.write_all(&record)?;
+        self.write_at += RECORD as u64;
+        Ok(())
+    }
+
+    /// Oldest value still spilled, None once everything came back.
+    pub(crate) fn pop(&mut self) -> io::Result<Option<Envelope<u64>>> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+        let mut record = [0u8; RECORD];
+        self.file.seek(SeekFrom::Start(self.read_at))?;
+        self.file.read_exact(&mut record)?;
+        self.read_at += RECORD as u64;
+        if self.is_empty() {
+            // all read back, the next spill starts over at the front of the file
+            self.file.set_len(0)?;
+            self.read_at = 0;
+            self.write_at = 0;
+        }
+        let field = |at: usize| u64::from_le_bytes(record[at..at + 8].try_into().expect("8 bytes"));
+        let nanos = field(16) as i64;
+        let offset = Duration::from_nanos(nanos.unsigned_abs());
+        let captured = if nanos >= 0 { self.base + offset } else { self.base.checked_sub(offset).unwrap_or(self.base) };
+        Ok(Some(Envelope::new(field(0), captured, field(8))))
+    }
+}
+
+/// Disk buffer behind the worker under `--backpressure spill`. Values the worker had no logger
+/// room for arrive on `spill_rx` and go back on `unspill_tx` in the order they came, as fast as
+/// the worker takes them, however long the logger stays behind.
+/// The file lives in `state`, so a restart after a panic hands back what was spilled.
+pub async fn run(actor: SteadyActorShadow
+                 , spill_rx: SteadyRx<Envelope<u64>>
+                 , unspill_tx: SteadyTx<Envelope<u64>>
+                 , state: SteadyState<Option<SpillFile>>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors like the worker
+    let actor = actor.into_spotlight([&spill_rx], [&unspill_tx]);
+    internal_behavior(actor, spill_rx, unspill_tx, state).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , spill_rx: SteadyRx<Envelope<u64>>
+                                           , unspill_tx: SteadyTx<Envelope<u64>>
+                                           , state: SteadyState<Option<SpillFile>>) -> Result<(),Box<dyn Error>> {
+    let path = actor.args::<crate::MainArg>().expect("unable to downcast").spill_path.clone();
+
+    let mut spill_rx = spill_rx.lock().await;
+    let mut unspill_tx = unspill_tx.lock().await;
+    // created on the first start, handed back as it was left after a panic and restart
+    let mut state = state.lock(|| None).await;
+    if state.is_none() {
+        *state = Some(SpillFile::create(&path).map_err(AppError::SinkIo).context(format!("creating spill file {}", path))?);
+    }
+    let file = state.as_mut().expect("created on the first start");
+    let mut spilled = 0u64;
+
+    // the worker closes its side once everything it spilled came back, so nothing is left here either
+    while actor.is_running(|| i!(spill_rx.is_closed_and_empty())
+                              && i!(file.is_empty())
+                              && i!(unspill_tx.mark_closed())) {
+        if file.is_empty() {
+            await_for_all!(actor.wait_avail(&mut spill_rx, 1));
+        } else {
+            await_for_any!(actor.wait_avail(&mut spill_rx, 1), actor.wait_vacant(&mut unspill_tx, 1));
+        }
+
+        while let Some(item) = actor.try_take(&mut spill_rx) {
+            file.push(&item)?;
+            spilled += 1;
+        }
+        // everything goes through the file, so what comes back keeps the order it went in
+        for _ in 0..actor.vacant_units(&mut unspill_tx) {
+            match file.pop()? {
+                Some(item) => {
+                    let _ = actor.try_send(&mut unspill_tx, item);
+                }
+                None => break,
+            }
+        }
+    }
+    // only a shutdown which timed out leaves values behind on disk
+    info!("Spill buffered {} values, {} left on disk", spilled, file.len());
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod spill_tests {
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::clock::Clock;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    /// A directory of its own for one test run, removed again by the test.
+    fn temp_dir(name: &str) -> io::Result<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("standard_{}_{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        Ok(dir)
+    }
+
+    #[test]
+    fn test_spill_file() -> io::Result<()> {
+        let dir = temp_dir("spill_file_test")?;
+        let path = dir.join("spill.bin");
+        let mut file = SpillFile::create(&path)?;
+        // one captured before the file, one after, both keep their capture time
+        let before = Envelope::new(0, Instant::now(), 3);
+        let after = Envelope::new(1, Instant::now() + Duration::from_millis(5), 5);
+        file.push(&before)?;
+        file.push(&after)?;
+        assert_eq!(file.len(), 2);
+        assert_eq!(file.pop()?, Some(before));
+        file.push(&before)?;
+        assert_eq!(file.pop()?, Some(after));
+        assert_eq!(file.pop()?, Some(before));
+        assert_eq!(file.pop()?, None);
+        // emptied, the file was cut back to nothing
+        assert_eq!(std::fs::metadata(&path)?.len(), 0);
+        std::fs::remove_dir_all(&dir)
+    }
+
+    /// Stands in for the worker taking values back, every one into `taken`.
+    async fn collect(actor: SteadyActorShadow, unspill_rx: SteadyRx<Envelope<u64>>, taken: Arc<Mutex<Vec<Envelope<u64>>>>) -> Result<(), Box<dyn Error>> {
+        let mut actor = actor.into_spotlight([&unspill_rx], []);
+        let mut unspill_rx = unspill_rx.lock().await;
+        while actor.is_running(|| i!(unspill_rx.is_closed_and_empty())) {
+            await_for_all!(actor.wait_avail(&mut unspill_rx, 1));
+            while let Some(item) = actor.try_take(&mut unspill_rx) {
+                taken.lock().expect("taken lock").push(item);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_spill() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let dir = temp_dir("spill_test")?;
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { spill_path: dir.join("spill.bin").to_string_lossy().to_string(), clock, ..MainArg::default() });
+        let (spill_tx, spill_rx) = graph.channel_builder().with_capacity(8).build();
+        // less room back than was spilled, the rest waits on disk
+        let (unspill_tx, unspill_rx) = graph.channel_builder().with_capacity(2).build();
+        let state = new_state();
+        let taken = Arc::new(Mutex::new(Vec::new()));
+        let worker_taken = taken.clone();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, spill_rx.clone(), unspill_tx.clone(), state.clone()), SoloAct);
+        graph.actor_builder().with_name("Worker")
+            .build(move |context| collect(context, unspill_rx.clone(), worker_taken.clone()), SoloAct);
+
+        let captured = Instant::now();
+        let spilled = sealed(captured, vec![3, 5, 7, 9, 11]);
+        spill_tx.testing_send_all(spilled.clone(), true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        std::fs::remove_dir_all(&dir)?;
+        // everything went through the file and came back in the order it went in
+        assert_eq!(*taken.lock().expect("taken lock"), spilled);
+        assert_in_logs!(["Spill buffered 5 values, 0 left on disk"]);
+        Ok(())
+    }
+}
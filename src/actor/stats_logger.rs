@@ -0,0 +1,49 @@
+use steady_state::*;
+use crate::actor::aggregator::StatsSummary;
+
+/// Terminal consumer for aggregated statistics.
+/// Keeping summaries on their own channel and logger means they are never
+/// interleaved with, or delayed behind, the per-message output.
+pub async fn run(actor: SteadyActorShadow, summary_rx: SteadyRx<StatsSummary>) -> Result<(),Box<dyn Error>> {
+    //not simulated, the aggregator feeding it is already tested through its neighbors
+    internal_behavior(actor.into_spotlight([&summary_rx], []), summary_rx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , rx: SteadyRx<StatsSummary>) -> Result<(),Box<dyn Error>> {
+    let locale = actor.args::<crate::MainArg>().expect("unable to downcast").locale;
+    let mut rx = rx.lock().await;
+    while actor.is_running(|| rx.is_closed_and_empty()) {
+        await_for_all!(actor.wait_avail(&mut rx, 1));
+
+        while let Some(s) = actor.try_take(&mut rx) {
+            let value = |v: Option<u64>| v.map_or("-".to_string(), |v| locale.number(v));
+            // only shown under --rule, the classic run has nothing labeled
+            let labeled = if s.labeled > 0 { format!(" labeled {}", locale.number(s.labeled)) } else { String::new() };
+            info!("Stats fizz {} buzz {} fizzbuzz {} values {}{} rate {}/s min {} max {}"
+                  , locale.number(s.fizz), locale.number(s.buzz), locale.number(s.fizzbuzz), locale.number(s.values), labeled
+                  , locale.decimal(s.messages_per_sec, 1), value(s.min_value), value(s.max_value));
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_stats_logger() -> Result<(), Box<dyn std::error::Error>> {
+    use steady_logger::*;
+    let _guard = start_log_capture();
+
+    let args = crate::arg::MainArg { locale: crate::format::Locale::De, ..Default::default() };
+    let mut graph = GraphBuilder::for_testing().build(args);
+    let (summary_tx, summary_rx) = graph.channel_builder().build();
+
+    graph.actor_builder().with_name("UnitTest")
+        .build(move |context| internal_behavior(context, summary_rx.clone()), SoloAct);
+
+    graph.start();
+    summary_tx.testing_send_all(vec![StatsSummary { fizz: 4, buzz: 1234, messages_per_sec: 2.5, ..StatsSummary::default() }], true);
+    graph.request_shutdown();
+    graph.block_until_stopped(Duration::from_secs(1))?;
+    assert_in_logs!(["Stats fizz 4 buzz 1.234 fizzbuzz 0 values 0 rate 2,5/s min - max -"]);
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use steady_state::*;
+use crate::actor::heartbeat::{DriftGauge, Heartbeat};
+
+/// Raised once per silence of the heartbeat: the last beat seen and how long it has been quiet.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct WatchdogAlert {
+    pub(crate) last_beat: Option<u64>,
+    pub(crate) silent_for: Duration,
+}
+
+/// Liveness check on a copy of the heartbeat: no beat within twice the interval the heartbeat
+/// is on logs an error and sends an alert, once, until beats arrive again. The limit follows
+/// the interval `drift` reports, so a rate set at runtime or a faster pace under backlog moves
+/// it too, and --rate stands in until the heartbeat has started. A pause is reported like a
+/// stalled heartbeat.
+pub async fn run(actor: SteadyActorShadow, heartbeat_rx: SteadyRx<Heartbeat>, alert_tx: SteadyTx<WatchdogAlert>, drift: DriftGauge) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&heartbeat_rx], [&alert_tx]);
+    if actor.use_internal_behavior {
+        internal_behavior(actor, heartbeat_rx, alert_tx, drift).await
+    } else { //edge actor so we simulate it when testing from main
+        actor.simulated_behavior(vec!(&heartbeat_rx, &alert_tx)).await
+    }
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , heartbeat_rx: SteadyRx<Heartbeat>
+                                           , alert_tx: SteadyTx<WatchdogAlert>
+                                           , drift: DriftGauge) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let clock = args.clock.clone();
+    let rate = Duration::from_millis(args.rate_ms);
+
+    let mut heartbeat_rx = heartbeat_rx.lock().await;
+    let mut alert_tx = alert_tx.lock().await;
+    let mut last_seen = clock.now();
+    let mut last_beat = None;
+    // one alert per silence, re-armed by the next beat
+    let mut alerted = false;
+
+    while actor.is_running(|| i!(heartbeat_rx.is_closed_and_empty()) && i!(alert_tx.mark_closed())) {
+        if heartbeat_rx.is_closed_and_empty() {
+            // a one-shot heartbeat closed its channel, the silence from here is intended
+            actor.wait_shutdown().await;
+            continue;
+        }
+        // read again on every wake, a rate set meanwhile applies to the silence so far
+        let limit = 2 * drift.interval().unwrap_or(rate);
+        let remaining = if alerted { limit } else { limit.saturating_sub(clock.since(last_seen)) };
+        await_for_any!(actor.wait_avail(&mut heartbeat_rx, 1), actor.wait(remaining));
+
+        while let Some(beat) = actor.try_take(&mut heartbeat_rx) {
+            if alerted {
+                info!("Heartbeat back with beat {} after {:?}", beat.beat, clock.since(last_seen));
+            }
+            last_seen = clock.now();
+            last_beat = Some(beat.beat);
+            alerted = false;
+        }
+        let interval = drift.interval().unwrap_or(rate);
+        let silent_for = clock.since(last_seen);
+        if !alerted && silent_for >= 2 * interval {
+            alerted = true;
+            error!("No heartbeat for {:?}, more than twice the {:?} interval, last beat {:?}", silent_for, interval, last_beat);
+            if !actor.try_send(&mut alert_tx, WatchdogAlert { last_beat, silent_for }).is_sent() {
+                warn!("Watchdog alert dropped, alert channel full");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod watchdog_tests {
+    use std::time::SystemTime;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::clock::Clock;
+    use super::*;
+
+    #[test]
+    fn test_watchdog() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 100, clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (alert_tx, alert_rx) = graph.channel_builder().build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, heartbeat_rx.clone(), alert_tx.clone(), DriftGauge::default()), SoloAct);
+
+        // left open, a closed heartbeat channel is the intended silence of a one-shot trigger
+        heartbeat_tx.testing_send_all(vec![Heartbeat::on_time(0, SystemTime::now())], false);
+        graph.start();
+        // within twice the rate the silence is fine, past it a single alert goes out
+        std::thread::sleep(Duration::from_millis(100));
+        source.advance(Duration::from_millis(150));
+        std::thread::sleep(Duration::from_millis(100));
+        source.advance(Duration::from_millis(100));
+        std::thread::sleep(Duration::from_millis(200));
+        source.advance(Duration::from_millis(300));
+        std::thread::sleep(Duration::from_millis(200));
+        heartbeat_tx.testing_send_all(vec![], true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_steady_rx_eq_take!(&alert_rx, vec!(WatchdogAlert { last_beat: Some(0), silent_for: Duration::from_millis(250) }));
+        assert_in_logs!(["No heartbeat for 250ms, more than twice the 100ms interval, last beat Some(0)"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_watchdog_follows_interval() -> Result<(), Box<dyn Error>> {
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { rate_ms: 10, clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (alert_tx, alert_rx) = graph.channel_builder().build();
+        let drift = DriftGauge::default();
+        let gauge = drift.clone();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, heartbeat_rx.clone(), alert_tx.clone(), gauge.clone()), SoloAct);
+
+        // the rate was set to 50ms at runtime, --rate still saying 10ms
+        drift.set_interval(Duration::from_millis(50));
+        heartbeat_tx.testing_send_all(vec![Heartbeat::on_time(0, SystemTime::now())], false);
+        graph.start();
+        std::thread::sleep(Duration::from_millis(100));
+        // silent for twice --rate, well within the interval the heartbeat is on
+        source.advance(Duration::from_millis(40));
+        std::thread::sleep(Duration::from_millis(200));
+        heartbeat_tx.testing_send_all(vec![Heartbeat::on_time(1, SystemTime::now())], false);
+        std::thread::sleep(Duration::from_millis(100));
+        // past twice the interval an alert goes out
+        source.advance(Duration::from_millis(110));
+        std::thread::sleep(Duration::from_millis(200));
+        heartbeat_tx.testing_send_all(vec![], true);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_steady_rx_eq_take!(&alert_rx, vec!(WatchdogAlert { last_beat: Some(1), silent_for: Duration::from_millis(110) }));
+        Ok(())
+    }
+}
@@ -0,0 +1,249 @@
+use std::time::Instant;
+use steady_state::*;
+use crate::actor::batch_report::BatchReport;
+use crate::actor::heartbeat::Heartbeat;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::envelope::{Envelope, UNSEQUENCED};
+
+/// What closed a window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum WindowEnd {
+    /// It reached --window-count messages.
+    Count,
+    /// --window-beats heartbeats went by.
+    Beat,
+    /// The stream ended part way through it, the window holds fewer messages than a full one.
+    Shutdown,
+}
+
+/// One closed tumbling window: consecutive messages, each counted in exactly one window.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct WindowSummary {
+    /// Position of the window in the stream, from zero.
+    pub(crate) index: u64,
+    pub(crate) end: WindowEnd,
+    /// Messages by kind, `items` being all of them and `duration` the time the window was open.
+    pub(crate) kinds: BatchReport,
+    /// Highest sequence number counted so far, every message up to it falls in this window
+    /// or an earlier one. None until a sequenced message arrives.
+    pub(crate) watermark: Option<u64>,
+}
+
+/// The window being filled.
+struct Window {
+    index: u64,
+    opened: Instant,
+    kinds: BatchReport,
+    watermark: Option<u64>,
+}
+
+impl Window {
+    fn record(&mut self, msg: &Envelope<FizzBuzzMessage>) {
+        self.kinds.items += 1;
+        self.kinds.record(&msg.payload);
+        // priority values carry no sequence, they do not move the watermark
+        if msg.seq != UNSEQUENCED {
+            self.watermark = Some(self.watermark.map_or(msg.seq, |w| w.max(msg.seq)));
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.kinds.items == 0
+    }
+
+    /// Closes this window and opens the next one, the watermark carrying over.
+    fn close(&mut self, end: WindowEnd, now: Instant) -> WindowSummary {
+        let kinds = BatchReport { duration: now.saturating_duration_since(self.opened), ..self.kinds };
+        let summary = WindowSummary { index: self.index, end, kinds, watermark: self.watermark };
+        self.index += 1;
+        self.opened = now;
+        self.kinds = BatchReport::default();
+        summary
+    }
+}
+
+/// Tumbling-window stage, forwarding every message unchanged while emitting a `WindowSummary`
+/// each time --window-count messages went by or --window-beats heartbeats did, whichever comes first.
+pub async fn run(actor: SteadyActorShadow
+                 , heartbeat_rx: SteadyRx<Heartbeat>
+                 , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                 , forward_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                 , summary_tx: SteadyTx<WindowSummary>) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors
+    internal_behavior(actor.into_spotlight([&heartbeat_rx, &rx], [&forward_tx, &summary_tx]), heartbeat_rx, rx, forward_tx, summary_tx).await
+}
+
+async fn internal_behavior<A: SteadyActor>(mut actor: A
+                                           , heartbeat_rx: SteadyRx<Heartbeat>
+                                           , rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                                           , forward_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , summary_tx: SteadyTx<WindowSummary>) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let clock = args.clock.clone();
+    let window_count = args.window_count;
+    let window_beats = args.window_beats;
+
+    let mut heartbeat_rx = heartbeat_rx.lock().await;
+    let mut rx = rx.lock().await;
+    let mut forward_tx = forward_tx.lock().await;
+    let mut summary_tx = summary_tx.lock().await;
+
+    let mut window = Window { index: 0, opened: clock.now(), kinds: BatchReport::default(), watermark: None };
+    let mut beats = 0u64;
+
+    // Shutdown waits for the stream to end rather than cutting the current window short, and
+    // the last window goes out before the outgoing channels close, so no message is left uncounted.
+    while actor.is_running(|| i!(rx.is_closed_and_empty())
+                              && i!(forward_tx.mark_closed())
+                              && i!(summary_tx.mark_closed())) {
+        if heartbeat_rx.is_closed_and_empty() {
+            // a one-shot heartbeat closed its channel, only counts and the end of the stream close windows now
+            await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut forward_tx, 1));
+        } else {
+            await_for_any!(wait_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut forward_tx, 1))
+                         , actor.wait_avail(&mut heartbeat_rx, 1));
+        }
+
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut forward_tx));
+        for _ in 0..items {
+            if let Some(msg) = actor.try_take(&mut rx) {
+                window.record(&msg);
+                let _ = actor.try_send(&mut forward_tx, msg);
+                if window_count.is_some_and(|count| window.kinds.items >= count) {
+                    let summary = window.close(WindowEnd::Count, clock.now());
+                    actor.send_async(&mut summary_tx, summary, SendSaturation::AwaitForRoom).await;
+                }
+            }
+        }
+
+        // a beat window closes even when empty, a quiet stretch is worth reporting too
+        while actor.try_take(&mut heartbeat_rx).is_some() {
+            beats += 1;
+            if window_beats.is_some_and(|every| beats % every == 0) {
+                let summary = window.close(WindowEnd::Beat, clock.now());
+                actor.send_async(&mut summary_tx, summary, SendSaturation::AwaitForRoom).await;
+            }
+        }
+
+        // the stream ended: flush the partial window while the summary channel is still open
+        if rx.is_closed_and_empty() && !window.is_empty() {
+            let summary = window.close(WindowEnd::Shutdown, clock.now());
+            actor.send_async(&mut summary_tx, summary, SendSaturation::AwaitForRoom).await;
+        }
+    }
+    Ok(())
+}
+
+/// Terminal consumer of the window summaries, one log line per window.
+pub async fn run_logger(actor: SteadyActorShadow, summary_rx: SteadyRx<WindowSummary>) -> Result<(),Box<dyn Error>> {
+    let actor = actor.into_spotlight([&summary_rx], []);
+    if actor.use_internal_behavior {
+        logger_behavior(actor, summary_rx).await
+    } else { //edge actor so we simulate it when testing from main
+        actor.simulated_behavior(vec!(&summary_rx)).await
+    }
+}
+
+async fn logger_behavior<A: SteadyActor>(mut actor: A, rx: SteadyRx<WindowSummary>) -> Result<(),Box<dyn Error>> {
+    let mut rx = rx.lock().await;
+    while actor.is_running(|| i!(rx.is_closed_and_empty())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1));
+
+        while let Some(w) = actor.try_take(&mut rx) {
+            let k = &w.kinds;
+            let watermark = w.watermark.map_or("-".to_string(), |seq| seq.to_string());
+            info!("Window {} by {:?}: {} messages fizz {} buzz {} fizzbuzz {} values {} labeled {} in {:?} watermark {}"
+                  , w.index, w.end, k.items, k.fizz, k.buzz, k.fizzbuzz, k.values, k.labeled, k.duration, watermark);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod windower_tests {
+    use std::time::{Instant, SystemTime};
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use crate::clock::Clock;
+    use crate::envelope::envelope_tests::sealed;
+    use super::*;
+
+    fn summary(index: u64, end: WindowEnd, values: impl IntoIterator<Item = u64>, watermark: Option<u64>) -> WindowSummary {
+        let mut kinds = BatchReport::default();
+        values.into_iter().map(FizzBuzzMessage::new).for_each(|msg| { kinds.items += 1; kinds.record(&msg) });
+        WindowSummary { index, end, kinds, watermark }
+    }
+
+    #[test]
+    fn test_windower_by_count() -> Result<(), Box<dyn Error>> {
+        // the virtual clock stands still, every window was open for no time
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { window_count: Some(4), clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (tx, rx) = graph.channel_builder().build();
+        let (forward_tx, forward_rx) = graph.channel_builder().build();
+        let (summary_tx, summary_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, heartbeat_rx.clone(), rx.clone(), forward_tx.clone(), summary_tx.clone())
+                   , SoloAct);
+
+        let sent = sealed(Instant::now(), (1..=10).map(FizzBuzzMessage::new));
+        tx.testing_send_all(sent.clone(), true);
+        heartbeat_tx.testing_send_all(vec![], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        assert_steady_rx_eq_take!(&forward_rx, sent);
+        // two full windows, then the two values left flushed at shutdown
+        assert_steady_rx_eq_take!(&summary_rx, vec!(summary(0, WindowEnd::Count, 1..=4, Some(3))
+                                                  , summary(1, WindowEnd::Count, 5..=8, Some(7))
+                                                  , summary(2, WindowEnd::Shutdown, 9..=10, Some(9))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_windower_by_beat() -> Result<(), Box<dyn Error>> {
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { window_beats: Some(1), clock, ..MainArg::default() });
+        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+        let (tx, rx) = graph.channel_builder().build();
+        let (forward_tx, forward_rx) = graph.channel_builder().build();
+        let (summary_tx, summary_rx) = graph.channel_builder().build();
+
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| internal_behavior(context, heartbeat_rx.clone(), rx.clone(), forward_tx.clone(), summary_tx.clone())
+                   , SoloAct);
+
+        let sent = sealed(Instant::now(), (1..=6).map(FizzBuzzMessage::new));
+        tx.testing_send_all(sent.clone(), true);
+        heartbeat_tx.testing_send_all(vec![Heartbeat::on_time(0, SystemTime::now())], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        assert_steady_rx_eq_take!(&forward_rx, sent);
+        // the beat closed the window holding everything, leaving nothing for shutdown
+        assert_steady_rx_eq_take!(&summary_rx, vec!(summary(0, WindowEnd::Beat, 1..=6, Some(5))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_window_logger() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (summary_tx, summary_rx) = graph.channel_builder().build();
+        graph.actor_builder().with_name("UnitTest")
+            .build(move |context| logger_behavior(context, summary_rx.clone()), SoloAct);
+
+        summary_tx.testing_send_all(vec![summary(2, WindowEnd::Shutdown, 9..=10, Some(9))], true);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_in_logs!(["Window 2 by Shutdown: 2 messages fizz 1 buzz 1 fizzbuzz 0 values 0 labeled 0 in 0ns watermark 9"]);
+        Ok(())
+    }
+}
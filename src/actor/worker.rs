@@ -1,18 +1,35 @@
+use std::collections::VecDeque;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::thread::yield_now;
+use std::time::Instant;
+use serde::{Deserialize, Serialize};
 use steady_state::*;
+use crate::actor::batch_report::BatchReport;
+use crate::actor::dead_letter::{DeadLetter, Reason};
+use crate::actor::heartbeat::Pulse;
+use crate::actor::history::BatchStats;
+use crate::arg::Backpressure;
+use crate::envelope::Envelope;
+use crate::partitioner;
+use crate::rules::{ActiveRules, Classified, RuleSet, Rules};
+use crate::toggles::{Toggle, Toggles};
 
 // Over designed this enum is. much to learn here we have.
 // Memory-efficient message design using discriminant encoding for compact representation.
-// The repr(u64) attribute enables the entire enum to fit within 8 bytes, improving
-// cache performance and reducing memory allocation overhead in high-throughput scenarios.
-#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
-#[repr(u64)] // Pack everything into 8 bytes
+// The repr(u32) attribute leaves the 4-byte aligned Classified room beside the tag, so every
+// message fits within 16 bytes, improving cache performance in high-throughput scenarios.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(into = "WireMessage", try_from = "WireMessage")] // the packed form has no meaning outside this process
+#[repr(u32)] // Pack everything into 16 bytes, a u64 value beside the tag
 pub(crate) enum FizzBuzzMessage {
     #[default]
     FizzBuzz = 15,         // Discriminant is 15 - could have been any valid FizzBuzz
     Fizz = 3,              // Discriminant is 3 - could have been any valid Fizz
     Buzz = 5,              // Discriminant is 5 - could have been any valid Buzz
     Value(u64),            // Store u64 directly, use the fact that FizzBuzz/Fizz/Buzz only occupy small values
+    Classified(Classified), // Labels from the --rule settings in place of the fixed 3 and 5
 }
 
 impl FizzBuzzMessage {
@@ -25,31 +42,534 @@ impl FizzBuzzMessage {
             _      => FizzBuzzMessage::Value(value), // Neither
         }
     }
+
+    /// Classic classification which keeps the value, `15 -> FizzBuzz` where `new` gives a bare FizzBuzz.
+    pub(crate) fn classified(value: u64) -> Classified {
+        Rules::classic().classify(value)
+    }
+
+    /// Kind and value, the value of a Fizz, Buzz or FizzBuzz being its discriminant.
+    /// The kind is what keeps `Value(3)` apart from `Fizz`, both of which hold a 3.
+    pub(crate) fn parts(&self) -> (u8, u64) {
+        match self {
+            FizzBuzzMessage::FizzBuzz => (1, 15),
+            FizzBuzzMessage::Fizz     => (2, 3),
+            FizzBuzzMessage::Buzz     => (3, 5),
+            FizzBuzzMessage::Value(v) => (4, *v),
+            // the labels follow from the value and the rules
+            FizzBuzzMessage::Classified(c) => (5, c.value()),
+        }
+    }
+
+    /// The input value, which a bare Fizz, Buzz or FizzBuzz does not carry.
+    pub(crate) fn value(&self) -> Option<u64> {
+        match self {
+            FizzBuzzMessage::Value(v) => Some(*v),
+            FizzBuzzMessage::Classified(c) => Some(c.value()),
+            _ => None,
+        }
+    }
+
+    /// Whether `kind` is what `kind()` gives, without allocating it, for checks on every message.
+    pub(crate) fn is_kind(&self, kind: &str) -> bool {
+        match self {
+            FizzBuzzMessage::FizzBuzz => kind == "FizzBuzz",
+            FizzBuzzMessage::Fizz => kind == "Fizz",
+            FizzBuzzMessage::Buzz => kind == "Buzz",
+            FizzBuzzMessage::Value(_) => kind == "Value",
+            FizzBuzzMessage::Classified(c) if c.labels().is_empty() => kind == "Value",
+            FizzBuzzMessage::Classified(c) => c.labels().spell(kind),
+        }
+    }
+
+    /// What the value was classified as, `Fizz` or the labels of its rules run together,
+    /// `Value` when nothing matched it.
+    pub(crate) fn kind(&self) -> String {
+        match self {
+            FizzBuzzMessage::FizzBuzz => "FizzBuzz".to_string(),
+            FizzBuzzMessage::Fizz => "Fizz".to_string(),
+            FizzBuzzMessage::Buzz => "Buzz".to_string(),
+            FizzBuzzMessage::Value(_) => "Value".to_string(),
+            FizzBuzzMessage::Classified(c) if c.labels().is_empty() => "Value".to_string(),
+            FizzBuzzMessage::Classified(c) => c.labels().to_string(),
+        }
+    }
+
+    /// Compact wire form: the kind byte, then the value little endian.
+    pub(crate) fn encode(&self) -> [u8; ENCODED_LEN] {
+        let (kind, value) = self.parts();
+        let mut bytes = [kind; ENCODED_LEN];
+        bytes[1..].copy_from_slice(&value.to_le_bytes());
+        bytes
+    }
+
+    /// Reverses `encode`. Labels are not on the wire, so a classified message is only
+    /// decoded with the rule set it was classified under.
+    pub(crate) fn decode(bytes: &[u8], rules: Option<&Rules>) -> Result<Self, String> {
+        let bytes: &[u8; ENCODED_LEN] = bytes.try_into()
+            .map_err(|_| format!("an encoded message is {} bytes, got {}", ENCODED_LEN, bytes.len()))?;
+        let value = u64::from_le_bytes(bytes[1..].try_into().expect("8 bytes"));
+        let msg = match bytes[0] {
+            1 => FizzBuzzMessage::FizzBuzz,
+            2 => FizzBuzzMessage::Fizz,
+            3 => FizzBuzzMessage::Buzz,
+            4 => return Ok(FizzBuzzMessage::Value(value)),
+            5 => return rules.map(|rules| FizzBuzzMessage::Classified(rules.classify(value)))
+                             .ok_or_else(|| format!("classified {} needs the rules it was classified under", value)),
+            kind => return Err(format!("unknown message kind {}", kind)),
+        };
+        // a fixed kind always carries its own discriminant, anything else is corrupt
+        if msg.parts().1 != value {
+            return Err(format!("{:?} carrying {}", msg, value));
+        }
+        Ok(msg)
+    }
+}
+
+/// Bytes of one encoded `FizzBuzzMessage`.
+pub(crate) const ENCODED_LEN: usize = 9;
+
+/// Self-describing serde form of a `FizzBuzzMessage`, kind by name so `Value(3)` and `Fizz`
+/// never meet. Labels are spelled out for readers, but without the rules behind them a
+/// classified message is not deserialized, `decode` with the rules takes its place.
+#[derive(Serialize, Deserialize)]
+enum WireMessage {
+    FizzBuzz,
+    Fizz,
+    Buzz,
+    Value(u64),
+    Classified { value: u64, labels: Vec<String> },
+}
+
+impl From<FizzBuzzMessage> for WireMessage {
+    fn from(msg: FizzBuzzMessage) -> Self {
+        match msg {
+            FizzBuzzMessage::FizzBuzz => WireMessage::FizzBuzz,
+            FizzBuzzMessage::Fizz => WireMessage::Fizz,
+            FizzBuzzMessage::Buzz => WireMessage::Buzz,
+            FizzBuzzMessage::Value(v) => WireMessage::Value(v),
+            FizzBuzzMessage::Classified(c) => WireMessage::Classified { value: c.value(), labels: c.labels().names() },
+        }
+    }
+}
+
+impl TryFrom<WireMessage> for FizzBuzzMessage {
+    type Error = String;
+
+    fn try_from(wire: WireMessage) -> Result<Self, String> {
+        match wire {
+            WireMessage::FizzBuzz => Ok(FizzBuzzMessage::FizzBuzz),
+            WireMessage::Fizz => Ok(FizzBuzzMessage::Fizz),
+            WireMessage::Buzz => Ok(FizzBuzzMessage::Buzz),
+            WireMessage::Value(v) => Ok(FizzBuzzMessage::Value(v)),
+            WireMessage::Classified { value, .. } => Err(format!("classified {} needs the rules it was classified under", value)),
+        }
+    }
+}
+
+/// Business rule the worker applies to every accepted value. The actor loop owns batching,
+/// validation and backpressure, the transform only maps one input to one output, so a
+/// different rule drops in without touching the loop.
+pub(crate) trait Transform<In, Out>: Send {
+    fn apply(&mut self, input: In) -> Out;
+
+    /// Takes over rules sent while the graph runs, Err with the reason they were not taken.
+    fn reload(&mut self, _rules: &RuleSet) -> Result<(), String> {
+        Err("this transform has no rules to replace".to_string())
+    }
+
+    /// Test hook between classifying `input` and delivering its result. Unlike a failure in
+    /// `apply` a panic here is not caught, so the worker restarts with the value still queued.
+    #[cfg(test)]
+    fn delivering(&mut self, _input: In) {}
+}
+
+/// The default rule, classic FizzBuzz classification.
+#[derive(Copy, Clone, Default, Debug)]
+pub(crate) struct FizzBuzz;
+
+impl Transform<u64, FizzBuzzMessage> for FizzBuzz {
+    fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+        FizzBuzzMessage::new(value)
+    }
+}
+
+/// Failure injection for --poison-value: `inner` panics on the one value `poison`, the way a
+/// transform meets an input it cannot handle, while the worker keeps running around it.
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct Poisoned<T> {
+    pub(crate) poison: u64,
+    pub(crate) inner: T,
+}
+
+impl<T: Transform<u64, FizzBuzzMessage>> Transform<u64, FizzBuzzMessage> for Poisoned<T> {
+    fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+        if value == self.poison {
+            panic!("poison value {}", value);
+        }
+        self.inner.apply(value)
+    }
+
+    fn reload(&mut self, rules: &RuleSet) -> Result<(), String> {
+        self.inner.reload(rules)
+    }
+}
+
+
+/// Rolling FNV-1a checksum over every message emitted in one heartbeat batch.
+/// Two runs with identical input and configuration produce identical checksums
+/// per batch, so comparing the audit lines pinpoints the first batch that diverged.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct BatchChecksum(u64);
+
+impl Default for BatchChecksum {
+    fn default() -> Self {
+        BatchChecksum(0xcbf2_9ce4_8422_2325) // FNV-1a 64 bit offset basis
+    }
+}
+
+impl BatchChecksum {
+    /// Folds one emitted message into the checksum, kind first and then the value,
+    /// so that a Fizz and a Value which happen to share a number never collide.
+    pub(crate) fn update(&mut self, msg: &FizzBuzzMessage) {
+        // the rules behind any labels are the same for both runs
+        let (kind, value) = msg.parts();
+        for byte in std::iter::once(kind).chain(value.to_le_bytes()) {
+            self.0 ^= byte as u64;
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3); // FNV-1a 64 bit prime
+        }
+    }
+
+    pub(crate) fn value(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Values the worker moves per call with --batch; the take and send buffers live on the stack.
+pub(crate) const WORKER_SLICE: usize = 256;
+
+/// Running totals of one batch, which --batch works through a slice at a time.
+#[derive(Default)]
+pub(crate) struct BatchTally {
+    items: u64,
+    rejected: u64,
+    checksum: BatchChecksum,
+    kinds: BatchReport,
+}
+
+impl BatchTally {
+    /// Counts one message emitted, the item itself included.
+    pub(crate) fn emitted(&mut self, msg: &FizzBuzzMessage) {
+        self.items += 1;
+        self.checksum.update(msg);
+        self.kinds.record(msg);
+    }
+
+    pub(crate) fn stats(&self, beat: Option<u64>) -> BatchStats {
+        BatchStats { beat, items: self.items, rejected: self.rejected, checksum: self.checksum.value() }
+    }
+
+    pub(crate) fn report(&self, duration: Duration) -> BatchReport {
+        BatchReport { items: self.items, duration, ..self.kinds }
+    }
+}
+
+/// Lifetime counts of everything the worker classified, kept in a `SteadyState` so a restart
+/// after a panic carries on counting. A value counts once it is taken, so one peeked when the
+/// worker failed is counted by the restarted worker instead, never twice.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct WorkerState {
+    pub(crate) batches: u64,
+    pub(crate) rejected: u64,
+    /// Values the transform failed on, counted among the rejected.
+    pub(crate) poisoned: u64,
+    /// Values dropped under `--backpressure truncate`, never counted among the items.
+    pub(crate) truncated: u64,
+    /// Values sent to the spill actor and values taken back from it, equal once it is empty.
+    pub(crate) spilled: u64,
+    pub(crate) returned: u64,
+    /// Messages by kind, `items` being everything taken including the rejected values.
+    pub(crate) kinds: BatchReport,
+}
+
+impl WorkerState {
+    /// Counts one value taken, whether classified or made a dead letter.
+    fn count(&mut self, outcome: Result<&FizzBuzzMessage, &DeadLetter>) {
+        self.kinds.items += 1;
+        match outcome {
+            Ok(msg) => self.kinds.record(msg),
+            Err(letter) => {
+                self.rejected += 1;
+                if letter.reason == Reason::Poisoned {
+                    self.poisoned += 1;
+                }
+            }
+        }
+    }
+
+    pub(crate) fn report(&self) -> String {
+        let k = &self.kinds;
+        format!("Worker totals over {} batches: items {} fizz {} buzz {} fizzbuzz {} values {} labeled {} rejected {} poisoned {} truncated {} spilled {}"
+                , self.batches, k.items, k.fizz, k.buzz, k.fizzbuzz, k.values, k.labeled, self.rejected, self.poisoned, self.truncated, self.spilled)
+    }
+}
+
+/// Applies `transform` to one value, or makes it a dead letter when `validate` is on and it is
+/// above `max_value`, or when the transform panics on it. Touches no channel, so pool members
+/// run it unlocked.
+pub(crate) fn classify<T: Transform<u64, FizzBuzzMessage>>(item: Envelope<u64>, validate: bool, max_value: u64
+                                               , transform: &mut T, tally: &mut BatchTally) -> Result<Envelope<FizzBuzzMessage>, DeadLetter> {
+    if validate && item.payload > max_value {
+        // Fault isolation: one bad value must not stall or crash the whole pipeline.
+        tally.items += 1;
+        tally.rejected += 1;
+        return Err(DeadLetter { value: item.payload, reason: Reason::AboveMaxValue });
+    }
+    // A value the transform cannot handle would fail again after every restart, the value
+    // still being first in the channel, so the failure is caught here and the value set aside.
+    match catch_unwind(AssertUnwindSafe(|| transform.apply(item.payload))) {
+        Ok(payload) => {
+            // the result keeps the sequence number and capture time of its value
+            let msg = item.map(|_| payload);
+            tally.emitted(&msg.payload);
+            Ok(msg)
+        }
+        Err(_) => {
+            warn!("Transform failed on {}, sent to the dead letters", item.payload);
+            tally.items += 1;
+            tally.rejected += 1;
+            Err(DeadLetter { value: item.payload, reason: Reason::Poisoned })
+        }
+    }
+}
+
+/// Copies the values at the front of `rx` into `into` without taking them, as many as fit.
+fn peek_into<A: SteadyActor>(actor: &A, rx: &mut Rx<Envelope<u64>>, into: &mut [Envelope<u64>]) -> usize {
+    let (first, second) = actor.peek_slice(rx);
+    let mut count = 0;
+    for (slot, item) in into.iter_mut().zip(first.iter().chain(second)) {
+        *slot = *item;
+        count += 1;
+    }
+    count
+}
+
+/// Hands the newest rules sent since the last batch to the transform. Called between batches
+/// only, so a batch is never classified under two rule sets; sets superseded before a batch
+/// are never applied.
+pub(crate) fn reload<T: Transform<u64, FizzBuzzMessage>>(transform: &mut T, latest: Option<RuleSet>, beat: Option<u64>) {
+    if let Some(rules) = latest {
+        match transform.reload(&rules) {
+            Ok(()) => info!("Rules now {} from batch {:?}", rules, beat),
+            Err(e) => warn!("Rules {} ignored, {}", rules, e),
+        }
+    }
+}
+
+/// One in this many slots of a batch stays reserved for generator values while any wait,
+/// so a steady stream of priority values delays regular traffic but never starves it.
+pub(crate) const REGULAR_SHARE: usize = 4;
+
+/// Splits the `room` of one batch between the priority and the regular values waiting,
+/// priority first once the regular share is set aside.
+fn plan_batch(priority: usize, regular: usize, room: usize) -> (usize, usize) {
+    let reserved = regular.min(room.div_ceil(REGULAR_SHARE));
+    let priority = priority.min(room - reserved);
+    (priority, regular.min(room - priority))
+}
+
+/// Members of a `--workers N` pool still running. Members share every channel, so the last
+/// one to leave is the one closing the outputs.
+/// A rule set reaches only the member taking it off the shared rules channel, so that member
+/// posts it in `rules`, numbered, and every member takes over each number once.
+#[derive(Clone, Debug)]
+pub(crate) struct WorkerPool {
+    running: Arc<AtomicUsize>,
+    rules: Arc<Mutex<(u64, Option<RuleSet>)>>,
+}
+
+impl WorkerPool {
+    pub(crate) fn new(members: usize) -> Self {
+        WorkerPool { running: Arc::new(AtomicUsize::new(members)), rules: Arc::new(Mutex::new((0, None))) }
+    }
+
+    /// Counts one member out, true for the last.
+    fn leave(&self) -> bool {
+        self.running.fetch_sub(1, Ordering::SeqCst) == 1
+    }
+
+    /// Posts the set this member `taken` off the channel, if any, and returns the latest set
+    /// posted since `seen`, the number of the last set this member took over.
+    fn rules_since(&self, taken: Option<RuleSet>, seen: &mut u64) -> Option<RuleSet> {
+        let mut posted = self.rules.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(rules) = taken {
+            *posted = (posted.0 + 1, Some(rules));
+        }
+        if posted.0 == *seen {
+            return None;
+        }
+        *seen = posted.0;
+        posted.1.clone()
+    }
+}
+
+/// What one `--workers N` pool member took off the shared inputs and has not delivered yet,
+/// kept in a `SteadyState` so a member restarted after a panic delivers it before taking more.
+/// The inputs are free for the next member as soon as a batch is taken, so the member cannot
+/// peek and take after sending as the solo worker does; holding the batch here is what keeps
+/// every value, each let go once its message or dead letter is sent.
+/// Each member also keeps the lifetime counts of what it delivered, logged at its shutdown.
+#[derive(Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct MemberState {
+    pub(crate) pending: VecDeque<Envelope<u64>>,
+    /// Heartbeat of the batch in hand.
+    pub(crate) beat: Option<u64>,
+    /// Counted as values are delivered; a batch counts once delivered whole, and only when it
+    /// held values, as which member finds a beat with nothing waiting is down to timing.
+    pub(crate) totals: WorkerState,
+    /// Set once this member found every input closed and empty and counted itself out of the
+    /// pool, so a restart never counts it out twice.
+    pub(crate) left: bool,
+}
+
+/// The worker's two channels to the spill actor under `--backpressure spill`: values the logger
+/// has no room for go out on `tx` unclassified, and come back on `rx` in the order they went.
+#[derive(Clone)]
+pub(crate) struct Spill {
+    pub(crate) tx: SteadyTx<Envelope<u64>>,
+    pub(crate) rx: SteadyRx<Envelope<u64>>,
 }
 
 /// Multi-input coordination actor demonstrating complex data flow patterns.
 /// Worker actors commonly integrate multiple data streams with different timing
 /// characteristics while maintaining processing order and system responsiveness.
+/// Values on `priority_rx` are always taken ahead of the generator traffic of the same batch.
+/// With `--shards N` there is one logger channel per shard, each value going to the shard
+/// its hash picks so every value keeps its order, otherwise `logger_txs` holds just one.
+/// Lifetime counts live in `state` and are logged once more at shutdown.
+/// Rule sets on `rules_rx` replace the classification from the next batch on.
+/// `--backpressure` picks what happens to values the logger has no room for, `spill` is only
+/// given for `--backpressure spill`.
 pub async fn run(actor: SteadyActorShadow
-                 , heartbeat_rx: SteadyRx<u64> //the type can be any struct or primitive or enum...
-                 , generator_rx: SteadyRx<u64>
-                 , logger_tx: SteadyTx<FizzBuzzMessage>) -> Result<(),Box<dyn Error>> {
+                 , heartbeat_rx: SteadyRx<Pulse> //the type can be any struct or primitive or enum...
+                 , generator_rx: SteadyRx<Envelope<u64>>
+                 , priority_rx: SteadyRx<Envelope<u64>>
+                 , rules_rx: SteadyRx<RuleSet>
+                 , logger_txs: Vec<SteadyTx<Envelope<FizzBuzzMessage>>>
+                 , dead_letter_tx: SteadyTx<DeadLetter>
+                 , stats_tx: SteadyTx<BatchStats>
+                 , report_tx: SteadyTx<BatchReport>
+                 , backlog_tx: SteadyTx<usize>
+                 , spill: Option<Spill>
+                 , state: SteadyState<WorkerState>
+                 , toggles: Toggles
+                 , rules: Option<Rules>) -> Result<(),Box<dyn Error>> {
+    let poison_value = actor.args::<crate::MainArg>().and_then(|args| args.poison_value);
+    // --rule settings replace the classic classification, and may be replaced again while running
+    match poison_value {
+        Some(poison) => spotlight(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_txs, dead_letter_tx, stats_tx, report_tx, backlog_tx, spill, state, toggles
+                                  , Poisoned { poison, inner: ActiveRules(rules) }).await,
+        None => spotlight(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_txs, dead_letter_tx, stats_tx, report_tx, backlog_tx, spill, state, toggles, ActiveRules(rules)).await //#!#//
+    }
+}
+
+/// The spotlight takes its channels as fixed arrays, so the logger channel is added by shape:
+/// the single logger is watched from both ends, the `--shards` channels from their loggers only.
+/// The spill channels come with the single logger only, `--backpressure spill` excluding shards.
+async fn spotlight<T: Transform<u64, FizzBuzzMessage>>(actor: SteadyActorShadow
+                                           , heartbeat_rx: SteadyRx<Pulse>
+                                           , generator_rx: SteadyRx<Envelope<u64>>
+                                           , priority_rx: SteadyRx<Envelope<u64>>
+                                           , rules_rx: SteadyRx<RuleSet>
+                                           , logger_txs: Vec<SteadyTx<Envelope<FizzBuzzMessage>>>
+                                           , dead_letter_tx: SteadyTx<DeadLetter>
+                                           , stats_tx: SteadyTx<BatchStats>
+                                           , report_tx: SteadyTx<BatchReport>
+                                           , backlog_tx: SteadyTx<usize>
+                                           , spill: Option<Spill>
+                                           , state: SteadyState<WorkerState>
+                                           , toggles: Toggles
+                                           , transform: T) -> Result<(),Box<dyn Error>> {
     //this is NOT on the edge of the graph so we do not want to simulate it as it will be tested by its simulated neighbors
-    internal_behavior(actor.into_spotlight([&heartbeat_rx, &generator_rx], [&logger_tx]), heartbeat_rx, generator_rx, logger_tx).await //#!#//
+    match (logger_txs.as_slice(), &spill) {
+        ([logger_tx], Some(Spill { tx: spill_tx, rx: unspill_rx })) => {
+            let actor = actor.into_spotlight([&heartbeat_rx, &generator_rx, &priority_rx, &rules_rx, unspill_rx], [logger_tx, &dead_letter_tx, &stats_tx, &report_tx, &backlog_tx, spill_tx]);
+            internal_behavior(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_txs, dead_letter_tx, stats_tx, report_tx, backlog_tx, spill, state, toggles, transform).await
+        }
+        ([logger_tx], None) => {
+            let actor = actor.into_spotlight([&heartbeat_rx, &generator_rx, &priority_rx, &rules_rx], [logger_tx, &dead_letter_tx, &stats_tx, &report_tx, &backlog_tx]);
+            internal_behavior(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_txs, dead_letter_tx, stats_tx, report_tx, backlog_tx, spill, state, toggles, transform).await
+        }
+        _ => {
+            let actor = actor.into_spotlight([&heartbeat_rx, &generator_rx, &priority_rx, &rules_rx], [&dead_letter_tx, &stats_tx, &report_tx, &backlog_tx]);
+            internal_behavior(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_txs, dead_letter_tx, stats_tx, report_tx, backlog_tx, spill, state, toggles, transform).await
+        }
+    }
 }
 
 /// Batch processing pattern triggered by external timing signals enables efficient
 /// bulk operations while maintaining responsive timing control and proper resource
 /// utilization across variable load conditions.
-async fn internal_behavior<A: SteadyActor>(mut actor: A
-                                           , heartbeat_rx: SteadyRx<u64> //the type can be any struct or primitive or enum...
-                                           , generator_rx: SteadyRx<u64>
-                                           , logger_tx: SteadyTx<FizzBuzzMessage>) -> Result<(),Box<dyn Error>> {
+async fn internal_behavior<A: SteadyActor, T: Transform<u64, FizzBuzzMessage>>(mut actor: A
+                                           , heartbeat_rx: SteadyRx<Pulse> //the type can be any struct or primitive or enum...
+                                           , generator_rx: SteadyRx<Envelope<u64>>
+                                           , priority_rx: SteadyRx<Envelope<u64>>
+                                           , rules_rx: SteadyRx<RuleSet>
+                                           , logger_txs: Vec<SteadyTx<Envelope<FizzBuzzMessage>>>
+                                           , dead_letter_tx: SteadyTx<DeadLetter>
+                                           , stats_tx: SteadyTx<BatchStats>
+                                           , report_tx: SteadyTx<BatchReport>
+                                           , backlog_tx: SteadyTx<usize>
+                                           , spill: Option<Spill>
+                                           , state: SteadyState<WorkerState>
+                                           , toggles: Toggles
+                                           , mut transform: T) -> Result<(),Box<dyn Error>> {
+
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    // Values above this ceiling cannot be classified and are routed to the dead-letter actor.
+    let max_value = args.max_value.unwrap_or(u64::MAX);
+    let clock = args.clock.clone();
+    // Past this a batch stops where it is, the rest stays queued for the next one.
+    let deadline = args.batch_deadline_ms.map(Duration::from_millis);
+    // Truncate and spill take every value waiting and deal with what the logger has no room for,
+    // wait sizes the batch to that room.
+    let backpressure = args.backpressure;
+    // Bulk take and send through two stack buffers instead of one call per value.
+    let slices = args.batch;
+    let mut taken_slice = [Envelope::new(0, Instant::now(), 0u64); WORKER_SLICE];
+    let mut ready_slice = [Envelope::new(0, Instant::now(), FizzBuzzMessage::default()); WORKER_SLICE];
+    let mut classified = Vec::with_capacity(WORKER_SLICE);
+    // --prefetch: the front of the generator channel is classified while the beat is awaited,
+    // so the batch the beat starts finds its first slice ready to send. The values stay queued
+    // until sent, the classifications are dropped when the rules or the validation change.
+    let prefetch = slices && args.prefetch;
+    let mut prefetched: Vec<Result<Envelope<FizzBuzzMessage>, DeadLetter>> = Vec::with_capacity(WORKER_SLICE);
+    let mut prefetched_validate = true;
 
     // Very standard pattern to lock the actor's resources for exclusive use.  //#!#//
     let mut heartbeat_rx = heartbeat_rx.lock().await;
     let mut generator_rx = generator_rx.lock().await;
-    let mut logger_tx = logger_tx.lock().await;
+    let mut priority_rx = priority_rx.lock().await;
+    let mut rules_rx = rules_rx.lock().await;
+    let mut loggers = Vec::with_capacity(logger_txs.len());
+    for logger_tx in &logger_txs {
+        loggers.push(logger_tx.lock().await);
+    }
+    let mut dead_letter_tx = dead_letter_tx.lock().await;
+    let mut stats_tx = stats_tx.lock().await;
+    let mut report_tx = report_tx.lock().await;
+    let mut backlog_tx = backlog_tx.lock().await;
+    let (mut spill_tx, mut unspill_rx) = match &spill {
+        Some(spill) => (Some(spill.tx.lock().await), Some(spill.rx.lock().await)),
+        None => (None, None),
+    };
+    // the same hash for the same value, so one value always lands on one shard and stays in order
+    let shard_of = partitioner::build(args.partitioner, loggers.len() as u64, args.virtual_nodes);
+    // created on the first start, handed back as it was left after a panic and restart
+    let mut state = state.lock(WorkerState::default).await;
+    if state.kinds.items > 0 {
+        info!("Worker resuming after {} values", state.kinds.items);
+    }
 
     // When a shutdown is requested, is_running will call the closure to determine if this actor will accept or veto the shutdown.
     // If the closure returns true then the shutdown was accepted, and we will exit the while loop.  It is typical to use
@@ -61,9 +581,24 @@ async fn internal_behavior<A: SteadyActor>(mut actor: A
     while actor.is_running( //we only accept shutdown when ALL these are true
                            || i!(heartbeat_rx.is_closed_and_empty())
                            && i!(generator_rx.is_closed_and_empty())
-                           && i!(logger_tx.mark_closed())                 // must be last
+                           && i!(priority_rx.is_closed_and_empty())
+                           && i!(rules_rx.is_closed_and_empty())
+                           && i!(state.spilled == state.returned)                                                       // all back from the spill
+                           && i!(spill_tx.as_mut().is_none_or(|tx| tx.mark_closed()))
+                           && i!(unspill_rx.as_mut().is_none_or(|rx| rx.is_closed_and_empty()))
+                           && i!(loggers.iter_mut().fold(true, |closed, tx| tx.mark_closed() && closed))                 // must be last
+                           && i!(dead_letter_tx.mark_closed())
+                           && i!(stats_tx.mark_closed())
+                           && i!(report_tx.mark_closed())
+                           && i!(backlog_tx.mark_closed())
                          ) {                 //#!#//
 
+        if heartbeat_rx.is_closed_and_empty() {
+            // the heartbeat closed its channel after a one-shot trigger: no further batch
+            // until the shutdown drain
+            actor.wait_shutdown().await;
+        }
+
         // There are many ways to design an actor, but this is the standard approach to use as the default.
         // Put all the required needs into a single await_for macro call, we have 3 different macros to choose from,
         // and the macros can be nested as needed by using 'wait' editions inside 'await' editions.
@@ -79,22 +614,412 @@ async fn internal_behavior<A: SteadyActor>(mut actor: A
         // The await_for macros all return a boolean 'clean' which is true if all the conditions were met, this will be
         // false if it had to exit early due to a shutdown in progress.
 
-        let clean = await_for_all!(actor.wait_avail(&mut heartbeat_rx,1)  //#!#//
-                                       , actor.wait_avail(&mut generator_rx,1)
-                                       , actor.wait_vacant(&mut logger_tx, 1)
-        );
+        // any value may hash to the shard with the least room, so that one bounds the batch
+        let fullest = (0..loggers.len()).min_by_key(|shard| actor.vacant_units(&mut loggers[*shard])).unwrap_or(0);
+        let clean = if backpressure == Backpressure::Wait && prefetch {
+            let ready = await_for_all!(wait_for_any!(actor.wait_avail(&mut generator_rx,1), actor.wait_avail(&mut priority_rx,1))
+                                     , actor.wait_vacant(&mut loggers[fullest], 1));
+            if prefetched.is_empty() {
+                prefetched_validate = toggles.is_enabled(Toggle::Validation);
+            }
+            // each value arriving ahead of the beat is classified while the beat is still awaited,
+            // up to one slice, the limit a wait on the channel can reach
+            let limit = WORKER_SLICE.min(generator_rx.capacity());
+            let fill = async {
+                while prefetched.len() < limit && actor.wait_avail(&mut generator_rx, prefetched.len() + 1).await {
+                    let peeked = peek_into(&actor, &mut generator_rx, &mut taken_slice[..limit]);
+                    if peeked <= prefetched.len() {
+                        break;
+                    }
+                    for item in &taken_slice[prefetched.len()..peeked] {
+                        prefetched.push(classify(*item, prefetched_validate, max_value, &mut transform, &mut BatchTally::default()));
+                    }
+                }
+                true
+            };
+            let _ = await_for_any!(actor.wait_avail(&mut heartbeat_rx,1), fill);
+            ready && await_for_all!(actor.wait_avail(&mut heartbeat_rx,1))
+        } else if backpressure == Backpressure::Wait {
+            await_for_all!(actor.wait_avail(&mut heartbeat_rx,1)  //#!#//
+                               , wait_for_any!(actor.wait_avail(&mut generator_rx,1), actor.wait_avail(&mut priority_rx,1))
+                               , actor.wait_vacant(&mut loggers[fullest], 1)
+            )
+        } else if let Some(unspill_rx) = unspill_rx.as_mut() {
+            // a full logger holds nothing up, and values back from the spill are work of their own
+            await_for_all!(actor.wait_avail(&mut heartbeat_rx,1)
+                               , wait_for_any!(actor.wait_avail(&mut generator_rx,1), actor.wait_avail(&mut priority_rx,1), actor.wait_avail(unspill_rx,1))
+            )
+        } else {
+            await_for_all!(actor.wait_avail(&mut heartbeat_rx,1)
+                               , wait_for_any!(actor.wait_avail(&mut generator_rx,1), actor.wait_avail(&mut priority_rx,1))
+            )
+        };
 
         //if we have a heartbeat or a stop request then we need to process some work
-        if actor.try_take(&mut heartbeat_rx).is_some() || !clean { //#!#//
-            //check for how much work and how much room we have before we begin
-            let mut items = actor.avail_units(&mut generator_rx).min(actor.vacant_units(&mut logger_tx));           
-            while items>0 {
-                let item = actor.try_take(&mut generator_rx).expect("confirmed available but not found !!");
-                actor.send_async(&mut logger_tx, FizzBuzzMessage::new(item),SendSaturation::AwaitForRoom).await;
-                items -= 1;
+        let pulse = actor.try_take(&mut heartbeat_rx);
+        // a beat numbers its batch, a flush or the last beat asks for everything waiting
+        let (beat, drain) = match pulse {
+            Some(Pulse::Beat(heartbeat)) => (Some(heartbeat.beat), false),
+            Some(Pulse::Flush) => (None, true),
+            Some(Pulse::LastBeat) => {
+                info!("Last beat, draining {} values before shutdown", actor.avail_units(&mut generator_rx) + actor.avail_units(&mut priority_rx));
+                (None, true)
+            }
+            None => (None, false),
+        };
+        if pulse.is_some() || !clean { //#!#//
+            let mut latest = None;
+            while let Some(rules) = actor.try_take(&mut rules_rx) {
+                latest = Some(rules);
+            }
+            let reloaded = latest.is_some();
+            reload(&mut transform, latest, beat);
+            let started = clock.now();
+            //check for how much work and how much room we have before we begin,
+            // a drain takes all of it and awaits room for what does not fit, a batch which
+            // truncates or spills takes all of it too and drops or spills what does not
+            let room = if drain || backpressure != Backpressure::Wait { usize::MAX } else { actor.vacant_units(&mut loggers[fullest]) };
+            let (urgent, regular) = plan_batch(actor.avail_units(&mut priority_rx), actor.avail_units(&mut generator_rx), room);
+            // a flush, the last beat and the shutdown all ask for what is left
+            let draining = drain || !clean;
+            let mut tally = BatchTally::default();
+            // Toggles are read once per batch so a batch is never half validated.
+            let validate = toggles.is_enabled(Toggle::Validation);
+            if reloaded || validate != prefetched_validate {
+                prefetched.clear();
+            }
+            let overdue = || deadline.is_some_and(|deadline| clock.since(started) >= deadline);
+            let mut stopped = false;
+            let mut truncated = 0;
+            // values back from the spill first, as many as the logger has room for, as they were
+            // taken ahead of everything still waiting
+            let mut sources = Vec::with_capacity(3);
+            if let Some(unspill_rx) = unspill_rx.as_mut() {
+                let returned = actor.avail_units(unspill_rx).min(actor.vacant_units(&mut loggers[fullest]));
+                sources.push((unspill_rx, returned, true, false));
+            }
+            // then priority values, then the generator traffic which still fits this batch,
+            // the one source classified ahead
+            sources.extend([(&mut priority_rx, urgent, false, false), (&mut generator_rx, regular, false, prefetch)]);
+            'batch: for (rx, mut items, returned, ahead) in sources {
+                if slices {
+                    // one peek and one send per slice rather than a call per value, the slice taken
+                    // only once sent so a panic leaves it queued for the restarted worker
+                    while items>0 {
+                        if overdue() {
+                            stopped = true;
+                            break 'batch;
+                        }
+                        let from_prefetch = ahead && !prefetched.is_empty();
+                        if !from_prefetch {
+                            let peeked = peek_into(&actor, rx, &mut taken_slice[..items.min(WORKER_SLICE)]);
+                            classified.clear();
+                            for item in &taken_slice[..peeked] {
+                                classified.push(classify(*item, validate, max_value, &mut transform, &mut BatchTally::default()));
+                            }
+                        }
+                        let outcomes = if from_prefetch { &prefetched } else { &classified };
+                        let count = outcomes.len().min(items);
+                        if count == 0 {
+                            break;
+                        }
+                        let mut ready = 0;
+                        for outcome in &outcomes[..count] {
+                            match outcome {
+                                Ok(msg) => {
+                                    tally.emitted(&msg.payload);
+                                    ready_slice[ready] = *msg;
+                                    ready += 1;
+                                }
+                                Err(letter) => {
+                                    tally.items += 1;
+                                    tally.rejected += 1;
+                                    actor.send_async(&mut dead_letter_tx, *letter, SendSaturation::AwaitForRoom).await;
+                                }
+                            }
+                        }
+                        // a drain may hold more than the logger has room for, the rest goes as room frees up
+                        let mut sent = 0;
+                        while sent < ready {
+                            // --batch keeps to a single logger, --shards sends value by value
+                            await_for_all!(actor.wait_vacant(&mut loggers[0], 1));
+                            sent += actor.send_slice(&mut loggers[0], &ready_slice[sent..ready]).item_count();
+                        }
+                        actor.advance_take_index(rx, count);
+                        for outcome in &outcomes[..count] {
+                            state.count(outcome.as_ref().map(|msg| &msg.payload));
+                        }
+                        if returned {
+                            state.returned += count as u64;
+                        }
+                        if from_prefetch {
+                            prefetched.drain(..count);
+                        }
+                        items -= count;
+                    }
+                } else {
+                    while items>0 {
+                        if overdue() {
+                            stopped = true;
+                            break 'batch;
+                        }
+                        // Peek, deliver, then take: a panic before the send leaves the value in the
+                        // channel for the restarted worker, so nothing is lost between the two.
+                        let item = actor.try_peek(rx).copied().expect("confirmed available but not found !!");
+                        let shard = shard_of.partition_of(item.payload) as usize;
+                        if backpressure != Backpressure::Wait && !returned {
+                            let full = actor.vacant_units(&mut loggers[shard]) == 0;
+                            // while anything is out on the spill the new values follow it, so none overtakes it
+                            if let Some(spill_tx) = spill_tx.as_mut() && (full || state.spilled > state.returned) {
+                                if !actor.send_async(spill_tx, item, SendSaturation::AwaitForRoom).await.is_sent() {
+                                    break 'batch;
+                                }
+                                let _ = actor.try_take(rx);
+                                state.spilled += 1;
+                                items -= 1;
+                                continue;
+                            }
+                            // a drain delivers everything, it awaits room like --backpressure wait
+                            if full && backpressure == Backpressure::Truncate && !draining {
+                                let _ = actor.try_take(rx);
+                                state.truncated += 1;
+                                truncated += 1;
+                                items -= 1;
+                                continue;
+                            }
+                        }
+                        let outcome = classify(item, validate, max_value, &mut transform, &mut tally);
+                        #[cfg(test)]
+                        transform.delivering(item.payload);
+                        let sent = match outcome {
+                            Ok(msg) => {
+                                actor.send_async(&mut loggers[shard], msg, SendSaturation::AwaitForRoom).await.is_sent()
+                            }
+                            Err(letter) => {
+                                actor.send_async(&mut dead_letter_tx, letter, SendSaturation::AwaitForRoom).await.is_sent()
+                            }
+                        };
+                        if !sent {
+                            // cut short by a shutdown, the value stays queued for the drain
+                            break 'batch;
+                        }
+                        let _ = actor.try_take(rx);
+                        if returned {
+                            state.returned += 1;
+                        }
+                        state.count(outcome.as_ref().map(|msg| &msg.payload));
+                        items -= 1;
+                    }
+                }
+            }
+            if let Some(deadline) = deadline.filter(|_| stopped) {
+                warn!("Batch {:?} passed its {:?} deadline after {} of {} values, the rest waits for the next batch"
+                      , beat, deadline, tally.items, urgent + regular);
+            }
+            if truncated > 0 {
+                warn!("Batch {:?} truncated, {} of {} values dropped with the logger full", beat, truncated, urgent + regular);
+            }
+            state.batches += 1;
+            // The batch id is the heartbeat which triggered it, None for a flush or the final drain.
+            if toggles.is_enabled(Toggle::Audit) {
+                info!("Audit batch {:?} items {} checksum {:016x}", beat, tally.items, tally.checksum.value());
+            }
+            // Stats are observability only, if the history actor falls behind we drop rather than stall.
+            let _ = actor.try_send(&mut stats_tx, tally.stats(beat));
+            let _ = actor.try_send(&mut report_tx, tally.report(clock.since(started)));
+            // Closed-loop pacing: the heartbeat shortens its interval while this backlog stays high.
+            let backlog = actor.avail_units(&mut generator_rx);
+            let _ = actor.try_send(&mut backlog_tx, backlog);
+        }
+    }
+    info!("{}", state.report());
+    Ok(())
+}
+
+/// One member of a `--workers N` pool. Every member reads the same heartbeat and generator
+/// channels and writes the same outputs, holding them only to take or to deliver one batch,
+/// so one member runs the transform while another waits for the next beat.
+/// With --batch a member takes and sends a slice at a time, as the solo worker does.
+pub async fn run_member(actor: SteadyActorShadow
+                        , heartbeat_rx: SteadyRx<Pulse>
+                        , generator_rx: SteadyRx<Envelope<u64>>
+                        , priority_rx: SteadyRx<Envelope<u64>>
+                        , rules_rx: SteadyRx<RuleSet>
+                        , logger_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                        , dead_letter_tx: SteadyTx<DeadLetter>
+                        , stats_tx: SteadyTx<BatchStats>
+                        , report_tx: SteadyTx<BatchReport>
+                        , backlog_tx: SteadyTx<usize>
+                        , state: SteadyState<MemberState>
+                        , toggles: Toggles
+                        , rules: Option<Rules>
+                        , pool: WorkerPool) -> Result<(),Box<dyn Error>> {
+    //not on the edge of the graph, tested through its simulated neighbors like the solo worker
+    let actor = actor.into_spotlight([&heartbeat_rx, &generator_rx, &priority_rx, &rules_rx], [&logger_tx, &dead_letter_tx, &stats_tx, &report_tx, &backlog_tx]);
+    let poison_value = actor.args::<crate::MainArg>().and_then(|args| args.poison_value);
+    match poison_value {
+        Some(poison) => member_behavior(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_tx, dead_letter_tx, stats_tx, report_tx, backlog_tx, state, toggles
+                                        , Poisoned { poison, inner: ActiveRules(rules) }, pool).await,
+        None => member_behavior(actor, heartbeat_rx, generator_rx, priority_rx, rules_rx, logger_tx, dead_letter_tx, stats_tx, report_tx, backlog_tx, state, toggles, ActiveRules(rules), pool).await,
+    }
+}
+
+async fn member_behavior<A: SteadyActor, T: Transform<u64, FizzBuzzMessage>>(mut actor: A
+                                           , heartbeat_rx: SteadyRx<Pulse>
+                                           , generator_rx: SteadyRx<Envelope<u64>>
+                                           , priority_rx: SteadyRx<Envelope<u64>>
+                                           , rules_rx: SteadyRx<RuleSet>
+                                           , logger_tx: SteadyTx<Envelope<FizzBuzzMessage>>
+                                           , dead_letter_tx: SteadyTx<DeadLetter>
+                                           , stats_tx: SteadyTx<BatchStats>
+                                           , report_tx: SteadyTx<BatchReport>
+                                           , backlog_tx: SteadyTx<usize>
+                                           , state: SteadyState<MemberState>
+                                           , toggles: Toggles
+                                           , mut transform: T
+                                           , pool: WorkerPool) -> Result<(),Box<dyn Error>> {
+    let args = actor.args::<crate::MainArg>().expect("unable to downcast");
+    let max_value = args.max_value.unwrap_or(u64::MAX);
+    let clock = args.clock.clone();
+    let slices = args.batch;
+    let mut taken_slice = [Envelope::new(0, Instant::now(), 0u64); WORKER_SLICE];
+    let mut ready_slice = [Envelope::new(0, Instant::now(), FizzBuzzMessage::default()); WORKER_SLICE];
+    // created on the first start, handed back as it was left after a panic and restart
+    let mut state = state.lock(MemberState::default).await;
+    if !state.pending.is_empty() {
+        info!("Worker member resuming with {} values", state.pending.len());
+    }
+    // number of the last rule set this member took over from the pool, from zero again after
+    // a restart as the transform is back to the startup rules and takes the latest set over anew
+    let mut rules_seen = 0;
+
+    while actor.is_running(|| i!(state.left)) {
+        if state.left {
+            actor.wait_shutdown().await;
+            continue;
+        }
+
+        // The inputs are held only while taking a batch, then the next member waits for the next beat.
+        // A batch still held from before a restart goes out first.
+        let (beat, batch, backlog, done, latest) = if !state.pending.is_empty() {
+            (state.beat, true, actor.avail_units(&mut generator_rx.lock().await), false, None)
+        } else {
+            let mut heartbeat_rx = heartbeat_rx.lock().await;
+            let mut generator_rx = generator_rx.lock().await;
+            let mut priority_rx = priority_rx.lock().await;
+            if heartbeat_rx.is_closed_and_empty() && !(generator_rx.is_closed_and_empty() && priority_rx.is_closed_and_empty()) {
+                // as for the solo worker, a one-shot heartbeat means no batch until the shutdown drain
+                actor.wait_shutdown().await;
+            }
+            let clean = await_for_all!(actor.wait_avail(&mut heartbeat_rx, 1)
+                                     , wait_for_any!(actor.wait_avail(&mut generator_rx, 1), actor.wait_avail(&mut priority_rx, 1)));
+
+            let pulse = actor.try_take(&mut heartbeat_rx);
+            let (beat, drain) = match pulse {
+                Some(Pulse::Beat(heartbeat)) => (Some(heartbeat.beat), false),
+                Some(Pulse::Flush) => (None, true),
+                Some(Pulse::LastBeat) => {
+                    info!("Last beat, draining {} values before shutdown", actor.avail_units(&mut generator_rx) + actor.avail_units(&mut priority_rx));
+                    (None, true)
+                }
+                None => (None, false),
+            };
+            let batch = pulse.is_some() || !clean;
+            if batch {
+                let room = if drain { usize::MAX } else { actor.vacant_units(&mut logger_tx.lock().await) };
+                let (urgent, regular) = plan_batch(actor.avail_units(&mut priority_rx), actor.avail_units(&mut generator_rx), room);
+                for (rx, mut items) in [(&mut priority_rx, urgent), (&mut generator_rx, regular)] {
+                    if slices {
+                        // one take per slice rather than a call per value
+                        while items > 0 {
+                            let count = actor.take_slice(rx, &mut taken_slice[..items.min(WORKER_SLICE)]).item_count();
+                            if count == 0 {
+                                break;
+                            }
+                            state.pending.extend(&taken_slice[..count]);
+                            items -= count;
+                        }
+                    } else {
+                        for _ in 0..items {
+                            state.pending.push_back(actor.try_take(rx).expect("confirmed available but not found !!"));
+                        }
+                    }
+                }
+                state.beat = beat;
+            }
+            let done = heartbeat_rx.is_closed_and_empty() && generator_rx.is_closed_and_empty() && priority_rx.is_closed_and_empty();
+            let mut rules_rx = rules_rx.lock().await;
+            let mut taken = None;
+            while let Some(rules) = actor.try_take(&mut rules_rx) {
+                taken = Some(rules);
+            }
+            (beat, batch, actor.avail_units(&mut generator_rx), done, pool.rules_since(taken, &mut rules_seen))
+        };
+
+        reload(&mut transform, latest, beat);
+        if batch {
+            let started = clock.now();
+            let mut tally = BatchTally::default();
+            let validate = toggles.is_enabled(Toggle::Validation);
+            let held = state.pending.len();
+            let outcomes: Vec<_> = state.pending.iter().map(|item| classify(*item, validate, max_value, &mut transform, &mut tally)).collect();
+            // The outputs are held only while delivering, always in this order so members never deadlock.
+            {
+                let mut logger_tx = logger_tx.lock().await;
+                let mut dead_letter_tx = dead_letter_tx.lock().await;
+                let mut next = 0;
+                while next < outcomes.len() {
+                    #[cfg(test)]
+                    transform.delivering(state.pending.front().map_or(0, |item| item.payload));
+                    let sent = match &outcomes[next] {
+                        Ok(_) if slices => {
+                            // a run of messages goes out as one slice
+                            let mut ready = 0;
+                            while ready < WORKER_SLICE && let Some(Ok(msg)) = outcomes.get(next + ready) {
+                                ready_slice[ready] = *msg;
+                                ready += 1;
+                            }
+                            await_for_all!(actor.wait_vacant(&mut logger_tx, 1));
+                            actor.send_slice(&mut logger_tx, &ready_slice[..ready]).item_count()
+                        }
+                        Ok(msg) => usize::from(actor.send_async(&mut logger_tx, *msg, SendSaturation::AwaitForRoom).await.is_sent()),
+                        Err(letter) => usize::from(actor.send_async(&mut dead_letter_tx, *letter, SendSaturation::AwaitForRoom).await.is_sent()),
+                    };
+                    if sent == 0 {
+                        // cut short by a shutdown, the rest stays held for the next round
+                        break;
+                    }
+                    for outcome in &outcomes[next..next + sent] {
+                        state.pending.pop_front();
+                        state.totals.count(outcome.as_ref().map(|msg| &msg.payload));
+                    }
+                    next += sent;
+                }
+            }
+            if held > 0 && state.pending.is_empty() {
+                state.totals.batches += 1;
+            }
+            if toggles.is_enabled(Toggle::Audit) {
+                info!("Audit batch {:?} items {} checksum {:016x}", beat, tally.items, tally.checksum.value());
+            }
+            let _ = actor.try_send(&mut stats_tx.lock().await, tally.stats(beat));
+            let _ = actor.try_send(&mut report_tx.lock().await, tally.report(clock.since(started)));
+            let _ = actor.try_send(&mut backlog_tx.lock().await, backlog);
+        }
+
+        if done && state.pending.is_empty() {
+            state.left = true;
+            if pool.leave() {
+                // every member has delivered its last batch, the shared outputs can close
+                let _ = logger_tx.lock().await.mark_closed();
+                let _ = dead_letter_tx.lock().await.mark_closed();
+                let _ = stats_tx.lock().await.mark_closed();
+                let _ = report_tx.lock().await.mark_closed();
+                let _ = backlog_tx.lock().await.mark_closed();
             }
         }
     }
+    info!("{}", state.totals.report());
     Ok(())
 }
 
@@ -103,39 +1028,715 @@ async fn internal_behavior<A: SteadyActor>(mut actor: A
 #[cfg(test)]
 pub(crate) mod worker_tests {
 
+    use std::sync::atomic::AtomicBool;
+    use std::time::{Instant, SystemTime};
     use steady_state::*;
+    use crate::actor::heartbeat::Heartbeat;
+    use crate::arg::MainArg;
+    use crate::clock::{Clock, VirtualClock};
+    use crate::envelope::UNSEQUENCED;
+    use crate::envelope::envelope_tests::sealed;
     use super::*;
 
+    /// What sets one worker under test apart, `Setup::new` giving it one logger channel and no spill.
+    struct Setup<T> {
+        transform: T,
+        toggles: Toggles,
+        loggers: usize,
+        logger_capacity: Option<usize>,
+        spill: Option<Spill>,
+    }
+
+    impl<T> Setup<T> {
+        fn new(transform: T) -> Self {
+            Setup { transform, toggles: Toggles::default(), loggers: 1, logger_capacity: None, spill: None }
+        }
+    }
+
+    /// The test side of every channel around one solo worker.
+    struct Harness {
+        generate_tx: SteadyTx<Envelope<u64>>,
+        priority_tx: SteadyTx<Envelope<u64>>,
+        rules_tx: SteadyTx<RuleSet>,
+        heartbeat_tx: SteadyTx<Pulse>,
+        logger_rxs: Vec<SteadyRx<Envelope<FizzBuzzMessage>>>,
+        dead_letter_rx: SteadyRx<DeadLetter>,
+        report_rx: SteadyRx<BatchReport>,
+        _stats_rx: SteadyRx<BatchStats>,
+        _backlog_rx: SteadyRx<usize>,
+    }
+
+    impl Harness {
+        /// Builds the worker's channels and spawns it on them as `UnitTest`, always through
+        /// internal_behavior for testing.
+        fn spawn<T: Transform<u64, FizzBuzzMessage> + Clone + Sync + 'static>(graph: &mut Graph, setup: Setup<T>) -> Self {
+            let (generate_tx, generate_rx) = graph.channel_builder().build();
+            let (priority_tx, priority_rx) = graph.channel_builder().build();
+            let (rules_tx, rules_rx) = graph.channel_builder().build::<RuleSet>();
+            let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+            let logger_builder = match setup.logger_capacity {
+                Some(capacity) => graph.channel_builder().with_capacity(capacity),
+                None => graph.channel_builder(),
+            };
+            let (logger_txs, logger_rxs): (Vec<_>, Vec<_>) = (0..setup.loggers).map(|_| logger_builder.build::<Envelope<FizzBuzzMessage>>()).unzip();
+            let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().build::<DeadLetter>();
+            let (stats_tx, stats_rx) = graph.channel_builder().build::<BatchStats>();
+            let (report_tx, report_rx) = graph.channel_builder().build::<BatchReport>();
+            let (backlog_tx, backlog_rx) = graph.channel_builder().build::<usize>();
+            let state = new_state();
+
+            let Setup { transform, toggles, spill, .. } = setup;
+            graph.actor_builder().with_name("UnitTest")
+                .build(move |context| internal_behavior(context
+                                                        , heartbeat_rx.clone()
+                                                        , generate_rx.clone()
+                                                        , priority_rx.clone()
+                                                        , rules_rx.clone()
+                                                        , logger_txs.clone()
+                                                        , dead_letter_tx.clone()
+                                                        , stats_tx.clone()
+                                                        , report_tx.clone()
+                                                        , backlog_tx.clone()
+                                                        , spill.clone()
+                                                        , state.clone()
+                                                        , toggles.clone()
+                                                        , transform.clone())
+                       , SoloAct
+                );
+            Harness { generate_tx, priority_tx, rules_tx, heartbeat_tx, logger_rxs, dead_letter_rx, report_rx, _stats_rx: stats_rx, _backlog_rx: backlog_rx }
+        }
+
+        /// Builds the same channels for a pool of `members` on one logger, each member spawned
+        /// on all of them with a state of its own.
+        fn spawn_pool<T: Transform<u64, FizzBuzzMessage> + Clone + Sync + 'static>(graph: &mut Graph, members: usize, transform: T) -> Self {
+            let (generate_tx, generate_rx) = graph.channel_builder().build();
+            let (priority_tx, priority_rx) = graph.channel_builder().build();
+            let (rules_tx, rules_rx) = graph.channel_builder().build::<RuleSet>();
+            let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
+            let (logger_tx, logger_rx) = graph.channel_builder().build::<Envelope<FizzBuzzMessage>>();
+            let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().build::<DeadLetter>();
+            let (stats_tx, stats_rx) = graph.channel_builder().build::<BatchStats>();
+            let (report_tx, report_rx) = graph.channel_builder().build::<BatchReport>();
+            let (backlog_tx, backlog_rx) = graph.channel_builder().build::<usize>();
+
+            let pool = WorkerPool::new(members);
+            for index in 0..members {
+                let (heartbeat_rx, generate_rx, priority_rx, rules_rx, logger_tx) = (heartbeat_rx.clone(), generate_rx.clone(), priority_rx.clone(), rules_rx.clone(), logger_tx.clone());
+                let (dead_letter_tx, stats_tx, report_tx, backlog_tx) = (dead_letter_tx.clone(), stats_tx.clone(), report_tx.clone(), backlog_tx.clone());
+                let (state, transform, pool) = (new_state(), transform.clone(), pool.clone());
+                graph.actor_builder().with_name_and_suffix("UnitTest", index)
+                    .build(move |context| member_behavior(context
+                                                          , heartbeat_rx.clone()
+                                                          , generate_rx.clone()
+                                                          , priority_rx.clone()
+                                                          , rules_rx.clone()
+                                                          , logger_tx.clone()
+                                                          , dead_letter_tx.clone()
+                                                          , stats_tx.clone()
+                                                          , report_tx.clone()
+                                                          , backlog_tx.clone()
+                                                          , state.clone()
+                                                          , Toggles::default()
+                                                          , transform.clone()
+                                                          , pool.clone())
+                           , SoloAct
+                    );
+            }
+            Harness { generate_tx, priority_tx, rules_tx, heartbeat_tx, logger_rxs: vec![logger_rx], dead_letter_rx, report_rx, _stats_rx: stats_rx, _backlog_rx: backlog_rx }
+        }
+
+        /// Sends `values` from the generator and then `beats`, closing every input.
+        fn send(&self, captured: Instant, values: Vec<u64>, beats: Vec<Pulse>) {
+            self.send_all(sealed(captured, values), vec![], vec![], beats);
+        }
+
+        fn send_all(&self, values: Vec<Envelope<u64>>, priority: Vec<Envelope<u64>>, rules: Vec<RuleSet>, beats: Vec<Pulse>) {
+            self.generate_tx.testing_send_all(values, true);
+            self.priority_tx.testing_send_all(priority, true);
+            self.rules_tx.testing_send_all(rules, true);
+            self.heartbeat_tx.testing_send_all(beats, true);
+        }
+    }
+
+    fn beat(beat: u64) -> Pulse {
+        Pulse::Beat(Heartbeat::on_time(beat, SystemTime::now()))
+    }
+
     #[test]
     fn test_worker() -> Result<(), Box<dyn Error>> {
         // Always create the GraphBuilder::for_testing()
-        let mut graph = GraphBuilder::for_testing().build(());
-        let (generate_tx, generate_rx) = graph.channel_builder().build();
-        let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build();
-        let (logger_tx, logger_rx) = graph.channel_builder().build::<FizzBuzzMessage>();
-
-        // Always use internal_behavior for testing
-        graph.actor_builder().with_name("UnitTest")
-            .build(move |context| internal_behavior(context
-                                                    , heartbeat_rx.clone()
-                                                    , generate_rx.clone()
-                                                    , logger_tx.clone())
-                   , SoloAct
-            );
-        
-        generate_tx.testing_send_all(vec![0,1,2,3,4,5], true);
-        heartbeat_tx.testing_send_all(vec![0], true);
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup::new(FizzBuzz));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![0,1,2,3,4,5], vec![beat(0)]);
         graph.start();
         // because clean shutdown waits for closed and empty
         // , it does not happen until our test data is digested. 
         graph.request_shutdown();// critical before block_until_stopped
         graph.block_until_stopped(Duration::from_secs(1))?;
-        assert_steady_rx_eq_take!(&logger_rx, [FizzBuzzMessage::FizzBuzz
-                                              ,FizzBuzzMessage::Value(1)
-                                              ,FizzBuzzMessage::Value(2)
-                                              ,FizzBuzzMessage::Fizz
-                                              ,FizzBuzzMessage::Value(4)
-                                              ,FizzBuzzMessage::Buzz]);
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::FizzBuzz
+                                                                          ,FizzBuzzMessage::Value(1)
+                                                                          ,FizzBuzzMessage::Value(2)
+                                                                          ,FizzBuzzMessage::Fizz
+                                                                          ,FizzBuzzMessage::Value(4)
+                                                                          ,FizzBuzzMessage::Buzz]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_dead_letter() -> Result<(), Box<dyn Error>> {
+        // a clock standing still makes the batch duration zero
+        let (clock, _source) = Clock::virtual_clock();
+        let args = MainArg { max_value: Some(3), clock, ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let worker = Harness::spawn(&mut graph, Setup::new(FizzBuzz));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![2,3,4], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // Rejected values never reach the logger, they are isolated on the dead-letter channel.
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(2), FizzBuzzMessage::Fizz]));
+        assert_steady_rx_eq_take!(&worker.dead_letter_rx, [DeadLetter { value: 4, reason: Reason::AboveMaxValue }]);
+        // the rejected value counts as an item of the batch but under no kind
+        assert_steady_rx_eq_take!(&worker.report_rx, [BatchReport { items: 3, fizz: 1, values: 1, ..BatchReport::default() }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_slices() -> Result<(), Box<dyn Error>> {
+        let args = MainArg { max_value: Some(4), batch: true, ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let worker = Harness::spawn(&mut graph, Setup::new(FizzBuzz));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![0,1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the same results and dead letters as one value per call
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::FizzBuzz
+                                                                          ,FizzBuzzMessage::Value(1)
+                                                                          ,FizzBuzzMessage::Value(2)
+                                                                          ,FizzBuzzMessage::Fizz
+                                                                          ,FizzBuzzMessage::Value(4)]));
+        assert_steady_rx_eq_take!(&worker.dead_letter_rx, [DeadLetter { value: 5, reason: Reason::AboveMaxValue }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_prefetch() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let (clock, _source) = Clock::virtual_clock();
+        let args = MainArg { max_value: Some(4), batch: true, prefetch: true, clock, ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let worker = Harness::spawn(&mut graph, Setup::new(FizzBuzz));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![0,1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // classified ahead of the beat or not, every value arrives once and in order
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::FizzBuzz
+                                                                          ,FizzBuzzMessage::Value(1)
+                                                                          ,FizzBuzzMessage::Value(2)
+                                                                          ,FizzBuzzMessage::Fizz
+                                                                          ,FizzBuzzMessage::Value(4)]));
+        assert_steady_rx_eq_take!(&worker.dead_letter_rx, [DeadLetter { value: 5, reason: Reason::AboveMaxValue }]);
+        // the batch counts the values it sent, none twice for having been classified early
+        assert_steady_rx_eq_take!(&worker.report_rx, [BatchReport { items: 6, fizz: 1, fizzbuzz: 1, values: 3, ..BatchReport::default() }]);
+        assert_in_logs!(["Worker totals over 1 batches: items 6 fizz 1 buzz 0 fizzbuzz 1 values 3 labeled 0 rejected 1 poisoned 0 truncated 0 spilled 0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_validation_toggled_off() -> Result<(), Box<dyn Error>> {
+        let args = MainArg { max_value: Some(3), ..MainArg::default() };
+        let mut graph = GraphBuilder::for_testing().build(args);
+        let toggles = Toggles::default();
+        toggles.set(Toggle::Validation, false);
+        let worker = Harness::spawn(&mut graph, Setup { toggles, ..Setup::new(FizzBuzz) });
+
+        let captured = Instant::now();
+        worker.send(captured, vec![2,3,4], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // With validation off the ceiling is ignored and nothing is dead-lettered.
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(2), FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(4)]));
+        assert_steady_rx_eq_take!(&worker.dead_letter_rx, Vec::<DeadLetter>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_last_beat() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup::new(FizzBuzz));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![3,4,5], vec![Pulse::LastBeat]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        assert_in_logs!(["Last beat, draining 3 values before shutdown"]);
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(4), FizzBuzzMessage::Buzz]));
+        Ok(())
+    }
+
+    /// Stateful stand-in rule: reports how many values it has seen instead of classifying them.
+    #[derive(Clone)]
+    struct Counting(u64);
+
+    impl Transform<u64, FizzBuzzMessage> for Counting {
+        fn apply(&mut self, _value: u64) -> FizzBuzzMessage {
+            self.0 += 1;
+            FizzBuzzMessage::Value(self.0)
+        }
+    }
+
+    #[test]
+    fn test_worker_custom_transform() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup::new(Counting(0)));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![3,5,15], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the loop is unchanged, only the rule applied to each value differs
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(1), FizzBuzzMessage::Value(2), FizzBuzzMessage::Value(3)]));
         Ok(())
     }
+
+    /// Classic rule which moves a virtual clock on by a millisecond per value, standing in for slow work.
+    #[derive(Clone)]
+    struct Slow(Arc<VirtualClock>);
+
+    impl Transform<u64, FizzBuzzMessage> for Slow {
+        fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+            self.0.advance(Duration::from_millis(1));
+            FizzBuzzMessage::new(value)
+        }
+    }
+
+    #[test]
+    fn test_worker_batch_deadline() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let (clock, source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { batch_deadline_ms: Some(2), clock, ..MainArg::default() });
+        let worker = Harness::spawn(&mut graph, Setup::new(Slow(source)));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![1,2,3,4,5], vec![beat(0), beat(1)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // each beat gets two values in before the deadline, the shutdown drain takes the last
+        assert_in_logs!(["Batch Some(0) passed its 2ms deadline after 2 of 5 values, the rest waits for the next batch"
+                       , "Batch Some(1) passed its 2ms deadline after 2 of 3 values, the rest waits for the next batch"]);
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(1)
+                                                                          ,FizzBuzzMessage::Value(2)
+                                                                          ,FizzBuzzMessage::Fizz
+                                                                          ,FizzBuzzMessage::Value(4)
+                                                                          ,FizzBuzzMessage::Buzz]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_truncate() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg { backpressure: Backpressure::Truncate, ..MainArg::default() });
+        // room for two of the five values, nothing takes from the logger while the worker runs
+        let worker = Harness::spawn(&mut graph, Setup { logger_capacity: Some(2), ..Setup::new(FizzBuzz) });
+
+        let captured = Instant::now();
+        worker.send(captured, vec![1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the batch takes everything waiting, what the logger has no room for is gone
+        assert_in_logs!(["Batch Some(0) truncated, 3 of 5 values dropped with the logger full"
+                       , "Worker totals over 1 batches: items 2 fizz 0 buzz 0 fizzbuzz 0 values 2 labeled 0 rejected 0 poisoned 0 truncated 3 spilled 0"]);
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(1), FizzBuzzMessage::Value(2)]));
+        Ok(())
+    }
+
+    /// Stands in for the logger held up until the first batch is reported, then takes every
+    /// message into `taken`.
+    async fn collect_after_batch(actor: SteadyActorShadow, logger_rx: SteadyRx<Envelope<FizzBuzzMessage>>, report_rx: SteadyRx<BatchReport>
+                                 , taken: Arc<Mutex<Vec<Envelope<FizzBuzzMessage>>>>) -> Result<(), Box<dyn Error>> {
+        let mut actor = actor.into_spotlight([&logger_rx, &report_rx], []);
+        let mut logger_rx = logger_rx.lock().await;
+        let mut report_rx = report_rx.lock().await;
+        await_for_all!(actor.wait_avail(&mut report_rx, 1));
+        while actor.is_running(|| i!(logger_rx.is_closed_and_empty())) {
+            await_for_all!(actor.wait_avail(&mut logger_rx, 1));
+            while let Some(msg) = actor.try_take(&mut logger_rx) {
+                taken.lock().expect("taken lock").push(msg);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_spill() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let dir = std::env::temp_dir().join(format!("standard_worker_spill_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        let (clock, _source) = Clock::virtual_clock();
+        let mut graph = GraphBuilder::for_testing().build(MainArg { backpressure: Backpressure::Spill
+                                                                  , spill_path: dir.join("spill.bin").to_string_lossy().to_string()
+                                                                  , clock
+                                                                  , ..MainArg::default() });
+        let (spill_tx, spill_rx) = graph.channel_builder().build();
+        let (unspill_tx, unspill_rx) = graph.channel_builder().build();
+        let spill = Spill { tx: spill_tx, rx: unspill_rx };
+        let spill_state = new_state();
+        let worker = Harness::spawn(&mut graph, Setup { logger_capacity: Some(2), spill: Some(spill), ..Setup::new(FizzBuzz) });
+        graph.actor_builder().with_name("Spill")
+            .build(move |context| crate::actor::spill::run(context, spill_rx.clone(), unspill_tx.clone(), spill_state.clone())
+                   , SoloAct);
+        let taken = Arc::new(Mutex::new(Vec::new()));
+        let (logger_rx, report_rx, logger_taken) = (worker.logger_rxs[0].clone(), worker.report_rx.clone(), taken.clone());
+        graph.actor_builder().with_name("Logger")
+            .build(move |context| collect_after_batch(context, logger_rx.clone(), report_rx.clone(), logger_taken.clone()), SoloAct);
+
+        let captured = Instant::now();
+        worker.send(captured, vec![1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        let _ = std::fs::remove_dir_all(&dir);
+        // two fit the logger, the rest came back from the spill in order as the logger made room
+        assert_eq!(*taken.lock().expect("taken lock"), sealed(captured, [FizzBuzzMessage::Value(1), FizzBuzzMessage::Value(2), FizzBuzzMessage::Fizz
+                                                                       , FizzBuzzMessage::Value(4), FizzBuzzMessage::Buzz]));
+        assert_in_logs!(["Spill buffered 3 values, 0 left on disk"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_poison() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup::new(Poisoned { poison: u64::MAX, inner: FizzBuzz }));
+
+        let captured = Instant::now();
+        let sent = sealed(captured, vec![1, u64::MAX, 3, u64::MAX, 5]);
+        worker.send_all(sent.clone(), vec![], vec![], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the values around each poison flow on in order, each keeping its sequence number
+        let delivered: Vec<_> = sent.into_iter().filter(|item| item.payload != u64::MAX).map(|item| item.map(FizzBuzzMessage::new)).collect();
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], delivered);
+        assert_steady_rx_eq_take!(&worker.dead_letter_rx, [DeadLetter { value: u64::MAX, reason: Reason::Poisoned }
+                                                         , DeadLetter { value: u64::MAX, reason: Reason::Poisoned }]);
+        // caught inside the batch, the worker never restarted and counted each poison once
+        assert_in_logs!(["Transform failed on 18446744073709551615, sent to the dead letters"
+                       , "Worker totals over 1 batches: items 5 fizz 1 buzz 1 fizzbuzz 0 values 1 labeled 0 rejected 2 poisoned 2 truncated 0 spilled 0"]);
+        Ok(())
+    }
+
+    /// Classic rule which panics the first time it is about to deliver one value, like a bug
+    /// hit mid-batch outside the transform, where the failure is not caught.
+    #[derive(Clone)]
+    struct PanicOnce {
+        value: u64,
+        armed: Arc<AtomicBool>,
+    }
+
+    impl Transform<u64, FizzBuzzMessage> for PanicOnce {
+        fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+            FizzBuzzMessage::new(value)
+        }
+
+        fn delivering(&mut self, value: u64) {
+            if value == self.value && self.armed.swap(false, Ordering::SeqCst) {
+                panic!("deliberate worker failure delivering {}", value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_worker_panic_loses_nothing() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup::new(PanicOnce { value: 3, armed: Arc::new(AtomicBool::new(true)) }));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // 3 was only peeked when the worker failed, the restarted worker finds it still queued
+        // and every value arrives exactly once
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(1)
+                                                                          ,FizzBuzzMessage::Value(2)
+                                                                          ,FizzBuzzMessage::Fizz
+                                                                          ,FizzBuzzMessage::Value(4)
+                                                                          ,FizzBuzzMessage::Buzz]));
+        // the counts kept across the restart, 1 and 2 from before it and each value only once
+        assert_in_logs!(["Worker resuming after 2 values"
+                       , "Worker totals over 1 batches: items 5 fizz 1 buzz 1 fizzbuzz 0 values 3 labeled 0 rejected 0 poisoned 0 truncated 0 spilled 0"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_rules() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let rules = Rules::from_settings(&["3=Fizz".to_string(), "7=Bazz".to_string()]).expect("valid rules").expect("rules given");
+        let worker = Harness::spawn(&mut graph, Setup::new(rules.clone()));
+
+        let captured = Instant::now();
+        worker.send(captured, vec![5,7,21], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // 5 matches no rule, the fixed Buzz is gone with the classic classification
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [5, 7, 21].map(|v| FizzBuzzMessage::Classified(rules.classify(v)))));
+        assert_eq!(rules.classify(21).labels().to_string(), "FizzBazz");
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_reload() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        // started classic, as without any --rule
+        let worker = Harness::spawn(&mut graph, Setup::new(ActiveRules(None)));
+
+        let captured = Instant::now();
+        let classic = RuleSet::from_settings(&[]).expect("no rules is valid");
+        let bazz = RuleSet::from_settings(&["3=Fizz".to_string(), "7=Bazz".to_string()]).expect("valid rules");
+        // only the newest of the sets sent before the batch is applied
+        worker.send_all(sealed(captured, vec![7,21]), vec![], vec![classic, bazz.clone()], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        let rules = Rules::register(bazz.0.expect("rules given")).expect("registered by the worker");
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], sealed(captured, [7, 21].map(|v| FizzBuzzMessage::Classified(rules.classify(v)))));
+        assert_in_logs!(["Rules now 3=Fizz 7=Bazz from batch Some(0)"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_shards() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup { loggers: 2, ..Setup::new(FizzBuzz) });
+
+        let captured = Instant::now();
+        worker.send(captured, vec![0,1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the default modulo partitioner puts even values on shard 0 and odd ones on shard 1,
+        // each shard in the order its values were generated
+        let expected = |shard| sealed(captured, (0..6).map(FizzBuzzMessage::new)).into_iter().filter(move |e| e.seq % 2 == shard).collect::<Vec<_>>();
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], expected(0));
+        assert_steady_rx_eq_take!(&worker.logger_rxs[1], expected(1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_pool() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        // two members on the very same channels
+        let pool = Harness::spawn_pool(&mut graph, 2, FizzBuzz);
+
+        let captured = Instant::now();
+        pool.send(captured, vec![0,1,2,3,4,5], vec![beat(0), beat(1)]);
+        graph.start();
+        // the graph only stops once the last member out has closed the shared outputs
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the first beat takes every waiting value, whichever member it reached
+        assert_in_logs!(["Worker totals over 1 batches: items 6 fizz 1 buzz 1 fizzbuzz 1 values 3 labeled 0 rejected 0 poisoned 0 truncated 0 spilled 0"]);
+        assert_steady_rx_eq_take!(&pool.logger_rxs[0], sealed(captured, [FizzBuzzMessage::FizzBuzz
+                                                                        ,FizzBuzzMessage::Value(1)
+                                                                        ,FizzBuzzMessage::Value(2)
+                                                                        ,FizzBuzzMessage::Fizz
+                                                                        ,FizzBuzzMessage::Value(4)
+                                                                        ,FizzBuzzMessage::Buzz]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_pool_slices() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg { batch: true, max_value: Some(100), ..MainArg::default() });
+        let pool = Harness::spawn_pool(&mut graph, 2, FizzBuzz);
+
+        let captured = Instant::now();
+        pool.send(captured, vec![1,2,3,500,5,6], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // taken as one slice, sent as the runs of messages either side of the dead letter
+        assert_in_logs!(["Worker totals over 1 batches: items 6 fizz 2 buzz 1 fizzbuzz 0 values 2 labeled 0 rejected 1 poisoned 0 truncated 0 spilled 0"]);
+        let mut expected = sealed(captured, [FizzBuzzMessage::Value(1), FizzBuzzMessage::Value(2), FizzBuzzMessage::Fizz
+                                           , FizzBuzzMessage::Value(500), FizzBuzzMessage::Buzz, FizzBuzzMessage::Fizz]);
+        expected.remove(3);
+        assert_steady_rx_eq_take!(&pool.logger_rxs[0], expected);
+        assert_steady_rx_eq_take!(&pool.dead_letter_rx, [DeadLetter { value: 500, reason: Reason::AboveMaxValue }]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_worker_pool_panic_loses_nothing() -> Result<(), Box<dyn Error>> {
+        use steady_logger::*;
+        let _guard = start_log_capture();
+
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        // whichever member takes the batch fails delivering 3, once
+        let pool = Harness::spawn_pool(&mut graph, 2, PanicOnce { value: 3, armed: Arc::new(AtomicBool::new(true)) });
+
+        let captured = Instant::now();
+        pool.send(captured, vec![1,2,3,4,5], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // the values taken but not yet sent were held in the member's state, and the restarted
+        // member sends them before anything else
+        // and its counts carried on, each value counted once
+        assert_in_logs!(["Worker member resuming with 3 values"
+                       , "Worker totals over 1 batches: items 5 fizz 1 buzz 1 fizzbuzz 0 values 3 labeled 0 rejected 0 poisoned 0 truncated 0 spilled 0"]);
+        assert_steady_rx_eq_take!(&pool.logger_rxs[0], sealed(captured, [FizzBuzzMessage::Value(1)
+                                                                        ,FizzBuzzMessage::Value(2)
+                                                                        ,FizzBuzzMessage::Fizz
+                                                                        ,FizzBuzzMessage::Value(4)
+                                                                        ,FizzBuzzMessage::Buzz]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_pool_rules() {
+        let pool = WorkerPool::new(2);
+        let bazz = RuleSet::from_settings(&["7=Bazz".to_string()]).expect("valid rules");
+        let (mut first, mut second) = (0, 0);
+        assert_eq!(pool.rules_since(None, &mut first), None);
+        // the member taking the set off the channel takes it over, and so does the other one, once
+        assert_eq!(pool.rules_since(Some(bazz.clone()), &mut first), Some(bazz.clone()));
+        assert_eq!(pool.rules_since(None, &mut second), Some(bazz));
+        assert_eq!(pool.rules_since(None, &mut first), None);
+        assert_eq!(pool.rules_since(None, &mut second), None);
+        // back to classic is a set like any other
+        let classic = RuleSet::from_settings(&[]).expect("no rules is valid");
+        assert_eq!(pool.rules_since(Some(classic.clone()), &mut second), Some(classic.clone()));
+        assert_eq!(pool.rules_since(None, &mut first), Some(classic));
+    }
+
+    #[test]
+    fn test_worker_priority() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let worker = Harness::spawn(&mut graph, Setup::new(FizzBuzz));
+
+        let captured = Instant::now();
+        worker.send_all(sealed(captured, vec![0,1,2]), vec![Envelope::new(UNSEQUENCED, captured, 9)], vec![], vec![beat(0)]);
+        graph.start();
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(1))?;
+        // sent last but taken first
+        let mut expected = vec![Envelope::new(UNSEQUENCED, captured, FizzBuzzMessage::Fizz)];
+        expected.extend(sealed(captured, [FizzBuzzMessage::FizzBuzz, FizzBuzzMessage::Value(1), FizzBuzzMessage::Value(2)]));
+        assert_steady_rx_eq_take!(&worker.logger_rxs[0], expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan_batch() {
+        // room for everything, nothing held back
+        assert_eq!(plan_batch(3, 5, 64), (3, 5));
+        // a flood of priority values still leaves a quarter of the batch to the generator
+        assert_eq!(plan_batch(100, 100, 64), (48, 16));
+        // the share is only kept while generator values wait
+        assert_eq!(plan_batch(100, 0, 64), (64, 0));
+        assert_eq!(plan_batch(100, 2, 64), (62, 2));
+        // and the generator takes what priority leaves unused
+        assert_eq!(plan_batch(4, 100, 64), (4, 60));
+        assert_eq!(plan_batch(5, 5, 1), (0, 1));
+        assert_eq!(plan_batch(5, 5, 0), (0, 0));
+    }
+
+    #[test]
+    fn test_encode_decode() {
+        // Value(3) and Fizz share the number 3, only the kind tells them apart
+        assert_ne!(FizzBuzzMessage::Value(3).encode(), FizzBuzzMessage::Fizz.encode());
+        assert_ne!(FizzBuzzMessage::Value(15).encode(), FizzBuzzMessage::FizzBuzz.encode());
+        let rules = Rules::from_settings(&["3=Fizz".to_string()]).expect("valid rules");
+        let mut messages: Vec<_> = (0..16).map(FizzBuzzMessage::new).collect();
+        messages.extend([FizzBuzzMessage::Value(3), FizzBuzzMessage::Value(5), FizzBuzzMessage::Value(u64::MAX)]);
+        messages.extend(rules.as_ref().map(|rules| FizzBuzzMessage::Classified(rules.classify(9))));
+        for msg in messages {
+            assert_eq!(FizzBuzzMessage::decode(&msg.encode(), rules.as_ref()), Ok(msg));
+        }
+        assert!(FizzBuzzMessage::decode(&FizzBuzzMessage::Value(9).encode()[..8], None).is_err());
+        assert!(FizzBuzzMessage::decode(&[9, 0, 0, 0, 0, 0, 0, 0, 0], None).is_err());
+        // a Fizz is always 3, anything else was corrupted on the way
+        assert!(FizzBuzzMessage::decode(&[2, 4, 0, 0, 0, 0, 0, 0, 0], None).is_err());
+        let classified = rules.as_ref().map(|rules| FizzBuzzMessage::Classified(rules.classify(9)).encode()).expect("rules given");
+        assert!(FizzBuzzMessage::decode(&classified, None).is_err());
+    }
+
+    #[test]
+    fn test_serde() -> Result<(), serde_json::Error> {
+        assert_eq!(serde_json::to_string(&FizzBuzzMessage::Fizz)?, "\"Fizz\"");
+        assert_eq!(serde_json::to_string(&FizzBuzzMessage::Value(3))?, "{\"Value\":3}");
+        for msg in [FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(3), FizzBuzzMessage::FizzBuzz, FizzBuzzMessage::Value(15)] {
+            assert_eq!(serde_json::from_str::<FizzBuzzMessage>(&serde_json::to_string(&msg)?)?, msg);
+        }
+        let rules = Rules::from_settings(&["3=Fizz".to_string(), "7=Bazz".to_string()]).expect("valid rules").expect("rules given");
+        let classified = serde_json::to_string(&FizzBuzzMessage::Classified(rules.classify(21)))?;
+        assert_eq!(classified, "{\"Classified\":{\"value\":21,\"labels\":[\"Fizz\",\"Bazz\"]}}");
+        // readable, but only decode with the rules rebuilds it
+        assert!(serde_json::from_str::<FizzBuzzMessage>(&classified).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_message_size() {
+        // labels under --rule must not make the message on every channel bigger
+        assert_eq!(std::mem::size_of::<FizzBuzzMessage>(), std::mem::size_of::<(u64, u64)>());
+    }
+
+    #[test]
+    fn test_batch_checksum() {
+        let mut first = BatchChecksum::default();
+        let mut second = BatchChecksum::default();
+        (0..100).map(FizzBuzzMessage::new).for_each(|m| first.update(&m));
+        (0..100).map(FizzBuzzMessage::new).for_each(|m| second.update(&m));
+        assert_eq!(first, second);
+
+        // order matters, a swapped pair must be detected
+        let mut swapped = BatchChecksum::default();
+        [FizzBuzzMessage::Value(1), FizzBuzzMessage::Fizz].iter().for_each(|m| swapped.update(m));
+        let mut expected = BatchChecksum::default();
+        [FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(1)].iter().for_each(|m| expected.update(m));
+        assert_ne!(swapped, expected);
+    }
 }
@@ -1,4 +1,12 @@
-use clap::Parser;
+use std::time::Duration;
+use clap::{Parser, Subcommand, ValueEnum};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use steady_state::LogLevel;
+use crate::calibration::Calibration;
+use crate::clock::Clock;
+use crate::format::Locale;
+use crate::pacing::ReplayPacing;
 
 /// Command-line argument structure demonstrating runtime configuration integration.
 /// This is normal 'clap' and for more details you should review their documentation.
@@ -15,6 +23,741 @@ pub(crate) struct MainArg {
     /// that need predictable completion behavior.
     #[arg(short = 'b', long = "beats", default_value = "120")]
     pub(crate) beats: u64,
+
+    /// What reaching --beats does: shut down, pause the heartbeat (and with it the batches)
+    /// until resumed, restart the count for cyclical runs, or only emit an event.
+    #[arg(long = "on-beats-complete", value_enum, default_value = "shutdown")]
+    pub(crate) on_beats_complete: OnBeatsComplete,
+
+    /// Beats missed while a crashed heartbeat was restarting: skip them and carry on from
+    /// the restart, or send them back to back before resuming the schedule.
+    #[arg(long = "catchup-policy", value_enum, default_value = "skip")]
+    pub(crate) catchup_policy: CatchupPolicy,
+
+    /// Fires heartbeats on wall-clock multiples of the rate and numbers them by that slot,
+    /// so batch ids line up across pipelines and instances.
+    #[arg(long = "align-beats")]
+    pub(crate) align_beats: bool,
+
+    /// Holds the first heartbeat until the next wall-clock second or minute, later beats
+    /// staying phase-locked to it at --rate, so logs and batches fall on clean timestamps.
+    #[arg(long = "align", value_enum, conflicts_with = "align_beats")]
+    pub(crate) align: Option<Align>,
+
+    /// One-shot trigger: the heartbeat waits N milliseconds, sends a single beat, then closes
+    /// its channels and exits, so the worker runs one batch after a warmup.
+    #[arg(long = "trigger-after-ms", conflicts_with_all = ["align_beats", "align"])]
+    pub(crate) trigger_after_ms: Option<u64>,
+
+    /// Moves every heartbeat interval by a random offset within ±N milliseconds, drawn from
+    /// --seed mixed with the process id, so instances started together spread their beats out
+    /// even with the same seed. Not used with --align-beats.
+    #[arg(long = "jitter-ms", default_value = "0")]
+    pub(crate) jitter_ms: u64,
+
+    /// Generator backlog, reported by the worker after every batch, above which the heartbeat
+    /// beats four times as fast to drain it, relaxing once it is under half. 0 turns it off.
+    /// Not used with --align-beats.
+    #[arg(long = "backlog-threshold", default_value = "0")]
+    pub(crate) backlog_threshold: usize,
+
+    /// Validation ceiling for the worker. Values above this limit are treated as
+    /// unprocessable and routed to the dead-letter actor instead of the logger.
+    /// When not provided every value is considered valid.
+    #[arg(long = "max-value")]
+    pub(crate) max_value: Option<u64>,
+
+    /// Destination file for the dead-letter actor. Rejected messages are appended
+    /// here so they can be inspected after the run without digging through logs.
+    #[arg(long = "dead-letter-path", default_value = "dead_letter.log")]
+    pub(crate) dead_letter_path: String,
+
+    /// When set, worker output is written to this file by the file logger
+    /// instead of being printed through the console logger.
+    #[arg(long = "out-file")]
+    pub(crate) out_file: Option<String>,
+
+    /// Size in bytes at which the file logger rotates its output file.
+    #[arg(long = "rotate-bytes", default_value = "10485760")]
+    pub(crate) rotate_bytes: u64,
+
+    /// Number of rotated files kept next to the active output file.
+    #[arg(long = "rotate-keep", default_value = "5")]
+    pub(crate) rotate_keep: usize,
+
+    /// Pipeline variant to build from this binary.
+    #[arg(long = "topology", value_enum, default_value = "standard")]
+    pub(crate) topology: TopologyKind,
+
+    /// Number of chains built by the parallel topology.
+    #[arg(long = "pipelines", default_value = "4")]
+    pub(crate) pipelines: usize,
+
+    /// Shorthand for --topology parallel --pipelines N.
+    /// Used to stress graph construction, telemetry and shutdown at scale.
+    #[arg(long = "stress-topology")]
+    pub(crate) stress_topology: Option<usize>,
+
+    /// Address for the HTTP control endpoint, e.g. 127.0.0.1:9901.
+    /// The endpoint accepts /pause, /resume, /set-rate?ms=N, /history?window=5m, /toggle?name=audit&state=off,
+    /// /info and /shutdown. The parallel topology instead serves /stop-pipeline?index=N, /info and /shutdown.
+    #[arg(long = "control-addr")]
+    pub(crate) control_addr: Option<String>,
+
+    /// Minutes of per-heartbeat batch statistics retained in memory for /history.
+    #[arg(long = "history-minutes", default_value = "10")]
+    pub(crate) history_minutes: u64,
+
+    /// Inserts the router after the worker, sending each message kind to its own logger
+    /// while the main logger (or file logger) still receives the merged stream.
+    #[arg(long = "route-by-kind", alias = "fanout")]
+    pub(crate) route_by_kind: bool,
+
+    /// Inserts the aggregator after the worker to publish rolling statistics.
+    #[arg(long = "aggregate")]
+    pub(crate) aggregate: bool,
+
+    /// Length of each aggregation window in milliseconds.
+    #[arg(long = "aggregate-ms", default_value = "1000")]
+    pub(crate) aggregate_ms: u64,
+
+    /// Extra heartbeat tick rates in milliseconds, as a list such as `--tick-ms 50,1000`, each
+    /// driven on a channel of its own and counted by a tick logger.
+    #[arg(long = "tick-ms", value_delimiter = ',')]
+    pub(crate) tick_ms: Vec<u64>,
+
+    /// Inserts the windower after the worker, closing a tumbling window every N messages.
+    /// With --window-beats too, a window closes on whichever comes first.
+    #[arg(long = "window-count", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) window_count: Option<u64>,
+
+    /// Inserts the windower after the worker, closing a tumbling window every N heartbeats.
+    #[arg(long = "window-beats", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) window_beats: Option<u64>,
+
+    /// Locale for numbers and durations in summaries and reports.
+    #[arg(long = "locale", value_enum, default_value = "en")]
+    pub(crate) locale: Locale,
+
+    /// How the generator produces values: an increasing counter, seeded pseudo-random values,
+    /// numbers piped in on stdin or counter bursts.
+    #[arg(long = "gen-mode", value_enum, default_value = "counter")]
+    pub(crate) gen_mode: GenMode,
+
+    /// Seed for --gen-mode random, the same seed always yields the same sequence.
+    #[arg(long = "seed", default_value = "0")]
+    pub(crate) seed: u64,
+
+    /// Target generator throughput in messages per second, sent in batches every 10 ms
+    /// instead of as fast as the channel accepts.
+    #[arg(long = "gen-rate", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) gen_rate: Option<u64>,
+
+    /// Most values the generator sends in one slice when the channel has room for them,
+    /// 1 sends every value on its own.
+    #[arg(long = "batch-size", default_value = "64", value_parser = clap::value_parser!(u64).range(1..).map(|n| n as usize))]
+    pub(crate) batch_size: usize,
+
+    /// What the generator does when the channel to the worker is full: await room,
+    /// drop the newest values or hold the newest --batch-size values, dropping the oldest.
+    #[arg(long = "overflow-policy", value_enum, default_value = "await")]
+    pub(crate) overflow_policy: OverflowPolicy,
+
+    /// Number of generators, each producing the values --partitioner assigns to it,
+    /// merged into the single channel the worker reads.
+    #[arg(long = "generators", default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) generators: u64,
+
+    /// How values are spread over --generators partitions and --shards loggers.
+    #[arg(long = "partitioner", value_enum, default_value = "modulo")]
+    pub(crate) partitioner: PartitionStrategy,
+
+    /// Points per partition on the --partitioner consistent hash ring; more points even out the shares.
+    #[arg(long = "virtual-nodes", default_value = "64", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) virtual_nodes: u64,
+
+    /// Number of workers sharing the heartbeat and generator channels, each taking whole
+    /// batches in turn, their results merged into the single channel the logger reads.
+    #[arg(long = "workers", default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) workers: u64,
+
+    /// Workers take and send each batch in slices through stack buffers rather than one
+    /// call per value, compare the two with `bench --compare-batch`.
+    #[arg(long = "batch")]
+    pub(crate) batch: bool,
+
+    /// With --batch the worker classifies the values waiting at the front of the generator
+    /// channel while it awaits the beat, so the batch sends them as soon as the beat comes.
+    /// Compare the batch latency against plain slices with `bench --compare-prefetch`.
+    #[arg(long = "prefetch")]
+    pub(crate) prefetch: bool,
+
+    /// Classification rule as divisor=label, repeated for more, e.g. --rule 3=Fizz --rule 7=Bazz.
+    /// Each value gets the labels of every rule dividing it; without rules it is classic FizzBuzz.
+    #[arg(long = "rule")]
+    pub(crate) rules: Vec<String>,
+
+    /// Classic FizzBuzz results keep the value they came from, the logger printing `15 -> FizzBuzz`
+    /// rather than a bare FizzBuzz. The same as --rule 3=Fizz --rule 5=Buzz; rules given win.
+    #[arg(long = "keep-values")]
+    pub(crate) keep_values: bool,
+
+    /// Longest one worker batch may classify for. Past it the worker stops mid-batch, leaving
+    /// the rest in the channel for the next beat, so telemetry and shutdown stay responsive.
+    /// Pool members take their whole batch before classifying it, so it is not used with --workers.
+    #[arg(long = "batch-deadline-ms", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) batch_deadline_ms: Option<u64>,
+
+    /// Runs the worker as an explicit state machine, Idle, Collecting and Flushing, which holds
+    /// values until the beat or until it holds this many, then flushes them all to the logger.
+    #[arg(long = "fsm-flush", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) fsm_flush: Option<u64>,
+
+    /// What the single worker does when the logger is full mid-batch: wait for room, truncate
+    /// the batch, dropping the values which do not fit, or spill them to --spill-path and
+    /// classify them once they come back.
+    #[arg(long = "backpressure", value_enum, default_value = "wait")]
+    pub(crate) backpressure: Backpressure,
+
+    /// Disk buffer of the spill actor under --backpressure spill, truncated when the run starts.
+    #[arg(long = "spill-path", default_value = "spill.bin")]
+    pub(crate) spill_path: String,
+
+    /// Number of logger shards. The worker sends each value to the shard --partitioner picks
+    /// for it, each shard with a logger of its own, so the same value always keeps its order.
+    #[arg(long = "shards", default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) shards: u64,
+
+    /// Checkpoint file for the generator position, read at startup so a restarted process
+    /// resumes from the last checkpoint instead of zero.
+    #[arg(long = "checkpoint")]
+    pub(crate) checkpoint: Option<String>,
+
+    /// Values sent between generator checkpoints.
+    #[arg(long = "checkpoint-every", default_value = "1000", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) checkpoint_every: u64,
+
+    /// Failure injection: the generator panics after every N values it sends, so the
+    /// actor restart and the recovery of its state from SteadyState can be watched.
+    #[arg(long = "panic-every", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) panic_every: Option<u64>,
+
+    /// Failure injection: the worker's transform panics on this value, u64::MAX say. The worker
+    /// catches the failure, counts the value as poisoned and sends it to the dead letters.
+    #[arg(long = "poison-value")]
+    pub(crate) poison_value: Option<u64>,
+
+    /// Throttles the generator while more than this percentage of its outgoing channel is
+    /// waiting for the worker, cutting its rate in proportion and restoring it as the lag clears.
+    #[arg(long = "throttle-high", value_parser = clap::value_parser!(u8).range(1..100))]
+    pub(crate) throttle_high: Option<u8>,
+
+    /// Lag percentage below which a throttled generator speeds back up.
+    #[arg(long = "throttle-low", default_value = "20", value_parser = clap::value_parser!(u8).range(0..100))]
+    pub(crate) throttle_low: u8,
+
+    /// Values sent back to back in each burst of --gen-mode burst.
+    #[arg(long = "burst-size", default_value = "1000", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) burst_size: u64,
+
+    /// Milliseconds of silence between bursts of --gen-mode burst.
+    #[arg(long = "burst-idle-ms", default_value = "1000")]
+    pub(crate) burst_idle_ms: u64,
+
+    /// First value of the counter sequence.
+    #[arg(long = "start", default_value = "0")]
+    pub(crate) start: u64,
+
+    /// Last value the counter may produce; once passed the run shuts down.
+    #[arg(long = "end")]
+    pub(crate) end: Option<u64>,
+
+    /// Increment between consecutive counter values.
+    #[arg(long = "step", default_value = "1", value_parser = clap::value_parser!(u64).range(1..))]
+    pub(crate) step: u64,
+
+    /// Reads generator values from this file, one per line, and shuts down at its end.
+    #[arg(long = "input")]
+    pub(crate) input: Option<String>,
+
+    /// Column holding the values with `--gen-mode csv`: a zero-based index, every row being data,
+    /// or a header name, the first row then being the header.
+    #[arg(long = "csv-column", default_value = "0")]
+    pub(crate) csv_column: String,
+
+    /// Inserts the dedup stage, dropping values repeated within the last N values.
+    #[arg(long = "dedup-window")]
+    pub(crate) dedup_window: Option<usize>,
+
+    /// Key file (`id=<64 hex chars>` per line) used to encrypt each payload written
+    /// by the file logger. The highest id encrypts, older ids remain for decryption.
+    #[arg(long = "payload-keys")]
+    pub(crate) payload_keys: Option<String>,
+
+    /// Inserts the rate limiter, capping the generator stream at N messages per second.
+    #[arg(long = "max-rate")]
+    pub(crate) max_rate: Option<u64>,
+
+    /// Appends one JSON line per delivered message to this file, recording its source,
+    /// the stages it passed through and its destination.
+    #[arg(long = "lineage")]
+    pub(crate) lineage: Option<String>,
+
+    /// Records all generator traffic with timestamps to this binary file.
+    #[arg(long = "record")]
+    pub(crate) record: Option<String>,
+
+    /// Replays a recording in place of the generator, shutting down at its end.
+    #[arg(long = "replay")]
+    pub(crate) replay: Option<String>,
+
+    /// Timing used when replaying recorded traffic: original, max or x<factor>.
+    #[arg(long = "replay-pacing", default_value = "original")]
+    pub(crate) replay_pacing: ReplayPacing,
+
+    /// TOML file supplying any of these options, flags given on the command line win.
+    /// Check a file with the validate-config subcommand before deploying it.
+    #[arg(long = "config")]
+    pub(crate) config: Option<String>,
+
+    /// Builds the graph, verifies every channel has a producer and a consumer,
+    /// prints the topology and exits without starting anything.
+    #[arg(long = "dry-run")]
+    pub(crate) dry_run: bool,
+
+    /// Builds the graph and writes a diagram of its actors and channels to this file,
+    /// Mermaid for .mmd or .mermaid files and DOT otherwise, then exits.
+    #[arg(long = "export-graph")]
+    pub(crate) export_graph: Option<String>,
+
+    /// Capacity of every channel in the standard graph.
+    #[arg(long = "channel-capacity", default_value = "64")]
+    pub(crate) channel_capacity: usize,
+
+    /// Capacity of the heartbeat→worker channel, overriding --channel-capacity.
+    #[arg(long = "heartbeat-cap")]
+    pub(crate) heartbeat_cap: Option<usize>,
+
+    /// Capacity of the generator→worker channel, overriding --channel-capacity.
+    #[arg(long = "gen-cap")]
+    pub(crate) generator_cap: Option<usize>,
+
+    /// Capacity of the worker→logger channel, overriding --channel-capacity.
+    #[arg(long = "worker-cap")]
+    pub(crate) worker_cap: Option<usize>,
+
+    /// Shifts every timestamp taken by the pipeline forward by this many milliseconds,
+    /// for exercising windows and drift statistics against a skewed clock.
+    #[arg(long = "clock-offset-ms", default_value = "0")]
+    pub(crate) clock_offset_ms: u64,
+
+    /// Time source used by every time-dependent actor, set from --clock-offset-ms
+    /// at startup and replaced with a virtual clock in tests.
+    #[arg(skip)]
+    pub(crate) clock: Clock,
+
+    /// Measures classification and sink write cost at startup and derives the channel
+    /// capacity and aggregation window from them, unless those were given explicitly.
+    #[arg(long = "calibrate")]
+    pub(crate) calibrate: bool,
+
+    /// Startup measurements when --calibrate was given, reported at /info.
+    #[arg(skip)]
+    pub(crate) calibration: Option<Calibration>,
+
+    /// Most verbose log level written.
+    #[arg(long = "log-level", value_enum, default_value = "info")]
+    pub(crate) log_level: LogVerbosity,
+
+    /// Where log lines go: console, file or both.
+    #[arg(long = "log-target", value_enum, default_value = "console")]
+    pub(crate) log_target: LogTarget,
+
+    /// Log file written when --log-target is file or both, appended to across runs.
+    #[arg(long = "log-file", default_value = "standard.log")]
+    pub(crate) log_file: String,
+
+    /// How the logger writes each message: a text log line, or one JSON object per line on
+    /// stdout for jq or a log shipper.
+    #[arg(long = "log-format", value_enum, default_value = "text")]
+    pub(crate) log_format: LogFormat,
+
+    /// File the --log-format csv rows go to instead of stdout, started afresh on each run.
+    #[arg(long = "csv-file")]
+    pub(crate) csv_file: Option<String>,
+
+    /// Syslog endpoint as host:port. The logger ships every message there as an RFC 5424
+    /// line in place of its console lines.
+    #[arg(long = "syslog")]
+    pub(crate) syslog: Option<String>,
+
+    /// How messages reach --syslog, one datagram each or framed on a TCP connection.
+    #[arg(long = "syslog-transport", value_enum, default_value = "udp")]
+    pub(crate) syslog_transport: SyslogTransport,
+
+    /// Syslog facility of every message, 0 to 23, 1 is user and 16 to 23 are local0 to local7.
+    #[arg(long = "syslog-facility", default_value = "1", value_parser = clap::value_parser!(u8).range(0..24))]
+    pub(crate) syslog_facility: u8,
+
+    /// Severity per message kind as kind=severity, e.g. Fizz=notice,FizzBuzz=warning.
+    /// Severities are the RFC 5424 keywords or 0 to 7, kinds not given are info.
+    #[arg(long = "syslog-severity", value_delimiter = ',')]
+    pub(crate) syslog_severity: Vec<String>,
+
+    /// Destinations the logger writes every message to at once, console, file=PATH or json=PATH,
+    /// e.g. console,json=results.jsonl. A sink that fails is counted and the others go on.
+    #[arg(long = "log-sink", value_delimiter = ',')]
+    pub(crate) log_sinks: Vec<String>,
+
+    /// Writes one in every N messages as 1/N, so a run at full generator speed stays readable.
+    /// What sampling drops is counted in a summary line every few seconds.
+    #[arg(long = "log-sample")]
+    pub(crate) log_sample: Option<String>,
+
+    /// Kinds the logger writes, e.g. fizzbuzz,value, all of them when not given.
+    /// Labels of --rule other than these never pass a filter.
+    #[arg(long = "log-filter", value_enum, value_delimiter = ',')]
+    pub(crate) log_filter: Vec<LogKind>,
+
+    /// JSON file the logger writes its end-of-run summary to on a clean shutdown: counts per
+    /// kind, first and last timestamp and the rate between them.
+    #[arg(long = "summary-out")]
+    pub(crate) summary_out: Option<String>,
+
+    /// Initial state of runtime toggles as name=on|off, e.g. validation=off,audit=on.
+    /// Toggles can be changed later through /toggle on the control endpoint.
+    #[arg(long = "toggle", value_delimiter = ',')]
+    pub(crate) toggles: Vec<String>,
+
+    /// Fill percentiles tracked on the heartbeat→worker channel, e.g. 50,80,99.
+    #[arg(long = "heartbeat-percentiles", value_delimiter = ',', default_value = "80"
+         , value_parser = clap::value_parser!(u8).range(1..100))]
+    pub(crate) heartbeat_percentiles: Vec<u8>,
+
+    /// Fill percentiles tracked on the generator→worker channel.
+    #[arg(long = "generator-percentiles", value_delimiter = ',', default_value = "80"
+         , value_parser = clap::value_parser!(u8).range(1..100))]
+    pub(crate) generator_percentiles: Vec<u8>,
+
+    /// Fill percentiles tracked on the worker→logger channel.
+    #[arg(long = "worker-percentiles", value_delimiter = ',', default_value = "80"
+         , value_parser = clap::value_parser!(u8).range(1..100))]
+    pub(crate) worker_percentiles: Vec<u8>,
+
+    /// Optional subcommand, when absent the pipeline is run.
+    #[command(subcommand)]
+    pub(crate) command: Option<Command>,
+}
+
+/// Pipeline variants selectable with --topology.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum TopologyKind {
+    /// A single source→worker→sink chain, no heartbeat or control plane.
+    Minimal,
+    /// The full pipeline, shaped by the optional stage flags.
+    Standard,
+    /// The full pipeline with every message kind routed to its own logger.
+    Fanout,
+    /// The full pipeline with the worker split into a parser and a classifier.
+    Staged,
+    /// Independent source→worker→sink chains, --pipelines of them.
+    Parallel,
+}
+
+impl TopologyKind {
+    /// Variants without a heartbeat never count down their beats and need a timed shutdown.
+    pub(crate) fn has_heartbeat(self) -> bool {
+        matches!(self, TopologyKind::Standard | TopologyKind::Fanout | TopologyKind::Staged)
+    }
+}
+
+impl MainArg {
+    /// Topology to build, --stress-topology N overriding --topology.
+    pub(crate) fn selected_topology(&self) -> TopologyKind {
+        if self.stress_topology.is_some() { TopologyKind::Parallel } else { self.topology }
+    }
+
+    /// Chains built by the parallel topology.
+    pub(crate) fn parallel_pipelines(&self) -> usize {
+        self.stress_topology.unwrap_or(self.pipelines)
+    }
+
+    pub(crate) fn heartbeat_capacity(&self) -> usize {
+        self.heartbeat_cap.unwrap_or(self.channel_capacity)
+    }
+
+    pub(crate) fn generator_capacity(&self) -> usize {
+        self.generator_cap.unwrap_or(self.channel_capacity)
+    }
+
+    pub(crate) fn worker_capacity(&self) -> usize {
+        self.worker_cap.unwrap_or(self.channel_capacity)
+    }
+
+    /// Whether the windower runs, closing windows by count, by heartbeat or both.
+    pub(crate) fn windowed(&self) -> bool {
+        self.window_count.is_some() || self.window_beats.is_some()
+    }
+}
+
+/// Heartbeat handling of the beats missed while it was down, after an actor restart.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum CatchupPolicy {
+    /// Drop them, the next beat is an interval after the restart. The original behavior.
+    Skip,
+    /// Send one beat for each deadline missed, at once, then keep to the old schedule.
+    Burst,
+}
+
+/// Wall-clock boundary the first heartbeat waits for with --align.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Align {
+    Second,
+    Minute,
+}
+
+impl Align {
+    /// Length of the boundary's period on the wall clock.
+    pub(crate) fn period(&self) -> Duration {
+        match self {
+            Align::Second => Duration::from_secs(1),
+            Align::Minute => Duration::from_secs(60),
+        }
+    }
+}
+
+/// Heartbeat behavior once it has sent --beats beats.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum OnBeatsComplete {
+    /// Request a graceful shutdown of the whole graph, the original behavior.
+    Shutdown,
+    /// Stop beating until a resume command arrives.
+    Pause,
+    /// Start counting again from zero and keep beating.
+    RestartCount,
+    /// Keep beating and publish a BeatsComplete event for other actors.
+    EmitEvent,
+}
+
+/// Generator behavior when the outgoing channel has no room.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum OverflowPolicy {
+    /// Wait for the consumer to make room, nothing is lost. The original behavior.
+    Await,
+    /// Drop values produced while the channel is full, counting them.
+    DropNewest,
+    /// Hold values produced while the channel is full, dropping the oldest held one
+    /// to make space, so the freshest values go out once there is room.
+    DropOldest,
+}
+
+/// Worker behavior when the logger has no room during a batch.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub(crate) enum Backpressure {
+    /// Await room for every value, nothing is lost. The original behavior.
+    Wait,
+    /// Drop the values of the batch the logger has no room for, counting them.
+    Truncate,
+    /// Send the values the logger has no room for to the spill actor, which buffers them on
+    /// disk and hands them back in order as the worker catches up.
+    Spill,
+}
+
+/// Value sequences the generator can produce.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum GenMode {
+    /// 0, 1, 2, ... the original, fully predictable stream.
+    Counter,
+    /// Pseudo-random u64 values from a seeded generator.
+    Random,
+    /// Successive primes from --start up, from an incremental sieve: a CPU-bound source.
+    Primes,
+    /// Numbers read one per line from stdin, the end of stdin ends the run.
+    Stdin,
+    /// One column (--csv-column) of the CSV file given with --input; rows without a number
+    /// there are reported on the input errors channel and skipped.
+    Csv,
+    /// The counter sent in bursts of --burst-size values separated by --burst-idle-ms of silence,
+    /// filling channels fast enough to set off their fill alerts.
+    Burst,
+}
+
+/// Hash functions selectable for partitioning values across generators.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum PartitionStrategy {
+    /// value % N, even over sequential values but nearly every value moves when N changes.
+    Modulo,
+    /// The single multiply FxHash, scattering sequential values across partitions.
+    Fxhash,
+    /// A hash ring with --virtual-nodes points per partition, moving only about 1/N of values when N changes.
+    Consistent,
+}
+
+/// Log levels selectable from the command line or configuration file.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogVerbosity {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogVerbosity {
+    pub(crate) fn level(self) -> LogLevel {
+        match self {
+            LogVerbosity::Error => LogLevel::Error,
+            LogVerbosity::Warn => LogLevel::Warn,
+            LogVerbosity::Info => LogLevel::Info,
+            LogVerbosity::Debug => LogLevel::Debug,
+            LogVerbosity::Trace => LogLevel::Trace,
+        }
+    }
+}
+
+/// Destinations selectable with --log-target.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogTarget {
+    Console,
+    File,
+    Both,
+}
+
+/// Formats selectable with --log-format.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogFormat {
+    /// `Msg Fizz` through the framework logger. The original behavior.
+    Text,
+    /// `{"kind":"Fizz","value":9,"ts":1700000000000}` on stdout, bare Fizz, Buzz and FizzBuzz lines without `value` unless --keep-values.
+    Json,
+    /// `sequence,value,classification,timestamp` rows on stdout or in --csv-file, under one header.
+    Csv,
+}
+
+/// Message kinds selectable with --log-filter.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum LogKind {
+    Fizz,
+    Buzz,
+    #[value(name = "fizzbuzz")]
+    FizzBuzz,
+    Value,
+}
+
+impl LogKind {
+    /// The kind as a message names it, see `FizzBuzzMessage::kind`.
+    pub(crate) fn label(self) -> &'static str {
+        match self {
+            LogKind::Fizz => "Fizz",
+            LogKind::Buzz => "Buzz",
+            LogKind::FizzBuzz => "FizzBuzz",
+            LogKind::Value => "Value",
+        }
+    }
+}
+
+/// Transports selectable with --syslog-transport.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum SyslogTransport {
+    Udp,
+    Tcp,
+}
+
+/// Subcommands which do something other than running the pipeline.
+#[derive(Subcommand, Debug, PartialEq, Clone)]
+pub(crate) enum Command {
+    /// Run the pipeline, the same as giving no subcommand.
+    Run,
+    /// Build the graph without starting it, catching wiring and option errors (same as --dry-run).
+    Validate,
+    /// Run a fixed-duration throughput benchmark and print a report.
+    Bench {
+        /// Benchmark duration in seconds.
+        #[arg(long = "seconds", default_value = "10")]
+        seconds: u64,
+        /// Number of parallel source→worker→sink pipelines measured.
+        #[arg(long = "pipelines", default_value = "1")]
+        pipelines: usize,
+        /// Measure twice, workers sending one value per call and then in --batch slices.
+        #[arg(long = "compare-batch")]
+        compare_batch: bool,
+        /// Measure the latency of the batch each beat starts in one worker, twice: in --batch
+        /// slices and then with --prefetch. --seconds bounds each run, --pipelines is not used.
+        #[arg(long = "compare-prefetch", conflicts_with = "compare_batch")]
+        compare_prefetch: bool,
+    },
+    /// Recommend worker count, channel capacity and batch size for a throughput and p99
+    /// latency target from calibration and queueing estimates, optionally benchmarking the result.
+    Plan {
+        /// Messages per second the deployment must sustain.
+        #[arg(long = "target-throughput", value_parser = clap::value_parser!(u64).range(1..))]
+        target_throughput: u64,
+        /// 99th percentile latency allowed per message, in milliseconds.
+        #[arg(long = "target-p99", value_parser = clap::value_parser!(u64).range(1..))]
+        target_p99: u64,
+        /// Run a bench with the recommended settings to check the throughput.
+        #[arg(long = "validate")]
+        validate: bool,
+        /// Duration of the validating bench in seconds.
+        #[arg(long = "seconds", default_value = "10")]
+        seconds: u64,
+    },
+    /// Process a single value through the worker, print the result and exit with
+    /// 3, 5 or 15 for Fizz, Buzz or FizzBuzz, 0 for a plain value and 2 when rejected.
+    Once {
+        /// The value to classify.
+        #[arg(long = "value")]
+        value: u64,
+    },
+    /// Classify every number in a file (one per line) and write the results as CSV,
+    /// exiting once the input is exhausted.
+    Batch {
+        /// Input file with one value per line.
+        #[arg(long = "in")]
+        input: String,
+        /// CSV output file with line,value,result rows.
+        #[arg(long = "out")]
+        output: String,
+        /// Number of parallel workers in the pool.
+        #[arg(long = "workers", default_value = "2")]
+        workers: usize,
+    },
+    /// List the files this configuration wrote that are older than --keep-days: the log file,
+    /// --out-file and its rotations, the dead letters and the spill file. --apply deletes them.
+    Housekeeping {
+        /// Files modified within this many days are kept.
+        #[arg(long = "keep-days", default_value = "7")]
+        keep_days: u64,
+        /// Delete the expired files. Without it they are only listed.
+        #[arg(long = "apply")]
+        apply: bool,
+    },
+    /// Decrypt an --out-file archive sealed with --payload-keys and print its lines. Files
+    /// rotated out before a key rotation open with the older keys kept in the key file.
+    Open {
+        /// Path of the sealed output file or one of its rotations.
+        file: String,
+    },
+    /// Print the JSON Schema of the configuration file.
+    ConfigSchema,
+    /// Check a configuration file against the schema, reporting precise error locations.
+    ValidateConfig {
+        /// Path of the TOML configuration file to check.
+        file: String,
+    },
 }
 
 /// Default implementation provides fallback values for testing and API usage.
@@ -25,6 +768,97 @@ impl Default for MainArg { //#!#//
         MainArg {
             rate_ms: 1000,
             beats: 120,
+            on_beats_complete: OnBeatsComplete::Shutdown,
+            catchup_policy: CatchupPolicy::Skip,
+            align_beats: false,
+            align: None,
+            trigger_after_ms: None,
+            jitter_ms: 0,
+            backlog_threshold: 0,
+            max_value: None,
+            dead_letter_path: "dead_letter.log".to_string(),
+            out_file: None,
+            rotate_bytes: 10 * 1024 * 1024,
+            rotate_keep: 5,
+            topology: TopologyKind::Standard,
+            pipelines: 4,
+            stress_topology: None,
+            control_addr: None,
+            history_minutes: 10,
+            route_by_kind: false,
+            aggregate: false,
+            aggregate_ms: 1000,
+            tick_ms: Vec::new(),
+            window_count: None,
+            window_beats: None,
+            locale: Locale::En,
+            gen_mode: GenMode::Counter,
+            seed: 0,
+            gen_rate: None,
+            batch_size: 64,
+            overflow_policy: OverflowPolicy::Await,
+            generators: 1,
+            partitioner: PartitionStrategy::Modulo,
+            virtual_nodes: 64,
+            workers: 1,
+            batch: false,
+            prefetch: false,
+            rules: Vec::new(),
+            keep_values: false,
+            batch_deadline_ms: None,
+            fsm_flush: None,
+            backpressure: Backpressure::Wait,
+            spill_path: "spill.bin".to_string(),
+            shards: 1,
+            checkpoint: None,
+            checkpoint_every: 1000,
+            panic_every: None,
+            poison_value: None,
+            throttle_high: None,
+            throttle_low: 20,
+            burst_size: 1000,
+            burst_idle_ms: 1000,
+            start: 0,
+            end: None,
+            step: 1,
+            input: None,
+            csv_column: "0".to_string(),
+            dedup_window: None,
+            payload_keys: None,
+            max_rate: None,
+            lineage: None,
+            record: None,
+            replay: None,
+            replay_pacing: ReplayPacing::Original,
+            config: None,
+            dry_run: false,
+            export_graph: None,
+            channel_capacity: 64,
+            heartbeat_cap: None,
+            generator_cap: None,
+            worker_cap: None,
+            clock_offset_ms: 0,
+            clock: Clock::default(),
+            calibrate: false,
+            calibration: None,
+            log_level: LogVerbosity::Info,
+            log_target: LogTarget::Console,
+            log_format: LogFormat::Text,
+            csv_file: None,
+            syslog: None,
+            syslog_transport: SyslogTransport::Udp,
+            syslog_facility: 1,
+            syslog_severity: Vec::new(),
+            log_sinks: Vec::new(),
+            log_sample: None,
+            log_filter: Vec::new(),
+            summary_out: None,
+            log_file: "standard.log".to_string(),
+            toggles: Vec::new(),
+            heartbeat_percentiles: vec![80],
+            generator_percentiles: vec![80],
+            worker_percentiles: vec![80],
+            command: None,
         }
     }
 }
@@ -0,0 +1,332 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use steady_state::*;
+use crate::actor::worker::{FizzBuzzMessage, Transform};
+use crate::arg::MainArg;
+use crate::error::{AppError, Context};
+use crate::logging;
+use crate::rules::{ActiveRules, Rules};
+use crate::sink::Sink;
+
+const NAME_BATCH_SOURCE: &str = "BATCH_SOURCE";
+const NAME_BATCH_WORKER: &str = "BATCH_WORKER";
+const NAME_BATCH_SINK: &str = "BATCH_SINK";
+
+/// One input value and the line it was read from.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct BatchItem {
+    pub(crate) line: u64,
+    pub(crate) value: u64,
+}
+
+/// One output row, `result` is None when the value was above `--max-value`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq)]
+pub(crate) struct BatchRow {
+    pub(crate) line: u64,
+    pub(crate) value: u64,
+    pub(crate) result: Option<FizzBuzzMessage>,
+}
+
+/// Totals printed when the batch completes.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub(crate) struct BatchSummary {
+    pub(crate) rows: u64,
+    pub(crate) fizz: u64,
+    pub(crate) buzz: u64,
+    pub(crate) fizzbuzz: u64,
+    pub(crate) values: u64,
+    /// Values given at least one label by the --rule settings.
+    pub(crate) labeled: u64,
+    pub(crate) rejected: u64,
+    /// Lines which were not a number and were skipped.
+    pub(crate) invalid: u64,
+}
+
+impl BatchSummary {
+    fn record(&mut self, row: &BatchRow) {
+        self.rows += 1;
+        match row.result {
+            Some(FizzBuzzMessage::Fizz) => self.fizz += 1,
+            Some(FizzBuzzMessage::Buzz) => self.buzz += 1,
+            Some(FizzBuzzMessage::FizzBuzz) => self.fizzbuzz += 1,
+            Some(FizzBuzzMessage::Value(_)) => self.values += 1,
+            Some(FizzBuzzMessage::Classified(c)) if c.labels().is_empty() => self.values += 1,
+            Some(FizzBuzzMessage::Classified(_)) => self.labeled += 1,
+            None => self.rejected += 1,
+        }
+    }
+}
+
+/// CSV output with a `line,value,result` header.
+pub(crate) struct CsvSink {
+    out: BufWriter<File>,
+}
+
+impl CsvSink {
+    pub(crate) fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "line,value,result")?;
+        Ok(CsvSink { out })
+    }
+}
+
+impl Sink<BatchRow> for CsvSink {
+    fn write(&mut self, row: &BatchRow) -> io::Result<()> {
+        match row.result {
+            Some(FizzBuzzMessage::Value(v)) => writeln!(self.out, "{},{},{}", row.line, row.value, v),
+            Some(FizzBuzzMessage::Classified(c)) if c.labels().is_empty() => writeln!(self.out, "{},{},{}", row.line, row.value, c.value()),
+            Some(FizzBuzzMessage::Classified(c)) => writeln!(self.out, "{},{},{}", row.line, row.value, c.labels()),
+            Some(msg) => writeln!(self.out, "{},{},{:?}", row.line, row.value, msg),
+            None => writeln!(self.out, "{},{},rejected", row.line, row.value),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Processes every number in `input` and writes one CSV row per value to `output`.
+/// Unlike the streaming graph this runs to completion: the end of the input file
+/// closes the pipeline, and the summary is printed once everything has drained.
+pub(crate) fn run(args: MainArg, input: String, output: String, workers: usize) -> Result<(), Box<dyn Error>> {
+    let summary = Arc::new(Mutex::new(BatchSummary::default()));
+    let graph_summary = summary.clone();
+    let graph_args = args.clone();
+    let started = Instant::now();
+    logging::release_runner(&args)
+        .run(args, move |mut graph| {
+            let sink = CsvSink::create(&output).map_err(AppError::SinkIo).context(format!("creating {}", output))?;
+            build_batch_graph(&mut graph, &graph_args, input.clone(), workers, sink, graph_summary.clone());
+            graph.start();
+            graph.block_until_stopped(Duration::from_secs(15))
+        })?;
+    let summary = summary.lock().expect("summary lock").clone();
+    let locale = graph_args.locale;
+    let labeled = if summary.labeled > 0 { format!(" labeled {}", locale.number(summary.labeled)) } else { String::new() };
+    println!("Batch rows {} fizz {} buzz {} fizzbuzz {} values {}{} rejected {} invalid {} in {}"
+             , locale.number(summary.rows), locale.number(summary.fizz), locale.number(summary.buzz)
+             , locale.number(summary.fizzbuzz), locale.number(summary.values), labeled, locale.number(summary.rejected)
+             , locale.number(summary.invalid), locale.duration(started.elapsed()));
+    Ok(())
+}
+
+/// File source → pool of `workers` classifiers → one sink.
+/// Items are dealt to the workers round-robin and collected in the same rotation,
+/// so the output keeps the input order while the classification runs in parallel.
+pub(crate) fn build_batch_graph<S: Sink<BatchRow> + 'static>(graph: &mut Graph, args: &MainArg, input: String, workers: usize
+                                                            , sink: S, summary: Arc<Mutex<BatchSummary>>) {
+    let workers = workers.max(1);
+    let max_value = args.max_value.unwrap_or(u64::MAX);
+    let rules = Rules::from_args(&args).expect("rule settings are validated at startup");
+    let channel_builder = graph.channel_builder().with_capacity(args.channel_capacity);
+    let actor_builder = graph.actor_builder().with_load_avg().with_mcpu_avg();
+
+    let (item_txs, item_rxs): (Vec<_>, Vec<_>) = (0..workers).map(|_| channel_builder.build()).unzip();
+    let (row_txs, row_rxs): (Vec<_>, Vec<_>) = (0..workers).map(|_| channel_builder.build()).unzip();
+
+    let source_summary = summary.clone();
+    actor_builder.with_name(NAME_BATCH_SOURCE)
+        .build(move |actor| source(actor, input.clone(), item_txs.clone(), source_summary.clone()), SoloAct);
+    for (index, (item_rx, row_tx)) in item_rxs.into_iter().zip(row_txs).enumerate() {
+        actor_builder.with_name_and_suffix(NAME_BATCH_WORKER, index)
+            .build(move |actor| classify(actor, item_rx.clone(), row_tx.clone(), max_value, rules.clone()), SoloAct);
+    }
+    // The sink is moved into the actor once; a restarted sink would need a fresh file anyway.
+    let sink = Arc::new(Mutex::new(Some(sink)));
+    actor_builder.with_name(NAME_BATCH_SINK)
+        .build(move |actor| collect(actor, row_rxs.clone(), sink.clone(), summary.clone()), SoloAct);
+}
+
+/// Reads the input line by line, dealing values to the workers in rotation.
+/// Channel endpoints are not registered with the spotlight because the pool size
+/// is only known at runtime, shutdown still waits for every channel to drain.
+async fn source(actor: SteadyActorShadow, input: String, txs: Vec<SteadyTx<BatchItem>>
+                , summary: Arc<Mutex<BatchSummary>>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([], []);
+    let file = File::open(&input).map_err(AppError::SinkIo).context(format!("opening {}", input))?;
+    let mut lines = BufReader::new(file).lines();
+    let mut locked = Vec::with_capacity(txs.len());
+    for tx in &txs {
+        locked.push(tx.lock().await);
+    }
+    let mut txs = locked;
+    let mut line = 0u64;
+    let mut dealt = 0usize;
+    let mut done = false;
+
+    // every channel is marked closed, not just up to the first which reports false
+    while actor.is_running(|| txs.iter_mut().fold(true, |closed, tx| tx.mark_closed() && closed)) {
+        if done {
+            await_for_all!(actor.wait_shutdown());
+            continue;
+        }
+        match lines.next().transpose()? {
+            Some(text) => {
+                line += 1;
+                let text = text.trim();
+                if text.is_empty() {
+                    continue;
+                }
+                match text.parse() {
+                    Ok(value) => {
+                        // dealt counts sent items only, matching the rotation the sink collects in
+                        let tx = &mut txs[dealt % txs.len()];
+                        actor.send_async(tx, BatchItem { line, value }, SendSaturation::AwaitForRoom).await;
+                        dealt += 1;
+                    }
+                    Err(_) => {
+                        warn!("{}:{}: not a number: {}", input, line, text);
+                        summary.lock().expect("summary lock").invalid += 1;
+                    }
+                }
+            }
+            None => {
+                // end of input ends the job, shutdown completes once every channel has drained
+                done = true;
+                actor.request_shutdown().await;
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn classify(actor: SteadyActorShadow, rx: SteadyRx<BatchItem>, tx: SteadyTx<BatchRow>, max_value: u64, rules: Option<Rules>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([&rx], [&tx]);
+    // the same rules as the streaming worker, classic FizzBuzz without any
+    let mut transform = ActiveRules(rules);
+    let mut rx = rx.lock().await;
+    let mut tx = tx.lock().await;
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut tx, 1));
+        let items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut tx));
+        for _ in 0..items {
+            if let Some(item) = actor.try_take(&mut rx) {
+                let result = (item.value <= max_value).then(|| transform.apply(item.value));
+                let _ = actor.try_send(&mut tx, BatchRow { line: item.line, value: item.value, result });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Takes rows in the same rotation the source dealt them, restoring input order.
+async fn collect<S: Sink<BatchRow>>(actor: SteadyActorShadow, rxs: Vec<SteadyRx<BatchRow>>
+                                    , sink: Arc<Mutex<Option<S>>>, summary: Arc<Mutex<BatchSummary>>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([], []);
+    let mut sink = sink.lock().expect("sink lock").take().ok_or("batch sink already consumed")?;
+    let mut locked = Vec::with_capacity(rxs.len());
+    for rx in &rxs {
+        locked.push(rx.lock().await);
+    }
+    let mut rxs = locked;
+    let mut next = 0;
+
+    while actor.is_running(|| rxs.iter_mut().all(|rx| rx.is_closed_and_empty())) {
+        await_for_all!(actor.wait_avail(&mut rxs[next], 1));
+        while let Some(row) = actor.try_take(&mut rxs[next]) {
+            sink.write(&row)?;
+            summary.lock().expect("summary lock").record(&row);
+            next = (next + 1) % rxs.len();
+        }
+        sink.flush()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod batch_tests {
+    use crate::sink::{verify_order, FakeSink};
+    use super::*;
+
+    #[test]
+    fn test_batch() -> Result<(), Box<dyn Error>> {
+        let input = std::env::temp_dir().join("standard_batch_test.txt");
+        std::fs::write(&input, "1\n3\n\n5\nabc\n15\n20\n")?;
+        let args = MainArg { max_value: Some(15), ..MainArg::default() };
+
+        let sink = FakeSink::default();
+        let summary = Arc::new(Mutex::new(BatchSummary::default()));
+        let mut graph = GraphBuilder::for_testing().build(args.clone());
+        build_batch_graph(&mut graph, &args, input.to_string_lossy().to_string(), 3, sink.clone(), summary.clone());
+        graph.start();
+        // no request_shutdown, the end of the input file finishes the job
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        // input order survives the worker pool, blank and invalid lines keep their numbering
+        assert_eq!(sink.take_records(), vec![
+            BatchRow { line: 1, value: 1, result: Some(FizzBuzzMessage::Value(1)) },
+            BatchRow { line: 2, value: 3, result: Some(FizzBuzzMessage::Fizz) },
+            BatchRow { line: 4, value: 5, result: Some(FizzBuzzMessage::Buzz) },
+            BatchRow { line: 6, value: 15, result: Some(FizzBuzzMessage::FizzBuzz) },
+            BatchRow { line: 7, value: 20, result: None },
+        ]);
+        let summary = summary.lock().expect("summary lock").clone();
+        assert_eq!((summary.rows, summary.rejected, summary.invalid), (5, 1, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_order() -> Result<(), Box<dyn Error>> {
+        let input = std::env::temp_dir().join("standard_batch_order_test.txt");
+        std::fs::write(&input, (0..2000).map(|v| format!("{}\n", v)).collect::<String>())?;
+        let args = MainArg::default();
+
+        let sink = FakeSink::default();
+        let summary = Arc::new(Mutex::new(BatchSummary::default()));
+        let mut graph = GraphBuilder::for_testing().build(args.clone());
+        build_batch_graph(&mut graph, &args, input.to_string_lossy().to_string(), 4, sink.clone(), summary.clone());
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(5))?;
+
+        // four workers classify in parallel, yet the CSV rows stay in input order
+        let rows = sink.take_records();
+        assert_eq!(rows.len(), 2000);
+        verify_order(&rows, |row| row.line)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_csv_sink() -> Result<(), Box<dyn Error>> {
+        let path = std::env::temp_dir().join("standard_batch_test.csv");
+        let mut sink = CsvSink::create(&path)?;
+        sink.write(&BatchRow { line: 1, value: 3, result: Some(FizzBuzzMessage::Fizz) })?;
+        sink.write(&BatchRow { line: 2, value: 4, result: Some(FizzBuzzMessage::Value(4)) })?;
+        sink.write(&BatchRow { line: 3, value: 99, result: None })?;
+        sink.flush()?;
+        assert_eq!(std::fs::read_to_string(&path)?, "line,value,result\n1,3,Fizz\n2,4,4\n3,99,rejected\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_rules() -> Result<(), Box<dyn Error>> {
+        let input = std::env::temp_dir().join("standard_batch_rules_test.txt");
+        std::fs::write(&input, "7
+9
+21
+22
+")?;
+        let args = MainArg { rules: vec!["3=Fizz".to_string(), "7=Bazz".to_string()], ..MainArg::default() };
+
+        let sink = FakeSink::default();
+        let summary = Arc::new(Mutex::new(BatchSummary::default()));
+        let mut graph = GraphBuilder::for_testing().build(args.clone());
+        build_batch_graph(&mut graph, &args, input.to_string_lossy().to_string(), 2, sink.clone(), summary.clone());
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+
+        let path = std::env::temp_dir().join("standard_batch_rules_test.csv");
+        let mut csv = CsvSink::create(&path)?;
+        for row in sink.take_records() {
+            csv.write(&row)?;
+        }
+        csv.flush()?;
+        assert_eq!(std::fs::read_to_string(&path)?, "line,value,result\n1,7,Bazz\n2,9,Fizz\n3,21,FizzBazz\n4,22,22\n");
+        let summary = summary.lock().expect("summary lock").clone();
+        assert_eq!((summary.labeled, summary.values), (3, 1));
+        Ok(())
+    }
+}
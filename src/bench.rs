@@ -0,0 +1,252 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use steady_state::*;
+use crate::actor::batch_report::BatchReport;
+use crate::actor::dead_letter::DeadLetter;
+use crate::actor::heartbeat::{Heartbeat, Pulse};
+use crate::actor::history::BatchStats;
+use crate::actor::worker::{self, FizzBuzzMessage, WORKER_SLICE};
+use crate::arg::MainArg;
+use crate::clock;
+use crate::envelope::Envelope;
+use crate::logging;
+use crate::format::Locale;
+use crate::rules::RuleSet;
+use crate::stress;
+use crate::toggles::Toggles;
+
+const NAME_LATENCY_FEED: &str = "LATENCY_FEED";
+const NAME_LATENCY_WORKER: &str = "LATENCY_WORKER";
+const NAME_LATENCY_SINK: &str = "LATENCY_SINK";
+
+/// Idle time between a slice of values and the beat which asks for it, the time --prefetch
+/// has to classify the slice in.
+const BEAT_GAP: Duration = Duration::from_millis(1);
+
+/// Two worker modes measured one after the other.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Compare {
+    /// One value per call against `--batch` slices, the per-call overhead showing in the ratio.
+    Batch,
+    /// `--batch` slices against `--prefetch`, measured as the latency of the batch each beat
+    /// starts over one pipeline worker, not as throughput: values classified while the beat is
+    /// awaited are only sent once it comes.
+    Prefetch,
+}
+
+/// Fixed-duration throughput benchmark over the stress pipelines.
+/// The stress graph has no heartbeat pacing, so the number measured is what
+/// the channels and scheduler can sustain rather than what the demo is set to produce.
+/// With `compare` the pipelines are measured twice, once in each worker mode.
+pub(crate) fn run(args: MainArg, duration: Duration, pipelines: usize, compare: Option<Compare>) -> Result<(), Box<dyn Error>> {
+    let locale = args.locale;
+    match compare {
+        Some(Compare::Batch) => {
+            let per_value = measure(MainArg { batch: false, prefetch: false, ..args.clone() }, duration, pipelines)?;
+            let sliced = measure(MainArg { batch: true, prefetch: false, ..args }, duration, pipelines)?;
+            println!("Per value {}", report(locale, pipelines, per_value.0, per_value.1));
+            println!("Slices    {}", report(locale, pipelines, sliced.0, sliced.1));
+            println!("{}", comparison(locale, "Slices", "per value", per_value, sliced));
+        }
+        Some(Compare::Prefetch) => {
+            // one beat per slice fed, with the gap as long again for the worker to keep up
+            let beats = (duration.as_nanos() / (2 * BEAT_GAP.as_nanos())).max(1) as u64;
+            let sliced = measure_latency(MainArg { batch: true, prefetch: false, ..args.clone() }, beats)?;
+            let prefetched = measure_latency(MainArg { batch: true, prefetch: true, ..args }, beats)?;
+            println!("Slices    {}", latency_report(locale, &sliced));
+            println!("Prefetch  {}", latency_report(locale, &prefetched));
+            println!("{}", latency_comparison(locale, "Prefetch", "slices", &sliced, &prefetched));
+        }
+        None => {
+            let (messages, elapsed) = measure(args, duration, pipelines)?;
+            println!("{}", report(locale, pipelines, messages, elapsed));
+        }
+    }
+    Ok(())
+}
+
+/// Runs the benchmark graph for `duration` and returns the messages delivered and the time taken.
+pub(crate) fn measure(args: MainArg, duration: Duration, pipelines: usize) -> Result<(u64, Duration), Box<dyn Error>> {
+    let measured = Arc::new(Mutex::new((0, Duration::ZERO)));
+    let graph_measured = measured.clone();
+    logging::release_runner(&args)
+        .with_stack_size(2 * 1024 * 1024)
+        .run(args, move |mut graph| {
+            let registry = stress::build_stress_graph(&mut graph, pipelines);
+            let started = Instant::now();
+            graph.start();
+            std::thread::sleep(duration);
+            graph.request_shutdown();
+            let result = graph.block_until_stopped(Duration::from_secs(15));
+            *graph_measured.lock().expect("bench result lock") = (registry.delivered(), started.elapsed());
+            result
+        })?;
+    let measured = *measured.lock().expect("bench result lock");
+    Ok(measured)
+}
+
+/// Runs one pipeline worker for `beats` beats, each a slice of values fed ahead of its beat,
+/// and returns the duration of every batch the beats started, as the worker reported them.
+pub(crate) fn measure_latency(args: MainArg, beats: u64) -> Result<Vec<Duration>, Box<dyn Error>> {
+    let measured = Arc::new(Mutex::new(Vec::new()));
+    let graph_measured = measured.clone();
+    logging::release_runner(&args)
+        .with_stack_size(2 * 1024 * 1024)
+        .run(args, move |mut graph| {
+            let (heartbeat_tx, heartbeat_rx) = graph.channel_builder().build::<Pulse>();
+            // one slice fits, so every value is there before its beat
+            let (generator_tx, generator_rx) = graph.channel_builder().with_capacity(WORKER_SLICE).build::<Envelope<u64>>();
+            let (priority_tx, priority_rx) = graph.channel_builder().build::<Envelope<u64>>();
+            let (rules_tx, rules_rx) = graph.channel_builder().build::<RuleSet>();
+            let (logger_tx, logger_rx) = graph.channel_builder().with_capacity(4 * WORKER_SLICE).build::<Envelope<FizzBuzzMessage>>();
+            let (dead_letter_tx, dead_letter_rx) = graph.channel_builder().build::<DeadLetter>();
+            let (stats_tx, stats_rx) = graph.channel_builder().build::<BatchStats>();
+            let (report_tx, report_rx) = graph.channel_builder().build::<BatchReport>();
+            let (backlog_tx, backlog_rx) = graph.channel_builder().build::<usize>();
+
+            graph.actor_builder().with_name(NAME_LATENCY_FEED)
+                .build(move |actor| feed(actor, heartbeat_tx.clone(), generator_tx.clone(), priority_tx.clone(), rules_tx.clone(), beats), SoloAct);
+            let state = new_state();
+            graph.actor_builder().with_name(NAME_LATENCY_WORKER)
+                .build(move |actor| worker::run(actor, heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), vec![logger_tx.clone()], dead_letter_tx.clone()
+                                                , stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), None, state.clone(), Toggles::default(), None)
+                       , SoloAct);
+            let durations = graph_measured.clone();
+            graph.actor_builder().with_name(NAME_LATENCY_SINK)
+                .build(move |actor| drain(actor, logger_rx.clone(), dead_letter_rx.clone(), stats_rx.clone(), report_rx.clone(), backlog_rx.clone(), durations.clone()), SoloAct);
+
+            let started = Instant::now();
+            graph.start();
+            while graph_measured.lock().expect("bench result lock").len() < beats as usize && started.elapsed() < Duration::from_secs(60) {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            graph.request_shutdown();
+            graph.block_until_stopped(Duration::from_secs(15))
+        })?;
+    let measured = measured.lock().expect("bench result lock").clone();
+    Ok(measured)
+}
+
+/// Sends `beats` slices of values, each followed by its beat once the gap has passed.
+async fn feed(actor: SteadyActorShadow, heartbeat_tx: SteadyTx<Pulse>, generator_tx: SteadyTx<Envelope<u64>>
+              , priority_tx: SteadyTx<Envelope<u64>>, rules_tx: SteadyTx<RuleSet>, beats: u64) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([], [&heartbeat_tx, &generator_tx, &priority_tx, &rules_tx]);
+    let mut heartbeat_tx = heartbeat_tx.lock().await;
+    let mut generator_tx = generator_tx.lock().await;
+    let mut priority_tx = priority_tx.lock().await;
+    let mut rules_tx = rules_tx.lock().await;
+    let mut beat = 0;
+    let mut value = 0;
+    // priority and rules stay open and empty until the end, a closed input would end every wait of the worker
+    while actor.is_running(|| i!(heartbeat_tx.mark_closed()) && i!(generator_tx.mark_closed())
+                              && i!(priority_tx.mark_closed()) && i!(rules_tx.mark_closed())) {
+        if beat == beats {
+            actor.wait_shutdown().await;
+            continue;
+        }
+        for _ in 0..WORKER_SLICE {
+            if !actor.send_async(&mut generator_tx, Envelope::new(value, Instant::now(), value), SendSaturation::AwaitForRoom).await.is_sent() {
+                break;
+            }
+            value += 1;
+        }
+        actor.wait(BEAT_GAP).await;
+        let _ = actor.send_async(&mut heartbeat_tx, Pulse::Beat(Heartbeat::on_time(beat, SystemTime::now())), SendSaturation::AwaitForRoom).await;
+        beat += 1;
+    }
+    Ok(())
+}
+
+/// Empties every worker output, keeping the duration of each batch which had values in it.
+async fn drain(actor: SteadyActorShadow, logger_rx: SteadyRx<Envelope<FizzBuzzMessage>>, dead_letter_rx: SteadyRx<DeadLetter>
+               , stats_rx: SteadyRx<BatchStats>, report_rx: SteadyRx<BatchReport>, backlog_rx: SteadyRx<usize>
+               , durations: Arc<Mutex<Vec<Duration>>>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([&logger_rx, &dead_letter_rx, &stats_rx, &report_rx, &backlog_rx], []);
+    let mut logger_rx = logger_rx.lock().await;
+    let mut dead_letter_rx = dead_letter_rx.lock().await;
+    let mut stats_rx = stats_rx.lock().await;
+    let mut report_rx = report_rx.lock().await;
+    let mut backlog_rx = backlog_rx.lock().await;
+    let mut messages = [Envelope::new(0, Instant::now(), FizzBuzzMessage::default()); WORKER_SLICE];
+    while actor.is_running(|| i!(logger_rx.is_closed_and_empty()) && i!(dead_letter_rx.is_closed_and_empty())
+                              && i!(stats_rx.is_closed_and_empty()) && i!(report_rx.is_closed_and_empty())
+                              && i!(backlog_rx.is_closed_and_empty())) {
+        await_for_any!(actor.wait_avail(&mut logger_rx, 1), actor.wait_avail(&mut report_rx, 1)
+                     , actor.wait_avail(&mut dead_letter_rx, 1), actor.wait_avail(&mut stats_rx, 1), actor.wait_avail(&mut backlog_rx, 1));
+        while actor.take_slice(&mut logger_rx, &mut messages[..]).item_count() > 0 {}
+        while actor.try_take(&mut dead_letter_rx).is_some() || actor.try_take(&mut stats_rx).is_some() || actor.try_take(&mut backlog_rx).is_some() {}
+        while let Some(report) = actor.try_take(&mut report_rx) {
+            if report.items > 0 {
+                durations.lock().expect("bench result lock").push(report.duration);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Mean and worst of the batch `durations`, in microseconds.
+pub(crate) fn latency_report(locale: Locale, durations: &[Duration]) -> String {
+    let total: Duration = durations.iter().sum();
+    let max = durations.iter().max().copied().unwrap_or_default();
+    format!("batch latency mean {} us max {} us over {} beats"
+            , locale.decimal(micros(clock::mean(total, durations.len() as u64)), 1), locale.decimal(micros(max), 1), locale.number(durations.len() as u64))
+}
+
+/// Mean batch latency of `name` as a fraction of the `base_name` mean, lower being better.
+pub(crate) fn latency_comparison(locale: Locale, name: &str, base_name: &str, base: &[Duration], measured: &[Duration]) -> String {
+    let mean = |durations: &[Duration]| micros(clock::mean(durations.iter().sum(), durations.len() as u64));
+    let base = mean(base);
+    let ratio = if base > 0.0 { mean(measured) / base } else { 0.0 };
+    format!("{} mean batch latency {}x the {} latency", name, locale.decimal(ratio, 2), base_name)
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
+
+/// One line summary, elapsed includes the shutdown drain so every counted message is in the rate.
+pub(crate) fn report(locale: Locale, pipelines: usize, messages: u64, elapsed: Duration) -> String {
+    format!("Bench pipelines {} messages {} elapsed {} throughput {}/s"
+            , pipelines, locale.number(messages), locale.duration(elapsed), locale.decimal(throughput(messages, elapsed), 1))
+}
+
+/// Throughput of `name` as a multiple of the `base_name` throughput, each given as (messages, elapsed).
+pub(crate) fn comparison(locale: Locale, name: &str, base_name: &str, base: (u64, Duration), measured: (u64, Duration)) -> String {
+    let base = throughput(base.0, base.1);
+    let ratio = if base > 0.0 { throughput(measured.0, measured.1) / base } else { 0.0 };
+    format!("{} {}x the {} throughput", name, locale.decimal(ratio, 2), base_name)
+}
+
+fn throughput(messages: u64, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+    if secs > 0.0 { messages as f64 / secs } else { 0.0 }
+}
+
+#[cfg(test)]
+pub(crate) mod bench_tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        assert_eq!(report(Locale::En, 2, 1_500_000, Duration::from_secs(2))
+                   , "Bench pipelines 2 messages 1,500,000 elapsed 2.0 s throughput 750,000.0/s");
+        assert_eq!(report(Locale::En, 1, 0, Duration::ZERO)
+                   , "Bench pipelines 1 messages 0 elapsed 0 ms throughput 0.0/s");
+    }
+
+    #[test]
+    fn test_comparison() {
+        let second = Duration::from_secs(1);
+        assert_eq!(comparison(Locale::En, "Slices", "per value", (400_000, second), (1_000_000, second)), "Slices 2.50x the per value throughput");
+        assert_eq!(comparison(Locale::En, "Slices", "per value", (0, second), (1_000_000, second)), "Slices 0.00x the per value throughput");
+    }
+
+    #[test]
+    fn test_latency_report() {
+        let micros = |values: &[u64]| values.iter().map(|value| Duration::from_micros(*value)).collect::<Vec<_>>();
+        assert_eq!(latency_report(Locale::En, &micros(&[10, 20, 30, 1500])), "batch latency mean 390.0 us max 1,500.0 us over 4 beats");
+        assert_eq!(latency_report(Locale::En, &[]), "batch latency mean 0.0 us max 0.0 us over 0 beats");
+        assert_eq!(latency_comparison(Locale::En, "Prefetch", "slices", &micros(&[40, 60]), &micros(&[10, 20])), "Prefetch mean batch latency 0.30x the slices latency");
+        assert_eq!(latency_comparison(Locale::En, "Prefetch", "slices", &[], &micros(&[10])), "Prefetch mean batch latency 0.00x the slices latency");
+    }
+}
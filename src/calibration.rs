@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::hint::black_box;
+use std::io::{self, BufWriter, Write};
+use std::time::Instant;
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::arg::MainArg;
+use crate::clock;
+
+const CLASSIFY_SAMPLES: u64 = 200_000;
+const WRITE_SAMPLES: u64 = 20_000;
+/// Work one batch should take, bounding the latency a beat adds to the messages it drains.
+const BATCH_BUDGET: Duration = Duration::from_millis(1);
+/// Messages of work one aggregation window should cover to give stable statistics.
+const WINDOW_MESSAGES: u32 = 100_000;
+
+/// Per-message costs measured on this machine at startup.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Calibration {
+    pub(crate) classify: Duration,
+    pub(crate) write: Duration,
+}
+
+impl Calibration {
+    /// Times the worker's classification and a buffered file write of its output line.
+    pub(crate) fn measure() -> io::Result<Self> {
+        let started = Instant::now();
+        for value in 0..CLASSIFY_SAMPLES {
+            black_box(FizzBuzzMessage::new(black_box(value)));
+        }
+        let classify = clock::mean(started.elapsed(), CLASSIFY_SAMPLES);
+
+        let path = std::env::temp_dir().join("standard_calibration.tmp");
+        let mut out = BufWriter::new(File::create(&path)?);
+        let started = Instant::now();
+        for value in 0..WRITE_SAMPLES {
+            writeln!(out, "{:?}", FizzBuzzMessage::new(value))?;
+        }
+        out.flush()?;
+        let write = clock::mean(started.elapsed(), WRITE_SAMPLES);
+        drop(out);
+        let _ = std::fs::remove_file(&path);
+        Ok(Calibration { classify, write })
+    }
+
+    pub(crate) fn per_message(&self) -> Duration {
+        (self.classify + self.write).max(Duration::from_nanos(1))
+    }
+
+    /// Channel capacity holding one batch budget of work, a power of two in 16..=65536.
+    pub(crate) fn batch_size(&self) -> usize {
+        let messages = BATCH_BUDGET.as_nanos() / self.per_message().as_nanos();
+        (messages as usize).clamp(16, 65536).next_power_of_two().min(65536)
+    }
+
+    /// Aggregation window covering `WINDOW_MESSAGES` of work, in 100..=10000 ms.
+    pub(crate) fn window_ms(&self) -> u64 {
+        ((self.per_message() * WINDOW_MESSAGES).as_millis() as u64).clamp(100, 10_000)
+    }
+
+    /// Replaces the batch size and coalescing window defaults, never a value the user chose.
+    pub(crate) fn apply(&self, args: &mut MainArg, matches: &ArgMatches) {
+        let defaults = MainArg::default();
+        let chosen = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if !chosen("channel_capacity") && args.channel_capacity == defaults.channel_capacity {
+            args.channel_capacity = self.batch_size();
+        }
+        if !chosen("aggregate_ms") && args.aggregate_ms == defaults.aggregate_ms {
+            args.aggregate_ms = self.window_ms();
+        }
+        args.calibration = Some(*self);
+    }
+
+    /// Startup log line with the measurements and the settings in effect.
+    pub(crate) fn report(&self, args: &MainArg) -> String {
+        format!("Calibration: classify {:?}/msg, write {:?}/msg, channel capacity {}, aggregate window {} ms"
+                , self.classify, self.write, args.channel_capacity, args.aggregate_ms)
+    }
+
+    /// Entry for the run description served at /info.
+    pub(crate) fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "classify_ns": self.classify.as_nanos() as u64,
+            "write_ns": self.write.as_nanos() as u64,
+            "batch_size": self.batch_size(),
+            "window_ms": self.window_ms(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod calibration_tests {
+    use super::*;
+
+    #[test]
+    fn test_calibration_suggestions() {
+        let slow = Calibration { classify: Duration::from_micros(10), write: Duration::from_micros(40) };
+        assert_eq!(slow.batch_size(), 32); // 20 messages per millisecond, rounded up
+        assert_eq!(slow.window_ms(), 5_000);
+
+        let fast = Calibration { classify: Duration::from_nanos(5), write: Duration::from_nanos(15) };
+        assert_eq!(fast.batch_size(), 65536);
+        assert_eq!(fast.window_ms(), 100);
+
+        let measured = Calibration::measure().expect("calibration");
+        assert!(measured.per_message() > Duration::ZERO);
+    }
+}
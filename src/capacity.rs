@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use crate::probe::Snapshot;
+
+/// Fill at or above which a channel is reported as undersized.
+const CROWDED_FILL: f64 = 90.0;
+/// Fill at or below which a channel is reported as oversized.
+const IDLE_FILL: f64 = 10.0;
+
+/// Capacity tuning suggestions from the fill percentiles recorded over a run.
+/// For every channel the highest tracked percentile is judged at its worst value:
+/// a nearly full channel is a backpressure point, a nearly empty one wastes memory.
+pub(crate) fn suggestions(snapshots: &[Snapshot]) -> Vec<String> {
+    // (from, to) -> (percentile in hundredths, worst fill seen)
+    let mut channels: BTreeMap<(String, String), (u32, f64)> = BTreeMap::new();
+    for sample in snapshots.iter().flat_map(|snapshot| snapshot.samples.iter()) {
+        if sample.name != "percentile_filled" {
+            continue;
+        }
+        let (Some(from), Some(to), Some(p)) = (sample.labels.get("from"), sample.labels.get("to")
+                                               , sample.labels.get("p").and_then(|p| p.parse::<u32>().ok())) else {
+            continue;
+        };
+        let entry = channels.entry((from.clone(), to.clone())).or_insert((p, sample.value));
+        if p > entry.0 {
+            *entry = (p, sample.value);
+        } else if p == entry.0 {
+            entry.1 = entry.1.max(sample.value);
+        }
+    }
+    channels.into_iter().filter_map(|((from, to), (p, fill))| {
+        let advice = if fill >= CROWDED_FILL {
+            "consider 2x capacity or rate limiting"
+        } else if fill <= IDLE_FILL {
+            "capacity could be halved"
+        } else {
+            return None;
+        };
+        Some(format!("{}→{} p{} fill {}%: {}", from, to, p as f64 / 100.0, fill, advice))
+    }).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod capacity_tests {
+    use crate::probe::parse_metrics;
+    use super::*;
+
+    #[test]
+    fn test_suggestions() {
+        let snapshot = |metrics: &str| Snapshot { samples: parse_metrics(metrics), red: Vec::new() };
+        let snapshots = vec![
+            snapshot("percentile_filled{from=\"generator\", to=\"worker\", p=8000} 70\n\
+                      percentile_filled{from=\"generator\", to=\"worker\", p=9900} 85\n\
+                      percentile_filled{from=\"worker\", to=\"logger\", p=8000} 3\n\
+                      percentile_filled{from=\"heartbeat\", to=\"worker\", p=8000} 40\n"),
+            snapshot("percentile_filled{from=\"generator\", to=\"worker\", p=9900} 97\n\
+                      avg_mCPU{actor_name=\"worker\"} 17\n"),
+        ];
+        assert_eq!(suggestions(&snapshots), vec![
+            "generator→worker p99 fill 97%: consider 2x capacity or rate limiting".to_string(),
+            "worker→logger p80 fill 3%: capacity could be halved".to_string(),
+        ]);
+    }
+}
@@ -0,0 +1,247 @@
+use std::fmt;
+use std::sync::Arc;
+#[cfg(test)]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time for every time-dependent feature.
+/// Actors ask the clock carried in the graph args instead of `Instant::now()`,
+/// so tests can swap in a virtual clock and step time explicitly.
+pub(crate) trait ClockSource: Send + Sync {
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time since the Unix epoch, for aligning work across processes.
+    fn wall(&self) -> Duration;
+
+    /// Which clock this is, for comparing and printing argument sets.
+    fn kind(&self) -> ClockKind;
+}
+
+/// The kind of a clock source, with the offset of a skewed system clock.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ClockKind {
+    System,
+    Offset(Duration),
+    #[cfg(test)]
+    Virtual,
+}
+
+fn system_wall() -> Duration {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default()
+}
+
+/// The monotonic system clock.
+pub(crate) struct SystemClock;
+
+impl ClockSource for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn wall(&self) -> Duration {
+        system_wall()
+    }
+
+    fn kind(&self) -> ClockKind {
+        ClockKind::System
+    }
+}
+
+/// The system clock shifted forward by a fixed offset, for simulating skew between instances.
+pub(crate) struct OffsetClock {
+    offset: Duration,
+}
+
+impl ClockSource for OffsetClock {
+    fn now(&self) -> Instant {
+        Instant::now() + self.offset
+    }
+
+    fn wall(&self) -> Duration {
+        system_wall() + self.offset
+    }
+
+    fn kind(&self) -> ClockKind {
+        ClockKind::Offset(self.offset)
+    }
+}
+
+/// Clock which only moves when advanced, making windows and timeouts deterministic in tests.
+#[cfg(test)]
+pub(crate) struct VirtualClock {
+    origin: Instant,
+    wall_origin: Duration,
+    elapsed_micros: AtomicU64,
+}
+
+#[cfg(test)]
+impl VirtualClock {
+    /// Starts with the wall clock reading `wall` since the Unix epoch.
+    pub(crate) fn new(wall: Duration) -> Self {
+        VirtualClock { origin: Instant::now(), wall_origin: wall, elapsed_micros: AtomicU64::new(0) }
+    }
+
+    fn elapsed(&self) -> Duration {
+        Duration::from_micros(self.elapsed_micros.load(Ordering::SeqCst))
+    }
+
+    pub(crate) fn advance(&self, by: Duration) {
+        self.elapsed_micros.fetch_add(by.as_micros() as u64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+impl ClockSource for VirtualClock {
+    fn now(&self) -> Instant {
+        self.origin + self.elapsed()
+    }
+
+    fn wall(&self) -> Duration {
+        self.wall_origin + self.elapsed()
+    }
+
+    fn kind(&self) -> ClockKind {
+        ClockKind::Virtual
+    }
+}
+
+/// Shared handle to the clock source, carried in `MainArg` so every actor sees the same time.
+#[derive(Clone)]
+pub(crate) struct Clock(Arc<dyn ClockSource>);
+
+impl Clock {
+    pub(crate) fn offset(offset: Duration) -> Self {
+        Clock(Arc::new(OffsetClock { offset }))
+    }
+
+    /// A virtual clock and the handle used to advance it.
+    #[cfg(test)]
+    pub(crate) fn virtual_clock() -> (Self, Arc<VirtualClock>) {
+        Clock::virtual_clock_at(Duration::ZERO)
+    }
+
+    /// A virtual clock whose wall time starts at `wall` since the Unix epoch.
+    #[cfg(test)]
+    pub(crate) fn virtual_clock_at(wall: Duration) -> (Self, Arc<VirtualClock>) {
+        let source = Arc::new(VirtualClock::new(wall));
+        (Clock(source.clone()), source)
+    }
+
+    pub(crate) fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    pub(crate) fn wall(&self) -> Duration {
+        self.0.wall()
+    }
+
+    /// The wall-clock reading as a `SystemTime`.
+    pub(crate) fn system_time(&self) -> SystemTime {
+        UNIX_EPOCH + self.wall()
+    }
+
+    /// Time since `earlier` on this clock, zero if `earlier` is in its future.
+    pub(crate) fn since(&self, earlier: Instant) -> Duration {
+        self.now().saturating_duration_since(earlier)
+    }
+}
+
+/// Mean of `count` durations adding up to `total`, zero for none. The count stays a u64,
+/// `Duration / u32` would wrap it past four billion.
+pub(crate) fn mean(total: Duration, count: u64) -> Duration {
+    if count == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_nanos((total.as_nanos() / count as u128) as u64)
+}
+
+/// RFC 3339 UTC time with milliseconds for `millis` since the Unix epoch, `2023-11-14T22:13:20.000Z`.
+pub(crate) fn timestamp(millis: u64) -> String {
+    let (days, rest) = (millis / 86_400_000, millis % 86_400_000);
+    let (hour, minute, second, milli) = (rest / 3_600_000, rest / 60_000 % 60, rest / 1000 % 60, rest % 1000);
+    // days to a civil date, the proleptic Gregorian calendar in eras of 400 years
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day, hour, minute, second, milli)
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock(Arc::new(SystemClock))
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Clock").field(&self.0.kind()).finish()
+    }
+}
+
+/// Clocks of the same kind and offset are equal, system time reads the same through either.
+/// A virtual clock only moves when its own source is advanced, so it equals only itself.
+impl PartialEq for Clock {
+    fn eq(&self, other: &Self) -> bool {
+        match (self.0.kind(), other.0.kind()) {
+            #[cfg(test)]
+            (ClockKind::Virtual, ClockKind::Virtual) => Arc::ptr_eq(&self.0, &other.0),
+            (kind, other_kind) => kind == other_kind,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod clock_tests {
+    use super::*;
+
+    #[test]
+    fn test_clock_sources() {
+        let (clock, source) = Clock::virtual_clock();
+        let start = clock.now();
+        std::thread::sleep(Duration::from_millis(5));
+        assert_eq!(clock.since(start), Duration::ZERO);
+        source.advance(Duration::from_secs(90));
+        assert_eq!(clock.since(start), Duration::from_secs(90));
+        assert_eq!(clock.wall(), Duration::from_secs(90));
+        assert_eq!(Clock::default().since(clock.now()), Duration::ZERO);
+
+        assert_eq!(mean(Duration::from_millis(6), 3), Duration::from_millis(2));
+        assert_eq!(mean(Duration::from_secs(5), u32::MAX as u64 + 1), Duration::from_nanos(1));
+        assert_eq!(mean(Duration::from_secs(1), 0), Duration::ZERO);
+
+        let skewed = Clock::offset(Duration::from_secs(3600));
+        assert!(skewed.now() >= Instant::now() + Duration::from_secs(3599));
+    }
+
+    #[test]
+    fn test_clock_eq() {
+        assert_eq!(Clock::default(), Clock::default());
+        assert_eq!(Clock::offset(Duration::from_secs(5)), Clock::offset(Duration::from_secs(5)));
+        assert_ne!(Clock::offset(Duration::from_secs(5)), Clock::offset(Duration::from_secs(6)));
+        assert_ne!(Clock::default(), Clock::offset(Duration::from_secs(5)));
+
+        let (clock, _source) = Clock::virtual_clock();
+        assert_eq!(clock, clock.clone());
+        assert_ne!(clock, Clock::virtual_clock().0);
+        assert_ne!(clock, Clock::default());
+        assert_eq!(format!("{:?}", Clock::offset(Duration::from_secs(5))), "Clock(Offset(5s))");
+
+        // argument sets differing only in their clock are not equal
+        use crate::arg::MainArg;
+        assert_ne!(MainArg { clock, ..MainArg::default() }, MainArg::default());
+    }
+
+    #[test]
+    fn test_timestamp() {
+        assert_eq!(timestamp(0), "1970-01-01T00:00:00.000Z");
+        assert_eq!(timestamp(1_700_000_000_123), "2023-11-14T22:13:20.123Z");
+        // a leap day
+        assert_eq!(timestamp(951_782_400_000), "2000-02-29T00:00:00.000Z");
+    }
+}
@@ -0,0 +1,297 @@
+use std::error::Error;
+use std::fmt;
+use clap::ArgMatches;
+use clap::parser::ValueSource;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use crate::arg::{Align, Backpressure, CatchupPolicy, GenMode, LogFormat, LogKind, LogTarget, LogVerbosity, MainArg, OnBeatsComplete, OverflowPolicy, PartitionStrategy, SyslogTransport, TopologyKind};
+use crate::format::Locale;
+
+/// Typed configuration file layout, the single source of truth for both
+/// the exported JSON Schema and validation. Every field is optional because
+/// command-line flags may supply or override any of them.
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Default, PartialEq, Clone)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct AppConfig {
+    /// Heartbeat period in milliseconds.
+    pub(crate) rate_ms: Option<u64>,
+    /// Number of beats before the heartbeat requests shutdown.
+    pub(crate) beats: Option<u64>,
+    /// Behavior once the beats are sent: shutdown, pause, restart-count or emit-event.
+    pub(crate) on_beats_complete: Option<OnBeatsComplete>,
+    /// Skip or burst the beats missed while the heartbeat restarted.
+    pub(crate) catchup_policy: Option<CatchupPolicy>,
+    /// Align heartbeats on wall-clock multiples of the rate.
+    pub(crate) align_beats: Option<bool>,
+    /// Wall-clock boundary, second or minute, the heartbeat phase-locks to.
+    pub(crate) align: Option<Align>,
+    /// Delay before the single beat of a one-shot heartbeat.
+    pub(crate) trigger_after_ms: Option<u64>,
+    /// Random offset within ±this many milliseconds added to every heartbeat interval.
+    pub(crate) jitter_ms: Option<u64>,
+    /// Generator backlog above which the heartbeat speeds up.
+    pub(crate) backlog_threshold: Option<usize>,
+    /// Values above this ceiling are sent to the dead-letter actor.
+    pub(crate) max_value: Option<u64>,
+    /// File the dead-letter actor appends rejected values to.
+    pub(crate) dead_letter_path: Option<String>,
+    /// Output file for the rotating file logger.
+    pub(crate) out_file: Option<String>,
+    /// Size in bytes at which the output file is rotated.
+    pub(crate) rotate_bytes: Option<u64>,
+    /// Number of rotated output files to keep.
+    pub(crate) rotate_keep: Option<usize>,
+    /// Pipeline variant to build.
+    pub(crate) topology: Option<TopologyKind>,
+    /// Number of chains built by the parallel topology.
+    pub(crate) pipelines: Option<usize>,
+    /// Number of parallel stress pipelines to build instead of the standard graph.
+    pub(crate) stress_topology: Option<usize>,
+    /// Address of the HTTP control endpoint.
+    pub(crate) control_addr: Option<String>,
+    /// Minutes of batch statistics retained for the history endpoint.
+    pub(crate) history_minutes: Option<u64>,
+    /// Route each message kind to its own logger, next to the merged stream.
+    #[serde(alias = "fanout")]
+    pub(crate) route_by_kind: Option<bool>,
+    /// Publish rolling statistics from the aggregator.
+    pub(crate) aggregate: Option<bool>,
+    /// Length of each aggregation window in milliseconds.
+    pub(crate) aggregate_ms: Option<u64>,
+    /// Extra heartbeat tick rates in milliseconds.
+    pub(crate) tick_ms: Option<Vec<u64>>,
+    /// Messages per tumbling window.
+    pub(crate) window_count: Option<u64>,
+    /// Heartbeats per tumbling window.
+    pub(crate) window_beats: Option<u64>,
+    /// Locale for numbers and durations in summaries and reports.
+    pub(crate) locale: Option<Locale>,
+    /// Drop values repeated within this many recent values.
+    pub(crate) dedup_window: Option<usize>,
+    /// Key file used to encrypt file logger payloads.
+    pub(crate) payload_keys: Option<String>,
+    /// Cap on generator messages per second.
+    pub(crate) max_rate: Option<u64>,
+    /// Per-message lineage JSONL file.
+    pub(crate) lineage: Option<String>,
+    /// Record generator traffic to this file.
+    pub(crate) record: Option<String>,
+    /// Replay this recording instead of running the generator.
+    pub(crate) replay: Option<String>,
+    /// Generator value sequence: counter or random.
+    pub(crate) gen_mode: Option<GenMode>,
+    /// Seed for the random generator mode.
+    pub(crate) seed: Option<u64>,
+    /// Target generator throughput in messages per second.
+    pub(crate) gen_rate: Option<u64>,
+    /// Most values the generator sends in one slice.
+    pub(crate) batch_size: Option<usize>,
+    /// Generator behavior on a full channel: await, drop-newest or drop-oldest.
+    pub(crate) overflow_policy: Option<OverflowPolicy>,
+    /// Number of partitioned generators merged into the worker channel.
+    pub(crate) generators: Option<u64>,
+    /// Strategy spreading values over the generators: modulo, fxhash or consistent.
+    pub(crate) partitioner: Option<PartitionStrategy>,
+    /// Points per partition on the consistent hash ring.
+    pub(crate) virtual_nodes: Option<u64>,
+    /// Number of workers sharing the generator channel.
+    pub(crate) workers: Option<u64>,
+    /// Workers take and send in slices rather than one value per call.
+    pub(crate) batch: Option<bool>,
+    /// Stress workers await the next slice while classifying the current one.
+    pub(crate) prefetch: Option<bool>,
+    /// Classification rules as divisor=label, in label order.
+    pub(crate) rules: Option<Vec<String>>,
+    /// Classic results keep the value they came from.
+    pub(crate) keep_values: Option<bool>,
+    /// Longest one worker batch may classify for, in milliseconds.
+    pub(crate) batch_deadline_ms: Option<u64>,
+    /// Values the state machine worker holds before it flushes.
+    pub(crate) fsm_flush: Option<u64>,
+    /// What the single worker does when the logger is full mid-batch.
+    pub(crate) backpressure: Option<Backpressure>,
+    /// Disk buffer of the spill actor.
+    pub(crate) spill_path: Option<String>,
+    /// Number of logger shards the worker hashes values over.
+    pub(crate) shards: Option<u64>,
+    /// Checkpoint file the generator position is saved to and resumed from.
+    pub(crate) checkpoint: Option<String>,
+    /// Values sent between generator checkpoints.
+    pub(crate) checkpoint_every: Option<u64>,
+    /// Values the generator sends before each deliberate panic.
+    pub(crate) panic_every: Option<u64>,
+    /// Value the worker's transform deliberately fails on.
+    pub(crate) poison_value: Option<u64>,
+    /// Consumer lag percentage above which the generator is throttled.
+    pub(crate) throttle_high: Option<u8>,
+    /// Consumer lag percentage below which throttling eases off.
+    pub(crate) throttle_low: Option<u8>,
+    /// Values per burst in the burst generator mode.
+    pub(crate) burst_size: Option<u64>,
+    /// Milliseconds between bursts.
+    pub(crate) burst_idle_ms: Option<u64>,
+    /// First counter value.
+    pub(crate) start: Option<u64>,
+    /// Last counter value, the run ends after it.
+    pub(crate) end: Option<u64>,
+    /// Counter increment.
+    pub(crate) step: Option<u64>,
+    /// File of generator values, one per line.
+    pub(crate) input: Option<String>,
+    /// CSV column holding the values, an index or a header name.
+    pub(crate) csv_column: Option<String>,
+    /// Capacity of every channel in the standard graph.
+    pub(crate) channel_capacity: Option<usize>,
+    /// Capacity of the heartbeat→worker channel.
+    pub(crate) heartbeat_cap: Option<usize>,
+    /// Capacity of the generator→worker channel.
+    pub(crate) generator_cap: Option<usize>,
+    /// Capacity of the worker→logger channel.
+    pub(crate) worker_cap: Option<usize>,
+    /// Milliseconds added to every timestamp taken by the pipeline.
+    pub(crate) clock_offset_ms: Option<u64>,
+    /// Derive channel capacity and aggregation window from startup measurements.
+    pub(crate) calibrate: Option<bool>,
+    /// Most verbose log level written.
+    pub(crate) log_level: Option<LogVerbosity>,
+    /// Where log lines go: console, file or both.
+    pub(crate) log_target: Option<LogTarget>,
+    /// Log file written when the target is file or both.
+    pub(crate) log_file: Option<String>,
+    /// How the logger writes each message: text, json or csv.
+    pub(crate) log_format: Option<LogFormat>,
+    /// File the csv rows go to instead of stdout.
+    pub(crate) csv_file: Option<String>,
+    /// Syslog endpoint as host:port.
+    pub(crate) syslog: Option<String>,
+    /// udp or tcp.
+    pub(crate) syslog_transport: Option<SyslogTransport>,
+    /// Syslog facility of every message, 0 to 23.
+    pub(crate) syslog_facility: Option<u8>,
+    /// Severity per message kind as kind=severity.
+    pub(crate) syslog_severity: Option<Vec<String>>,
+    /// Destinations the logger writes every message to: console, file=PATH or json=PATH.
+    pub(crate) log_sinks: Option<Vec<String>>,
+    /// Writes one in every N messages, as 1/N.
+    pub(crate) log_sample: Option<String>,
+    /// Kinds the logger writes: fizz, buzz, fizzbuzz or value.
+    pub(crate) log_filter: Option<Vec<LogKind>>,
+    /// JSON file the logger writes its end-of-run summary to.
+    pub(crate) summary_out: Option<String>,
+    /// Initial state of runtime toggles as name=on|off.
+    pub(crate) toggles: Option<Vec<String>>,
+    /// Fill percentiles tracked on the heartbeat→worker channel.
+    pub(crate) heartbeat_percentiles: Option<Vec<u8>>,
+    /// Fill percentiles tracked on the generator→worker channel.
+    pub(crate) generator_percentiles: Option<Vec<u8>>,
+    /// Fill percentiles tracked on the worker→logger channel.
+    pub(crate) worker_percentiles: Option<Vec<u8>>,
+}
+
+/// Configuration problem with the exact location it was found at.
+#[derive(Debug, PartialEq)]
+pub(crate) struct ConfigError {
+    pub(crate) path: String,
+    pub(crate) line: usize,
+    pub(crate) column: usize,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}: {}", self.path, self.line, self.column, self.message)
+    }
+}
+
+impl Error for ConfigError {}
+
+/// JSON Schema generated from [`AppConfig`], pretty printed for humans and editors.
+pub(crate) fn schema_json() -> String {
+    let schema = schemars::schema_for!(AppConfig);
+    serde_json::to_string_pretty(&schema).expect("schema is always serializable")
+}
+
+/// Parses configuration text, mapping any failure to a 1-based line and column.
+pub(crate) fn parse(path: &str, text: &str) -> Result<AppConfig, ConfigError> {
+    toml::from_str(text).map_err(|e| {
+        let offset = e.span().map(|span| span.start).unwrap_or(0);
+        let (line, column) = line_column(text, offset);
+        ConfigError { path: path.to_string(), line, column, message: e.message().to_string() }
+    })
+}
+
+/// Reads and validates the configuration file at `path`.
+pub(crate) fn load(path: &str) -> Result<AppConfig, Box<dyn Error>> {
+    let text = std::fs::read_to_string(path)?;
+    Ok(parse(path, &text)?)
+}
+
+/// Fills every option not given on the command line from the file.
+/// Values clap only defaulted are replaced, so a file setting beats a default
+/// but never an explicit flag.
+pub(crate) fn apply(config: AppConfig, args: &mut MainArg, matches: &ArgMatches) {
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+    macro_rules! merge {
+        ($($field:ident),*) => {$(
+            if let Some(value) = config.$field {
+                if !from_cli(stringify!($field)) { args.$field = value; }
+            }
+        )*};
+    }
+    macro_rules! merge_optional {
+        ($($field:ident),*) => {$(
+            if config.$field.is_some() && !from_cli(stringify!($field)) { args.$field = config.$field; }
+        )*};
+    }
+    merge!(rate_ms, beats, on_beats_complete, catchup_policy, align_beats, jitter_ms, backlog_threshold, dead_letter_path, rotate_bytes, rotate_keep, history_minutes, route_by_kind, aggregate
+         , aggregate_ms, tick_ms, locale, channel_capacity, clock_offset_ms, gen_mode, seed, generators, partitioner, virtual_nodes, workers, batch, prefetch, backpressure, spill_path, rules, keep_values, shards, checkpoint_every, batch_size, overflow_policy, throttle_low, burst_size, burst_idle_ms, start, step, csv_column, calibrate, log_level, log_target, log_file, log_format, syslog_transport, syslog_facility, syslog_severity, log_sinks, log_filter, toggles, topology, pipelines
+         , heartbeat_percentiles, generator_percentiles, worker_percentiles);
+    merge_optional!(max_value, align, trigger_after_ms, batch_deadline_ms, fsm_flush, window_count, window_beats, out_file, csv_file, syslog, log_sample, summary_out, stress_topology, control_addr, dedup_window, payload_keys
+                  , max_rate, gen_rate, throttle_high, lineage, checkpoint, panic_every, poison_value, record, replay, end, input, heartbeat_cap, generator_cap, worker_cap);
+}
+
+fn line_column(text: &str, offset: usize) -> (usize, usize) {
+    let before = &text[..offset.min(text.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rfind('\n').map(|nl| offset - nl).unwrap_or(offset + 1);
+    (line, column)
+}
+
+#[cfg(test)]
+pub(crate) mod config_tests {
+    use super::*;
+
+    #[test]
+    fn test_schema_and_validation() {
+        let schema = schema_json();
+        assert!(schema.contains("\"rate_ms\""));
+        assert!(schema.contains("\"additionalProperties\": false"));
+
+        let config = parse("ok.toml", "rate_ms = 250\nbeats = 10\n").expect("valid config");
+        assert_eq!(config.rate_ms, Some(250));
+        assert_eq!(config.beats, Some(10));
+
+        // errors point at the offending key, not just the file
+        let error = parse("bad.toml", "rate_ms = 250\nbeets = 10\n").expect_err("unknown key");
+        assert_eq!((error.line, error.column), (2, 1));
+        let error = parse("bad.toml", "rate_ms = \"fast\"\n").expect_err("wrong type");
+        assert_eq!((error.line, error.column), (1, 11));
+    }
+
+    #[test]
+    fn test_cli_overrides_file() {
+        use clap::CommandFactory;
+
+        let matches = MainArg::command().get_matches_from(["standard", "--rate", "5", "--config", "standard.toml"]);
+        let mut args = MainArg { rate_ms: 5, config: Some("standard.toml".to_string()), ..MainArg::default() };
+        let config = parse("standard.toml", "rate_ms = 250\nbeats = 10\nchannel_capacity = 128\nlog_level = \"warn\"\n")
+            .expect("valid config");
+        apply(config, &mut args, &matches);
+
+        assert_eq!(args.rate_ms, 5);
+        assert_eq!(args.beats, 10);
+        assert_eq!(args.channel_capacity, 128);
+        assert_eq!(args.log_level, LogVerbosity::Warn);
+        assert_eq!(args.out_file, None);
+    }
+}
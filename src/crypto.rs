@@ -0,0 +1,139 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+
+const NONCE_LEN: usize = 12;
+const KEY_ID_LEN: usize = 4;
+
+/// Payload-level encryption failure, kept separate from I/O errors so callers can
+/// tell "file unreadable" from "payload tampered with or key unknown".
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum CryptoError {
+    UnknownKey(u32),
+    Malformed,
+    Authentication,
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::UnknownKey(id) => write!(f, "no key with id {}", id),
+            CryptoError::Malformed => write!(f, "malformed envelope"),
+            CryptoError::Authentication => write!(f, "payload failed authentication"),
+        }
+    }
+}
+
+impl Error for CryptoError {}
+
+/// Set of payload keys where the newest one encrypts and every retained one decrypts.
+/// Rotation adds a key without removing the old ones, so data sealed before the
+/// rotation (for example in archives) stays readable with the retired key.
+pub(crate) struct KeyRing {
+    active: u32,
+    keys: BTreeMap<u32, ChaCha20Poly1305>,
+}
+
+impl KeyRing {
+    pub(crate) fn new(id: u32, key: [u8; 32]) -> Self {
+        let mut keys = BTreeMap::new();
+        keys.insert(id, ChaCha20Poly1305::new(Key::from_slice(&key)));
+        KeyRing { active: id, keys }
+    }
+
+    /// Parses a key file of `id=<64 hex chars>` lines, the highest id is active.
+    pub(crate) fn parse(text: &str) -> Result<Self, Box<dyn Error>> {
+        let mut ring: Option<KeyRing> = None;
+        for line in text.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (id, hex) = line.split_once('=').ok_or("expected id=<hex key>")?;
+            let bytes = from_hex(hex.trim()).ok_or("key is not valid hex")?;
+            let key: [u8; 32] = bytes.try_into().map_err(|_| "key must be 32 bytes")?;
+            let id: u32 = id.trim().parse()?;
+            match ring.as_mut() {
+                Some(ring) => ring.rotate(id, key),
+                None => ring = Some(KeyRing::new(id, key)),
+            }
+        }
+        Ok(ring.ok_or("key file holds no keys")?)
+    }
+
+    /// Makes `id` the key for new payloads while keeping older keys for decryption.
+    pub(crate) fn rotate(&mut self, id: u32, key: [u8; 32]) {
+        self.keys.insert(id, ChaCha20Poly1305::new(Key::from_slice(&key)));
+        self.active = self.active.max(id);
+    }
+
+    /// Drops a key entirely, payloads sealed with it can no longer be opened.
+    #[cfg(test)]
+    pub(crate) fn retire(&mut self, id: u32) {
+        if id != self.active {
+            self.keys.remove(&id);
+        }
+    }
+
+    /// Envelope layout: key id (LE u32) | nonce | ciphertext with tag.
+    pub(crate) fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let cipher = &self.keys[&self.active];
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let sealed = cipher.encrypt(&nonce, plaintext).expect("encryption of in-memory buffer");
+        let mut envelope = Vec::with_capacity(KEY_ID_LEN + NONCE_LEN + sealed.len());
+        envelope.extend_from_slice(&self.active.to_le_bytes());
+        envelope.extend_from_slice(&nonce);
+        envelope.extend_from_slice(&sealed);
+        envelope
+    }
+
+    pub(crate) fn open(&self, envelope: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if envelope.len() < KEY_ID_LEN + NONCE_LEN {
+            return Err(CryptoError::Malformed);
+        }
+        let (id, rest) = envelope.split_at(KEY_ID_LEN);
+        let (nonce, sealed) = rest.split_at(NONCE_LEN);
+        let id = u32::from_le_bytes(id.try_into().expect("split at key id length"));
+        let cipher = self.keys.get(&id).ok_or(CryptoError::UnknownKey(id))?;
+        cipher.decrypt(Nonce::from_slice(nonce), sealed).map_err(|_| CryptoError::Authentication)
+    }
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len()).step_by(2).map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok()).collect()
+}
+
+#[cfg(test)]
+pub(crate) mod crypto_tests {
+    use super::*;
+
+    #[test]
+    fn test_decrypt_with_retired_keys_after_rotation() {
+        let mut ring = KeyRing::new(1, [7u8; 32]);
+        let archived = ring.seal(b"Fizz");
+
+        ring.rotate(2, [9u8; 32]);
+        let fresh = ring.seal(b"Buzz");
+        // the old archive is still readable after rotation
+        assert_eq!(ring.open(&archived).expect("old key kept"), b"Fizz");
+        assert_eq!(ring.open(&fresh).expect("active key"), b"Buzz");
+        assert_eq!(&fresh[..4], &2u32.to_le_bytes());
+
+        // a reader with only the key file contents decrypts both generations
+        let key_file = format!("1={}\n2={}\n", to_hex(&[7u8; 32]), to_hex(&[9u8; 32]));
+        let replay = KeyRing::parse(&key_file).expect("valid key file");
+        assert_eq!(replay.open(&archived).expect("retired key"), b"Fizz");
+
+        // once fully retired the data is gone, and tampering is always detected
+        ring.retire(1);
+        assert_eq!(ring.open(&archived), Err(CryptoError::UnknownKey(1)));
+        let mut tampered = fresh.clone();
+        *tampered.last_mut().expect("not empty") ^= 1;
+        assert_eq!(ring.open(&tampered), Err(CryptoError::Authentication));
+    }
+}
@@ -0,0 +1,97 @@
+/// Column of a CSV input holding the values: a zero-based position, every row being data,
+/// or a name looked up in the header row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum CsvColumn {
+    Index(usize),
+    Name(String),
+}
+
+impl CsvColumn {
+    /// A number selects the column by position, anything else by header name.
+    pub(crate) fn parse(text: &str) -> Self {
+        match text.trim().parse() {
+            Ok(index) => CsvColumn::Index(index),
+            Err(_) => CsvColumn::Name(text.trim().to_string()),
+        }
+    }
+
+    /// Position of the column, found in `header` when selected by name.
+    pub(crate) fn resolve(&self, header: &str) -> Result<usize, String> {
+        match self {
+            CsvColumn::Index(index) => Ok(*index),
+            CsvColumn::Name(name) => split_row(header)?.iter().position(|field| field.trim() == name)
+                                                      .ok_or_else(|| format!("no column named {} in the header", name)),
+        }
+    }
+}
+
+/// Fields of one CSV row. Quoted fields may hold commas and doubled quotes,
+/// but not line breaks: every line is a row of its own.
+pub(crate) fn split_row(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') if field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+            }
+            (false, ',') => fields.push(std::mem::take(&mut field)),
+            (false, c) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+    Ok(fields)
+}
+
+/// Value in the field at `index` of `line`, None for blank lines.
+pub(crate) fn extract(line: &str, index: usize) -> Result<Option<u64>, String> {
+    if line.trim().is_empty() {
+        return Ok(None);
+    }
+    let fields = split_row(line)?;
+    let field = fields.get(index).ok_or_else(|| format!("row has {} fields, no column {}", fields.len(), index))?;
+    let text = field.trim();
+    match text.parse() {
+        Ok(value) => Ok(Some(value)),
+        Err(_) if text.is_empty() => Err(format!("column {} is empty", index)),
+        Err(_) => Err(format!("column {} not a number: {}", index, text)),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod csv_tests {
+    use super::*;
+
+    #[test]
+    fn test_split_row() {
+        assert_eq!(split_row("a,b,,c"), Ok(vec!["a".into(), "b".into(), "".into(), "c".into()]));
+        assert_eq!(split_row(r#"1,"x, ""y""",2"#), Ok(vec!["1".into(), r#"x, "y""#.into(), "2".into()]));
+        assert!(split_row(r#"1,"open"#).is_err());
+    }
+
+    #[test]
+    fn test_extract() {
+        let column = CsvColumn::parse("amount");
+        assert_eq!(column.resolve("id, amount,note"), Ok(1));
+        assert!(column.resolve("id,total").is_err());
+        assert_eq!(CsvColumn::parse("2"), CsvColumn::Index(2));
+
+        assert_eq!(extract("7, 42 ,x", 1), Ok(Some(42)));
+        assert_eq!(extract("  ", 1), Ok(None));
+        assert_eq!(extract("7,,x", 1), Err("column 1 is empty".to_string()));
+        assert_eq!(extract("7,lots,x", 1), Err("column 1 not a number: lots".to_string()));
+        assert_eq!(extract("7", 1), Err("row has 1 fields, no column 1".to_string()));
+    }
+}
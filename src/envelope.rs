@@ -0,0 +1,132 @@
+use std::time::{Duration, Instant};
+use crate::clock::{self, Clock};
+
+/// A payload stamped where it entered the graph: its position in the source stream
+/// and the clock reading when it was captured. Stages which only forward or transform
+/// the payload keep both, so the terminal actor can measure end-to-end latency and
+/// notice messages which never arrived.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Envelope<T> {
+    /// Position in the source stream, increasing by one per message sent.
+    pub(crate) seq: u64,
+    pub(crate) captured: Instant,
+    pub(crate) payload: T,
+}
+
+impl<T> Envelope<T> {
+    pub(crate) fn new(seq: u64, captured: Instant, payload: T) -> Self {
+        Envelope { seq, captured, payload }
+    }
+
+    /// The same stamp around a new payload, for stages which transform messages.
+    pub(crate) fn map<U>(self, f: impl FnOnce(T) -> U) -> Envelope<U> {
+        Envelope { seq: self.seq, captured: self.captured, payload: f(self.payload) }
+    }
+
+    /// Time since capture on `clock`.
+    pub(crate) fn latency(&self, clock: &Clock) -> Duration {
+        clock.since(self.captured)
+    }
+}
+
+/// Sequence number of a message injected beside the source stream, such as a value sent to the
+/// worker's priority channel. It has no place in the stream, so gap detection passes over it.
+pub(crate) const UNSEQUENCED: u64 = u64::MAX;
+
+/// Counts sequence numbers a consumer expected but never saw.
+/// A number lower than expected means the source started over (a restart or a new
+/// replay), so counting continues from there rather than reporting a gap.
+#[derive(Default)]
+pub(crate) struct GapDetector {
+    expected: Option<u64>,
+    missing: u64,
+}
+
+impl GapDetector {
+    /// Records `seq` and returns how many numbers were skipped just before it.
+    pub(crate) fn observe(&mut self, seq: u64) -> u64 {
+        if seq == UNSEQUENCED {
+            return 0;
+        }
+        let skipped = match self.expected {
+            Some(expected) if seq > expected => seq - expected,
+            _ => 0,
+        };
+        self.missing += skipped;
+        self.expected = Some(seq + 1);
+        skipped
+    }
+
+    pub(crate) fn missing(&self) -> u64 {
+        self.missing
+    }
+}
+
+/// Delivery summary kept by a terminal actor: how much arrived, how much went
+/// missing on the way and how long it took from capture.
+#[derive(Default)]
+pub(crate) struct Delivery {
+    gaps: GapDetector,
+    delivered: u64,
+    total: Duration,
+    max: Duration,
+}
+
+impl Delivery {
+    pub(crate) fn record<T>(&mut self, envelope: &Envelope<T>, clock: &Clock) {
+        self.gaps.observe(envelope.seq);
+        let latency = envelope.latency(clock);
+        self.delivered += 1;
+        self.total += latency;
+        self.max = self.max.max(latency);
+    }
+
+    pub(crate) fn delivered(&self) -> u64 {
+        self.delivered
+    }
+
+    pub(crate) fn report(&self) -> String {
+        let mean = clock::mean(self.total, self.delivered);
+        format!("Delivered {} messages, {} missing, latency mean {:?} max {:?}"
+                , self.delivered, self.gaps.missing(), mean, self.max)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod envelope_tests {
+    use super::*;
+
+    /// Envelopes for `payloads` numbered from zero and all captured at `captured`,
+    /// matching what a source sends while a virtual clock stands still.
+    pub(crate) fn sealed<T>(captured: Instant, payloads: impl IntoIterator<Item = T>) -> Vec<Envelope<T>> {
+        payloads.into_iter().enumerate().map(|(seq, payload)| Envelope::new(seq as u64, captured, payload)).collect()
+    }
+
+    #[test]
+    fn test_gap_detector() {
+        let mut gaps = GapDetector::default();
+        assert_eq!(gaps.observe(0), 0);
+        assert_eq!(gaps.observe(1), 0);
+        assert_eq!(gaps.observe(4), 2);
+        // the source started over, that is not a gap
+        assert_eq!(gaps.observe(0), 0);
+        assert_eq!(gaps.observe(2), 1);
+        // an injected message is neither a gap nor a restart
+        assert_eq!(gaps.observe(UNSEQUENCED), 0);
+        assert_eq!(gaps.observe(3), 0);
+        assert_eq!(gaps.missing(), 3);
+    }
+
+    #[test]
+    fn test_delivery() {
+        let (clock, source) = Clock::virtual_clock();
+        let captured = clock.now();
+        let mut delivery = Delivery::default();
+        source.advance(Duration::from_millis(2));
+        delivery.record(&Envelope::new(0, captured, 'a'), &clock);
+        source.advance(Duration::from_millis(2));
+        delivery.record(&Envelope::new(2, captured, 'b'), &clock);
+        assert_eq!(delivery.delivered(), 2);
+        assert_eq!(delivery.report(), "Delivered 2 messages, 1 missing, latency mean 3ms max 4ms");
+    }
+}
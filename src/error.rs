@@ -0,0 +1,131 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+use crate::config::ConfigError;
+
+/// Crate-wide failure hierarchy.
+/// Actors still hand the framework a `Box<dyn Error>`, but what they box is an `AppError`,
+/// so main can recover the kind to pick an exit code.
+#[derive(Debug)]
+pub(crate) enum AppError {
+    /// Invalid configuration file or flag combination.
+    Config(String),
+    /// Reading or writing a file-backed sink or source failed.
+    SinkIo(io::Error),
+    /// Binding or talking to a network endpoint failed.
+    Network(io::Error),
+    /// The graph did not stop within the allowed time.
+    ShutdownTimeout(Duration),
+    /// Failure reported by an actor or the runtime outside this hierarchy.
+    Actor(String),
+    /// Any of the above with a note on what was being attempted.
+    Context { context: String, source: Box<AppError> },
+}
+
+impl AppError {
+    /// Wraps the error with what was being attempted when it happened.
+    pub(crate) fn context(self, context: impl Into<String>) -> Self {
+        AppError::Context { context: context.into(), source: Box::new(self) }
+    }
+
+    /// The underlying error beneath any context attachments.
+    pub(crate) fn root(&self) -> &AppError {
+        match self {
+            AppError::Context { source, .. } => source.root(),
+            other => other,
+        }
+    }
+
+    /// Process exit status, following the BSD sysexits conventions.
+    pub(crate) fn exit_code(&self) -> u8 {
+        match self.root() {
+            AppError::Config(_) => 78,                // EX_CONFIG
+            AppError::SinkIo(_) => 74,                // EX_IOERR
+            AppError::Network(_) => 69,               // EX_UNAVAILABLE
+            AppError::ShutdownTimeout(_) => 75,       // EX_TEMPFAIL
+            AppError::Actor(_) => 70,                 // EX_SOFTWARE
+            AppError::Context { .. } => unreachable!("root never returns a context"),
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::Config(message) => write!(f, "configuration error: {}", message),
+            AppError::SinkIo(e) => write!(f, "sink I/O error: {}", e),
+            AppError::Network(e) => write!(f, "network error: {}", e),
+            AppError::ShutdownTimeout(timeout) => write!(f, "graph did not stop within {:?}", timeout),
+            AppError::Actor(message) => write!(f, "{}", message),
+            AppError::Context { context, source } => write!(f, "{}: {}", context, source),
+        }
+    }
+}
+
+impl Error for AppError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AppError::SinkIo(e) | AppError::Network(e) => Some(e),
+            AppError::Context { source, .. } => Some(source.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConfigError> for AppError {
+    fn from(e: ConfigError) -> Self {
+        AppError::Config(e.to_string())
+    }
+}
+
+/// Recovers the kind from errors which passed through the framework as `Box<dyn Error>`.
+impl From<Box<dyn Error>> for AppError {
+    fn from(e: Box<dyn Error>) -> Self {
+        let e = match e.downcast::<AppError>() {
+            Ok(app) => return *app,
+            Err(e) => e,
+        };
+        let e = match e.downcast::<ConfigError>() {
+            Ok(config) => return (*config).into(),
+            Err(e) => e,
+        };
+        match e.downcast::<io::Error>() {
+            Ok(io) => AppError::SinkIo(*io),
+            Err(e) => AppError::Actor(e.to_string()),
+        }
+    }
+}
+
+/// Attaches context to any result whose error converts into [`AppError`].
+pub(crate) trait Context<T> {
+    fn context(self, context: impl Into<String>) -> Result<T, AppError>;
+}
+
+impl<T, E: Into<AppError>> Context<T> for Result<T, E> {
+    fn context(self, context: impl Into<String>) -> Result<T, AppError> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod error_tests {
+    use super::*;
+
+    #[test]
+    fn test_app_error() {
+        let e = AppError::Network(io::Error::new(io::ErrorKind::AddrInUse, "in use"))
+            .context("binding control endpoint 127.0.0.1:9901");
+        assert_eq!(e.to_string(), "binding control endpoint 127.0.0.1:9901: network error: in use");
+        assert_eq!(e.exit_code(), 69);
+
+        // the kind survives a trip through the framework's boxed errors
+        let boxed: Box<dyn Error> = Box::new(AppError::Config("bad rate".to_string()).context("loading standard.toml"));
+        let e = AppError::from(boxed);
+        assert_eq!(e.exit_code(), 78);
+
+        let boxed: Box<dyn Error> = Box::new(io::Error::new(io::ErrorKind::Interrupted, "eintr"));
+        assert_eq!(AppError::from(boxed).exit_code(), 74);
+        assert_eq!(AppError::from(Box::<dyn Error>::from("odd")).exit_code(), 70);
+    }
+}
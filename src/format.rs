@@ -0,0 +1,94 @@
+use std::time::Duration;
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Locale used when numbers and durations are shown to people.
+/// Large throughput numbers are unreadable without grouping, and the
+/// grouping and decimal marks differ between regions.
+#[derive(ValueEnum, Serialize, Deserialize, JsonSchema, Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum Locale {
+    /// 1,234,567.8
+    #[default]
+    En,
+    /// 1.234.567,8
+    De,
+    /// 1 234 567,8 (narrow no-break space grouping)
+    Fr,
+    /// 1234567.8 for machine friendly output
+    Plain,
+}
+
+impl Locale {
+    fn separators(self) -> (&'static str, char) {
+        match self {
+            Locale::En => (",", '.'),
+            Locale::De => (".", ','),
+            Locale::Fr => ("\u{202f}", ','),
+            Locale::Plain => ("", '.'),
+        }
+    }
+
+    /// Whole number with thousands grouping.
+    pub(crate) fn number(self, value: u64) -> String {
+        let (group, _) = self.separators();
+        let digits = value.to_string();
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3 * group.len());
+        for (i, c) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                out.push_str(group);
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Fractional number with grouping on the integer part and a fixed number of places.
+    pub(crate) fn decimal(self, value: f64, places: usize) -> String {
+        let (_, point) = self.separators();
+        let fixed = format!("{:.*}", places, value.abs());
+        let (whole, fraction) = fixed.split_once('.').unwrap_or((&fixed, ""));
+        let sign = if value < 0.0 && fixed.chars().any(|c| c.is_ascii_digit() && c != '0') { "-" } else { "" };
+        let whole = self.number(whole.parse().unwrap_or(0));
+        if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}{}{}", sign, whole, point, fraction)
+        }
+    }
+
+    /// Duration in the largest sensible units, e.g. `850 ms`, `12.5 s`, `1 h 02 m 03 s`.
+    pub(crate) fn duration(self, value: Duration) -> String {
+        let secs = value.as_secs();
+        if secs == 0 {
+            format!("{} ms", self.number(value.as_millis() as u64))
+        } else if secs < 60 {
+            format!("{} s", self.decimal(value.as_secs_f64(), 1))
+        } else if secs < 3600 {
+            format!("{} m {:02} s", secs / 60, secs % 60)
+        } else {
+            format!("{} h {:02} m {:02} s", self.number(secs / 3600), secs % 3600 / 60, secs % 60)
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod format_tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_formatting() {
+        assert_eq!(Locale::En.number(1234567), "1,234,567");
+        assert_eq!(Locale::De.number(1234567), "1.234.567");
+        assert_eq!(Locale::Fr.number(1234567), "1\u{202f}234\u{202f}567");
+        assert_eq!(Locale::Plain.number(1234567), "1234567");
+        assert_eq!(Locale::En.number(999), "999");
+        assert_eq!(Locale::En.decimal(12345.678, 1), "12,345.7");
+        assert_eq!(Locale::De.decimal(12345.678, 2), "12.345,68");
+        assert_eq!(Locale::En.decimal(-0.04, 1), "0.0");
+        assert_eq!(Locale::En.duration(Duration::from_millis(850)), "850 ms");
+        assert_eq!(Locale::De.duration(Duration::from_millis(12_500)), "12,5 s");
+        assert_eq!(Locale::En.duration(Duration::from_secs(3723)), "1 h 02 m 03 s");
+    }
+}
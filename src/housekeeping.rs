@@ -0,0 +1,142 @@
+use std::error::Error;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+use crate::arg::MainArg;
+
+/// One file past its retention.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Expired {
+    pub(crate) path: PathBuf,
+    pub(crate) kind: &'static str,
+    pub(crate) bytes: u64,
+    pub(crate) age: Duration,
+}
+
+/// The files this tool writes with the settings in `args`: the log file, the `--out-file`
+/// output with its rotations, the dead letters and the spill file.
+/// Nothing else is ever a candidate, however old, and no directory is searched beyond
+/// the one holding the output for its rotations.
+pub(crate) fn artifacts(args: &MainArg) -> io::Result<Vec<(PathBuf, &'static str)>> {
+    let mut artifacts = vec![(PathBuf::from(&args.log_file), "log")];
+    if let Some(out_file) = &args.out_file {
+        let out_file = PathBuf::from(out_file);
+        artifacts.push((out_file.clone(), "output"));
+        artifacts.extend(rotations(&out_file)?.into_iter().map(|path| (path, "rotated output")));
+    }
+    artifacts.push((PathBuf::from(&args.dead_letter_path), "dead letters"));
+    artifacts.push((PathBuf::from(&args.spill_path), "spill"));
+    Ok(artifacts)
+}
+
+/// The file logger's rotations of `path`, `results.txt.1` and up, next to it.
+fn rotations(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let Some(name) = path.file_name().map(|name| format!("{}.", name.to_string_lossy())) else { return Ok(Vec::new()) };
+    let dir = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+        _ => PathBuf::from("."),
+    };
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut rotations = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        if file_name.strip_prefix(&name).is_some_and(|index| !index.is_empty() && index.bytes().all(|b| b.is_ascii_digit())) {
+            rotations.push(path.with_file_name(file_name));
+        }
+    }
+    rotations.sort();
+    Ok(rotations)
+}
+
+/// Every one of `artifacts` last modified more than `keep` before `now` (wall time since the
+/// Unix epoch), removed only with `apply`. Artifacts not written yet are passed over.
+pub(crate) fn prune(artifacts: &[(PathBuf, &'static str)], keep: Duration, now: Duration, apply: bool) -> io::Result<Vec<Expired>> {
+    let mut expired = Vec::new();
+    for (path, kind) in artifacts {
+        let metadata = match fs::metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => continue,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e),
+        };
+        let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let age = now.saturating_sub(modified);
+        if age > keep {
+            if apply {
+                fs::remove_file(path)?;
+            }
+            expired.push(Expired { path: path.clone(), kind, bytes: metadata.len(), age });
+        }
+    }
+    expired.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(expired)
+}
+
+/// Lists the artifacts of this configuration older than `keep_days`, and with `apply` removes
+/// them, so long-lived deployments do not fill their disks.
+pub(crate) fn run(args: MainArg, keep_days: u64, apply: bool) -> Result<(), Box<dyn Error>> {
+    let keep = Duration::from_secs(keep_days * 24 * 60 * 60);
+    let expired = prune(&artifacts(&args)?, keep, args.clock.wall(), apply)?;
+    let verb = if apply { "removed" } else { "would remove" };
+    for file in &expired {
+        println!("{} {} ({}, {} bytes, {} days old)", verb, file.path.display(), file.kind, file.bytes
+                 , file.age.as_secs() / (24 * 60 * 60));
+    }
+    let bytes: u64 = expired.iter().map(|file| file.bytes).sum();
+    println!("{} {} files, {} bytes older than {} days", verb, expired.len(), bytes, keep_days);
+    if !apply && !expired.is_empty() {
+        println!("run again with --apply to remove them");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod housekeeping_tests {
+    use super::*;
+    use crate::clock::Clock;
+
+    #[test]
+    fn test_prune() -> Result<(), Box<dyn Error>> {
+        let root = std::env::temp_dir().join("standard_housekeeping_test");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("runs"))?;
+        let at = |name: &str| root.join(name).to_string_lossy().to_string();
+        let args = MainArg { log_file: at("standard.log")
+                           , out_file: Some(at("runs/results.txt"))
+                           , dead_letter_path: at("dead_letter.log")
+                           , spill_path: at("spill.bin")
+                           , ..MainArg::default() };
+        // no spill file was written, other files next to the artifacts are not ours
+        for name in ["standard.log", "runs/results.txt", "runs/results.txt.2", "dead_letter.log"
+                     , "other.log", "runs/results.txt.old", "runs/lineage.jsonl"] {
+            fs::write(root.join(name), "x")?;
+        }
+        // everything was just written, so ten days on it is all ten days old
+        let (clock, source) = Clock::virtual_clock_at(Clock::default().wall());
+        source.advance(Duration::from_secs(10 * 24 * 60 * 60));
+        let days = |n: u64| Duration::from_secs(n * 24 * 60 * 60);
+        let artifacts = artifacts(&args)?;
+
+        assert!(prune(&artifacts, days(30), clock.wall(), false)?.is_empty());
+
+        let planned = prune(&artifacts, days(7), clock.wall(), false)?;
+        let kinds: Vec<_> = planned.iter().map(|file| file.kind).collect();
+        assert_eq!(kinds, vec!["dead letters", "output", "rotated output", "log"]);
+        assert!(root.join("standard.log").exists(), "a dry run removed a file");
+
+        assert_eq!(prune(&artifacts, days(7), clock.wall(), true)?, planned);
+        assert!(!root.join("standard.log").exists());
+        assert!(!root.join("runs/results.txt.2").exists());
+        // files this configuration did not write stay
+        for name in ["other.log", "runs/results.txt.old", "runs/lineage.jsonl"] {
+            assert!(root.join(name).exists(), "{} was removed", name);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,41 @@
+use flexi_logger::{Duplicate, FileSpec, Logger, LoggerHandle};
+use steady_state::*;
+use crate::arg::{LogTarget, LogVerbosity, MainArg};
+
+/// Starts file logging when `--log-target` asks for it.
+/// The framework's own logger only writes to the console, so file targets install
+/// the logger here instead and the runners are built without `with_logging`.
+/// The returned handle must live until the end of main, dropping it stops the file writer.
+pub(crate) fn init(args: &MainArg) -> Result<Option<LoggerHandle>, Box<dyn Error>> {
+    let duplicate = match args.log_target {
+        LogTarget::Console => return Ok(None),
+        LogTarget::File => Duplicate::None,
+        LogTarget::Both => Duplicate::All,
+    };
+    let handle = Logger::try_with_str(level_spec(args.log_level))?
+        .log_to_file(FileSpec::try_from(&args.log_file)?)
+        .append()
+        .duplicate_to_stderr(duplicate)
+        .start()?;
+    Ok(Some(handle))
+}
+
+/// Release runner with console logging, unless [`init`] already routed logging elsewhere.
+pub(crate) fn release_runner(args: &MainArg) -> SteadyRunner {
+    let runner = SteadyRunner::release_build();
+    if args.log_target == LogTarget::Console {
+        runner.with_logging(args.log_level.level())
+    } else {
+        runner
+    }
+}
+
+fn level_spec(level: LogVerbosity) -> &'static str {
+    match level {
+        LogVerbosity::Error => "error",
+        LogVerbosity::Warn => "warn",
+        LogVerbosity::Info => "info",
+        LogVerbosity::Debug => "debug",
+        LogVerbosity::Trace => "trace",
+    }
+}
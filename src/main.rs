@@ -1,41 +1,318 @@
 use steady_state::*;
-use arg::MainArg;
+use clap::{CommandFactory, FromArgMatches};
+use arg::{Backpressure, Command, GenMode, LogFormat, MainArg, TopologyKind};
+use error::{AppError, Context};
+use topology::{GraphFormat, Topology};
 mod arg;
+mod batch;
+mod bench;
+mod calibration;
+mod capacity;
+mod clock;
+mod config;
+mod crypto;
+mod csv;
+mod envelope;
+mod error;
+mod format;
+mod housekeeping;
+mod logging;
+mod monitoring;
+mod once;
+mod overflow;
+mod pacing;
+mod partitioner;
+mod planner;
+mod primes;
+mod probe;
+mod request;
+mod rules;
+mod sink;
+mod stress;
+mod syslog;
+mod throttle;
+mod toggles;
+mod topology;
 
 /// Actor module organization demonstrates scalable code structure.
 /// This pattern enables clean separation of concerns while maintaining
 /// visibility and reusability across different deployment configurations.
 pub(crate) mod actor {//#!#//
     pub(crate) mod heartbeat;
+    pub(crate) mod heartbeat_metrics;
+    pub(crate) mod watchdog;
     pub(crate) mod generator;
     pub(crate) mod worker;
+    pub(crate) mod parser;
+    pub(crate) mod classifier;
+    pub(crate) mod fsm_worker;
+    pub(crate) mod spill;
+    pub(crate) mod batch_report;
     pub(crate) mod logger;
+    pub(crate) mod dead_letter;
+    pub(crate) mod file_logger;
+    pub(crate) mod control_api;
+    pub(crate) mod history;
+    pub(crate) mod router;
+    pub(crate) mod aggregator;
+    pub(crate) mod stats_logger;
+    pub(crate) mod windower;
+    pub(crate) mod dedup;
+    pub(crate) mod rate_limiter;
+    pub(crate) mod recorder;
+    pub(crate) mod replayer;
+    pub(crate) mod lineage;
+    pub(crate) mod merger;
+    pub(crate) mod input_errors;
 }
 
 /// Application entry point demonstrating production-ready initialization patterns.
 /// This includes command-line processing, logging setup, graph construction,
 /// and lifecycle management with proper error handling and resource cleanup.
-fn main() -> Result<(), Box<dyn Error>> {
+fn main() -> std::process::ExitCode {
+    match run_app() {
+        Ok(()) => std::process::ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            std::process::ExitCode::from(e.exit_code())
+        }
+    }
+}
 
-    let cli_args = MainArg::parse();
+/// Upper bound on graceful shutdown once every actor has been asked to stop.
+const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
 
+fn run_app() -> Result<(), AppError> {
+    let matches = MainArg::command().get_matches();
+    let mut cli_args = MainArg::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    // File settings fill in whatever was not given as a flag.
+    if let Some(path) = cli_args.config.clone() {
+        config::apply(config::load(&path).context(format!("loading {}", path))?, &mut cli_args, &matches);
+    }
+    if cli_args.clock_offset_ms > 0 {
+        cli_args.clock = clock::Clock::offset(Duration::from_millis(cli_args.clock_offset_ms));
+    }
+    if cli_args.calibrate {
+        calibration::Calibration::measure().map_err(AppError::SinkIo).context("calibrating")?
+            .apply(&mut cli_args, &matches);
+    }
+    // Kept alive until main returns when logging goes to a file.
+    let _log_file = logging::init(&cli_args).context(format!("opening log file {}", cli_args.log_file))?;
+    // Reject bad toggle and rule settings here, before any actor is built.
+    toggles::Toggles::from_settings(&cli_args.toggles).map_err(AppError::Config)?;
+    rules::Rules::parse(&cli_args.rules).map_err(AppError::Config)?;
+    // The router splits by the classic kinds, labels from --rule have no channel of their own.
+    if cli_args.route_by_kind && (!cli_args.rules.is_empty() || cli_args.keep_values) {
+        return Err(AppError::Config("--route-by-kind splits Fizz, Buzz and FizzBuzz, it cannot be combined with --rule or --keep-values".to_string()));
+    }
+    // Pool members take their whole batch before classifying it, there is nothing left to stop.
+    if cli_args.workers > 1 && cli_args.batch_deadline_ms.is_some() {
+        return Err(AppError::Config("--batch-deadline-ms stops a single worker mid-batch, it cannot be combined with --workers".to_string()));
+    }
+    // Shards end in loggers of their own, one worker hashing value by value into them.
+    if cli_args.shards > 1 && (cli_args.workers > 1 || cli_args.batch || cli_args.aggregate || cli_args.windowed() || cli_args.route_by_kind
+                               || cli_args.lineage.is_some() || cli_args.out_file.is_some()) {
+        return Err(AppError::Config("--shards sends from one worker straight to its loggers, it cannot be combined with --workers, --batch, --aggregate, --window-count, --window-beats, --route-by-kind, --lineage or --out-file".to_string()));
+    }
+    // The staged topology has one parser and one classifier in place of the worker variants.
+    if cli_args.topology == TopologyKind::Staged && (cli_args.workers > 1 || cli_args.shards > 1 || cli_args.batch || cli_args.batch_deadline_ms.is_some()) {
+        return Err(AppError::Config("--topology staged splits a single worker in two, it cannot be combined with --workers, --shards, --batch or --batch-deadline-ms".to_string()));
+    }
+    // The state machine worker replaces the single worker, flushing whole on its own schedule.
+    if cli_args.fsm_flush.is_some() && (cli_args.workers > 1 || cli_args.shards > 1 || cli_args.batch || cli_args.batch_deadline_ms.is_some()
+                                        || cli_args.topology == TopologyKind::Staged) {
+        return Err(AppError::Config("--fsm-flush replaces the single worker, it cannot be combined with --workers, --shards, --batch, --batch-deadline-ms or --topology staged".to_string()));
+    }
+    // Truncating and spilling decide value by value on the one logger of the single worker.
+    if cli_args.backpressure != Backpressure::Wait && (cli_args.workers > 1 || cli_args.shards > 1 || cli_args.batch || cli_args.fsm_flush.is_some()
+                                                     || cli_args.topology == TopologyKind::Staged) {
+        return Err(AppError::Config("--backpressure truncate and spill act on the logger of a single worker, they cannot be combined with --workers, --shards, --batch, --fsm-flush or --topology staged".to_string()));
+    }
+    // Prefetch classifies ahead within the slices of the single worker.
+    if cli_args.prefetch && (!cli_args.batch || cli_args.workers > 1) {
+        return Err(AppError::Config("--prefetch classifies --batch slices ahead of the beat in the single worker, it needs --batch and cannot be combined with --workers".to_string()));
+    }
+    // The CSV rows make one table, written by the one logger at the end of the pipeline.
+    if cli_args.log_format == LogFormat::Csv && (cli_args.route_by_kind || cli_args.shards > 1 || cli_args.out_file.is_some()) {
+        return Err(AppError::Config("--log-format csv writes one table from the single logger, it cannot be combined with --route-by-kind, --shards or --out-file".to_string()));
+    }
+    if cli_args.csv_file.is_some() && cli_args.log_format != LogFormat::Csv {
+        return Err(AppError::Config("--csv-file holds the rows of --log-format csv".to_string()));
+    }
+    // Syslog takes the place of the logger's console lines, it has a format of its own.
+    syslog::Severities::from_settings(&cli_args.syslog_severity).map_err(AppError::Config)?;
+    if cli_args.syslog_facility > 23 {
+        return Err(AppError::Config(format!("syslog facilities run from 0 to 23, got {}", cli_args.syslog_facility)));
+    }
+    if cli_args.syslog.is_some() && (cli_args.log_format != LogFormat::Text || cli_args.out_file.is_some()) {
+        return Err(AppError::Config("--syslog ships RFC 5424 lines from the console logger, it cannot be combined with --log-format json or csv, or --out-file".to_string()));
+    }
+    // The fan-out lives in the single logger, each sink in a format of its own.
+    sink::SinkSpec::parse_all(&cli_args.log_sinks).map_err(AppError::Config)?;
+    if !cli_args.log_sinks.is_empty() && (cli_args.log_format != LogFormat::Text || cli_args.syslog.is_some() || cli_args.out_file.is_some()
+                                          || cli_args.route_by_kind || cli_args.shards > 1) {
+        return Err(AppError::Config("--log-sink gives the single logger its sinks, it cannot be combined with --log-format json or csv, --syslog, --out-file, --route-by-kind or --shards".to_string()));
+    }
+    // Sampling thins out the loggers, the --out-file logger is the record of everything.
+    if let Some(sample) = &cli_args.log_sample {
+        sink::parse_sample(sample).map_err(AppError::Config)?;
+    }
+    if (cli_args.log_sample.is_some() || !cli_args.log_filter.is_empty()) && cli_args.out_file.is_some() {
+        return Err(AppError::Config("--log-sample and --log-filter thin out the logger, the --out-file logger writes every message".to_string()));
+    }
+    // Every logger sums up its own messages, only a single one has the whole run to write.
+    if cli_args.summary_out.is_some() && (cli_args.route_by_kind || cli_args.shards > 1) {
+        return Err(AppError::Config("--summary-out is written by the single logger, it cannot be combined with --route-by-kind or --shards".to_string()));
+    }
+    // Only generated values can be partitioned, a file, stdin or a replay is a single stream.
+    if cli_args.generators > 1 && (cli_args.input.is_some() || cli_args.replay.is_some() || cli_args.gen_mode == GenMode::Stdin) {
+        return Err(AppError::Config("--generators needs generated values, not --input, --replay or --gen-mode stdin".to_string()));
+    }
+    if cli_args.gen_mode == GenMode::Csv && cli_args.input.is_none() {
+        return Err(AppError::Config("--gen-mode csv reads the file given with --input".to_string()));
+    }
+    // A zero rate would be due again on every pass of the heartbeat loop.
+    if cli_args.tick_ms.contains(&0) {
+        return Err(AppError::Config("--tick-ms rates must be at least 1 millisecond".to_string()));
+    }
 
-    SteadyRunner::release_build()
+    // Subcommands which never start the graph are handled before any runtime setup.
+    match &cli_args.command {
+        Some(Command::ConfigSchema) => {
+            println!("{}", config::schema_json());
+            return Ok(());
+        }
+        Some(Command::ValidateConfig { file }) => {
+            config::load(file).context(format!("loading {}", file))?;
+            println!("{}: valid", file);
+            return Ok(());
+        }
+        Some(Command::Validate) => return Ok(dry_run(cli_args)?),
+        Some(Command::Bench { seconds, pipelines, compare_batch, compare_prefetch }) => {
+            let (duration, pipelines) = (Duration::from_secs(*seconds), *pipelines);
+            let compare = if *compare_batch {
+                Some(bench::Compare::Batch)
+            } else if *compare_prefetch {
+                Some(bench::Compare::Prefetch)
+            } else {
+                None
+            };
+            return Ok(bench::run(cli_args, duration, pipelines, compare)?);
+        }
+        Some(Command::Plan { target_throughput, target_p99, validate, seconds }) => {
+            let (throughput, p99_ms, validate, seconds) = (*target_throughput, *target_p99, *validate, *seconds);
+            return Ok(planner::run(cli_args, throughput, p99_ms, validate, seconds)?);
+        }
+        Some(Command::Once { value }) => {
+            let value = *value;
+            std::process::exit(once::run(cli_args, value)?);
+        }
+        Some(Command::Batch { input, output, workers }) => {
+            let (input, output, workers) = (input.clone(), output.clone(), *workers);
+            return Ok(batch::run(cli_args, input, output, workers)?);
+        }
+        Some(Command::Housekeeping { keep_days, apply }) => {
+            let (keep_days, apply) = (*keep_days, *apply);
+            return Ok(housekeeping::run(cli_args, keep_days, apply)?);
+        }
+        Some(Command::Open { file }) => {
+            return Ok(actor::file_logger::print_archive(&cli_args, file)?);
+        }
+        Some(Command::Run) | None => {}
+    }
+    if cli_args.dry_run {
+        return Ok(dry_run(cli_args)?);
+    }
+    if let Some(path) = cli_args.export_graph.clone() {
+        return Ok(export_graph(cli_args, path)?);
+    }
+
+    let graph_args = cli_args.clone();
+
+    logging::release_runner(&cli_args)
         .with_stack_size(2 * 1024 * 1024)
-        .with_logging(LogLevel::Info)
         .run(cli_args, move |mut graph| {
-            build_graph(&mut graph);
+            if let Some(calibration) = &graph_args.calibration {
+                info!("{}", calibration.report(&graph_args));
+            }
+            build_selected_topology(&mut graph, &graph_args);
+            // Fill levels are recorded while running so the report can suggest capacities.
+            let recording = probe::TelemetryProbe::new(monitoring::TELEMETRY_ADDR).record(Duration::from_millis(500));
 
             // Synchronous startup ensures all actors are ready before proceeding.
             // This prevents race conditions during initialization and provides
             // predictable system behavior from the start.
             graph.start();
+            if !graph_args.selected_topology().has_heartbeat() {
+                // Heartbeat-less topologies run for the same duration
+                // the standard graph would take to count its beats.
+                std::thread::sleep(Duration::from_millis(graph_args.rate_ms * graph_args.beats));
+                graph.request_shutdown();
+            }
             // Blocking wait with timeout prevents infinite hangs while allowing
             // graceful shutdown completion. The timeout you set should be larger than
             // the expected cleanup duration for all actors to avoid premature termination.
-            graph.block_until_stopped(Duration::from_secs(15))
+            let result = graph.block_until_stopped(SHUTDOWN_TIMEOUT)
+                .map_err(|_| Box::new(AppError::ShutdownTimeout(SHUTDOWN_TIMEOUT)) as Box<dyn Error>);
+            // Shutdown report
+            info!("{}", monitoring::percentile_report(&graph_args));
+            for suggestion in capacity::suggestions(&recording.finish()) {
+                info!("Capacity suggestion: {}", suggestion);
+            }
+            result
+        })?;
+    Ok(())
+
+}
+
+/// Builds the graph without starting it, prints the topology and checks that
+/// every channel has both a producer and a consumer.
+fn dry_run(args: MainArg) -> Result<(), Box<dyn Error>> {
+    let graph_args = args.clone();
+    logging::release_runner(&args)
+        .run(args, move |mut graph| {
+            let topology = build_selected_topology(&mut graph, &graph_args);
+            print!("{}", topology.summary());
+            topology.verify()?;
+            println!("graph valid");
+            Ok(())
+        })
+}
+
+/// Builds the graph without starting it and writes its diagram to `path`.
+fn export_graph(args: MainArg, path: String) -> Result<(), Box<dyn Error>> {
+    let graph_args = args.clone();
+    logging::release_runner(&args)
+        .run(args, move |mut graph| {
+            let topology = build_selected_topology(&mut graph, &graph_args);
+            std::fs::write(&path, topology.export(GraphFormat::from_path(&path)))?;
+            println!("graph written to {}", path);
+            Ok(())
         })
+}
+
+/// Builds the pipeline variant chosen with --topology.
+fn build_selected_topology(graph: &mut Graph, args: &MainArg) -> Topology {
+    match args.selected_topology() {
+        TopologyKind::Minimal => build_parallel_graph(graph, args, 1),
+        TopologyKind::Standard | TopologyKind::Staged => build_graph(graph, args),
+        TopologyKind::Fanout => build_fanout_graph(graph, args),
+        TopologyKind::Parallel => build_parallel_graph(graph, args, args.parallel_pipelines()),
+    }
+}
 
+/// The standard pipeline with content-based routing always on.
+fn build_fanout_graph(graph: &mut Graph, args: &MainArg) -> Topology {
+    build_graph(graph, &MainArg { route_by_kind: true, ..args.clone() })
+}
+
+/// Independent source→worker→sink chains without heartbeat or control plane.
+fn build_parallel_graph(graph: &mut Graph, args: &MainArg, pipelines: usize) -> Topology {
+    let registry = stress::build_stress_graph(graph, pipelines);
+    if let Some(addr) = &args.control_addr {
+        stress::build_pipeline_control(graph, registry, addr.clone());
+    }
+    stress::stress_topology(pipelines, args.control_addr.is_some())
 }
 
 /// Actor name constants enable refactoring safety and consistent identification.
@@ -44,29 +321,147 @@ fn main() -> Result<(), Box<dyn Error>> {
 const NAME_HEARTBEAT: &str = "HEARTBEAT";
 const NAME_GENERATOR: &str = "GENERATOR";
 const NAME_WORKER: &str = "WORKER";
+const NAME_PARSER: &str = "PARSER";
+const NAME_CLASSIFIER: &str = "CLASSIFIER";
+const NAME_FSM_WORKER: &str = "FSM_WORKER";
+const NAME_SPILL: &str = "SPILL";
 const NAME_LOGGER: &str = "LOGGER";
+const NAME_DEAD_LETTER: &str = "DEAD_LETTER";
+const NAME_FILE_LOGGER: &str = "FILE_LOGGER";
+const NAME_CONTROL_API: &str = "CONTROL_API";
+const NAME_HISTORY: &str = "HISTORY";
+const NAME_ROUTER: &str = "ROUTER";
+const NAME_LOGGER_FIZZ: &str = "LOGGER_FIZZ";
+const NAME_LOGGER_BUZZ: &str = "LOGGER_BUZZ";
+const NAME_LOGGER_FIZZBUZZ: &str = "LOGGER_FIZZBUZZ";
+const NAME_LOGGER_VALUE: &str = "LOGGER_VALUE";
+const NAME_AGGREGATOR: &str = "AGGREGATOR";
+const NAME_STATS_LOGGER: &str = "STATS_LOGGER";
+const NAME_WINDOWER: &str = "WINDOWER";
+const NAME_WINDOW_LOGGER: &str = "WINDOW_LOGGER";
+const NAME_DEDUP: &str = "DEDUP";
+const NAME_RATE_LIMITER: &str = "RATE_LIMITER";
+const NAME_RECORDER: &str = "RECORDER";
+const NAME_REPLAYER: &str = "REPLAYER";
+const NAME_LINEAGE: &str = "LINEAGE";
+const NAME_MERGER: &str = "MERGER";
+const NAME_INPUT_ERRORS: &str = "INPUT_ERRORS";
+const NAME_HEARTBEAT_METRICS: &str = "HEARTBEAT_METRICS";
+const NAME_WATCHDOG: &str = "WATCHDOG";
+const NAME_BATCH_REPORT: &str = "BATCH_REPORT";
+const NAME_TICK_LOGGER: &str = "TICK_LOGGER";
+
+/// Priority hints describe how much an actor cares about scheduling latency.
+/// Latency-critical actors sit on the hot path and get a dedicated thread, while
+/// best-effort actors such as sinks share a troupe so their work never competes
+/// with the core pipeline for cores under load.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Priority {
+    LatencyCritical,
+    BestEffort,
+}
+
+impl Priority {
+    /// Maps the hint onto thread placement for the actor builder.
+    fn schedule_as(self, best_effort_troupe: &mut Troupe) -> ScheduleAs<'_> {
+        match self {
+            Priority::LatencyCritical => SoloAct,
+            Priority::BestEffort => MemberOf(best_effort_troupe),
+        }
+    }
+
+    /// Thread placement as shown in the dry-run topology summary.
+    fn placement(self) -> &'static str {
+        match self {
+            Priority::LatencyCritical => "solo",
+            Priority::BestEffort => "best-effort troupe",
+        }
+    }
+}
+
+/// Central table of priority hints so placement can be reviewed in one location.
+const PRIORITY_HEARTBEAT: Priority = Priority::LatencyCritical;
+const PRIORITY_GENERATOR: Priority = Priority::LatencyCritical;
+const PRIORITY_WORKER: Priority = Priority::LatencyCritical;
+const PRIORITY_PARSER: Priority = Priority::LatencyCritical;
+const PRIORITY_CLASSIFIER: Priority = Priority::LatencyCritical;
+const PRIORITY_FSM_WORKER: Priority = Priority::LatencyCritical;
+const PRIORITY_SPILL: Priority = Priority::BestEffort;
+const PRIORITY_LOGGER: Priority = Priority::BestEffort;
+const PRIORITY_DEAD_LETTER: Priority = Priority::BestEffort;
+const PRIORITY_FILE_LOGGER: Priority = Priority::BestEffort;
+const PRIORITY_CONTROL_API: Priority = Priority::BestEffort;
+const PRIORITY_HISTORY: Priority = Priority::BestEffort;
+const PRIORITY_ROUTER: Priority = Priority::LatencyCritical;
+const PRIORITY_AGGREGATOR: Priority = Priority::LatencyCritical;
+const PRIORITY_STATS_LOGGER: Priority = Priority::BestEffort;
+const PRIORITY_WINDOWER: Priority = Priority::LatencyCritical;
+const PRIORITY_WINDOW_LOGGER: Priority = Priority::BestEffort;
+const PRIORITY_DEDUP: Priority = Priority::LatencyCritical;
+const PRIORITY_RATE_LIMITER: Priority = Priority::LatencyCritical;
+const PRIORITY_RECORDER: Priority = Priority::LatencyCritical;
+const PRIORITY_REPLAYER: Priority = Priority::LatencyCritical;
+const PRIORITY_LINEAGE: Priority = Priority::LatencyCritical;
+const PRIORITY_MERGER: Priority = Priority::LatencyCritical;
+const PRIORITY_INPUT_ERRORS: Priority = Priority::BestEffort;
+const PRIORITY_HEARTBEAT_METRICS: Priority = Priority::BestEffort;
+const PRIORITY_WATCHDOG: Priority = Priority::BestEffort;
+const PRIORITY_BATCH_REPORT: Priority = Priority::BestEffort;
+const PRIORITY_TICK_LOGGER: Priority = Priority::BestEffort;
 
 /// Graph construction function demonstrates systematic actor system assembly.
 /// This pattern separates topology definition from application logic,
 /// enabling easier testing, configuration management, and deployment flexibility.
-fn build_graph(graph: &mut Graph) {
+/// The returned topology records every actor and channel for dry runs.
+fn build_graph(graph: &mut Graph, args: &MainArg) -> Topology {
+    let mut topology = Topology::new(args.channel_capacity);
 
     // Channel builder configuration applies consistent monitoring across all channels.
     // This provides uniform observability and alerting behavior without requiring
     // individual channel configuration or runtime performance analysis.
     let channel_builder = graph.channel_builder()
+        .with_capacity(args.channel_capacity)
         // Threshold-based alerting enables proactive monitoring of system health.
         // Red alerts indicate critical congestion requiring immediate attention,
         // while orange alerts provide early warning of developing bottlenecks.
         .with_filled_trigger(Trigger::AvgAbove(Filled::p90()), AlertColor::Red) //#!#//
-        .with_filled_trigger(Trigger::AvgAbove(Filled::p60()), AlertColor::Orange)
-        // Percentile monitoring provides statistical insight into channel utilization.
-        .with_filled_percentile(Percentile::p80());
+        .with_filled_trigger(Trigger::AvgAbove(Filled::p60()), AlertColor::Orange);
+
+    // Percentile monitoring provides statistical insight into channel utilization.
+    // The tracked percentiles and the capacity are chosen per channel from configuration.
+    let heartbeat_builder = monitoring::with_percentiles(&channel_builder, &args.heartbeat_percentiles)
+        .with_capacity(args.heartbeat_capacity());
+    let generator_builder = monitoring::with_percentiles(&channel_builder, &args.generator_percentiles)
+        .with_capacity(args.generator_capacity());
+    let worker_builder = monitoring::with_percentiles(&channel_builder, &args.worker_percentiles)
+        .with_capacity(args.worker_capacity());
 
     // The builder is used to build the channels. Note that we do NOT require any type information.
-    let (heartbeat_tx, heartbeat_rx) = channel_builder.build();
-    let (generator_tx, generator_rx) = channel_builder.build();
-    let (worker_tx, worker_rx) = channel_builder.build();
+    let (heartbeat_tx, heartbeat_rx) = heartbeat_builder.build();
+    let (generator_tx, generator_rx) = generator_builder.build();
+    let (worker_tx, worker_rx) = worker_builder.build();
+    let (dead_letter_tx, dead_letter_rx) = channel_builder.build();
+    let (heartbeat_control_tx, heartbeat_control_rx) = channel_builder.build();
+    let (heartbeat_events_tx, heartbeat_events_rx) = channel_builder.build();
+    let (heartbeat_metrics_tx, heartbeat_metrics_rx) = channel_builder.build();
+    let (watchdog_tx, watchdog_rx) = channel_builder.build();
+    let (heartbeat_timing_tx, heartbeat_timing_rx) = channel_builder.build();
+    let (watchdog_alert_tx, watchdog_alert_rx) = channel_builder.build();
+    let (generator_control_tx, generator_control_rx) = channel_builder.build();
+    let (priority_tx, priority_rx) = channel_builder.build();
+    let (stats_tx, stats_rx) = channel_builder.build();
+    let (report_tx, report_rx) = channel_builder.build();
+    let (backlog_tx, backlog_rx) = channel_builder.build();
+    let (history_query_tx, history_query_rx) = channel_builder.build();
+    let (history_reply_tx, history_reply_rx) = channel_builder.build();
+    topology.sized_channel("heartbeat", &args.heartbeat_percentiles, args.heartbeat_capacity());
+    topology.sized_channel("generator", &args.generator_percentiles, args.generator_capacity());
+    if args.shards <= 1 {
+        topology.sized_channel("worker", &args.worker_percentiles, args.worker_capacity());
+    }
+    for name in ["dead_letter", "heartbeat_control", "heartbeat_events", "heartbeat_metrics", "heartbeat_timing", "watchdog", "watchdog_alert", "generator_control", "priority", "stats", "batch_report", "backlog", "history_query", "history_reply"] {
+        topology.channel(name, &[]);
+    }
 
     // NOT needed for this demo but if we wanted to build a 'bundle' of channels which all have the
     //     same type and capacity it can be done this way.  to use individual channels just use btx[n]
@@ -87,7 +482,59 @@ fn build_graph(graph: &mut Graph) {
         // Values are normalized to 1024 units per core for consistent cross-platform metrics.
         .with_mcpu_avg();//#!#//
 
-    let mut shared_core = graph.actor_troupe();
+    // Best-effort actors share this troupe (one thread) keeping them off the hot path.
+    let mut best_effort = graph.actor_troupe();
+
+    // Runtime toggles shared by the actors which consult them and the control endpoint which flips them.
+    let toggles = toggles::Toggles::from_settings(&args.toggles).expect("toggle settings are validated at startup");
+    let worker_toggles = toggles.clone();
+    // Divisor→label rules replacing the classic classification, None keeps the bare FizzBuzz kinds.
+    let rules = rules::Rules::from_args(&args).expect("rule settings are validated at startup");
+    // Extra heartbeat rates, each on a channel of its own: the aggregator closes its window on one.
+    let mut ticks = Vec::new();
+    let tick_names: Vec<String> = (0..args.tick_ms.len()).map(|index| format!("tick{}", index)).collect();
+    let mut heartbeat_outputs = vec!["heartbeat", "heartbeat_timing", "heartbeat_events"];
+    // Copies of every beat for the actors which only observe timing, each on its own channel.
+    let mut heartbeat_subscribers = Vec::new();
+    for (name, tx) in [("heartbeat_metrics", heartbeat_metrics_tx), ("watchdog", watchdog_tx)] {
+        heartbeat_subscribers.push(tx);
+        heartbeat_outputs.push(name);
+    }
+    // The windower counts beats on a copy of its own, closing a window every --window-beats.
+    let window_heartbeat_rx = if args.windowed() {
+        let (tx, rx) = channel_builder.build();
+        heartbeat_subscribers.push(tx);
+        heartbeat_outputs.push("window_heartbeat");
+        topology.channel("window_heartbeat", &[]);
+        Some(rx)
+    } else {
+        None
+    };
+    let aggregate_tick_rx = if args.aggregate {
+        let (tick_tx, tick_rx) = channel_builder.build();
+        ticks.push(actor::heartbeat::Tick { rate: Duration::from_millis(args.aggregate_ms), tx: tick_tx });
+        topology.channel("aggregate_tick", &[]);
+        heartbeat_outputs.push("aggregate_tick");
+        Some(tick_rx)
+    } else {
+        None
+    };
+    // Every --tick-ms rate on a channel of its own, each counted by a tick logger.
+    let mut tick_rxs = Vec::with_capacity(args.tick_ms.len());
+    for (ms, name) in args.tick_ms.iter().zip(&tick_names) {
+        let (tick_tx, tick_rx) = channel_builder.build();
+        let rate = Duration::from_millis(*ms);
+        ticks.push(actor::heartbeat::Tick { rate, tx: tick_tx });
+        topology.channel(name.as_str(), &[]);
+        heartbeat_outputs.push(name.as_str());
+        tick_rxs.push((rate, tick_rx));
+    }
+
+    // Heartbeat scheduling accuracy, measured by the heartbeat and reported by the control endpoint,
+    // its current interval the limit of the watchdog.
+    let drift = actor::heartbeat::DriftGauge::default();
+    let heartbeat_drift = drift.clone();
+    let watchdog_drift = drift.clone();
 
     // State management demonstrates persistent actor behavior across restarts.
     // Each actor maintains independent state that survives crashes, enabling
@@ -97,28 +544,390 @@ fn build_graph(graph: &mut Graph) {
         //  note .clone() on lazy is doing a late init of our channel //#!#//
         // It is a very normal pattern to see every channel and state cloned here. This enables us
         // to keep an Arc here for recovery should this actor panic.  //#!#//
-        .build(move |actor| actor::heartbeat::run(actor, heartbeat_tx.clone(), state.clone()) 
-               , SoloAct);// MemberOf(&mut shared_core)); // could use troupe if desired
+        .build(move |actor| actor::heartbeat::run(actor, heartbeat_control_rx.clone(), backlog_rx.clone(), heartbeat_tx.clone(), heartbeat_subscribers.clone(), heartbeat_timing_tx.clone(), heartbeat_events_tx.clone(), state.clone(), heartbeat_drift.clone(), ticks.clone()) 
+               , PRIORITY_HEARTBEAT.schedule_as(&mut best_effort));
+    topology.actor(NAME_HEARTBEAT, PRIORITY_HEARTBEAT.placement(), &["heartbeat_control", "backlog"], &heartbeat_outputs);
+    for (index, ((rate, tick_rx), name)) in tick_rxs.into_iter().zip(&tick_names).enumerate() {
+        actor_builder.with_name_and_suffix(NAME_TICK_LOGGER, index)
+            .build(move |actor| actor::heartbeat::run_tick_logger(actor, rate, tick_rx.clone())
+                   , PRIORITY_TICK_LOGGER.schedule_as(&mut best_effort));
+        topology.actor(format!("{}{}", NAME_TICK_LOGGER, index), PRIORITY_TICK_LOGGER.placement(), &[name.as_str()], &[]);
+    }
 
-    // NOTE: that no type information is needed for state.
-    let state = new_state();
-    actor_builder.with_name(NAME_GENERATOR)
-        .build(move |actor| actor::generator::run(actor, generator_tx.clone(), state.clone()) 
-               , SoloAct);// MemberOf(&mut shared_core)); // could use SoloAct to isolate this actor
+    // A copy of every beat and its timing report feed the metrics actor, which reports how closely they keep to schedule.
+    actor_builder.with_name(NAME_HEARTBEAT_METRICS)
+        .build(move |actor| actor::heartbeat_metrics::run(actor, heartbeat_metrics_rx.clone(), heartbeat_timing_rx.clone())
+               , PRIORITY_HEARTBEAT_METRICS.schedule_as(&mut best_effort));
+    topology.actor(NAME_HEARTBEAT_METRICS, PRIORITY_HEARTBEAT_METRICS.placement(), &["heartbeat_metrics", "heartbeat_timing"], &[]);
+
+    // Liveness: another copy of the beats, alerting the control endpoint when they stop.
+    actor_builder.with_name(NAME_WATCHDOG)
+        .build(move |actor| actor::watchdog::run(actor, watchdog_rx.clone(), watchdog_alert_tx.clone(), watchdog_drift.clone())
+               , PRIORITY_WATCHDOG.schedule_as(&mut best_effort));
+    topology.actor(NAME_WATCHDOG, PRIORITY_WATCHDOG.placement(), &["watchdog"], &["watchdog_alert"]);
+
+    // One input errors channel per generator, a replay has none.
+    let mut input_errors = Vec::new();
+    if args.replay.is_some() {
+        // Recorded traffic replaces the generator as the source of values.
+        actor_builder.with_name(NAME_REPLAYER)
+            .build(move |actor| actor::replayer::run(actor, generator_control_rx.clone(), generator_tx.clone())
+                   , PRIORITY_REPLAYER.schedule_as(&mut best_effort));
+        topology.actor(NAME_REPLAYER, PRIORITY_REPLAYER.placement(), &["generator_control"], &["generator"]);
+    } else if args.generators > 1 {
+        // Each generator produces the values --partitioner assigns it into its own partition channel,
+        // the merger interleaves them so everything downstream sees one stream.
+        let partitioner = partitioner::build(args.partitioner, args.generators, args.virtual_nodes);
+        let mut partition_controls = Vec::new();
+        let mut partitions = Vec::new();
+        for index in 0..args.generators {
+            let (control_tx, control_rx) = channel_builder.build();
+            let (partition_tx, partition_rx) = generator_builder.build();
+            let (errors_tx, errors_rx) = channel_builder.build();
+            let state = new_state();
+            let partition = actor::generator::Partition { index, partitioner: partitioner.clone() };
+            actor_builder.with_name_and_suffix(NAME_GENERATOR, index as usize)
+                .build(move |actor| actor::generator::run_partition(actor, control_rx.clone(), partition_tx.clone(), errors_tx.clone(), state.clone(), partition.clone())
+                       , PRIORITY_GENERATOR.schedule_as(&mut best_effort));
+            topology.sized_channel("partition", &args.generator_percentiles, args.generator_capacity());
+            topology.channel("partition_control", &[]);
+            topology.channel("input_errors", &[]);
+            topology.actor(NAME_GENERATOR, PRIORITY_GENERATOR.placement(), &["partition_control"], &["partition", "input_errors"]);
+            partition_controls.push(control_tx);
+            partitions.push(partition_rx);
+            input_errors.push(errors_rx);
+        }
+        actor_builder.with_name(NAME_MERGER)
+            .build(move |actor| actor::merger::run(actor, generator_control_rx.clone(), partition_controls.clone(), partitions.clone(), generator_tx.clone())
+                   , PRIORITY_MERGER.schedule_as(&mut best_effort));
+        topology.fan_actor(NAME_MERGER, PRIORITY_MERGER.placement(), &["generator_control", "partition"], &["generator", "partition_control"]);
+    } else {
+        // NOTE: that no type information is needed for state.
+        let state = new_state();
+        let (errors_tx, errors_rx) = channel_builder.build();
+        actor_builder.with_name(NAME_GENERATOR)
+            .build(move |actor| actor::generator::run(actor, generator_control_rx.clone(), generator_tx.clone(), errors_tx.clone(), state.clone()) 
+                   , PRIORITY_GENERATOR.schedule_as(&mut best_effort));
+        topology.channel("input_errors", &[]);
+        topology.actor(NAME_GENERATOR, PRIORITY_GENERATOR.placement(), &["generator_control"], &["generator", "input_errors"]);
+        input_errors.push(errors_rx);
+    }
+    // Lines of --input which yielded no value are reported here instead of stopping the run.
+    if !input_errors.is_empty() {
+        actor_builder.with_name(NAME_INPUT_ERRORS)
+            .build(move |actor| actor::input_errors::run(actor, input_errors.clone())
+                   , PRIORITY_INPUT_ERRORS.schedule_as(&mut best_effort));
+        topology.fan_actor(NAME_INPUT_ERRORS, PRIORITY_INPUT_ERRORS.placement(), &["input_errors"], &[]);
+    }
+
+    // Name of the channel currently carrying the source stream, optional stages extend it.
+    let mut source_stream = "generator";
+    // Actors each delivered message passes through, recorded by the lineage stage.
+    let mut stages = vec![if args.replay.is_some() { NAME_REPLAYER } else { NAME_GENERATOR }];
+    if args.replay.is_none() && args.generators > 1 {
+        stages.push(NAME_MERGER);
+    }
+
+    // Optional tap: records the source traffic with timestamps for later replay.
+    let generator_rx = if args.record.is_some() {
+        let (recorded_tx, recorded_rx) = generator_builder.build();
+        actor_builder.with_name(NAME_RECORDER)
+            .build(move |actor| actor::recorder::run(actor, generator_rx.clone(), recorded_tx.clone())
+                   , PRIORITY_RECORDER.schedule_as(&mut best_effort));
+        topology.channel("recorded", &args.generator_percentiles);
+        topology.actor(NAME_RECORDER, PRIORITY_RECORDER.placement(), &[source_stream], &["recorded"]);
+        source_stream = "recorded";
+        stages.push(NAME_RECORDER);
+        recorded_rx
+    } else {
+        generator_rx
+    };
+
+    // Optional throttle: caps the generator stream at a fixed number of messages per second.
+    let generator_rx = if args.max_rate.is_some() {
+        let (limited_tx, limited_rx) = generator_builder.build();
+        actor_builder.with_name(NAME_RATE_LIMITER)
+            .build(move |actor| actor::rate_limiter::run(actor, generator_rx.clone(), limited_tx.clone())
+                   , PRIORITY_RATE_LIMITER.schedule_as(&mut best_effort));
+        topology.channel("limited", &args.generator_percentiles);
+        topology.actor(NAME_RATE_LIMITER, PRIORITY_RATE_LIMITER.placement(), &[source_stream], &["limited"]);
+        source_stream = "limited";
+        stages.push(NAME_RATE_LIMITER);
+        limited_rx
+    } else {
+        generator_rx
+    };
+
+    // Optional stateful filter: repeated values are dropped before they reach the worker.
+    let generator_rx = if args.dedup_window.is_some() {
+        let (unique_tx, unique_rx) = generator_builder.build();
+        actor_builder.with_name(NAME_DEDUP)
+            .build(move |actor| actor::dedup::run(actor, generator_rx.clone(), unique_tx.clone())
+                   , PRIORITY_DEDUP.schedule_as(&mut best_effort));
+        topology.channel("unique", &args.generator_percentiles);
+        topology.actor(NAME_DEDUP, PRIORITY_DEDUP.placement(), &[source_stream], &["unique"]);
+        source_stream = "unique";
+        stages.push(NAME_DEDUP);
+        unique_rx
+    } else {
+        generator_rx
+    };
 
     // Multi-input actors demonstrate complex data flow coordination.
     // The worker receives timing signals from heartbeat and data from generator,
     // enabling controlled batch processing with predictable timing behavior.
-    actor_builder.with_name(NAME_WORKER)
-        .build(move |actor| actor::worker::run(actor, heartbeat_rx.clone(), generator_rx.clone(), worker_tx.clone())
-               ,SoloAct);// MemberOf(&mut shared_core)); // could use SoloAct to isolate this actor
+    // One rules channel from the control endpoint, pool members share it like their other inputs.
+    let (rules_tx, rules_rx) = channel_builder.build();
+    topology.channel("rules", &[]);
+    // The parser holds the dead letter channel, so the classifier's poisoned values have their own.
+    let poisoned = (args.topology == TopologyKind::Staged).then(|| channel_builder.build());
+    let poisoned_rx = poisoned.as_ref().map(|(_, rx)| rx.clone());
+    if let Some((poisoned_tx, _)) = poisoned {
+        // Two stages in place of the worker, joined by a channel of typed numbers: the parser
+        // validates, the classifier classifies on the beat and reports as the worker would.
+        let (parsed_tx, parsed_rx) = generator_builder.build();
+        topology.sized_channel("parsed", &args.generator_percentiles, args.generator_capacity());
+        topology.channel("poisoned", &[]);
+        actor_builder.with_name(NAME_PARSER)
+            .build(move |actor| actor::parser::run(actor, generator_rx.clone(), priority_rx.clone(), parsed_tx.clone(), dead_letter_tx.clone(), worker_toggles.clone())
+                   , PRIORITY_PARSER.schedule_as(&mut best_effort));
+        topology.actor(NAME_PARSER, PRIORITY_PARSER.placement(), &[source_stream, "priority"], &["parsed", "dead_letter"]);
+        actor_builder.with_name(NAME_CLASSIFIER)
+            .build(move |actor| actor::classifier::run(actor, heartbeat_rx.clone(), parsed_rx.clone(), rules_rx.clone(), worker_tx.clone()
+                                                       , poisoned_tx.clone(), stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), rules.clone())
+                   , PRIORITY_CLASSIFIER.schedule_as(&mut best_effort));
+        topology.actor(NAME_CLASSIFIER, PRIORITY_CLASSIFIER.placement(), &["heartbeat", "parsed", "rules"]
+                       , &["worker", "poisoned", "stats", "batch_report", "backlog"]);
+    } else if args.fsm_flush.is_some() {
+        // The worker as an explicit state machine, its phase and held values in its SteadyState.
+        let state = new_state();
+        actor_builder.with_name(NAME_FSM_WORKER)
+            .build(move |actor| actor::fsm_worker::run(actor, heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), worker_tx.clone(), dead_letter_tx.clone()
+                                                       , stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), state.clone(), worker_toggles.clone(), rules.clone())
+                   , PRIORITY_FSM_WORKER.schedule_as(&mut best_effort));
+        topology.actor(NAME_FSM_WORKER, PRIORITY_FSM_WORKER.placement(), &["heartbeat", source_stream, "priority", "rules"]
+                       , &["worker", "dead_letter", "stats", "batch_report", "backlog"]);
+    } else if args.workers > 1 {
+        // A pool shares every worker channel: each beat goes to whichever member waits for it,
+        // so one member classifies its batch while the next takes the following one.
+        // Members run solo, a troupe would put them all on one thread.
+        let pool = actor::worker::WorkerPool::new(args.workers as usize);
+        for index in 0..args.workers {
+            let (heartbeat_rx, generator_rx, priority_rx, rules_rx, worker_tx) = (heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), worker_tx.clone());
+            let (dead_letter_tx, stats_tx, report_tx, backlog_tx) = (dead_letter_tx.clone(), stats_tx.clone(), report_tx.clone(), backlog_tx.clone());
+            let (toggles, rules, pool) = (worker_toggles.clone(), rules.clone(), pool.clone());
+            // each member keeps the batch it holds across its own restarts
+            let state = new_state();
+            actor_builder.with_name_and_suffix(NAME_WORKER, index as usize)
+                .build(move |actor| actor::worker::run_member(actor, heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), worker_tx.clone(), dead_letter_tx.clone()
+                                                              , stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), state.clone(), toggles.clone(), rules.clone(), pool.clone())
+                       , PRIORITY_WORKER.schedule_as(&mut best_effort));
+            topology.actor(format!("{}{}", NAME_WORKER, index), PRIORITY_WORKER.placement(), &["heartbeat", source_stream, "priority", "rules"]
+                           , &["worker", "dead_letter", "stats", "batch_report", "backlog"]);
+        }
+    } else if args.shards > 1 {
+        // Each value goes to the shard its hash picks, every shard with a logger of its own.
+        let mut shard_txs = Vec::new();
+        let mut shards = Vec::new();
+        for index in 0..args.shards {
+            let (shard_tx, shard_rx) = worker_builder.build();
+            let (label, state) = (format!("Shard{}", index), new_state());
+            actor_builder.with_name_and_suffix(NAME_LOGGER, index as usize)
+                .build(move |actor| actor::logger::run_for_kind(actor, shard_rx.clone(), state.clone(), label.clone())
+                       , PRIORITY_LOGGER.schedule_as(&mut best_effort));
+            let shard = format!("shard{}", index);
+            topology.sized_channel(shard.as_str(), &args.worker_percentiles, args.worker_capacity());
+            topology.actor(format!("{}{}", NAME_LOGGER, index), PRIORITY_LOGGER.placement(), &[shard.as_str()], &[]);
+            shard_txs.push(shard_tx);
+            shards.push(shard);
+        }
+        let mut outputs: Vec<&str> = shards.iter().map(String::as_str).collect();
+        outputs.extend(["dead_letter", "stats", "batch_report", "backlog"]);
+        let state = new_state();
+        actor_builder.with_name(NAME_WORKER)
+            .build(move |actor| actor::worker::run(actor, heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), shard_txs.clone(), dead_letter_tx.clone()
+                                                   , stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), None, state.clone(), worker_toggles.clone(), rules.clone())
+                   , PRIORITY_WORKER.schedule_as(&mut best_effort));
+        topology.actor(NAME_WORKER, PRIORITY_WORKER.placement(), &["heartbeat", source_stream, "priority", "rules"], &outputs);
+    } else {
+        let state = new_state();
+        let mut inputs = vec!["heartbeat", source_stream, "priority", "rules"];
+        let mut outputs = vec!["worker", "dead_letter", "stats", "batch_report", "backlog"];
+        // Under --backpressure spill what the logger has no room for goes to disk and comes back in order.
+        let spill = if args.backpressure == Backpressure::Spill {
+            let (spill_tx, spill_rx) = generator_builder.build();
+            let (unspill_tx, unspill_rx) = generator_builder.build();
+            let spill_state = new_state();
+            topology.sized_channel("spill", &args.generator_percentiles, args.generator_capacity());
+            topology.sized_channel("unspill", &args.generator_percentiles, args.generator_capacity());
+            actor_builder.with_name(NAME_SPILL)
+                .build(move |actor| actor::spill::run(actor, spill_rx.clone(), unspill_tx.clone(), spill_state.clone())
+                       , PRIORITY_SPILL.schedule_as(&mut best_effort));
+            topology.actor(NAME_SPILL, PRIORITY_SPILL.placement(), &["spill"], &["unspill"]);
+            inputs.push("unspill");
+            outputs.push("spill");
+            Some(actor::worker::Spill { tx: spill_tx, rx: unspill_rx })
+        } else {
+            None
+        };
+        actor_builder.with_name(NAME_WORKER)
+            .build(move |actor| actor::worker::run(actor, heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), vec![worker_tx.clone()], dead_letter_tx.clone()
+                                                   , stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), spill.clone(), state.clone(), worker_toggles.clone(), rules.clone())
+                   , PRIORITY_WORKER.schedule_as(&mut best_effort));
+        topology.actor(NAME_WORKER, PRIORITY_WORKER.placement(), &inputs, &outputs);
+    }
+    // Per-batch counts and timing from the worker, totalled by their own actor.
+    actor_builder.with_name(NAME_BATCH_REPORT)
+        .build(move |actor| actor::batch_report::run(actor, report_rx.clone())
+               , PRIORITY_BATCH_REPORT.schedule_as(&mut best_effort));
+    topology.actor(NAME_BATCH_REPORT, PRIORITY_BATCH_REPORT.placement(), &["batch_report"], &[]);
+    if args.topology == TopologyKind::Staged {
+        stages.extend([NAME_PARSER, NAME_CLASSIFIER]);
+    } else if args.fsm_flush.is_some() {
+        stages.push(NAME_FSM_WORKER);
+    } else {
+        stages.push(NAME_WORKER);
+    }
+
+    // Name of the channel currently carrying worker output, optional stages extend it.
+    let mut output_stream = "worker";
+
+    // Optional windowed aggregation: a pass-through stage publishing rolling statistics.
+    let worker_rx = if let Some(tick_rx) = aggregate_tick_rx {
+        let (forward_tx, forward_rx) = worker_builder.build();
+        let (summary_tx, summary_rx) = channel_builder.build();
+        actor_builder.with_name(NAME_AGGREGATOR)
+            .build(move |actor| actor::aggregator::run(actor, tick_rx.clone(), worker_rx.clone(), forward_tx.clone(), summary_tx.clone())
+                   , PRIORITY_AGGREGATOR.schedule_as(&mut best_effort));
+        actor_builder.with_name(NAME_STATS_LOGGER)
+            .build(move |actor| actor::stats_logger::run(actor, summary_rx.clone())
+                   , PRIORITY_STATS_LOGGER.schedule_as(&mut best_effort));
+        topology.channel("forward", &args.worker_percentiles);
+        topology.channel("summary", &[]);
+        topology.actor(NAME_AGGREGATOR, PRIORITY_AGGREGATOR.placement(), &["aggregate_tick", output_stream], &["forward", "summary"]);
+        topology.actor(NAME_STATS_LOGGER, PRIORITY_STATS_LOGGER.placement(), &["summary"], &[]);
+        output_stream = "forward";
+        stages.push(NAME_AGGREGATOR);
+        forward_rx
+    } else {
+        worker_rx
+    };
+
+    // Optional tumbling windows: a pass-through stage summarizing every N messages or N beats.
+    let worker_rx = if let Some(heartbeat_rx) = window_heartbeat_rx {
+        let (windowed_tx, windowed_rx) = worker_builder.build();
+        let (window_tx, window_rx) = channel_builder.build();
+        actor_builder.with_name(NAME_WINDOWER)
+            .build(move |actor| actor::windower::run(actor, heartbeat_rx.clone(), worker_rx.clone(), windowed_tx.clone(), window_tx.clone())
+                   , PRIORITY_WINDOWER.schedule_as(&mut best_effort));
+        actor_builder.with_name(NAME_WINDOW_LOGGER)
+            .build(move |actor| actor::windower::run_logger(actor, window_rx.clone())
+                   , PRIORITY_WINDOW_LOGGER.schedule_as(&mut best_effort));
+        topology.channel("windowed", &args.worker_percentiles);
+        topology.channel("window", &[]);
+        topology.actor(NAME_WINDOWER, PRIORITY_WINDOWER.placement(), &["window_heartbeat", output_stream], &["windowed", "window"]);
+        topology.actor(NAME_WINDOW_LOGGER, PRIORITY_WINDOW_LOGGER.placement(), &["window"], &[]);
+        output_stream = "windowed";
+        stages.push(NAME_WINDOWER);
+        windowed_rx
+    } else {
+        worker_rx
+    };
+
+    // Optional fan-out by content: the router splits worker output by kind so every
+    // kind gets its own consumer on its own channel, while the merged copy continues on.
+    let worker_rx = if args.route_by_kind {
+        let (merged_tx, merged_rx) = worker_builder.build();
+        let (fizz_tx, fizz_rx) = worker_builder.build();
+        let (buzz_tx, buzz_rx) = worker_builder.build();
+        let (fizzbuzz_tx, fizzbuzz_rx) = worker_builder.build();
+        let (value_tx, value_rx) = worker_builder.build();
+        actor_builder.with_name(NAME_ROUTER)
+            .build(move |actor| actor::router::run(actor, worker_rx.clone(), merged_tx.clone(), fizz_tx.clone(), buzz_tx.clone()
+                                                   , fizzbuzz_tx.clone(), value_tx.clone())
+                   , PRIORITY_ROUTER.schedule_as(&mut best_effort));
+        for name in ["merged", "fizz", "buzz", "fizzbuzz", "value"] {
+            topology.channel(name, &args.worker_percentiles);
+        }
+        topology.actor(NAME_ROUTER, PRIORITY_ROUTER.placement(), &[output_stream]
+                       , &["merged", "fizz", "buzz", "fizzbuzz", "value"]);
+        for (name, channel, rx) in [(NAME_LOGGER_FIZZ, "fizz", fizz_rx), (NAME_LOGGER_BUZZ, "buzz", buzz_rx)
+                                   , (NAME_LOGGER_FIZZBUZZ, "fizzbuzz", fizzbuzz_rx), (NAME_LOGGER_VALUE, "value", value_rx)] {
+            let state = new_state();
+            actor_builder.with_name(name)
+                .build(move |actor| actor::logger::run_for_kind(actor, rx.clone(), state.clone(), name.to_string())
+                       , PRIORITY_LOGGER.schedule_as(&mut best_effort));
+            topology.actor(name, PRIORITY_LOGGER.placement(), &[channel], &[]);
+        }
+        output_stream = "merged";
+        stages.push(NAME_ROUTER);
+        merged_rx
+    } else {
+        worker_rx
+    };
+
+    // Optional compliance record: the lineage of every message reaching the terminal sink.
+    let worker_rx = if args.lineage.is_some() {
+        let (traced_tx, traced_rx) = worker_builder.build();
+        let destination = match &args.out_file {
+            Some(path) => format!("{} {}", NAME_FILE_LOGGER, path),
+            None => NAME_LOGGER.to_string(),
+        };
+        let lineage = actor::lineage::Lineage { source: actor::lineage::Lineage::source(args), stages, destination };
+        actor_builder.with_name(NAME_LINEAGE)
+            .build(move |actor| actor::lineage::run(actor, worker_rx.clone(), traced_tx.clone(), lineage.clone())
+                   , PRIORITY_LINEAGE.schedule_as(&mut best_effort));
+        topology.channel("traced", &args.worker_percentiles);
+        topology.actor(NAME_LINEAGE, PRIORITY_LINEAGE.placement(), &[output_stream], &["traced"]);
+        output_stream = "traced";
+        traced_rx
+    } else {
+        worker_rx
+    };
 
     // Terminal actors focus on external system integration and side effects.
     // Loggers typically have no outgoing channels but provide essential
     // observability and debugging capabilities for system operation.
-    actor_builder.with_name(NAME_LOGGER)
-        .build(move |actor| actor::logger::run(actor, worker_rx.clone())
-               ,SoloAct);// MemberOf(&mut shared_core)); // could use SoloAct to isolate this actor
+    if args.shards > 1 {
+        // every shard already ends in a logger of its own, nothing is left on the worker channel
+    } else if args.out_file.is_some() {
+        // Long soak runs write to a rotating file rather than the console.
+        let state = new_state();
+        actor_builder.with_name(NAME_FILE_LOGGER)
+            .build(move |actor| actor::file_logger::run(actor, worker_rx.clone(), state.clone())
+                   , PRIORITY_FILE_LOGGER.schedule_as(&mut best_effort));
+        topology.actor(NAME_FILE_LOGGER, PRIORITY_FILE_LOGGER.placement(), &[output_stream], &[]);
+    } else {
+        let state = new_state();
+        actor_builder.with_name(NAME_LOGGER)
+            .build(move |actor| actor::logger::run(actor, worker_rx.clone(), state.clone())
+                   , PRIORITY_LOGGER.schedule_as(&mut best_effort));
+        topology.actor(NAME_LOGGER, PRIORITY_LOGGER.placement(), &[output_stream], &[]);
+    }
+
+    // Fault isolation keeps unprocessable values out of the main flow.
+    // The dead-letter actor persists them so nothing is silently lost.
+    let dead_letter_inputs: &[&str] = if poisoned_rx.is_some() { &["dead_letter", "poisoned"] } else { &["dead_letter"] };
+    actor_builder.with_name(NAME_DEAD_LETTER)
+        .build(move |actor| actor::dead_letter::run(actor, dead_letter_rx.clone(), poisoned_rx.clone())
+               , PRIORITY_DEAD_LETTER.schedule_as(&mut best_effort));
+    topology.actor(NAME_DEAD_LETTER, PRIORITY_DEAD_LETTER.placement(), dead_letter_inputs, &[]);
+
+    // The control plane lets operators pause, resume, re-rate or stop the graph at runtime.
+    actor_builder.with_name(NAME_CONTROL_API)
+        .build(move |actor| actor::control_api::run(actor, heartbeat_control_tx.clone(), generator_control_tx.clone(), priority_tx.clone(), rules_tx.clone()
+                                                    , history_query_tx.clone(), history_reply_rx.clone(), heartbeat_events_rx.clone(), watchdog_alert_rx.clone()
+                                                    , toggles.clone(), drift.clone())
+               , PRIORITY_CONTROL_API.schedule_as(&mut best_effort));
+    topology.actor(NAME_CONTROL_API, PRIORITY_CONTROL_API.placement(), &["history_reply", "heartbeat_events", "watchdog_alert"]
+                   , &["heartbeat_control", "generator_control", "priority", "rules", "history_query"]);
+
+    // Short-horizon history of batch statistics, answered over a query/reply channel pair.
+    actor_builder.with_name(NAME_HISTORY)
+        .build(move |actor| actor::history::run(actor, stats_rx.clone(), history_query_rx.clone(), history_reply_tx.clone())
+               , PRIORITY_HISTORY.schedule_as(&mut best_effort));
+    topology.actor(NAME_HISTORY, PRIORITY_HISTORY.placement(), &["stats", "history_query"], &["history_reply"]);
+    topology
 }
 
 /// Integration testing module demonstrates end-to-end system validation.
@@ -128,7 +937,11 @@ fn build_graph(graph: &mut Graph) {
 pub(crate) mod main_tests {
     use steady_state::*;
     use steady_state::graph_testing::*;
+    use std::time::{Instant, SystemTime};
+    use crate::actor::heartbeat::{Heartbeat, Pulse};
     use crate::actor::worker::FizzBuzzMessage;
+    use crate::envelope::{Envelope, GapDetector};
+    use crate::sink::verify_order;
     use super::*;
 
     #[test]
@@ -140,7 +953,7 @@ pub(crate) mod main_tests {
             .with_logging(LogLevel::Info)
             .run(MainArg::default(), move |mut graph| {
                 // We call the same production code to build the graph here for testing
-                build_graph(&mut graph);
+                build_graph(&mut graph, &MainArg::default());
                 graph.start();
 
                 // Stage management provides orchestrated testing of multi-actor scenarios.
@@ -148,9 +961,11 @@ pub(crate) mod main_tests {
                 // complex system interactions without manual coordination complexity.
                 let stage_manager = graph.stage_manager(); //#!#//
                 // This makes use of the "simulated" actors to mock what they send or expect to receive.
-                stage_manager.actor_perform(NAME_GENERATOR, StageDirection::Echo(15u64))?;
-                stage_manager.actor_perform(NAME_HEARTBEAT, StageDirection::Echo(100u64))?;
-                stage_manager.actor_perform(NAME_LOGGER,    StageWaitFor::Message(FizzBuzzMessage::FizzBuzz
+                // The worker keeps the envelope of the value, so the logger sees the same stamp.
+                let value = Envelope::new(0, Instant::now(), 15u64);
+                stage_manager.actor_perform(NAME_GENERATOR, StageDirection::Echo(value))?;
+                stage_manager.actor_perform(NAME_HEARTBEAT, StageDirection::Echo(Pulse::Beat(Heartbeat::on_time(100, SystemTime::now()))))?;
+                stage_manager.actor_perform(NAME_LOGGER,    StageWaitFor::Message(value.map(FizzBuzzMessage::new)
                                                                                   , Duration::from_secs(2)))?;
                 // Must stop stage manager which has been communicating to our simulated actors.
                 stage_manager.final_bow(); //#!#//
@@ -163,4 +978,75 @@ pub(crate) mod main_tests {
 
 
     }
+
+    /// One run of the whole graph, shut down mid-run after `run_for`, returning the sequence
+    /// numbers of the rows its logger wrote to `csv_file` in the order written.
+    fn run_to_csv(args: &MainArg, csv_file: String, run_for: Duration) -> Result<Vec<u64>, Box<dyn Error>> {
+        let args = MainArg { csv_file: Some(csv_file.clone()), ..args.clone() };
+        // a graph and state of its own, nothing survives from an earlier run but the files
+        let mut graph = GraphBuilder::for_testing().build(args.clone());
+        build_graph(&mut graph, &args);
+        graph.start();
+        std::thread::sleep(run_for);
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(5))?;
+        let rows = std::fs::read_to_string(&csv_file)?;
+        let sequences = rows.lines().skip(1).map(|row| row.split(',').next().unwrap_or_default().parse::<u64>())
+                            .collect::<Result<Vec<_>, _>>()?;
+        Ok(sequences)
+    }
+
+    #[test]
+    fn test_restart_from_checkpoint() -> Result<(), Box<dyn Error>> {
+        let dir = std::env::temp_dir().join(format!("standard_checkpoint_restart_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir)?;
+        let at = |name: &str| dir.join(name).to_string_lossy().to_string();
+        let args = MainArg { rate_ms: 10
+                           , beats: 1_000_000
+                           , gen_rate: Some(2000)
+                           , log_format: LogFormat::Csv
+                           , checkpoint: Some(at("generator.json"))
+                           , checkpoint_every: 50
+                           , dead_letter_path: at("dead_letter.log")
+                           , ..MainArg::default() };
+
+        let first = run_to_csv(&args, at("first.csv"), Duration::from_millis(300))?;
+        assert!(dir.join("generator.json").exists(), "no checkpoint written");
+        // a new process: fresh graph, fresh state, only the checkpoint file to go on
+        let second = run_to_csv(&args, at("second.csv"), Duration::from_millis(300))?;
+        std::fs::remove_dir_all(&dir)?;
+
+        assert!(!first.is_empty() && !second.is_empty());
+        assert_eq!(second.first().copied(), first.last().map(|seq| seq + 1), "the second run did not resume after the first");
+        // conservation and ordering at the logger across both runs: every sequence exactly once, in order
+        let combined: Vec<u64> = first.into_iter().chain(second).collect();
+        let mut gaps = GapDetector::default();
+        combined.iter().for_each(|seq| { gaps.observe(*seq); });
+        assert_eq!(gaps.missing(), 0);
+        verify_order(&combined, |seq| *seq)?;
+        assert_eq!(combined, (0..combined.len() as u64).collect::<Vec<_>>());
+        Ok(())
+    }
+
+    #[test]
+    fn topology_test() {
+        // every optional stage at once, each one must leave no channel dangling
+        let args = MainArg { replay: Some("unused.bin".to_string())
+                           , record: Some("unused.bin".to_string())
+                           , max_rate: Some(100)
+                           , dedup_window: Some(8)
+                           , aggregate: true
+                           , window_count: Some(100)
+                           , route_by_kind: true
+                           , ..MainArg::default() };
+        let staged = MainArg { topology: TopologyKind::Staged, ..MainArg::default() };
+        let fsm = MainArg { fsm_flush: Some(16), ..MainArg::default() };
+        let spill = MainArg { backpressure: Backpressure::Spill, ..MainArg::default() };
+        for args in [MainArg::default(), args, staged, fsm, spill] {
+            let mut graph = GraphBuilder::for_testing().build(args.clone());
+            let topology = build_graph(&mut graph, &args);
+            assert_eq!(topology.verify(), Ok(()), "{}", topology.summary());
+        }
+    }
 }
@@ -0,0 +1,35 @@
+use steady_state::*;
+use crate::arg::MainArg;
+
+/// Where the framework serves telemetry unless configured otherwise.
+pub(crate) const TELEMETRY_ADDR: &str = "127.0.0.1:9900";
+
+/// Maps a whole-number percentile onto the framework type, using the named
+/// constructors where they exist so telemetry labels match the common values.
+pub(crate) fn percentile(value: u8) -> Percentile {
+    match value {
+        25 => Percentile::p25(),
+        50 => Percentile::p50(),
+        75 => Percentile::p75(),
+        80 => Percentile::p80(),
+        90 => Percentile::p90(),
+        96 => Percentile::p96(),
+        99 => Percentile::p99(),
+        other => Percentile::custom(other as f64).expect("percentile range checked by arg parsing"),
+    }
+}
+
+/// Adds one filled-percentile series per requested value to the channel builder.
+/// Each series appears in telemetry with its own `p=` label.
+pub(crate) fn with_percentiles(builder: &ChannelBuilder, values: &[u8]) -> ChannelBuilder {
+    values.iter().fold(builder.clone(), |builder, p| builder.with_filled_percentile(percentile(*p)))
+}
+
+/// Shutdown report line listing which percentiles were tracked on each channel.
+pub(crate) fn percentile_report(args: &MainArg) -> String {
+    let list = |values: &[u8]| values.iter().map(|p| format!("p{}", p)).collect::<Vec<_>>().join(",");
+    format!("Channel fill percentiles: heartbeat->worker [{}] generator->worker [{}] worker->logger [{}]"
+            , list(&args.heartbeat_percentiles)
+            , list(&args.generator_percentiles)
+            , list(&args.worker_percentiles))
+}
@@ -0,0 +1,177 @@
+use std::sync::{Arc, Mutex};
+use steady_state::*;
+use crate::actor::batch_report::BatchReport;
+use crate::actor::dead_letter::DeadLetter;
+use crate::actor::heartbeat::{Heartbeat, Pulse};
+use crate::actor::history::BatchStats;
+use crate::actor::worker::{self, FizzBuzzMessage};
+use crate::arg::MainArg;
+use crate::envelope::Envelope;
+use crate::logging;
+use crate::rules::{RuleSet, Rules};
+use crate::toggles::Toggles;
+
+const NAME_ONCE_SOURCE: &str = "ONCE_SOURCE";
+const NAME_ONCE_WORKER: &str = "ONCE_WORKER";
+const NAME_ONCE_CAPTURE: &str = "ONCE_CAPTURE";
+
+/// What happened to the single value.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Processed(FizzBuzzMessage),
+    Rejected(DeadLetter),
+}
+
+impl Outcome {
+    /// Exit status for scripts: the Fizz/Buzz/FizzBuzz discriminant (3, 5, 15),
+    /// 0 for a plain value and 2 when the worker rejected the value.
+    /// Under --rule it is the product of the matched divisors, capped at 255.
+    pub(crate) fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Processed(FizzBuzzMessage::Classified(c)) if c.labels().is_empty() => 0,
+            Outcome::Processed(FizzBuzzMessage::Classified(c)) => c.labels().product().min(255) as i32,
+            Outcome::Processed(FizzBuzzMessage::Value(_)) => 0,
+            Outcome::Processed(FizzBuzzMessage::Fizz) => 3,
+            Outcome::Processed(FizzBuzzMessage::Buzz) => 5,
+            Outcome::Processed(FizzBuzzMessage::FizzBuzz) => 15,
+            Outcome::Rejected(_) => 2,
+        }
+    }
+}
+
+/// Pushes one value through the real worker and returns the exit status to use.
+pub(crate) fn run(args: MainArg, value: u64) -> Result<i32, Box<dyn Error>> {
+    let slot = Arc::new(Mutex::new(None));
+    let captured = slot.clone();
+    let graph_args = args.clone();
+    logging::release_runner(&args)
+        .run(args, move |mut graph| {
+            build_once_graph(&mut graph, &graph_args, value, captured.clone());
+            graph.start();
+            graph.block_until_stopped(Duration::from_secs(5))
+        })?;
+    let outcome = slot.lock().expect("outcome lock").take().ok_or("no result before shutdown")?;
+    match outcome {
+        Outcome::Processed(msg) => println!("{} {:?}", value, msg),
+        Outcome::Rejected(letter) => println!("{} rejected ({})", letter.value, letter.reason),
+    }
+    Ok(outcome.exit_code())
+}
+
+/// Minimal graph: a one-shot source feeds the production worker with the value and a
+/// single beat, and the capture actor stores whatever comes out and shuts the graph down.
+pub(crate) fn build_once_graph(graph: &mut Graph, args: &MainArg, value: u64, slot: Arc<Mutex<Option<Outcome>>>) {
+    let toggles = Toggles::from_settings(&args.toggles).expect("toggle settings are validated at startup");
+    let rules = Rules::from_args(&args).expect("rule settings are validated at startup");
+    let channel_builder = graph.channel_builder();
+    let (heartbeat_tx, heartbeat_rx) = channel_builder.build();
+    let (generator_tx, generator_rx) = channel_builder.build();
+    let (priority_tx, priority_rx) = channel_builder.build();
+    let (rules_tx, rules_rx) = channel_builder.build();
+    let (worker_tx, worker_rx) = channel_builder.build();
+    let (dead_letter_tx, dead_letter_rx) = channel_builder.build();
+    let (stats_tx, stats_rx) = channel_builder.build();
+    let (report_tx, report_rx) = channel_builder.build();
+    let (backlog_tx, backlog_rx) = channel_builder.build();
+
+    let actor_builder = graph.actor_builder();
+    actor_builder.with_name(NAME_ONCE_SOURCE)
+        .build(move |actor| source(actor, value, heartbeat_tx.clone(), generator_tx.clone(), priority_tx.clone(), rules_tx.clone()), SoloAct);
+    let state = new_state();
+    actor_builder.with_name(NAME_ONCE_WORKER)
+        .build(move |actor| worker::run(actor, heartbeat_rx.clone(), generator_rx.clone(), priority_rx.clone(), rules_rx.clone(), vec![worker_tx.clone()]
+                                        , dead_letter_tx.clone(), stats_tx.clone(), report_tx.clone(), backlog_tx.clone(), None, state.clone(), toggles.clone(), rules.clone()), SoloAct);
+    actor_builder.with_name(NAME_ONCE_CAPTURE)
+        .build(move |actor| capture(actor, worker_rx.clone(), dead_letter_rx.clone(), stats_rx.clone(), report_rx.clone(), backlog_rx.clone(), slot.clone()), SoloAct);
+}
+
+/// Sends the value and then the beat which makes the worker process it.
+/// The priority and rules channels carry nothing here and are only closed at shutdown.
+async fn source(actor: SteadyActorShadow, value: u64, heartbeat_tx: SteadyTx<Pulse>, generator_tx: SteadyTx<Envelope<u64>>
+                , priority_tx: SteadyTx<Envelope<u64>>, rules_tx: SteadyTx<RuleSet>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([], [&heartbeat_tx, &generator_tx, &priority_tx, &rules_tx]);
+    let clock = actor.args::<MainArg>().expect("unable to downcast").clock.clone();
+    let captured = clock.now();
+    let mut heartbeat_tx = heartbeat_tx.lock().await;
+    let mut generator_tx = generator_tx.lock().await;
+    let mut priority_tx = priority_tx.lock().await;
+    let mut rules_tx = rules_tx.lock().await;
+    actor.send_async(&mut generator_tx, Envelope::new(0, captured, value), SendSaturation::AwaitForRoom).await;
+    actor.send_async(&mut heartbeat_tx, Pulse::Beat(Heartbeat::on_time(0, clock.system_time())), SendSaturation::AwaitForRoom).await;
+    while actor.is_running(|| i!(heartbeat_tx.mark_closed()) && i!(generator_tx.mark_closed()) && i!(priority_tx.mark_closed()) && i!(rules_tx.mark_closed())) {
+        actor.wait_shutdown().await;
+    }
+    Ok(())
+}
+
+/// Keeps the first processed or rejected message, then requests shutdown.
+async fn capture(actor: SteadyActorShadow
+                 , worker_rx: SteadyRx<Envelope<FizzBuzzMessage>>
+                 , dead_letter_rx: SteadyRx<DeadLetter>
+                 , stats_rx: SteadyRx<BatchStats>
+                 , report_rx: SteadyRx<BatchReport>
+                 , backlog_rx: SteadyRx<usize>
+                 , slot: Arc<Mutex<Option<Outcome>>>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([&worker_rx, &dead_letter_rx, &stats_rx, &report_rx, &backlog_rx], []);
+    let mut worker_rx = worker_rx.lock().await;
+    let mut dead_letter_rx = dead_letter_rx.lock().await;
+    let mut stats_rx = stats_rx.lock().await;
+    let mut report_rx = report_rx.lock().await;
+    let mut backlog_rx = backlog_rx.lock().await;
+    while actor.is_running(|| i!(worker_rx.is_closed_and_empty())
+                              && i!(dead_letter_rx.is_closed_and_empty())
+                              && i!(stats_rx.is_closed_and_empty())
+                              && i!(report_rx.is_closed_and_empty())
+                              && i!(backlog_rx.is_closed_and_empty())) {
+        await_for_any!(actor.wait_avail(&mut worker_rx, 1), actor.wait_avail(&mut dead_letter_rx, 1));
+        let outcome = actor.try_take(&mut worker_rx).map(|msg| Outcome::Processed(msg.payload))
+            .or_else(|| actor.try_take(&mut dead_letter_rx).map(Outcome::Rejected));
+        while actor.try_take(&mut stats_rx).is_some() {}
+        while actor.try_take(&mut report_rx).is_some() {}
+        while actor.try_take(&mut backlog_rx).is_some() {}
+        if let Some(outcome) = outcome {
+            let mut slot = slot.lock().expect("outcome lock");
+            if slot.is_none() {
+                *slot = Some(outcome);
+                drop(slot);
+                actor.request_shutdown().await;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod once_tests {
+    use super::*;
+    use crate::actor::dead_letter::Reason;
+
+    fn once(args: MainArg, value: u64) -> Result<Option<Outcome>, Box<dyn Error>> {
+        let slot = Arc::new(Mutex::new(None));
+        let mut graph = GraphBuilder::for_testing().build(args.clone());
+        build_once_graph(&mut graph, &args, value, slot.clone());
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(2))?;
+        let outcome = slot.lock().expect("outcome lock").take();
+        Ok(outcome)
+    }
+
+    #[test]
+    fn test_once() -> Result<(), Box<dyn Error>> {
+        let outcome = once(MainArg::default(), 15)?;
+        assert_eq!(outcome, Some(Outcome::Processed(FizzBuzzMessage::FizzBuzz)));
+        assert_eq!(outcome.map(Outcome::exit_code), Some(15));
+
+        let outcome = once(MainArg { max_value: Some(10), ..MainArg::default() }, 11)?;
+        assert_eq!(outcome, Some(Outcome::Rejected(DeadLetter { value: 11, reason: Reason::AboveMaxValue })));
+        assert_eq!(outcome.map(Outcome::exit_code), Some(2));
+
+        // under --rule the status is the product of the matched divisors
+        let rules = vec!["3=Fizz".to_string(), "7=Bazz".to_string()];
+        let outcome = once(MainArg { rules: rules.clone(), ..MainArg::default() }, 42)?;
+        assert_eq!(outcome.map(Outcome::exit_code), Some(21));
+        let outcome = once(MainArg { rules, ..MainArg::default() }, 5)?;
+        assert_eq!(outcome.map(Outcome::exit_code), Some(0));
+        Ok(())
+    }
+}
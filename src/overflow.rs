@@ -0,0 +1,64 @@
+use std::collections::VecDeque;
+
+/// Bounded holding area in front of a full channel, for `--overflow-policy drop-oldest`.
+/// A producer which must not wait parks values here while the channel is full; past
+/// `limit` the oldest held value is evicted, so what eventually goes out is the freshest.
+pub(crate) struct DropOldest<T> {
+    held: VecDeque<T>,
+    limit: usize,
+    dropped: u64,
+}
+
+impl<T> DropOldest<T> {
+    pub(crate) fn new(limit: usize) -> Self {
+        let limit = limit.max(1);
+        DropOldest { held: VecDeque::with_capacity(limit), limit, dropped: 0 }
+    }
+
+    /// Holds `item`, evicting and counting the oldest held value when already at the limit.
+    pub(crate) fn push(&mut self, item: T) {
+        if self.held.len() == self.limit {
+            self.held.pop_front();
+            self.dropped += 1;
+        }
+        self.held.push_back(item);
+    }
+
+    /// Removes up to `count` held values, oldest first.
+    pub(crate) fn take(&mut self, count: usize) -> impl Iterator<Item = T> + '_ {
+        let count = count.min(self.held.len());
+        self.held.drain(..count)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.held.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.held.is_empty()
+    }
+
+    /// Values evicted so far.
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod overflow_tests {
+    use super::*;
+
+    #[test]
+    fn test_drop_oldest() {
+        let mut overflow = DropOldest::new(3);
+        (0..5).for_each(|value| overflow.push(value));
+        // 0 and 1 made way for 3 and 4
+        assert_eq!(overflow.dropped(), 2);
+        assert_eq!(overflow.len(), 3);
+        assert_eq!(overflow.take(2).collect::<Vec<_>>(), vec![2, 3]);
+        overflow.push(5);
+        assert_eq!(overflow.take(10).collect::<Vec<_>>(), vec![4, 5]);
+        assert!(overflow.is_empty());
+        assert_eq!(overflow.dropped(), 2);
+    }
+}
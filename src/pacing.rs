@@ -0,0 +1,73 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// How a replay spaces recorded messages in time.
+/// Reproducing the original temporal pattern matters for time sensitive bugs
+/// such as alert flapping, while `max` is best for fast regression runs.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum ReplayPacing {
+    /// Wait the recorded gap between consecutive messages.
+    Original,
+    /// Send as fast as downstream accepts.
+    Max,
+    /// Run at a multiple of the original speed, `x2` halves every gap.
+    Factor(f64),
+}
+
+impl ReplayPacing {
+    /// Delay to apply before a message which was recorded `recorded_gap` after its predecessor.
+    pub(crate) fn delay(&self, recorded_gap: Duration) -> Duration {
+        match self {
+            ReplayPacing::Original => recorded_gap,
+            ReplayPacing::Max => Duration::ZERO,
+            ReplayPacing::Factor(factor) => recorded_gap.div_f64(*factor),
+        }
+    }
+}
+
+impl FromStr for ReplayPacing {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text {
+            "original" => Ok(ReplayPacing::Original),
+            "max" => Ok(ReplayPacing::Max),
+            _ => text.strip_prefix('x')
+                     .and_then(|factor| factor.parse::<f64>().ok())
+                     .filter(|factor| factor.is_finite() && *factor > 0.0)
+                     .map(ReplayPacing::Factor)
+                     .ok_or_else(|| format!("expected original, max or x<factor> but found '{}'", text)),
+        }
+    }
+}
+
+impl fmt::Display for ReplayPacing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayPacing::Original => write!(f, "original"),
+            ReplayPacing::Max => write!(f, "max"),
+            ReplayPacing::Factor(factor) => write!(f, "x{}", factor),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod pacing_tests {
+    use super::*;
+
+    #[test]
+    fn test_replay_pacing() {
+        assert_eq!("original".parse(), Ok(ReplayPacing::Original));
+        assert_eq!("max".parse(), Ok(ReplayPacing::Max));
+        assert_eq!("x2.5".parse(), Ok(ReplayPacing::Factor(2.5)));
+        assert!("x0".parse::<ReplayPacing>().is_err());
+        assert!("fast".parse::<ReplayPacing>().is_err());
+
+        let gap = Duration::from_millis(100);
+        assert_eq!(ReplayPacing::Original.delay(gap), gap);
+        assert_eq!(ReplayPacing::Max.delay(gap), Duration::ZERO);
+        assert_eq!(ReplayPacing::Factor(2.0).delay(gap), Duration::from_millis(50));
+        assert_eq!(ReplayPacing::Factor(0.5).delay(gap), Duration::from_millis(200));
+    }
+}
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+use crate::arg::PartitionStrategy;
+
+/// Assigns every value to one of a fixed number of partitions.
+/// The key is extracted from the value first and only the key decides the partition,
+/// so values sharing a key always land together.
+pub(crate) trait Partitioner: Send + Sync {
+    /// Key the value is partitioned by, the value itself unless overridden.
+    fn key(&self, value: u64) -> u64 {
+        value
+    }
+
+    /// Partition in `0..count` owning `key`.
+    fn partition(&self, key: u64) -> u64;
+
+    fn partition_of(&self, value: u64) -> u64 {
+        self.partition(self.key(value))
+    }
+}
+
+/// Maps a 64 bit hash onto `0..count` using its high bits, without the bias of `%`.
+fn reduce(hash: u64, count: u64) -> u64 {
+    ((hash as u128 * count as u128) >> 64) as u64
+}
+
+/// SplitMix64 finalizer: spreads nearby inputs over the whole 64 bit range.
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// `key % count`: perfectly even over sequential keys, but changing the count moves almost every key.
+pub(crate) struct Modulo {
+    count: u64,
+}
+
+impl Partitioner for Modulo {
+    fn partition(&self, key: u64) -> u64 {
+        key % self.count
+    }
+}
+
+/// The single-word FxHash used by rustc: one multiply, cheap but weak on patterned keys.
+pub(crate) struct FxHash {
+    count: u64,
+}
+
+const FX_SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+impl Partitioner for FxHash {
+    fn partition(&self, key: u64) -> u64 {
+        reduce(key.wrapping_mul(FX_SEED), self.count)
+    }
+}
+
+/// Consistent hashing on a ring of `virtual_nodes` points per partition.
+/// A key belongs to the first point at or after its hash, so adding a partition
+/// only takes over the keys just before its own points, about 1/count of them.
+pub(crate) struct ConsistentHash {
+    /// (point, partition) sorted by point
+    ring: Vec<(u64, u64)>,
+}
+
+impl ConsistentHash {
+    pub(crate) fn new(count: u64, virtual_nodes: u64) -> Self {
+        let mut ring: Vec<(u64, u64)> = (0..count)
+            .flat_map(|partition| (0..virtual_nodes).map(move |node| (mix((partition << 32) | node), partition)))
+            .collect();
+        ring.sort_unstable();
+        ConsistentHash { ring }
+    }
+}
+
+impl Partitioner for ConsistentHash {
+    fn partition(&self, key: u64) -> u64 {
+        let hash = mix(key);
+        let point = self.ring.partition_point(|(point, _)| *point < hash);
+        // past the last point the ring wraps around to the first
+        self.ring.get(point).or(self.ring.first()).map_or(0, |(_, partition)| *partition)
+    }
+}
+
+/// Partitioner for `count` partitions using the configured strategy.
+pub(crate) fn build(strategy: PartitionStrategy, count: u64, virtual_nodes: u64) -> Arc<dyn Partitioner> {
+    let count = count.max(1);
+    match strategy {
+        PartitionStrategy::Modulo => Arc::new(Modulo { count }),
+        PartitionStrategy::Fxhash => Arc::new(FxHash { count }),
+        PartitionStrategy::Consistent => Arc::new(ConsistentHash::new(count, virtual_nodes.max(1))),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod partitioner_tests {
+    use super::*;
+
+    const STRATEGIES: [PartitionStrategy; 3] = [PartitionStrategy::Modulo, PartitionStrategy::Fxhash, PartitionStrategy::Consistent];
+
+    #[test]
+    fn test_distribution_uniformity() {
+        let (count, keys) = (8, 80_000u64);
+        for strategy in STRATEGIES {
+            let partitioner = build(strategy, count, 256);
+            let mut sizes = vec![0u64; count as usize];
+            for key in 0..keys {
+                sizes[partitioner.partition_of(key) as usize] += 1;
+            }
+            // every partition within 20% of an even share
+            let even = keys / count;
+            for size in sizes {
+                assert!(size.abs_diff(even) < even / 5, "{:?} uneven: {} vs {}", strategy, size, even);
+            }
+        }
+    }
+
+    #[test]
+    fn test_per_key_stability() {
+        for strategy in STRATEGIES {
+            // rebuilding the partitioner gives every key the same partition
+            let (first, second) = (build(strategy, 5, 64), build(strategy, 5, 64));
+            for key in (0..10_000).map(|k| k * 7919) {
+                assert_eq!(first.partition_of(key), second.partition_of(key), "{:?}", strategy);
+            }
+        }
+    }
+
+    #[test]
+    fn test_rebalance_moves() {
+        // growing from 4 to 5 partitions: consistent hashing moves roughly a fifth of the keys
+        let moved = |strategy| {
+            let (before, after) = (build(strategy, 4, 256), build(strategy, 5, 256));
+            (0..10_000u64).filter(|key| before.partition_of(*key) != after.partition_of(*key)).count()
+        };
+        assert!(moved(PartitionStrategy::Consistent) < 3_000);
+        assert!(moved(PartitionStrategy::Modulo) > 7_000);
+    }
+}
@@ -0,0 +1,111 @@
+use steady_state::*;
+use crate::arg::MainArg;
+use crate::bench;
+use crate::calibration::Calibration;
+use crate::format::Locale;
+
+/// Most workers the planner will recommend before calling the target unreachable.
+const MAX_WORKERS: u64 = 1024;
+/// ln(100): the 99th percentile of an exponential distribution in units of its mean.
+const P99_FACTOR: f64 = 4.605;
+
+/// Settings recommended for a throughput and latency target.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) struct Plan {
+    pub(crate) workers: u64,
+    pub(crate) channel_capacity: usize,
+    pub(crate) batch_size: usize,
+    /// Expected busy share of each worker.
+    pub(crate) utilization: f64,
+    /// Expected 99th percentile time a message spends queued and processed.
+    pub(crate) p99: Duration,
+}
+
+/// Plans for `throughput` messages per second with a 99th percentile latency of `p99`.
+/// Every worker is modelled as an M/M/1 queue served at the calibrated per-message cost,
+/// whose time in system is exponential with rate `service - arrival`; workers are added
+/// until that p99 fits the target. None when no count up to `MAX_WORKERS` gets there.
+pub(crate) fn plan(calibration: &Calibration, throughput: f64, p99: Duration) -> Option<Plan> {
+    let service = 1.0 / calibration.per_message().as_secs_f64();
+    let target = p99.as_secs_f64();
+    (1..=MAX_WORKERS).find_map(|workers| {
+        let arrival = throughput / workers as f64;
+        if arrival >= service {
+            return None;
+        }
+        let expected = P99_FACTOR / (service - arrival);
+        if expected > target {
+            return None;
+        }
+        // Little's law: the messages arriving within one target latency fit in the channel
+        let channel_capacity = ((arrival * target).ceil() as usize).clamp(16, 65536).next_power_of_two();
+        // one batch costs at most a quarter of the latency budget
+        let batch_size = ((target / 4.0 * service).round() as usize).clamp(1, calibration.batch_size());
+        Some(Plan { workers, channel_capacity, batch_size, utilization: arrival / service
+                  , p99: Duration::from_secs_f64(expected) })
+    })
+}
+
+/// Report of the recommendation with the flags that apply it.
+pub(crate) fn report(locale: Locale, plan: &Plan) -> String {
+    format!("Plan workers {} channel capacity {} batch size {} utilization {}% expected p99 {}\n\
+             Apply with: --pipelines {} --channel-capacity {} --batch-size {}"
+            , plan.workers, plan.channel_capacity, plan.batch_size, locale.decimal(plan.utilization * 100.0, 1)
+            , locale.duration(plan.p99), plan.workers, plan.channel_capacity, plan.batch_size)
+}
+
+/// Calibrates (unless --calibrate already did), prints a plan for the targets and with
+/// `validate` benchmarks the recommended worker count for `seconds` to check the throughput.
+pub(crate) fn run(args: MainArg, throughput: u64, p99_ms: u64, validate: bool, seconds: u64) -> Result<(), Box<dyn Error>> {
+    let calibration = match args.calibration {
+        Some(calibration) => calibration,
+        None => Calibration::measure()?,
+    };
+    println!("{}", calibration.report(&args));
+    let Some(plan) = plan(&calibration, throughput as f64, Duration::from_millis(p99_ms)) else {
+        return Err(format!("no plan up to {} workers reaches {}/s at p99 {} ms", MAX_WORKERS, throughput, p99_ms).into());
+    };
+    let locale = args.locale;
+    println!("{}", report(locale, &plan));
+    if validate {
+        let mut bench_args = args.clone();
+        bench_args.channel_capacity = plan.channel_capacity;
+        bench_args.batch_size = plan.batch_size;
+        let (messages, elapsed) = bench::measure(bench_args, Duration::from_secs(seconds), plan.workers as usize)?;
+        println!("{}", bench::report(locale, plan.workers as usize, messages, elapsed));
+        let measured = messages as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        let verdict = if measured >= throughput as f64 { "meets" } else { "misses" };
+        println!("Validation {} the target of {}/s", verdict, locale.number(throughput));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod planner_tests {
+    use super::*;
+
+    #[test]
+    fn test_plan() {
+        // 2µs per message: each worker serves 500,000/s
+        let calibration = Calibration { classify: Duration::from_micros(1), write: Duration::from_micros(1) };
+        let plan = plan(&calibration, 1_000_000.0, Duration::from_millis(1)).expect("reachable");
+        // two workers would run at 100%, three leave room for the latency target
+        assert_eq!(plan.workers, 3);
+        assert_eq!(plan.channel_capacity, 512); // 334 messages arrive per worker in 1 ms
+        assert_eq!(plan.batch_size, 125); // 250µs of work
+        assert!((plan.utilization - 2.0 / 3.0).abs() < 1e-9);
+        assert!(plan.p99 < Duration::from_micros(30));
+
+        // a tighter latency target needs more headroom per worker
+        let tight = super::plan(&calibration, 1_000_000.0, Duration::from_micros(20)).expect("reachable");
+        assert!(tight.workers > plan.workers);
+        assert!(tight.p99 <= Duration::from_micros(20));
+    }
+
+    #[test]
+    fn test_plan_unreachable() {
+        // no queue gets a 1ms message through with a 1ms p99
+        let slow = Calibration { classify: Duration::from_millis(1), write: Duration::ZERO };
+        assert_eq!(plan(&slow, 10.0, Duration::from_millis(1)), None);
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+/// Primes handed out before the sieve proper starts at 9.
+const SMALL_PRIMES: [u64; 4] = [2, 3, 5, 7];
+
+/// Incremental sieve of Eratosthenes producing the primes in order, without an upper bound.
+/// Only odd candidates are examined, and a base prime only starts crossing off multiples
+/// once the candidates reach its square. The base primes come from a nested sieve of
+/// their own, so the memory is one entry per prime up to the square root of the candidate.
+/// Everything is plain data, so the whole sieve lives in `GeneratorState` and a restart
+/// continues with the next prime instead of sieving again from 2.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub(crate) struct PrimeSieve {
+    /// How many of `SMALL_PRIMES` were handed out.
+    small: usize,
+    /// Next odd number to examine.
+    candidate: u64,
+    /// Next odd multiple still to be crossed off, with the step to the one after it.
+    composites: BTreeMap<u64, u64>,
+    /// Source of base primes, created once the small primes are used up.
+    base: Option<Box<PrimeSieve>>,
+    /// Next base prime to activate, it starts crossing off at its square.
+    base_prime: u64,
+    base_square: u64,
+    /// Prime returned by `peek` which has not been consumed yet.
+    peeked: Option<u64>,
+}
+
+impl PrimeSieve {
+    /// The next prime, the same one until `consume`; None once past u64.
+    pub(crate) fn peek(&mut self) -> Option<u64> {
+        if self.peeked.is_none() {
+            self.peeked = self.advance();
+        }
+        self.peeked
+    }
+
+    pub(crate) fn consume(&mut self) {
+        self.peeked = None;
+    }
+
+    fn advance(&mut self) -> Option<u64> {
+        if let Some(prime) = SMALL_PRIMES.get(self.small) {
+            self.small += 1;
+            return Some(*prime);
+        }
+        if self.base.is_none() {
+            // the base sieve skips 2, as even candidates are never examined
+            let mut base = PrimeSieve::default();
+            base.advance();
+            self.base_prime = base.advance()?;
+            self.base_square = self.base_prime * self.base_prime;
+            self.candidate = self.base_square;
+            self.base = Some(Box::new(base));
+        }
+        loop {
+            let candidate = self.candidate;
+            self.candidate = candidate.checked_add(2)?;
+            let step = match self.composites.remove(&candidate) {
+                Some(step) => step,
+                None if candidate < self.base_square => return Some(candidate),
+                None => {
+                    // the square of the next base prime: it joins the crossing off from here
+                    let step = 2 * self.base_prime;
+                    self.base_prime = self.base.as_mut()?.advance()?;
+                    self.base_square = self.base_prime.checked_mul(self.base_prime).unwrap_or(u64::MAX);
+                    step
+                }
+            };
+            let mut multiple = candidate.checked_add(step)?;
+            while self.composites.contains_key(&multiple) {
+                multiple = multiple.checked_add(step)?;
+            }
+            self.composites.insert(multiple, step);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod primes_tests {
+    use super::*;
+
+    fn take(sieve: &mut PrimeSieve, count: usize) -> Vec<u64> {
+        (0..count).map(|_| {
+            let prime = sieve.peek().expect("prime");
+            sieve.consume();
+            prime
+        }).collect()
+    }
+
+    #[test]
+    fn test_prime_sieve() {
+        let mut sieve = PrimeSieve::default();
+        assert_eq!(take(&mut sieve, 15), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47]);
+        // peeking twice does not skip a prime
+        assert_eq!(sieve.peek(), Some(53));
+        assert_eq!(sieve.peek(), Some(53));
+
+        // against trial division over the first ten thousand primes
+        let primes = take(&mut PrimeSieve::default(), 10_000);
+        let is_prime = |n: u64| n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| n % d != 0);
+        assert!(primes.iter().all(|p| is_prime(*p)));
+        assert_eq!(primes.last(), Some(&104_729));
+    }
+
+    #[test]
+    fn test_prime_sieve_resumes_from_json() -> Result<(), serde_json::Error> {
+        let mut sieve = PrimeSieve::default();
+        take(&mut sieve, 1000);
+        sieve.peek();
+        // a restart reads the state back and continues with the prime it had peeked
+        let mut restored: PrimeSieve = serde_json::from_str(&serde_json::to_string(&sieve)?)?;
+        assert_eq!(restored, sieve);
+        assert_eq!(take(&mut restored, 3), take(&mut sieve, 3));
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::JoinHandle;
+use steady_state::*;
+
+/// One Prometheus sample, e.g. `avg_mCPU{actor_name="worker"} 17`.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Sample {
+    pub(crate) name: String,
+    pub(crate) labels: BTreeMap<String, String>,
+    pub(crate) value: f64,
+}
+
+/// Everything read from the telemetry server at one instant.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct Snapshot {
+    pub(crate) samples: Vec<Sample>,
+    /// Actors and channels drawn red in the DOT graph, channels as `from -> to`.
+    pub(crate) red: Vec<String>,
+}
+
+/// Recorder and test-facing assertions over live telemetry.
+/// The probe samples the same `/metrics` and `/graph.dot` endpoints a person would
+/// watch on the dashboard, keeps every snapshot, and asserts over the whole run,
+/// so health and performance regressions fail an ordinary `cargo test`.
+/// Outside tests it records in the background to feed the shutdown report.
+pub(crate) struct TelemetryProbe {
+    addr: String,
+    snapshots: Vec<Snapshot>,
+}
+
+impl TelemetryProbe {
+    pub(crate) fn new(addr: impl Into<String>) -> Self {
+        TelemetryProbe { addr: addr.into(), snapshots: Vec::new() }
+    }
+
+    /// Takes one snapshot now.
+    pub(crate) fn sample(&mut self) -> io::Result<()> {
+        let metrics = http_get(&self.addr, "/metrics")?;
+        let dot = http_get(&self.addr, "/graph.dot")?;
+        self.snapshots.push(Snapshot { samples: parse_metrics(&metrics), red: parse_red(&dot) });
+        Ok(())
+    }
+
+    /// Samples every `interval` on a background thread until [`Recording::finish`].
+    /// Failed samples are skipped, the telemetry server may start after the recorder.
+    pub(crate) fn record(mut self, interval: Duration) -> Recording {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopping = stop.clone();
+        let thread = std::thread::spawn(move || {
+            while !stopping.load(Ordering::Relaxed) {
+                let _ = self.sample();
+                std::thread::sleep(interval);
+            }
+            self
+        });
+        Recording { stop, thread }
+    }
+
+    /// Largest value seen for `metric` on samples carrying `label="value"`.
+    #[cfg(test)]
+    pub(crate) fn max(&self, metric: &str, label: &str, value: &str) -> Option<f64> {
+        self.snapshots.iter()
+            .flat_map(|snapshot| snapshot.samples.iter())
+            .filter(|s| s.name == metric && s.labels.get(label).map(String::as_str) == Some(value))
+            .map(|s| s.value)
+            .reduce(f64::max)
+    }
+
+    /// Fails if the actor's average mCPU ever reached `limit` during the run.
+    #[cfg(test)]
+    pub(crate) fn assert_actor_mcpu_below(&self, actor_name: &str, limit: f64) {
+        let max = self.max("avg_mCPU", "actor_name", actor_name)
+            .unwrap_or_else(|| panic!("no avg_mCPU samples for actor {}", actor_name));
+        assert!(max < limit, "actor {} reached {} mCPU, limit {}", actor_name, max, limit);
+    }
+
+    /// Fails if any actor or channel was drawn red in any snapshot.
+    #[cfg(test)]
+    pub(crate) fn assert_no_red(&self) {
+        assert!(!self.snapshots.is_empty(), "no telemetry snapshots were taken");
+        let red: Vec<&String> = self.snapshots.iter().flat_map(|s| s.red.iter()).collect();
+        assert!(red.is_empty(), "entered red: {:?}", red);
+    }
+}
+
+/// Telemetry being recorded in the background.
+pub(crate) struct Recording {
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<TelemetryProbe>,
+}
+
+impl Recording {
+    /// Stops sampling and returns every snapshot taken.
+    pub(crate) fn finish(self) -> Vec<Snapshot> {
+        self.stop.store(true, Ordering::Relaxed);
+        self.thread.join().map(|probe| probe.snapshots).unwrap_or_default()
+    }
+}
+
+/// Parses the Prometheus text format as served by the telemetry server.
+pub(crate) fn parse_metrics(text: &str) -> Vec<Sample> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let (series, value) = line.rsplit_once(' ')?;
+            let value = value.parse().ok()?;
+            let (name, labels) = match series.split_once('{') {
+                Some((name, rest)) => (name, parse_labels(rest.strip_suffix('}')?)),
+                None => (series, BTreeMap::new()),
+            };
+            Some(Sample { name: name.to_string(), labels, value })
+        })
+        .collect()
+}
+
+fn parse_labels(text: &str) -> BTreeMap<String, String> {
+    text.split(',')
+        .filter_map(|pair| pair.trim().split_once('='))
+        .map(|(key, value)| (key.to_string(), value.trim_matches('"').to_string()))
+        .collect()
+}
+
+/// Names of DOT nodes and edges whose attributes include `color=red`.
+/// Labels span several lines, so the element is remembered from the line which opens it.
+pub(crate) fn parse_red(dot: &str) -> Vec<String> {
+    let mut red = Vec::new();
+    let mut current: Option<String> = None;
+    for line in dot.lines() {
+        if line.starts_with('"') {
+            if let Some((element, _)) = line.split_once(" [") {
+                current = Some(element.replace('"', ""));
+            }
+        }
+        if line.contains("color=red") {
+            if let Some(element) = current.take() {
+                red.push(element);
+            }
+        }
+    }
+    red
+}
+
+fn http_get(addr: &str, path: &str) -> io::Result<String> {
+    let mut stream = TcpStream::connect(addr)?;
+    stream.set_read_timeout(Some(Duration::from_secs(1)))?;
+    write!(stream, "GET {} HTTP/1.0\r\nHost: {}\r\n\r\n", path, addr)?;
+    let mut response = String::new();
+    stream.read_to_string(&mut response)?;
+    Ok(response.split_once("\r\n\r\n").map(|(_, body)| body.to_string()).unwrap_or_default())
+}
+
+#[cfg(test)]
+pub(crate) mod probe_tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_telemetry() {
+        let samples = parse_metrics("avg_mCPU{actor_name=\"worker\"} 17\n\
+                                     percentile_filled{from=\"generator\", to=\"worker\", p=8000} 100\n\
+                                     # comment\nbogus line\n");
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].name, "avg_mCPU");
+        assert_eq!(samples[0].labels.get("actor_name").map(String::as_str), Some("worker"));
+        assert_eq!(samples[1].labels.get("p").map(String::as_str), Some("8000"));
+        assert_eq!(samples[1].value, 100.0);
+
+        let dot = "\"worker\" [label=\"worker\nAvg mCPU: 0000 \n\", color=grey, penwidth=3 ];\n\
+                   \"generator\" -> \"worker\" [label=\"Window 10.2 secs\nfilled 80%ile 100 %\n\", color=red, penwidth=1];\n";
+        assert_eq!(parse_red(dot), vec!["generator -> worker".to_string()]);
+
+        let probe = TelemetryProbe {
+            addr: String::new(),
+            snapshots: vec![Snapshot { samples, red: Vec::new() }],
+        };
+        assert_eq!(probe.max("avg_mCPU", "actor_name", "worker"), Some(17.0));
+        probe.assert_actor_mcpu_below("worker", 100.0);
+        probe.assert_no_red();
+    }
+}
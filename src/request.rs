@@ -0,0 +1,131 @@
+use std::time::Instant;
+use steady_state::*;
+
+/// Messages carrying the id of the request they belong to.
+/// A server copies the id from each request into its reply.
+pub(crate) trait Correlated {
+    fn correlation_id(&self) -> u64;
+}
+
+/// Why a request produced no reply.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum RequestError {
+    /// The request channel was full, nothing was sent.
+    Full,
+    /// No matching reply arrived in time, a reply arriving later is discarded.
+    Timeout,
+}
+
+/// Request/reply over a pair of steady channels with a timeout.
+/// Each request gets a fresh id, and replies carrying any other id are late answers to
+/// requests which already timed out; they are dropped, which is how a timed out request
+/// is cancelled without a separate cancel message.
+pub(crate) struct RequestClient {
+    next_id: u64,
+}
+
+impl RequestClient {
+    pub(crate) fn new() -> Self {
+        RequestClient { next_id: 0 }
+    }
+
+    /// Sends the request built from a new id and waits up to `timeout` for its reply.
+    pub(crate) async fn call<A: SteadyActor, Q, R: Correlated>(&mut self, actor: &mut A
+                                                               , tx: &mut Tx<Q>, rx: &mut Rx<R>
+                                                               , timeout: Duration
+                                                               , request: impl FnOnce(u64) -> Q) -> Result<R, RequestError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        if !actor.try_send(tx, request(id)).is_sent() {
+            return Err(RequestError::Full);
+        }
+        // the deadline bounds a real wait, so it is measured on the system clock
+        let deadline = Instant::now() + timeout;
+        loop {
+            while let Some(reply) = actor.try_take(rx) {
+                if reply.correlation_id() == id {
+                    return Ok(reply);
+                }
+                trace!("Discarding late reply {} while waiting for {}", reply.correlation_id(), id);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(RequestError::Timeout);
+            }
+            await_for_any!(actor.wait_avail(rx, 1), actor.wait(remaining));
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod request_tests {
+    use std::sync::{Arc, Mutex};
+    use crate::arg::MainArg;
+    use super::*;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+    struct Echo {
+        id: u64,
+        value: u64,
+    }
+
+    impl Correlated for Echo {
+        fn correlation_id(&self) -> u64 {
+            self.id
+        }
+    }
+
+    /// Answers every request, but holds the first reply past the client's timeout.
+    async fn slow_server(actor: SteadyActorShadow, rx: SteadyRx<Echo>, tx: SteadyTx<Echo>) -> Result<(),Box<dyn Error>> {
+        let mut actor = actor.into_spotlight([&rx], [&tx]);
+        let mut rx = rx.lock().await;
+        let mut tx = tx.lock().await;
+        while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+            await_for_all!(actor.wait_avail(&mut rx, 1));
+            while let Some(echo) = actor.try_take(&mut rx) {
+                if echo.id == 0 {
+                    actor.wait(Duration::from_millis(300)).await;
+                }
+                actor.send_async(&mut tx, echo, SendSaturation::AwaitForRoom).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn client(actor: SteadyActorShadow, tx: SteadyTx<Echo>, rx: SteadyRx<Echo>
+                    , results: Arc<Mutex<Vec<Result<Echo, RequestError>>>>) -> Result<(),Box<dyn Error>> {
+        let mut actor = actor.into_spotlight([&rx], [&tx]);
+        let mut tx = tx.lock().await;
+        let mut rx = rx.lock().await;
+        let mut requests = RequestClient::new();
+        let first = requests.call(&mut actor, &mut tx, &mut rx, Duration::from_millis(100), |id| Echo { id, value: 10 }).await;
+        let second = requests.call(&mut actor, &mut tx, &mut rx, Duration::from_secs(1), |id| Echo { id, value: 20 }).await;
+        results.lock().expect("results lock").extend([first, second]);
+        actor.request_shutdown().await;
+        while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+            while actor.try_take(&mut rx).is_some() {}
+            await_for_any!(actor.wait_avail(&mut rx, 1), actor.wait_shutdown());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_timeout_ignores_late_reply() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let (query_tx, query_rx) = graph.channel_builder().build();
+        let (reply_tx, reply_rx) = graph.channel_builder().build();
+        let results = Arc::new(Mutex::new(Vec::new()));
+        let client_results = results.clone();
+
+        graph.actor_builder().with_name("Server")
+            .build(move |actor| slow_server(actor, query_rx.clone(), reply_tx.clone()), SoloAct);
+        graph.actor_builder().with_name("Client")
+            .build(move |actor| client(actor, query_tx.clone(), reply_rx.clone(), client_results.clone()), SoloAct);
+
+        graph.start();
+        graph.block_until_stopped(Duration::from_secs(3))?;
+        // the late answer to request 0 arrives first during the second call and is skipped
+        assert_eq!(*results.lock().expect("results lock"), vec![Err(RequestError::Timeout), Ok(Echo { id: 1, value: 20 })]);
+        Ok(())
+    }
+}
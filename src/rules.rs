@@ -0,0 +1,342 @@
+use std::fmt;
+use std::sync::{Arc, LazyLock, PoisonError, RwLock};
+use crate::actor::worker::{FizzBuzz, FizzBuzzMessage, Transform};
+use crate::arg::MainArg;
+
+/// Most rules one rule set holds, a value's matches are kept as one bit per rule.
+pub(crate) const MAX_RULES: usize = 64;
+
+/// Most distinct rule sets one run classifies with, the startup rules included.
+pub(crate) const MAX_RULE_SETS: usize = 1024;
+
+/// Every rule set a worker has classified with, the classic 3=Fizz and 5=Buzz first.
+/// Labels name their rules by index here so every message stays `Copy`, and a set sent
+/// again is found rather than kept twice.
+static REGISTRY: LazyLock<RwLock<Vec<Arc<[Rule]>>>> = LazyLock::new(|| RwLock::new(vec![Arc::from(vec![
+    Rule { divisor: 3, label: "Fizz".to_string() },
+    Rule { divisor: 5, label: "Buzz".to_string() },
+])]));
+
+fn registered(id: u32) -> Arc<[Rule]> {
+    REGISTRY.read().unwrap_or_else(PoisonError::into_inner)[id as usize].clone()
+}
+
+/// `3=Fizz 7=Bazz`, the settings as given.
+fn write_rules(f: &mut fmt::Formatter<'_>, rules: &[Rule]) -> fmt::Result {
+    for (index, rule) in rules.iter().enumerate() {
+        let separator = if index == 0 { "" } else { " " };
+        write!(f, "{}{}={}", separator, rule.divisor, rule.label)?;
+    }
+    Ok(())
+}
+
+/// One divisor→label mapping, `--rule 7=Bazz`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Rule {
+    pub(crate) divisor: u64,
+    pub(crate) label: String,
+}
+
+impl Rule {
+    fn parse(setting: &str) -> Result<Self, String> {
+        let (divisor, label) = setting.split_once('=').ok_or_else(|| format!("expected divisor=label, got {}", setting))?;
+        let divisor = divisor.trim().parse::<u64>().map_err(|_| format!("rule divisor must be a number, got {}", divisor))?;
+        if divisor == 0 {
+            return Err(format!("rule divisor must be above zero, got {}", setting));
+        }
+        let label = label.trim();
+        if label.is_empty() {
+            return Err(format!("rule {} has an empty label", setting));
+        }
+        Ok(Rule { divisor, label: label.to_string() })
+    }
+}
+
+/// The `--rule` settings in the order given, which is also the order of the labels,
+/// registered so every `Classified` can name them by `id`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Rules {
+    id: u32,
+    rules: Arc<[Rule]>,
+}
+
+impl Rules {
+    /// Checks the settings without keeping them, for startup validation.
+    pub(crate) fn parse(settings: &[String]) -> Result<Vec<Rule>, String> {
+        if settings.len() > MAX_RULES {
+            return Err(format!("at most {} rules, got {}", MAX_RULES, settings.len()));
+        }
+        settings.iter().map(|setting| Rule::parse(setting)).collect()
+    }
+
+    /// None without any rule, the worker then keeps the classic FizzBuzz classification.
+    pub(crate) fn from_settings(settings: &[String]) -> Result<Option<Self>, String> {
+        let rules = Self::parse(settings)?;
+        if rules.is_empty() {
+            return Ok(None);
+        }
+        Self::register(Arc::from(rules)).map(Some)
+    }
+
+    /// The registered set with these rules, registering them if no worker used them yet.
+    pub(crate) fn register(rules: Arc<[Rule]>) -> Result<Self, String> {
+        let mut registry = REGISTRY.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some(id) = registry.iter().position(|known| *known == rules) {
+            return Ok(Rules { id: id as u32, rules: registry[id].clone() });
+        }
+        if registry.len() >= MAX_RULE_SETS {
+            return Err(format!("at most {} rule sets in one run", MAX_RULE_SETS));
+        }
+        registry.push(rules.clone());
+        Ok(Rules { id: (registry.len() - 1) as u32, rules })
+    }
+
+    /// The `--rule` settings, else the classic rules under --keep-values, else None.
+    pub(crate) fn from_args(args: &MainArg) -> Result<Option<Self>, String> {
+        Ok(Self::from_settings(&args.rules)?.or_else(|| args.keep_values.then(Self::classic)))
+    }
+
+    pub(crate) fn classic() -> Self {
+        Rules { id: 0, rules: registered(0) }
+    }
+
+    pub(crate) fn classify(&self, value: u64) -> Classified {
+        Classified { value, rules: self.id }
+    }
+}
+
+impl Transform<u64, FizzBuzzMessage> for Rules {
+    fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+        FizzBuzzMessage::Classified(self.classify(value))
+    }
+}
+
+impl fmt::Display for Rules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_rules(f, &self.rules)
+    }
+}
+
+/// Rules sent to the workers while the graph runs, None for the bare classic kinds.
+/// Only parsed, a set is registered by the worker taking it over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct RuleSet(pub(crate) Option<Arc<[Rule]>>);
+
+impl RuleSet {
+    pub(crate) fn from_settings(settings: &[String]) -> Result<Self, String> {
+        let rules = Rules::parse(settings)?;
+        Ok(RuleSet((!rules.is_empty()).then(|| Arc::from(rules))))
+    }
+}
+
+impl fmt::Display for RuleSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(rules) => write_rules(f, rules),
+            None => f.write_str("classic"),
+        }
+    }
+}
+
+/// The worker's classification while it runs: rules, or None for the bare classic kinds.
+/// A set taken from the worker's rules channel is registered and takes over from the next batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct ActiveRules(pub(crate) Option<Rules>);
+
+impl Transform<u64, FizzBuzzMessage> for ActiveRules {
+    fn apply(&mut self, value: u64) -> FizzBuzzMessage {
+        match &mut self.0 {
+            Some(rules) => rules.apply(value),
+            None => FizzBuzz.apply(value),
+        }
+    }
+
+    fn reload(&mut self, rules: &RuleSet) -> Result<(), String> {
+        self.0 = rules.0.clone().map(Rules::register).transpose()?;
+        Ok(())
+    }
+}
+
+impl fmt::Display for ActiveRules {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.0 {
+            Some(rules) => rules.fmt(f),
+            None => f.write_str("classic"),
+        }
+    }
+}
+
+/// A value and the id of the registered rule set it was classified under, its labels those
+/// of every rule whose divisor divides it. Only 12 bytes at 4-byte alignment, so a message
+/// holding one is no bigger than one holding a bare `Value`.
+#[derive(Copy, Clone, PartialEq, Eq)]
+#[repr(C, packed(4))]
+pub(crate) struct Classified {
+    value: u64,
+    rules: u32,
+}
+
+impl Classified {
+    pub(crate) fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// Found again from the value and the registered set, rather than carried in the message.
+    pub(crate) fn labels(&self) -> Labels {
+        let value = self.value;
+        let matched = registered(self.rules).iter().enumerate()
+            .filter(|(_, rule)| value % rule.divisor == 0)
+            .fold(0u64, |matched, (index, _)| matched | (1 << index));
+        Labels { rules: self.rules, matched }
+    }
+}
+
+/// The value and what it was classified as, `15 -> FizzBuzz`, a value no rule matched
+/// standing for itself, `7 -> 7`.
+impl fmt::Display for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let labels = self.labels();
+        if labels.is_empty() {
+            write!(f, "{} -> {}", self.value(), self.value())
+        } else {
+            write!(f, "{} -> {}", self.value(), labels)
+        }
+    }
+}
+
+impl fmt::Debug for Classified {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Classified").field("value", &self.value()).field("labels", &self.labels()).finish()
+    }
+}
+
+/// The rules one value matched, in rule order, by the id of their registered set.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub(crate) struct Labels {
+    rules: u32,
+    matched: u64,
+}
+
+/// The rules of `rules` whose bit is set in `matched`.
+fn matches(rules: &[Rule], matched: u64) -> impl Iterator<Item = &Rule> {
+    rules.iter().enumerate().filter(move |(index, _)| matched & (1 << index) != 0).map(|(_, rule)| rule)
+}
+
+impl Labels {
+    pub(crate) fn names(&self) -> Vec<String> {
+        matches(&registered(self.rules), self.matched).map(|rule| rule.label.clone()).collect()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.matched == 0
+    }
+
+    /// Whether the labels run together are `text`, as `to_string` would give them without
+    /// building the string.
+    pub(crate) fn spell(&self, text: &str) -> bool {
+        let mut rest = text;
+        for rule in matches(&registered(self.rules), self.matched) {
+            match rest.strip_prefix(rule.label.as_str()) {
+                Some(after) => rest = after,
+                None => return false,
+            }
+        }
+        rest.is_empty()
+    }
+
+    /// Product of the matched divisors, 15 for Fizz and Buzz under the classic rules.
+    pub(crate) fn product(&self) -> u64 {
+        matches(&registered(self.rules), self.matched).fold(1u64, |product, rule| product.saturating_mul(rule.divisor))
+    }
+}
+
+/// The labels run together, `FizzBazz`, as the classic FizzBuzz prints them.
+impl fmt::Display for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        matches(&registered(self.rules), self.matched).try_for_each(|rule| f.write_str(&rule.label))
+    }
+}
+
+impl fmt::Debug for Labels {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(matches(&registered(self.rules), self.matched).map(|rule| &rule.label)).finish()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod rules_tests {
+    use super::*;
+
+    fn settings(rules: &[&str]) -> Vec<String> {
+        rules.iter().map(|rule| rule.to_string()).collect()
+    }
+
+    #[test]
+    fn test_classify() {
+        let rules = Rules::from_settings(&settings(&["3=Fizz", "5=Buzz", "7=Bazz"])).expect("valid rules").expect("rules given");
+        let labels = |value| rules.classify(value).labels().names();
+        assert_eq!(labels(21), vec!["Fizz", "Bazz"]);
+        assert_eq!(labels(105), vec!["Fizz", "Buzz", "Bazz"]);
+        assert!(labels(11).is_empty());
+        assert_eq!(rules.classify(35).labels().to_string(), "BuzzBazz");
+        assert!(rules.classify(35).labels().spell("BuzzBazz"));
+        assert!(!rules.classify(35).labels().spell("Buzz"));
+        assert!(!rules.classify(35).labels().spell("BuzzBazzBazz"));
+        assert_eq!(rules.classify(35).labels().product(), 35);
+        assert_eq!(format!("{:?}", rules.classify(21)), "Classified { value: 21, labels: [\"Fizz\", \"Bazz\"] }");
+    }
+
+    #[test]
+    fn test_classic() {
+        let classified = |value| FizzBuzzMessage::classified(value).to_string();
+        assert_eq!(classified(15), "15 -> FizzBuzz");
+        assert_eq!(classified(9), "9 -> Fizz");
+        assert_eq!(classified(10), "10 -> Buzz");
+        assert_eq!(classified(7), "7 -> 7");
+        assert_eq!(FizzBuzzMessage::classified(30).labels().product(), 15);
+
+        let args = |rules: &[&str], keep_values| MainArg { rules: settings(rules), keep_values, ..MainArg::default() };
+        assert_eq!(Rules::from_args(&args(&[], false)), Ok(None));
+        assert_eq!(Rules::from_args(&args(&[], true)), Ok(Some(Rules::classic())));
+        // rules given win, their labels already keep the value
+        assert_eq!(Rules::from_args(&args(&["7=Bazz"], true)).expect("valid rules").map(|rules| rules.classify(7).to_string())
+                   , Some("7 -> Bazz".to_string()));
+    }
+
+    #[test]
+    fn test_rule_set() {
+        let mut active = ActiveRules(None);
+        assert_eq!(active.to_string(), "classic");
+        assert_eq!(active.apply(21), FizzBuzzMessage::Fizz);
+        let bazz = RuleSet::from_settings(&settings(&["3=Fizz", "7=Bazz"])).expect("valid rules");
+        assert_eq!(bazz.to_string(), "3=Fizz 7=Bazz");
+        assert_eq!(active.reload(&bazz), Ok(()));
+        assert_eq!(active.to_string(), "3=Fizz 7=Bazz");
+        let registered = Rules::from_settings(&settings(&["3=Fizz", "7=Bazz"])).expect("valid rules").expect("rules given");
+        assert_eq!(active.apply(21), FizzBuzzMessage::Classified(registered.classify(21)));
+        assert_eq!(active.reload(&RuleSet::from_settings(&[]).expect("no rules is valid")), Ok(()));
+        assert_eq!(active, ActiveRules(None));
+        assert!(RuleSet::from_settings(&settings(&["0=Never"])).is_err());
+    }
+
+    #[test]
+    fn test_register() {
+        // the same rules sent again are the set already registered, not a new one
+        let first = Rules::from_settings(&settings(&["4=Four", "6=Six"])).expect("valid rules").expect("rules given");
+        let again = Rules::from_settings(&settings(&["4=Four", "6=Six"])).expect("valid rules").expect("rules given");
+        assert_eq!(first, again);
+        assert_eq!(first.classify(12).labels(), again.classify(12).labels());
+        assert_eq!(Rules::register(Arc::from(vec![Rule { divisor: 3, label: "Fizz".to_string() }
+                                                  , Rule { divisor: 5, label: "Buzz".to_string() }])), Ok(Rules::classic()));
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Rules::from_settings(&[]), Ok(None));
+        assert_eq!(Rules::parse(&settings(&[" 3 = Fizz "])), Ok(vec![Rule { divisor: 3, label: "Fizz".to_string() }]));
+        assert!(Rules::parse(&settings(&["Fizz"])).is_err());
+        assert!(Rules::parse(&settings(&["0=Never"])).is_err());
+        assert!(Rules::parse(&settings(&["three=Fizz"])).is_err());
+        assert!(Rules::parse(&settings(&["3="])).is_err());
+    }
+}
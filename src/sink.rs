@@ -0,0 +1,528 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
+use serde::Serialize;
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::arg::{LogKind, MainArg};
+use crate::clock::Clock;
+use crate::envelope::{Envelope, UNSEQUENCED};
+
+/// Destination for the records a terminal actor consumes.
+/// Separating "where output goes" from the actor loop lets every sink share the same
+/// shutdown and batching behavior, and lets tests swap in a [`FakeSink`].
+///
+/// Ordering guarantees, each enforced by its sink's tests with [`verify_order`]:
+/// - file logger, dead-letter file and batch CSV: strictly in arrival order
+///   (for batch, input order, even with a pool of workers);
+/// - per-kind loggers behind the router: in order within each kind, with no
+///   order between kinds, see [`verify_order_per_shard`].
+pub(crate) trait Sink<T>: Send {
+    fn write(&mut self, record: &T) -> io::Result<()>;
+
+    /// Writes a record with its envelope. Sinks which record where it came from override this,
+    /// the rest only ever see the payload.
+    fn write_sealed(&mut self, record: &Envelope<T>) -> io::Result<()> {
+        self.write(&record.payload)
+    }
+
+    /// Called once per drained batch so buffered sinks can bound their data at risk.
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// What the sink has to say about itself at shutdown, logged after the delivery summary.
+    fn report(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Console output through the framework logger, each line starting with `label`.
+pub(crate) struct ConsoleSink {
+    label: String,
+}
+
+impl Default for ConsoleSink {
+    fn default() -> Self {
+        ConsoleSink { label: "Msg".to_string() }
+    }
+}
+
+impl ConsoleSink {
+    /// Distinguishes a subset sink's lines from the merged view on the same console.
+    pub(crate) fn labeled(label: String) -> Self {
+        ConsoleSink { label }
+    }
+}
+
+impl Sink<FizzBuzzMessage> for ConsoleSink {
+    fn write(&mut self, msg: &FizzBuzzMessage) -> io::Result<()> {
+        // Message processing with structured logging integration.
+        // The framework automatically handles log formatting, threading,
+        // and output routing based on configuration.
+        match msg {
+            // the value with its labels, `15 -> FizzBuzz`
+            FizzBuzzMessage::Classified(c) => info!("{} {}", self.label, c),
+            _ => info!("{} {:?}", self.label, msg),
+        }
+        Ok(())
+    }
+}
+
+/// One line of `--log-format json`, the fields in this order.
+#[derive(Serialize)]
+struct JsonLine {
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<u64>,
+    /// Milliseconds since the Unix epoch when the logger wrote the line.
+    ts: u64,
+}
+
+/// JSON lines for jq or a log shipper, `{"kind":"Fizz","value":9,"ts":1700000000000}`.
+/// A bare Fizz, Buzz or FizzBuzz has lost its value, so its line has no `value` field.
+pub(crate) struct JsonSink<W: Write + Send> {
+    out: W,
+    clock: Clock,
+}
+
+impl<W: Write + Send> JsonSink<W> {
+    pub(crate) fn new(out: W, clock: Clock) -> Self {
+        JsonSink { out, clock }
+    }
+}
+
+impl<W: Write + Send> Sink<FizzBuzzMessage> for JsonSink<W> {
+    fn write(&mut self, msg: &FizzBuzzMessage) -> io::Result<()> {
+        let line = JsonLine { kind: msg.kind(), value: msg.value(), ts: self.clock.wall().as_millis() as u64 };
+        serde_json::to_writer(&mut self.out, &line)?;
+        writeln!(self.out)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// CSV rows for spreadsheets or pandas, `sequence,value,classification,timestamp` with the
+/// timestamp in milliseconds since the Unix epoch as in the JSON lines. A value from the priority
+/// channel, or one written without its envelope, has no place in the stream and no sequence,
+/// and a bare Fizz, Buzz or FizzBuzz has no value.
+pub(crate) struct CsvLog<W: Write + Send> {
+    out: W,
+    clock: Clock,
+}
+
+impl<W: Write + Send> CsvLog<W> {
+    pub(crate) fn new(out: W, clock: Clock) -> Self {
+        CsvLog { out, clock }
+    }
+
+    /// Written once per table, the caller keeps track of whether it already was.
+    pub(crate) fn header(&mut self) -> io::Result<()> {
+        writeln!(self.out, "sequence,value,classification,timestamp")
+    }
+
+    fn row(&mut self, seq: Option<u64>, msg: &FizzBuzzMessage) -> io::Result<()> {
+        let seq = seq.map(|seq| seq.to_string()).unwrap_or_default();
+        let value = msg.value().map(|value| value.to_string()).unwrap_or_default();
+        writeln!(self.out, "{},{},{},{}", seq, value, msg.kind(), self.clock.wall().as_millis())
+    }
+}
+
+impl<W: Write + Send> Sink<FizzBuzzMessage> for CsvLog<W> {
+    fn write(&mut self, msg: &FizzBuzzMessage) -> io::Result<()> {
+        self.row(None, msg)
+    }
+
+    fn write_sealed(&mut self, msg: &Envelope<FizzBuzzMessage>) -> io::Result<()> {
+        self.row(Some(msg.seq).filter(|seq| *seq != UNSEQUENCED), &msg.payload)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// One destination of `--log-sink`: `console`, `file=PATH` for the text lines of the file
+/// logger or `json=PATH` for JSON lines.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum SinkSpec {
+    Console,
+    File(String),
+    Json(String),
+}
+
+impl SinkSpec {
+    pub(crate) fn parse_all(settings: &[String]) -> Result<Vec<Self>, String> {
+        settings.iter().map(|setting| match setting.trim().split_once('=') {
+            None if setting.trim() == "console" => Ok(SinkSpec::Console),
+            Some(("file", path)) if !path.is_empty() => Ok(SinkSpec::File(path.to_string())),
+            Some(("json", path)) if !path.is_empty() => Ok(SinkSpec::Json(path.to_string())),
+            _ => Err(format!("expected console, file=PATH or json=PATH, got {}", setting)),
+        }).collect()
+    }
+}
+
+/// The setting it was given as, naming the sink in warnings and the error counts.
+impl fmt::Display for SinkSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SinkSpec::Console => write!(f, "console"),
+            SinkSpec::File(path) => write!(f, "file={}", path),
+            SinkSpec::Json(path) => write!(f, "json={}", path),
+        }
+    }
+}
+
+/// A sink of the fan-out with the errors it returned so far.
+struct Tap<T> {
+    name: String,
+    sink: Box<dyn Sink<T>>,
+    errors: Arc<AtomicU64>,
+}
+
+impl<T> Tap<T> {
+    fn count(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            // once per sink, a full disk would otherwise warn on every message
+            if self.errors.fetch_add(1, Ordering::Relaxed) == 0 {
+                warn!("Sink {} failed, counting its errors from here on: {}", self.name, e);
+            }
+        }
+    }
+}
+
+/// Writes every record to each of its sinks in the order they were added. A failing sink is
+/// counted and passed over, so one full disk does not stop the console or the other files.
+pub(crate) struct FanOut<T> {
+    taps: Vec<Tap<T>>,
+}
+
+impl<T> Default for FanOut<T> {
+    fn default() -> Self {
+        FanOut { taps: Vec::new() }
+    }
+}
+
+impl<T> FanOut<T> {
+    /// Adds a sink counting its errors into `errors`, which a restarted logger hands on
+    /// from its state so the counts cover the whole run.
+    pub(crate) fn with(mut self, name: impl Into<String>, sink: impl Sink<T> + 'static, errors: Arc<AtomicU64>) -> Self {
+        self.taps.push(Tap { name: name.into(), sink: Box::new(sink), errors });
+        self
+    }
+}
+
+impl<T> Sink<T> for FanOut<T> {
+    fn write(&mut self, record: &T) -> io::Result<()> {
+        for tap in &mut self.taps {
+            let result = tap.sink.write(record);
+            tap.count(result);
+        }
+        Ok(())
+    }
+
+    fn write_sealed(&mut self, record: &Envelope<T>) -> io::Result<()> {
+        for tap in &mut self.taps {
+            let result = tap.sink.write_sealed(record);
+            tap.count(result);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for tap in &mut self.taps {
+            let result = tap.sink.flush();
+            tap.count(result);
+        }
+        Ok(())
+    }
+
+    fn report(&self) -> Option<String> {
+        let counts: Vec<String> = self.taps.iter().map(|tap| format!("{} {}", tap.name, tap.errors.load(Ordering::Relaxed))).collect();
+        Some(format!("Sink errors: {}", counts.join(", ")))
+    }
+}
+
+/// How often [`Sampled`] logs what it dropped, if it dropped anything since the last time.
+const SAMPLE_SUMMARY_EVERY: Duration = Duration::from_secs(10);
+
+/// N of a `--log-sample 1/N` setting, one message written in every N.
+pub(crate) fn parse_sample(setting: &str) -> Result<u64, String> {
+    setting.trim().strip_prefix("1/").and_then(|every| every.parse::<u64>().ok()).filter(|every| *every > 0)
+        .ok_or_else(|| format!("expected 1/N with N at least 1, got {}", setting))
+}
+
+/// Messages written and dropped, since the last summary or over the whole run.
+#[derive(Clone, Copy, Default)]
+struct SampleCounts {
+    written: u64,
+    sampled_out: u64,
+    filtered_out: u64,
+}
+
+impl SampleCounts {
+    fn line(&self, over: &str) -> String {
+        format!("Logged {} of {} messages {}, {} dropped by sampling, {} by filter", self.written
+                , self.written + self.sampled_out + self.filtered_out, over, self.sampled_out, self.filtered_out)
+    }
+}
+
+/// `--log-filter` and `--log-sample` in front of another sink: messages of other kinds are dropped,
+/// then one in every `every` of the rest is written. The drops are counted, logged every
+/// [`SAMPLE_SUMMARY_EVERY`] and for the whole run in the report. The interval is checked on
+/// every write as well as on `flush`, which a logger that never drains its channel never calls.
+pub(crate) struct Sampled<S> {
+    sink: S,
+    kinds: Vec<LogKind>,
+    every: u64,
+    clock: Clock,
+    /// Messages through the filter, the first of every `every` is written.
+    passed: u64,
+    since_summary: SampleCounts,
+    total: SampleCounts,
+    summarized_at: Instant,
+}
+
+impl<S> Sampled<S> {
+    pub(crate) fn new(sink: S, kinds: Vec<LogKind>, every: u64, clock: Clock) -> Self {
+        let summarized_at = clock.now();
+        Sampled { sink, kinds, every, clock, passed: 0, since_summary: SampleCounts::default(), total: SampleCounts::default(), summarized_at }
+    }
+
+    pub(crate) fn from_args(sink: S, args: &MainArg) -> Result<Self, String> {
+        let every = args.log_sample.as_deref().map(parse_sample).transpose()?.unwrap_or(1);
+        Ok(Sampled::new(sink, args.log_filter.clone(), every, args.clock.clone()))
+    }
+
+    /// Whether `msg` is written, counting it either way.
+    fn keep(&mut self, msg: &FizzBuzzMessage) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.iter().any(|kind| msg.is_kind(kind.label())) {
+            self.since_summary.filtered_out += 1;
+            return false;
+        }
+        self.passed += 1;
+        if (self.passed - 1) % self.every == 0 {
+            self.since_summary.written += 1;
+            true
+        } else {
+            self.since_summary.sampled_out += 1;
+            false
+        }
+    }
+
+    /// Logs the counts if [`SAMPLE_SUMMARY_EVERY`] has passed since the last time.
+    fn summarize_when_due(&mut self) {
+        if self.clock.since(self.summarized_at) >= SAMPLE_SUMMARY_EVERY {
+            self.summarize();
+        }
+    }
+
+    fn summarize(&mut self) {
+        let counts = std::mem::take(&mut self.since_summary);
+        self.total.written += counts.written;
+        self.total.sampled_out += counts.sampled_out;
+        self.total.filtered_out += counts.filtered_out;
+        if counts.sampled_out + counts.filtered_out > 0 {
+            info!("{}", counts.line(&format!("in the last {:?}", self.clock.since(self.summarized_at))));
+        }
+        self.summarized_at = self.clock.now();
+    }
+}
+
+impl<S: Sink<FizzBuzzMessage>> Sink<FizzBuzzMessage> for Sampled<S> {
+    fn write(&mut self, msg: &FizzBuzzMessage) -> io::Result<()> {
+        self.summarize_when_due();
+        if self.keep(msg) { self.sink.write(msg) } else { Ok(()) }
+    }
+
+    fn write_sealed(&mut self, msg: &Envelope<FizzBuzzMessage>) -> io::Result<()> {
+        self.summarize_when_due();
+        if self.keep(&msg.payload) { self.sink.write_sealed(msg) } else { Ok(()) }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.summarize_when_due();
+        self.sink.flush()
+    }
+
+    fn report(&self) -> Option<String> {
+        let mut total = self.total;
+        total.written += self.since_summary.written;
+        total.sampled_out += self.since_summary.sampled_out;
+        total.filtered_out += self.since_summary.filtered_out;
+        let own = (total.sampled_out + total.filtered_out > 0).then(|| total.line("in all"));
+        match (own, self.sink.report()) {
+            (Some(own), Some(inner)) => Some(format!("{}\n{}", own, inner)),
+            (own, inner) => own.or(inner),
+        }
+    }
+}
+
+/// Reorder detector for sink output: `key` must never decrease from one record to the next.
+#[cfg(test)]
+pub(crate) fn verify_order<T, K: PartialOrd + std::fmt::Debug>(records: &[T], key: impl Fn(&T) -> K) -> Result<(), String> {
+    for (index, pair) in records.windows(2).enumerate() {
+        let (before, after) = (key(&pair[0]), key(&pair[1]));
+        if after < before {
+            return Err(format!("record {} has key {:?} after {:?}", index + 1, after, before));
+        }
+    }
+    Ok(())
+}
+
+/// Reorder detector for sharded sinks, only records within the same shard must be in order.
+#[cfg(test)]
+pub(crate) fn verify_order_per_shard<T, S, K>(records: &[T], shard: impl Fn(&T) -> S, key: impl Fn(&T) -> K) -> Result<(), String>
+    where S: Eq + std::hash::Hash + std::fmt::Debug, K: PartialOrd + std::fmt::Debug {
+    let mut last = std::collections::HashMap::new();
+    for (index, record) in records.iter().enumerate() {
+        let (shard, key) = (shard(record), key(record));
+        if let Some(before) = last.get(&shard) {
+            if key < *before {
+                return Err(format!("record {} in shard {:?} has key {:?} after {:?}", index, shard, key, before));
+            }
+        }
+        last.insert(shard, key);
+    }
+    Ok(())
+}
+
+/// Test double which captures typed records instead of producing output.
+/// Clones share the same buffer, keep one in the test and move the other into the actor.
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub(crate) struct FakeSink<T> {
+    records: std::sync::Arc<std::sync::Mutex<Vec<T>>>,
+}
+
+#[cfg(test)]
+impl<T> FakeSink<T> {
+    /// Removes and returns everything recorded so far, in write order.
+    pub(crate) fn take_records(&self) -> Vec<T> {
+        std::mem::take(&mut *self.records.lock().expect("fake sink lock"))
+    }
+}
+
+#[cfg(test)]
+impl<T: Clone + Send> Sink<T> for FakeSink<T> {
+    fn write(&mut self, record: &T) -> io::Result<()> {
+        self.records.lock().expect("fake sink lock").push(record.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod sink_tests {
+    use super::*;
+
+    #[test]
+    fn test_reorder_detection() {
+        assert_eq!(verify_order(&[1, 2, 2, 5], |v| *v), Ok(()));
+        assert_eq!(verify_order(&[1, 3, 2], |v| *v), Err("record 2 has key 2 after 3".to_string()));
+
+        let records = [("a", 1), ("b", 5), ("a", 2), ("b", 6)];
+        assert_eq!(verify_order_per_shard(&records, |r| r.0, |r| r.1), Ok(()));
+        let records = [("a", 2), ("b", 5), ("a", 1)];
+        assert_eq!(verify_order_per_shard(&records, |r| r.0, |r| r.1)
+                   , Err("record 2 in shard \"a\" has key 1 after 2".to_string()));
+    }
+
+    #[test]
+    fn test_sink_spec() {
+        let settings: Vec<String> = ["console", "file=out.log", " json=out.jsonl"].iter().map(|s| s.to_string()).collect();
+        let specs = SinkSpec::parse_all(&settings).expect("valid settings");
+        assert_eq!(specs, vec![SinkSpec::Console, SinkSpec::File("out.log".to_string()), SinkSpec::Json("out.jsonl".to_string())]);
+        assert_eq!(specs.iter().map(|spec| spec.to_string()).collect::<Vec<_>>(), vec!["console", "file=out.log", "json=out.jsonl"]);
+        assert!(SinkSpec::parse_all(&["file=".to_string()]).is_err());
+        assert!(SinkSpec::parse_all(&["syslog=host".to_string()]).is_err());
+    }
+
+    /// Fails every write, a disk that filled up.
+    struct Broken;
+
+    impl Sink<FizzBuzzMessage> for Broken {
+        fn write(&mut self, _msg: &FizzBuzzMessage) -> io::Result<()> {
+            Err(io::Error::other("disk full"))
+        }
+    }
+
+    #[test]
+    fn test_fan_out() -> io::Result<()> {
+        let (first, second) = (FakeSink::default(), FakeSink::default());
+        let errors: Vec<Arc<AtomicU64>> = (0..3).map(|_| Arc::default()).collect();
+        let mut fan_out = FanOut::default().with("first", first.clone(), errors[0].clone())
+            .with("broken", Broken, errors[1].clone()).with("second", second.clone(), errors[2].clone());
+        fan_out.write_sealed(&Envelope::new(0, std::time::Instant::now(), FizzBuzzMessage::Fizz))?;
+        fan_out.write(&FizzBuzzMessage::Value(7))?;
+        fan_out.flush()?;
+
+        // the sinks on either side of the broken one got everything
+        assert_eq!(first.take_records(), vec![FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(7)]);
+        assert_eq!(second.take_records(), vec![FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(7)]);
+        assert_eq!(fan_out.report(), Some("Sink errors: first 0, broken 2, second 0".to_string()));
+
+        // a restarted logger opens its sinks again and goes on with the counts
+        let mut fan_out = FanOut::default().with("first", first.clone(), errors[0].clone())
+            .with("broken", Broken, errors[1].clone()).with("second", second.clone(), errors[2].clone());
+        fan_out.write(&FizzBuzzMessage::Buzz)?;
+        assert_eq!(fan_out.report(), Some("Sink errors: first 0, broken 3, second 0".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sampled() -> io::Result<()> {
+        assert_eq!(parse_sample("1/10"), Ok(10));
+        assert!(parse_sample("1/0").is_err());
+        assert!(parse_sample("2/10").is_err());
+
+        let (clock, source) = Clock::virtual_clock();
+        let fake = FakeSink::default();
+        let mut sink = Sampled::new(fake.clone(), vec![LogKind::Fizz, LogKind::Value], 2, clock);
+        for value in 1..=10 {
+            let msg = match value {
+                _ if value % 15 == 0 => FizzBuzzMessage::FizzBuzz,
+                _ if value % 3 == 0 => FizzBuzzMessage::Fizz,
+                _ if value % 5 == 0 => FizzBuzzMessage::Buzz,
+                _ => FizzBuzzMessage::Value(value),
+            };
+            sink.write(&msg)?;
+        }
+        // the interval is up on the next write, with no flush in between
+        source.advance(SAMPLE_SUMMARY_EVERY);
+        sink.write(&FizzBuzzMessage::Classified(FizzBuzzMessage::classified(12)))?;
+        assert_eq!((sink.total.written, sink.total.sampled_out, sink.total.filtered_out), (4, 4, 2));
+        sink.write(&FizzBuzzMessage::Classified(FizzBuzzMessage::classified(15)))?;
+        sink.flush()?;
+
+        // Buzz at 5 and 10 filtered, every other one of the remaining eight written,
+        // then the classified Fizz written and the classified FizzBuzz filtered
+        assert_eq!(fake.take_records(), vec![FizzBuzzMessage::Value(1), FizzBuzzMessage::Fizz
+                                             , FizzBuzzMessage::Fizz, FizzBuzzMessage::Value(8)
+                                             , FizzBuzzMessage::Classified(FizzBuzzMessage::classified(12))]);
+        assert_eq!(sink.report(), Some("Logged 5 of 12 messages in all, 4 dropped by sampling, 3 by filter".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_json_sink() -> io::Result<()> {
+        let (clock, source) = Clock::virtual_clock_at(Duration::from_millis(1_700_000_000_000));
+        let mut sink = JsonSink::new(Vec::new(), clock);
+        sink.write(&FizzBuzzMessage::Fizz)?;
+        source.advance(Duration::from_millis(5));
+        sink.write(&FizzBuzzMessage::Value(7))?;
+        sink.write(&FizzBuzzMessage::Classified(FizzBuzzMessage::classified(30)))?;
+        sink.write(&FizzBuzzMessage::Classified(FizzBuzzMessage::classified(7)))?;
+        sink.write(&FizzBuzzMessage::Classified(FizzBuzzMessage::classified(9)))?;
+        assert_eq!(String::from_utf8(sink.out).expect("utf8")
+                   , "{\"kind\":\"Fizz\",\"ts\":1700000000000}\n\
+                      {\"kind\":\"Value\",\"value\":7,\"ts\":1700000000005}\n\
+                      {\"kind\":\"FizzBuzz\",\"value\":30,\"ts\":1700000000005}\n\
+                      {\"kind\":\"Value\",\"value\":7,\"ts\":1700000000005}\n\
+                      {\"kind\":\"Fizz\",\"value\":9,\"ts\":1700000000005}\n");
+        Ok(())
+    }
+}
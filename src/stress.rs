@@ -0,0 +1,303 @@
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use steady_state::*;
+use crate::actor::control_api::{parse_request, read_request_line, respond, ControlRequest};
+use crate::actor::worker::{FizzBuzzMessage, WORKER_SLICE};
+use crate::arg::MainArg;
+use crate::error::{AppError, Context};
+use crate::topology::Topology;
+
+const NAME_STRESS_SOURCE: &str = "STRESS_SOURCE";
+const NAME_STRESS_WORKER: &str = "STRESS_WORKER";
+const NAME_STRESS_SINK: &str = "STRESS_SINK";
+const NAME_STRESS_CONTROL: &str = "STRESS_CONTROL";
+
+/// How often the pipeline control listener is polled for new connections.
+const POLL_RATE: Duration = Duration::from_millis(50);
+
+#[derive(Default)]
+struct Lane {
+    stop: AtomicBool,
+    stopped: AtomicBool,
+    delivered: AtomicU64,
+}
+
+/// Lifecycle and delivery count of every stress pipeline.
+/// A stop request is seen by the pipeline's source, which closes its channel; the close
+/// then travels down the chain so each actor exits only after its input is drained,
+/// while the other pipelines and the rest of the graph keep running.
+#[derive(Clone)]
+pub(crate) struct Pipelines {
+    lanes: Arc<Vec<Lane>>,
+}
+
+impl Pipelines {
+    pub(crate) fn new(count: usize) -> Self {
+        Pipelines { lanes: Arc::new((0..count).map(|_| Lane::default()).collect()) }
+    }
+
+    /// Messages which reached a sink, summed over all pipelines.
+    pub(crate) fn delivered(&self) -> u64 {
+        self.lanes.iter().map(|lane| lane.delivered.load(Ordering::Relaxed)).sum()
+    }
+
+    pub(crate) fn delivered_by(&self, index: usize) -> u64 {
+        self.lanes[index].delivered.load(Ordering::Relaxed)
+    }
+
+    /// Asks one pipeline to drain and stop, false when there is no such running pipeline.
+    pub(crate) fn stop(&self, index: usize) -> bool {
+        self.lanes.get(index).is_some_and(|lane| !lane.stop.swap(true, Ordering::Relaxed))
+    }
+
+    pub(crate) fn is_stopped(&self, index: usize) -> bool {
+        self.lanes[index].stopped.load(Ordering::Relaxed)
+    }
+
+    fn is_stopping(&self, index: usize) -> bool {
+        self.lanes[index].stop.load(Ordering::Relaxed)
+    }
+
+    /// Called by the sink once the last message of a stopped pipeline is counted.
+    fn finished(&self, index: usize) {
+        self.lanes[index].stopped.store(true, Ordering::Relaxed);
+        info!("Pipeline {} stopped after delivering {} messages", index, self.delivered_by(index));
+    }
+
+    /// State and delivery count of every pipeline, served at /info.
+    pub(crate) fn summary(&self) -> serde_json::Value {
+        let lanes: Vec<_> = (0..self.lanes.len()).map(|index| {
+            let state = if self.is_stopped(index) {
+                "stopped"
+            } else if self.is_stopping(index) {
+                "draining"
+            } else {
+                "running"
+            };
+            serde_json::json!({ "pipeline": index, "state": state, "delivered": self.delivered_by(index) })
+        }).collect();
+        serde_json::json!({ "pipelines": lanes })
+    }
+}
+
+/// Large-scale topology builder which assembles `pipelines` independent
+/// source→worker→null sink chains. Each chain shares one troupe so the thread
+/// count grows with the number of pipelines rather than the number of actors.
+/// Returns the registry through which pipelines are counted and individually stopped.
+pub(crate) fn build_stress_graph(graph: &mut Graph, pipelines: usize) -> Pipelines {
+    let registry = Pipelines::new(pipelines);
+    let channel_builder = graph.channel_builder()
+        .with_filled_trigger(Trigger::AvgAbove(Filled::p90()), AlertColor::Red)
+        .with_filled_percentile(Percentile::p80());
+    let actor_builder = graph.actor_builder()
+        .with_load_avg()
+        .with_mcpu_avg();
+
+    for index in 0..pipelines {
+        let (source_tx, source_rx) = channel_builder.build();
+        let (worker_tx, worker_rx) = channel_builder.build();
+
+        let mut troupe = graph.actor_troupe();
+        let source_registry = registry.clone();
+        actor_builder.with_name_and_suffix(NAME_STRESS_SOURCE, index)
+            .build(move |actor| source(actor, source_tx.clone(), source_registry.clone(), index), MemberOf(&mut troupe));
+        actor_builder.with_name_and_suffix(NAME_STRESS_WORKER, index)
+            .build(move |actor| worker(actor, source_rx.clone(), worker_tx.clone()), MemberOf(&mut troupe));
+        let sink_registry = registry.clone();
+        actor_builder.with_name_and_suffix(NAME_STRESS_SINK, index)
+            .build(move |actor| null_sink(actor, worker_rx.clone(), sink_registry.clone(), index), MemberOf(&mut troupe));
+    }
+    registry
+}
+
+/// Adds the HTTP endpoint for stopping single pipelines, listening on `addr`.
+pub(crate) fn build_pipeline_control(graph: &mut Graph, registry: Pipelines, addr: String) {
+    graph.actor_builder().with_name(NAME_STRESS_CONTROL)
+        .build(move |actor| pipeline_control(actor, registry.clone(), addr.clone()), SoloAct);
+}
+
+/// Description of the graph `build_stress_graph` assembles, for dry runs.
+pub(crate) fn stress_topology(pipelines: usize, control: bool) -> Topology {
+    // stress channels use the builder default capacity
+    let mut topology = Topology::new(64);
+    for _ in 0..pipelines {
+        topology.channel("source", &[80]);
+        topology.channel("worker", &[80]);
+        topology.actor(NAME_STRESS_SOURCE, "pipeline troupe", &[], &["source"]);
+        topology.actor(NAME_STRESS_WORKER, "pipeline troupe", &["source"], &["worker"]);
+        topology.actor(NAME_STRESS_SINK, "pipeline troupe", &["worker"], &[]);
+    }
+    if control {
+        topology.actor(NAME_STRESS_CONTROL, "solo thread", &[], &[]);
+    }
+    topology
+}
+
+/// Counter source, sends as fast as downstream allows until its pipeline is stopped.
+async fn source(actor: SteadyActorShadow, tx: SteadyTx<u64>, registry: Pipelines, index: usize) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([], [&tx]);
+    let mut tx = tx.lock().await;
+    let mut value = 0u64;
+    while actor.is_running(|| tx.mark_closed()) {
+        if registry.is_stopping(index) {
+            // closing is the drain signal, the worker and sink finish what is in flight
+            tx.mark_closed();
+            break;
+        }
+        if actor.send_async(&mut tx, value, SendSaturation::AwaitForRoom).await.is_sent() {
+            value += 1;
+        }
+    }
+    Ok(())
+}
+
+/// Classifies everything available which fits in the outgoing channel.
+async fn worker(actor: SteadyActorShadow, rx: SteadyRx<u64>, tx: SteadyTx<FizzBuzzMessage>) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([&rx], [&tx]);
+    // --batch: one take and one send per slice instead of a call per value
+    let slices = actor.args::<MainArg>().is_some_and(|args| args.batch);
+    let mut taken = [0u64; WORKER_SLICE];
+    let mut ready = [FizzBuzzMessage::default(); WORKER_SLICE];
+    let mut rx = rx.lock().await;
+    let mut tx = tx.lock().await;
+    while actor.is_running(|| i!(rx.is_closed_and_empty()) && i!(tx.mark_closed())) {
+        await_for_all!(actor.wait_avail(&mut rx, 1), actor.wait_vacant(&mut tx, 1));
+        let mut items = actor.avail_units(&mut rx).min(actor.vacant_units(&mut tx));
+        if slices {
+            while items > 0 {
+                let count = actor.take_slice(&mut rx, &mut taken[..items.min(WORKER_SLICE)]).item_count();
+                if count == 0 {
+                    break;
+                }
+                classify_slice(&taken[..count], &mut ready[..count]);
+                let _ = actor.send_slice(&mut tx, &ready[..count]);
+                items -= count;
+            }
+        } else {
+            for _ in 0..items {
+                if let Some(value) = actor.try_take(&mut rx) {
+                    let _ = actor.try_send(&mut tx, FizzBuzzMessage::new(value));
+                }
+            }
+        }
+        if rx.is_closed_and_empty() {
+            tx.mark_closed();
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn classify_slice(values: &[u64], ready: &mut [FizzBuzzMessage]) {
+    for (value, msg) in values.iter().zip(ready.iter_mut()) {
+        *msg = FizzBuzzMessage::new(*value);
+    }
+}
+
+/// Discards everything, the point is to exercise the channel not the output.
+/// Only the count is kept, added once per drained batch.
+async fn null_sink(actor: SteadyActorShadow, rx: SteadyRx<FizzBuzzMessage>, registry: Pipelines, index: usize) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([&rx], []);
+    let mut rx = rx.lock().await;
+    while actor.is_running(|| rx.is_closed_and_empty()) {
+        await_for_all!(actor.wait_avail(&mut rx, 1));
+        let mut count = 0;
+        while actor.try_take(&mut rx).is_some() {
+            count += 1;
+        }
+        registry.lanes[index].delivered.fetch_add(count, Ordering::Relaxed);
+        if rx.is_closed_and_empty() {
+            registry.finished(index);
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Serves /stop-pipeline?index=N, /info and /shutdown for the stress topology.
+/// The other control commands address the heartbeat and generator, which this topology lacks.
+async fn pipeline_control(actor: SteadyActorShadow, registry: Pipelines, addr: String) -> Result<(),Box<dyn Error>> {
+    let mut actor = actor.into_spotlight([], []);
+    let listener = TcpListener::bind(&addr).map_err(AppError::Network)
+        .context(format!("binding control endpoint {}", addr))?;
+    listener.set_nonblocking(true).map_err(AppError::Network)?;
+    info!("Pipeline control listening on http://{}", addr);
+    while actor.is_running(|| true) {
+        await_for_all!(actor.wait_periodic(POLL_RATE));
+        while let Ok((stream, _)) = listener.accept() {
+            let request = read_request_line(&stream).and_then(|line| parse_request(&line));
+            let (status, body) = match request {
+                Some(ControlRequest::StopPipeline(index)) if registry.stop(index) => ("200 OK", "draining"),
+                Some(ControlRequest::StopPipeline(_)) => ("404 Not Found", "no such running pipeline"),
+                Some(ControlRequest::Info) => {
+                    respond(stream, "200 OK", "application/json", &registry.summary().to_string());
+                    continue;
+                }
+                Some(ControlRequest::Shutdown) => {
+                    actor.request_shutdown().await;
+                    ("200 OK", "shutting down")
+                }
+                Some(_) => ("404 Not Found", "not available in this topology"),
+                None => ("404 Not Found", "unknown command"),
+            };
+            info!("Pipeline control request {:?} -> {}", request, status);
+            respond(stream, status, "text/plain", body);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub(crate) mod stress_tests {
+    use std::time::Instant;
+    use steady_state::*;
+    use crate::arg::MainArg;
+    use super::*;
+
+    #[test]
+    fn test_stress_topology_200() -> Result<(), Box<dyn Error>> {
+        let started = Instant::now();
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let registry = build_stress_graph(&mut graph, 200);
+
+        graph.start();
+        std::thread::sleep(Duration::from_millis(200));
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(10))?;
+        assert!(registry.delivered() > 0);
+
+        // construction, start, traffic and clean shutdown of 600 actors within budget
+        assert!(started.elapsed() < Duration::from_secs(20), "took {:?}", started.elapsed());
+        Ok(())
+    }
+    #[test]
+    fn test_stop_one_pipeline() -> Result<(), Box<dyn Error>> {
+        let mut graph = GraphBuilder::for_testing().build(MainArg::default());
+        let registry = build_stress_graph(&mut graph, 3);
+
+        graph.start();
+        std::thread::sleep(Duration::from_millis(100));
+        assert!(registry.stop(1));
+        assert!(!registry.stop(1), "already draining");
+        assert!(!registry.stop(3), "no such pipeline");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !registry.is_stopped(1) {
+            assert!(Instant::now() < deadline, "pipeline 1 did not drain");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // the stopped pipeline delivers nothing more while its neighbours keep going
+        let stopped_total = registry.delivered_by(1);
+        let others = registry.delivered_by(0) + registry.delivered_by(2);
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(registry.delivered_by(1), stopped_total);
+        assert!(registry.delivered_by(0) + registry.delivered_by(2) > others);
+        assert_eq!(registry.summary()["pipelines"][1]["state"], "stopped");
+        assert_eq!(registry.summary()["pipelines"][0]["state"], "running");
+
+        graph.request_shutdown();
+        graph.block_until_stopped(Duration::from_secs(10))?;
+        Ok(())
+    }
+}
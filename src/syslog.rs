@@ -0,0 +1,270 @@
+use std::io::{self, Write};
+use std::net::{TcpStream, UdpSocket};
+use steady_state::*;
+use crate::actor::worker::FizzBuzzMessage;
+use crate::arg::{MainArg, SyslogTransport};
+use crate::clock::{self, Clock};
+use crate::error::{AppError, Context};
+use crate::sink::Sink;
+
+/// RFC 5424 severities by keyword, in order of their numeric value.
+const SEVERITIES: [&str; 8] = ["emerg", "alert", "crit", "err", "warning", "notice", "info", "debug"];
+
+/// Severity of a kind not given with --syslog-severity: informational.
+const DEFAULT_SEVERITY: u8 = 6;
+
+/// The `--syslog-severity` settings, kind=severity, `Fizz=notice` or `FizzBuzz=4`.
+/// Kinds are what the message was classified as, `Value` for a value nothing matched.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Severities(Vec<(String, u8)>);
+
+impl Severities {
+    pub(crate) fn from_settings(settings: &[String]) -> Result<Self, String> {
+        settings.iter().map(|setting| {
+            let (kind, severity) = setting.split_once('=').ok_or_else(|| format!("expected kind=severity, got {}", setting))?;
+            let severity = severity.trim();
+            let level = SEVERITIES.iter().position(|name| *name == severity).map(|level| level as u8)
+                .or_else(|| severity.parse::<u8>().ok().filter(|level| *level < 8))
+                .ok_or_else(|| format!("severity must be one of {} or 0 to 7, got {}", SEVERITIES.join(", "), severity))?;
+            Ok((kind.trim().to_string(), level))
+        }).collect::<Result<_, _>>().map(Severities)
+    }
+
+    /// The last setting for `kind` wins, as with repeated flags.
+    pub(crate) fn of(&self, kind: &str) -> u8 {
+        self.0.iter().rev().find(|(name, _)| name == kind).map_or(DEFAULT_SEVERITY, |(_, level)| *level)
+    }
+}
+
+/// Connected socket to the syslog endpoint. UDP sends one message per datagram, TCP frames
+/// each with its length in front, the octet counting of RFC 6587. A TCP connection which
+/// failed is dropped and made again on the next write.
+enum Transport {
+    Udp(UdpSocket),
+    Tcp { addr: String, stream: Option<TcpStream> },
+}
+
+impl Transport {
+    fn connect(addr: &str, transport: SyslogTransport) -> io::Result<Self> {
+        Ok(match transport {
+            SyslogTransport::Udp => {
+                // an unspecified local address of the same family as the endpoint
+                let local = if addr.starts_with('[') { "[::]:0" } else { "0.0.0.0:0" };
+                let socket = UdpSocket::bind(local)?;
+                socket.connect(addr)?;
+                Transport::Udp(socket)
+            }
+            SyslogTransport::Tcp => Transport::Tcp { addr: addr.to_string(), stream: Some(TcpStream::connect(addr)?) },
+        })
+    }
+
+    fn send(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Transport::Udp(socket) => socket.send(line.as_bytes()).map(|_| ()),
+            Transport::Tcp { addr, stream } => {
+                // taken out so an error leaves it dropped, to connect again on the next write
+                let mut connected = match stream.take() {
+                    Some(connected) => connected,
+                    None => TcpStream::connect(addr.as_str())?,
+                };
+                write!(connected, "{} {}", line.len(), line)?;
+                *stream = Some(connected);
+                Ok(())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Transport::Udp(_) => Ok(()),
+            Transport::Tcp { stream, .. } => match stream.take() {
+                Some(mut connected) => {
+                    connected.flush()?;
+                    *stream = Some(connected);
+                    Ok(())
+                }
+                None => Ok(()),
+            },
+        }
+    }
+}
+
+/// The name of this host as the kernel has it, or the NILVALUE when it has none to give.
+/// Shells keep `HOSTNAME` to themselves, so it is only asked when the files are missing.
+fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"].iter()
+        .filter_map(|path| std::fs::read_to_string(path).ok())
+        .chain(std::env::var("HOSTNAME").ok())
+        .map(|name| name.trim().to_string())
+        // printable ASCII without spaces is all RFC 5424 allows in the field
+        .find(|name| !name.is_empty() && name.bytes().all(|b| b.is_ascii_graphic()))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// Ships each message to syslog as an RFC 5424 line, `<PRI>1 TIMESTAMP HOST standard PID KIND - MSG`,
+/// the kind as message id so a collector can filter on it without parsing the text.
+/// A failed send is counted and the message passed over, as in [`crate::sink::FanOut`], so a
+/// collector that is down or restarting does not stop the logger.
+pub(crate) struct SyslogSink {
+    transport: Transport,
+    facility: u8,
+    severities: Severities,
+    clock: Clock,
+    hostname: String,
+    procid: u32,
+    errors: u64,
+}
+
+impl SyslogSink {
+    pub(crate) fn from_args(args: &MainArg) -> Result<Self, AppError> {
+        let addr = args.syslog.clone().expect("syslog sink requires --syslog");
+        let severities = Severities::from_settings(&args.syslog_severity).map_err(AppError::Config)?;
+        let transport = Transport::connect(&addr, args.syslog_transport).map_err(AppError::Network)
+            .context(format!("connecting to syslog {}", addr))?;
+        Ok(SyslogSink { transport, facility: args.syslog_facility, severities, clock: args.clock.clone(), hostname: hostname()
+                      , procid: std::process::id(), errors: 0 })
+    }
+
+    fn count(&mut self, result: io::Result<()>) {
+        if let Err(e) = result {
+            self.errors += 1;
+            // once, a collector that is down would otherwise warn on every message
+            if self.errors == 1 {
+                warn!("Syslog send failed, counting its errors from here on: {}", e);
+            }
+        }
+    }
+
+    fn line(&self, msg: &FizzBuzzMessage) -> String {
+        let kind = msg.kind();
+        let priority = self.facility as u32 * 8 + self.severities.of(&kind) as u32;
+        // the same text the console shows, `15 -> FizzBuzz` or `Fizz`
+        let text = match msg {
+            FizzBuzzMessage::Classified(c) => c.to_string(),
+            _ => format!("{:?}", msg),
+        };
+        format!("<{}>1 {} {} {} {} {} - {}", priority, clock::timestamp(self.clock.wall().as_millis() as u64)
+                , self.hostname, env!("CARGO_PKG_NAME"), self.procid, kind, text)
+    }
+}
+
+impl Sink<FizzBuzzMessage> for SyslogSink {
+    fn write(&mut self, msg: &FizzBuzzMessage) -> io::Result<()> {
+        let line = self.line(msg);
+        let result = self.transport.send(&line);
+        self.count(result);
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = self.transport.flush();
+        self.count(result);
+        Ok(())
+    }
+
+    fn report(&self) -> Option<String> {
+        Some(format!("Syslog errors: {}", self.errors))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod syslog_tests {
+    use std::io::{BufRead, BufReader, Read};
+    use std::net::TcpListener;
+    use std::time::Duration;
+    use super::*;
+
+    fn settings(severities: &[&str]) -> Vec<String> {
+        severities.iter().map(|setting| setting.to_string()).collect()
+    }
+
+    #[test]
+    fn test_severities() {
+        let severities = Severities::from_settings(&settings(&["Fizz=notice", "FizzBuzz=4", " Buzz = err "])).expect("valid settings");
+        assert_eq!(severities.of("Fizz"), 5);
+        assert_eq!(severities.of("FizzBuzz"), 4);
+        assert_eq!(severities.of("Buzz"), 3);
+        assert_eq!(severities.of("Value"), DEFAULT_SEVERITY);
+        assert!(Severities::from_settings(&settings(&["Fizz"])).is_err());
+        assert!(Severities::from_settings(&settings(&["Fizz=loud"])).is_err());
+        assert!(Severities::from_settings(&settings(&["Fizz=8"])).is_err());
+    }
+
+    fn args(addr: String, transport: SyslogTransport) -> MainArg {
+        let (clock, _source) = Clock::virtual_clock_at(Duration::from_millis(1_700_000_000_000));
+        MainArg { syslog: Some(addr), syslog_transport: transport, syslog_facility: 16
+                , syslog_severity: settings(&["Fizz=notice"]), clock, ..MainArg::default() }
+    }
+
+    /// The line after the hostname and process id, which depend on where the test runs.
+    fn without_host(line: &str) -> String {
+        let fields: Vec<&str> = line.splitn(6, ' ').collect();
+        format!("{} {} {}", fields[0], fields[1], fields[5])
+    }
+
+    #[test]
+    fn test_syslog_udp() -> Result<(), Box<dyn std::error::Error>> {
+        let collector = UdpSocket::bind("127.0.0.1:0")?;
+        collector.set_read_timeout(Some(Duration::from_secs(1)))?;
+        let mut sink = SyslogSink::from_args(&args(collector.local_addr()?.to_string(), SyslogTransport::Udp))?;
+        sink.write(&FizzBuzzMessage::Fizz)?;
+        sink.write(&FizzBuzzMessage::Classified(FizzBuzzMessage::classified(7)))?;
+
+        let mut datagram = [0u8; 512];
+        let received = collector.recv(&mut datagram)?;
+        // local0 is facility 16, notice severity 5: 16 * 8 + 5
+        assert_eq!(without_host(std::str::from_utf8(&datagram[..received])?), "<133>1 2023-11-14T22:13:20.000Z Fizz - Fizz");
+        let received = collector.recv(&mut datagram)?;
+        assert_eq!(without_host(std::str::from_utf8(&datagram[..received])?), "<134>1 2023-11-14T22:13:20.000Z Value - 7 -> 7");
+        Ok(())
+    }
+
+    #[test]
+    fn test_syslog_tcp() -> Result<(), Box<dyn std::error::Error>> {
+        let collector = TcpListener::bind("127.0.0.1:0")?;
+        let mut sink = SyslogSink::from_args(&args(collector.local_addr()?.to_string(), SyslogTransport::Tcp))?;
+        sink.write(&FizzBuzzMessage::Buzz)?;
+        sink.flush()?;
+        drop(sink);
+
+        let (stream, _) = collector.accept()?;
+        assert_eq!(without_host(&read_framed(stream)?), "<134>1 2023-11-14T22:13:20.000Z Buzz - Buzz");
+        Ok(())
+    }
+
+    /// One framed line from `stream`.
+    fn read_framed(stream: TcpStream) -> Result<String, Box<dyn std::error::Error>> {
+        let mut framed = BufReader::new(stream);
+        let mut length = Vec::new();
+        framed.read_until(b' ', &mut length)?;
+        let mut line = vec![0u8; std::str::from_utf8(&length)?.trim().parse()?];
+        framed.read_exact(&mut line)?;
+        Ok(String::from_utf8(line)?)
+    }
+
+    /// A collector dropping the connection costs the messages sent into the void, counted,
+    /// and the next write connects again.
+    #[test]
+    fn test_syslog_tcp_reconnect() -> Result<(), Box<dyn std::error::Error>> {
+        let collector = TcpListener::bind("127.0.0.1:0")?;
+        let mut sink = SyslogSink::from_args(&args(collector.local_addr()?.to_string(), SyslogTransport::Tcp))?;
+        let (stream, _) = collector.accept()?;
+        sink.write(&FizzBuzzMessage::Buzz)?;
+        // closed with the line unread, so the sink's side sees a reset
+        drop(stream);
+        for _ in 0..100 {
+            sink.write(&FizzBuzzMessage::Fizz)?;
+            if sink.errors > 0 {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(sink.errors > 0, "the closed connection never failed a write");
+
+        sink.write(&FizzBuzzMessage::Value(7))?;
+        let (stream, _) = collector.accept()?;
+        assert_eq!(without_host(&read_framed(stream)?), "<134>1 2023-11-14T22:13:20.000Z Value - Value(7)");
+        assert_eq!(sink.report(), Some(format!("Syslog errors: {}", sink.errors)));
+        Ok(())
+    }
+}
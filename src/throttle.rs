@@ -0,0 +1,72 @@
+use steady_state::*;
+
+/// Control interval of the generator throttle.
+pub(crate) const THROTTLE_TICK: Duration = Duration::from_millis(50);
+
+/// Congestion controller for the generator, driven by consumer lag: the share of the
+/// generator's outgoing channel still waiting to be taken.
+/// Above `high` percent the per-tick allowance is cut in proportion to the overshoot,
+/// below `low` percent it grows back by an eighth of the maximum per tick, so a slow
+/// consumer is met quickly and recovery does not immediately refill the channel.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Throttle {
+    high: usize,
+    low: usize,
+    max: usize,
+    allowance: usize,
+}
+
+impl Throttle {
+    /// Starts unthrottled at `max` messages per tick.
+    pub(crate) fn new(high: u8, low: u8, max: usize) -> Self {
+        let max = max.max(1);
+        Throttle { high: high as usize, low: low as usize, max, allowance: max }
+    }
+
+    /// Messages the generator may send in the current tick.
+    pub(crate) fn allowance(&self) -> usize {
+        self.allowance
+    }
+
+    /// Adjusts the allowance for `queued` of `capacity` messages waiting downstream,
+    /// returning the decision for the log when the allowance changed.
+    pub(crate) fn observe(&mut self, queued: usize, capacity: usize) -> Option<String> {
+        let lag = queued * 100 / capacity.max(1);
+        let next = if lag > self.high {
+            (self.allowance * self.high / lag).max(1)
+        } else if lag < self.low {
+            (self.allowance + self.max.div_ceil(8)).min(self.max)
+        } else {
+            self.allowance
+        };
+        if next == self.allowance {
+            return None;
+        }
+        let previous = std::mem::replace(&mut self.allowance, next);
+        Some(format!("consumer lag {}%: {} -> {} messages per {:?}", lag, previous, next, THROTTLE_TICK))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod throttle_tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle() {
+        let mut throttle = Throttle::new(80, 20, 64);
+        assert_eq!(throttle.allowance(), 64);
+        // a full channel cuts the allowance in proportion to the overshoot
+        assert!(throttle.observe(64, 64).is_some());
+        assert_eq!(throttle.allowance(), 51);
+        assert!(throttle.observe(60, 64).is_some());
+        assert_eq!(throttle.allowance(), 43);
+        // between the thresholds nothing changes
+        assert_eq!(throttle.observe(32, 64), None);
+        // as lag clears the allowance is restored step by step, never beyond the maximum
+        for expected in [51, 59, 64, 64] {
+            throttle.observe(0, 64);
+            assert_eq!(throttle.allowance(), expected);
+        }
+        assert_eq!(throttle.observe(0, 64), None);
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use steady_state::*;
+
+/// Optional behaviors which can be switched on or off while the graph runs.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum Toggle {
+    /// Worker applies `--max-value`, when off every value is classified.
+    Validation,
+    /// Worker writes its per-batch audit line with the checksum.
+    Audit,
+}
+
+impl Toggle {
+    pub(crate) const ALL: [Toggle; 2] = [Toggle::Validation, Toggle::Audit];
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Toggle::Validation => "validation",
+            Toggle::Audit => "audit",
+        }
+    }
+
+    pub(crate) fn parse(name: &str) -> Option<Toggle> {
+        Toggle::ALL.into_iter().find(|toggle| toggle.name() == name)
+    }
+}
+
+/// Registry of toggles shared by every actor which consults them.
+/// Flags are atomics so actors read them once per batch without locking,
+/// and a change made by the control endpoint is seen on the next batch.
+#[derive(Clone)]
+pub(crate) struct Toggles {
+    flags: Arc<[AtomicBool; Toggle::ALL.len()]>,
+}
+
+impl Default for Toggles {
+    /// Every optional behavior starts enabled.
+    fn default() -> Self {
+        Toggles { flags: Arc::new([AtomicBool::new(true), AtomicBool::new(true)]) }
+    }
+}
+
+impl Toggles {
+    /// Builds the registry from `name=on|off` settings given as flags or in the config file.
+    pub(crate) fn from_settings(settings: &[String]) -> Result<Self, String> {
+        let toggles = Toggles::default();
+        for setting in settings {
+            let (name, state) = setting.split_once('=').ok_or_else(|| format!("expected name=on|off, got {}", setting))?;
+            let toggle = Toggle::parse(name.trim()).ok_or_else(|| format!("unknown toggle {}", name))?;
+            let enabled = match state.trim() {
+                "on" => true,
+                "off" => false,
+                other => return Err(format!("toggle {} must be on or off, got {}", name, other)),
+            };
+            toggles.flag(toggle).store(enabled, Ordering::Relaxed);
+        }
+        Ok(toggles)
+    }
+
+    fn flag(&self, toggle: Toggle) -> &AtomicBool {
+        &self.flags[toggle as usize]
+    }
+
+    pub(crate) fn is_enabled(&self, toggle: Toggle) -> bool {
+        self.flag(toggle).load(Ordering::Relaxed)
+    }
+
+    /// Changes a toggle at runtime, recording the change in the audit log.
+    pub(crate) fn set(&self, toggle: Toggle, enabled: bool) {
+        let previous = self.flag(toggle).swap(enabled, Ordering::Relaxed);
+        if previous != enabled {
+            info!("Audit toggle {} {} -> {}", toggle.name(), on_off(previous), on_off(enabled));
+        }
+    }
+
+    /// Current state of every toggle, keyed by name.
+    pub(crate) fn snapshot(&self) -> BTreeMap<&'static str, bool> {
+        Toggle::ALL.into_iter().map(|toggle| (toggle.name(), self.is_enabled(toggle))).collect()
+    }
+}
+
+fn on_off(enabled: bool) -> &'static str {
+    if enabled { "on" } else { "off" }
+}
+
+#[cfg(test)]
+pub(crate) mod toggles_tests {
+    use super::*;
+
+    #[test]
+    fn test_toggles() {
+        let toggles = Toggles::from_settings(&["validation=off".to_string()]).expect("valid settings");
+        assert!(!toggles.is_enabled(Toggle::Validation));
+        assert!(toggles.is_enabled(Toggle::Audit));
+
+        // clones share the flags, this is how actors see changes from the control endpoint
+        let seen_by_actor = toggles.clone();
+        toggles.set(Toggle::Audit, false);
+        assert!(!seen_by_actor.is_enabled(Toggle::Audit));
+        assert_eq!(seen_by_actor.snapshot().get("audit"), Some(&false));
+
+        assert!(Toggles::from_settings(&["enrichment=on".to_string()]).is_err());
+        assert!(Toggles::from_settings(&["audit=maybe".to_string()]).is_err());
+        assert!(Toggles::from_settings(&["audit".to_string()]).is_err());
+    }
+}
@@ -0,0 +1,256 @@
+use std::fmt::Write;
+
+/// Alert triggers applied to every channel of the standard graph, as configured in `build_graph`.
+pub(crate) const CHANNEL_TRIGGERS: &str = "red when avg filled > 90%, orange when avg filled > 60%";
+
+struct ChannelInfo {
+    name: String,
+    percentiles: Vec<u8>,
+    capacity: Option<usize>,
+    // indexes into the actor list, names repeat in replicated sub-graphs
+    producers: Vec<usize>,
+    consumers: Vec<usize>,
+}
+
+struct ActorInfo {
+    name: String,
+    placement: &'static str,
+}
+
+/// Description of the graph recorded next to its construction.
+/// The framework wires channels lazily, so a channel nobody sends to or reads from
+/// only shows up at runtime as a stalled actor; recording both ends while building
+/// lets a dry run catch that before anything starts.
+pub(crate) struct Topology {
+    capacity: usize,
+    channels: Vec<ChannelInfo>,
+    actors: Vec<ActorInfo>,
+    errors: Vec<String>,
+}
+
+impl Topology {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Topology { capacity, channels: Vec::new(), actors: Vec::new(), errors: Vec::new() }
+    }
+
+    /// Declares a channel and the fill percentiles tracked on it.
+    pub(crate) fn channel(&mut self, name: impl Into<String>, percentiles: &[u8]) {
+        self.channels.push(ChannelInfo { name: name.into(), percentiles: percentiles.to_vec(), capacity: None
+                                       , producers: Vec::new(), consumers: Vec::new() });
+    }
+
+    /// Declares a channel whose capacity differs from the graph-wide default.
+    pub(crate) fn sized_channel(&mut self, name: impl Into<String>, percentiles: &[u8], capacity: usize) {
+        self.channel(name, percentiles);
+        if capacity != self.capacity {
+            if let Some(channel) = self.channels.last_mut() {
+                channel.capacity = Some(capacity);
+            }
+        }
+    }
+
+    /// Declares an actor with the channels it reads and writes.
+    /// Names resolve to the most recently declared channel, so repeated
+    /// sub-graphs can reuse the same channel names.
+    pub(crate) fn actor(&mut self, name: impl Into<String>, placement: &'static str
+                        , inputs: &[&str], outputs: &[&str]) {
+        let name = name.into();
+        let index = self.actors.len();
+        self.actors.push(ActorInfo { name: name.clone(), placement });
+        for input in inputs {
+            match self.channels.iter_mut().rev().find(|c| c.name == *input) {
+                Some(channel) => channel.consumers.push(index),
+                None => self.errors.push(format!("actor {} reads undeclared channel {}", name, input)),
+            }
+        }
+        for output in outputs {
+            match self.channels.iter_mut().rev().find(|c| c.name == *output) {
+                Some(channel) => channel.producers.push(index),
+                None => self.errors.push(format!("actor {} writes undeclared channel {}", name, output)),
+            }
+        }
+    }
+
+    /// Declares an actor joined to every channel declared so far under each name,
+    /// for the fan-in or fan-out side of repeated sub-graphs.
+    pub(crate) fn fan_actor(&mut self, name: impl Into<String>, placement: &'static str
+                            , inputs: &[&str], outputs: &[&str]) {
+        let name = name.into();
+        let index = self.actors.len();
+        self.actors.push(ActorInfo { name: name.clone(), placement });
+        for (names, reading) in [(inputs, true), (outputs, false)] {
+            for wanted in names {
+                let mut found = false;
+                for channel in self.channels.iter_mut().filter(|c| c.name == *wanted) {
+                    found = true;
+                    if reading { channel.consumers.push(index) } else { channel.producers.push(index) }
+                }
+                if !found {
+                    let verb = if reading { "reads" } else { "writes" };
+                    self.errors.push(format!("actor {} {} undeclared channel {}", name, verb, wanted));
+                }
+            }
+        }
+    }
+
+    /// Every channel needs at least one producer and one consumer.
+    pub(crate) fn verify(&self) -> Result<(), String> {
+        let mut errors = self.errors.clone();
+        for channel in &self.channels {
+            if channel.producers.is_empty() {
+                errors.push(format!("channel {} has no producer", channel.name));
+            }
+            if channel.consumers.is_empty() {
+                errors.push(format!("channel {} has no consumer", channel.name));
+            }
+        }
+        if errors.is_empty() { Ok(()) } else { Err(errors.join("\n")) }
+    }
+
+    pub(crate) fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Actors ({}):", self.actors.len());
+        for actor in &self.actors {
+            let _ = writeln!(out, "  {} [{}]", actor.name, actor.placement);
+        }
+        let _ = writeln!(out, "Channels ({}), capacity {}, {}:", self.channels.len(), self.capacity, CHANNEL_TRIGGERS);
+        for channel in &self.channels {
+            let percentiles = channel.percentiles.iter().map(|p| format!("p{}", p)).collect::<Vec<_>>().join(",");
+            let _ = writeln!(out, "  {} {} -> {}{}{}", channel.name
+                             , self.endpoint_list(&channel.producers), self.endpoint_list(&channel.consumers)
+                             , if percentiles.is_empty() { String::new() } else { format!(" [{}]", percentiles) }
+                             , channel.capacity.map(|c| format!(" capacity {}", c)).unwrap_or_default());
+        }
+        out
+    }
+
+    /// Diagram of actors and channels, each edge labelled with the channel's
+    /// name, capacity and tracked percentiles.
+    pub(crate) fn export(&self, format: GraphFormat) -> String {
+        let mut out = String::new();
+        match format {
+            GraphFormat::Dot => {
+                let _ = writeln!(out, "digraph standard {{");
+                let _ = writeln!(out, "  label=\"alerts: {}\";", CHANNEL_TRIGGERS);
+                let _ = writeln!(out, "  node [shape=box];");
+                for (index, actor) in self.actors.iter().enumerate() {
+                    let _ = writeln!(out, "  a{} [label=\"{}\\n{}\"];", index, actor.name, actor.placement);
+                }
+                for (from, to, label) in self.edges() {
+                    let _ = writeln!(out, "  a{} -> a{} [label=\"{}\"];", from, to, label);
+                }
+                let _ = writeln!(out, "}}");
+            }
+            GraphFormat::Mermaid => {
+                let _ = writeln!(out, "---\ntitle: \"alerts: {}\"\n---", CHANNEL_TRIGGERS);
+                let _ = writeln!(out, "flowchart LR");
+                for (index, actor) in self.actors.iter().enumerate() {
+                    let _ = writeln!(out, "  a{}[\"{}<br/>{}\"]", index, actor.name, actor.placement);
+                }
+                for (from, to, label) in self.edges() {
+                    let _ = writeln!(out, "  a{} -->|\"{}\"| a{}", from, label, to);
+                }
+            }
+        }
+        out
+    }
+
+    /// One (producer, consumer, label) per connected pair of actors.
+    fn edges(&self) -> Vec<(usize, usize, String)> {
+        let mut edges = Vec::new();
+        for channel in &self.channels {
+            let mut label = format!("{} cap {}", channel.name, channel.capacity.unwrap_or(self.capacity));
+            for p in &channel.percentiles {
+                let _ = write!(label, " p{}", p);
+            }
+            for from in &channel.producers {
+                for to in &channel.consumers {
+                    edges.push((*from, *to, label.clone()));
+                }
+            }
+        }
+        edges
+    }
+
+    fn endpoint_list(&self, actors: &[usize]) -> String {
+        if actors.is_empty() {
+            "?".to_string()
+        } else {
+            actors.iter().map(|a| self.actors[*a].name.as_str()).collect::<Vec<_>>().join(",")
+        }
+    }
+}
+
+/// Diagram formats written by `--export-graph`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub(crate) enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl GraphFormat {
+    /// Mermaid for `.mmd` and `.mermaid` files, DOT for anything else.
+    pub(crate) fn from_path(path: &str) -> Self {
+        if path.ends_with(".mmd") || path.ends_with(".mermaid") { GraphFormat::Mermaid } else { GraphFormat::Dot }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod topology_tests {
+    use super::*;
+
+    #[test]
+    fn test_topology() {
+        let mut topology = Topology::new(64);
+        topology.channel("generator", &[80]);
+        topology.channel("control", &[]);
+        topology.sized_channel("worker", &[], 256);
+        topology.actor("GENERATOR", "solo", &[], &["generator"]);
+        topology.actor("WORKER", "solo", &["generator"], &["worker"]);
+        topology.actor("LOGGER", "solo", &["worker"], &[]);
+        assert_eq!(topology.verify(), Err("channel control has no producer\nchannel control has no consumer".to_string()));
+
+        topology.actor("CONTROL", "troupe", &[], &["control", "missing"]);
+        topology.actor("GENERATOR_CONTROL", "troupe", &["control"], &[]);
+        assert_eq!(topology.verify(), Err("actor CONTROL writes undeclared channel missing".to_string()));
+
+        let summary = topology.summary();
+        assert!(summary.contains("  generator GENERATOR -> WORKER [p80]\n"));
+        assert!(summary.contains("  worker WORKER -> LOGGER capacity 256\n"));
+        assert!(summary.contains("Channels (3), capacity 64"));
+    }
+
+    #[test]
+    fn test_fan_actor() {
+        let mut topology = Topology::new(64);
+        for _ in 0..3 {
+            topology.channel("partition", &[]);
+            topology.actor("GENERATOR", "solo", &[], &["partition"]);
+        }
+        topology.channel("generator", &[]);
+        topology.fan_actor("MERGER", "solo", &["partition"], &["generator", "missing"]);
+        topology.actor("WORKER", "solo", &["generator"], &[]);
+        assert_eq!(topology.verify(), Err("actor MERGER writes undeclared channel missing".to_string()));
+        assert_eq!(topology.summary().matches("partition GENERATOR -> MERGER").count(), 3);
+    }
+
+    #[test]
+    fn test_export() {
+        let mut topology = Topology::new(64);
+        topology.channel("generator", &[80]);
+        topology.sized_channel("worker", &[], 256);
+        topology.actor("GENERATOR", "solo", &[], &["generator"]);
+        topology.actor("WORKER", "troupe", &["generator"], &["worker"]);
+        topology.actor("LOGGER", "solo", &["worker"], &[]);
+
+        let dot = topology.export(GraphFormat::from_path("graph.dot"));
+        assert!(dot.starts_with("digraph standard {\n"));
+        assert!(dot.contains("  a1 [label=\"WORKER\\ntroupe\"];\n"));
+        assert!(dot.contains("  a0 -> a1 [label=\"generator cap 64 p80\"];\n"));
+        assert!(dot.contains("  a1 -> a2 [label=\"worker cap 256\"];\n"));
+
+        let mermaid = topology.export(GraphFormat::from_path("graph.mmd"));
+        assert!(mermaid.contains("flowchart LR\n"));
+        assert!(mermaid.contains("  a0 -->|\"generator cap 64 p80\"| a1\n"));
+    }
+}